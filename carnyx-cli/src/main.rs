@@ -0,0 +1,121 @@
+//! Runs WAV files through a carnyx processor outside of any host.
+//!
+//! ```text
+//! carnyx-cli ladder in.wav out.wav --params settings.json --block-size 256
+//! ```
+//!
+//! The parameter file is a JSON object keyed by parameter name, as the host shows it.
+//! Numbers are normalized values; strings are parsed the way text typed into the host
+//! is, e.g. `{"cutoff": "800 Hz", "resonance": 0.5}`.
+
+use std::error::Error;
+use std::fs;
+use std::process;
+use std::sync::Arc;
+
+use carnyx::carnyx::{CarnyxHost, CarnyxProcessor};
+use carnyx::test::{render, NullCarnyxHost};
+use carnyx::wav::Wav;
+use ladder_filter::LadderProcessor;
+use serde_json::Value;
+
+const USAGE: &str = "usage: carnyx-cli <processor> <input.wav> <output.wav> [--params <file.json>] [--block-size <samples>]
+processors: ladder";
+
+const DEFAULT_BLOCK_SIZE: usize = 512;
+
+struct Args {
+    processor: String,
+    input: String,
+    output: String,
+    params: Option<String>,
+    block_size: usize,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut positional = Vec::new();
+    let mut params = None;
+    let mut block_size = DEFAULT_BLOCK_SIZE;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--params" => params = Some(args.next().ok_or("--params needs a file")?),
+            "--block-size" => {
+                block_size = args
+                    .next()
+                    .and_then(|size| size.parse().ok())
+                    .filter(|size| *size > 0)
+                    .ok_or("--block-size needs a number of samples")?
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    match (positional.next(), positional.next(), positional.next(), positional.next()) {
+        (Some(processor), Some(input), Some(output), None) => Ok(Args { processor, input, output, params, block_size }),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+/// Set parameters from the JSON file, failing on names the processor doesn't have.
+fn apply_params<P: CarnyxProcessor>(processor: &P, settings: &Value) -> Result<(), Box<dyn Error>> {
+    let settings = settings.as_object().ok_or("the parameter file should be a JSON object")?;
+    let model = processor.model();
+    let params = processor.all_parameters();
+    for (name, setting) in settings {
+        let param = params
+            .iter()
+            .find(|p| &p.name(&model) == name)
+            .ok_or_else(|| format!("no parameter named {:?}", name))?;
+        let value = match setting {
+            Value::Number(number) => number.as_f64().map(|v| v as f32).filter(|v| (0. ..=1.).contains(v)),
+            Value::String(text) => param.parse(&model, text),
+            _ => None,
+        };
+        let value = value.ok_or_else(|| format!("can't set {:?} to {}", name, setting))?;
+        param.set_value(&model, value);
+    }
+    Ok(())
+}
+
+/// Processors take as many channels as their main inputs, so wider files go through
+/// several instances, each taking the next group of channels.
+fn process_wav<P: CarnyxProcessor>(make_processor: impl Fn() -> P, args: &Args) -> Result<(), Box<dyn Error>> {
+    let input = Wav::read(&args.input).map_err(|e| format!("reading {}: {}", args.input, e))?;
+    let settings = match &args.params {
+        Some(path) => Some(serde_json::from_str::<Value>(&fs::read_to_string(path)?)?),
+        None => None,
+    };
+    let sample_rate = input.sample_rate as f32;
+    let mut channels = Vec::new();
+    let mut remaining = &input.channels[..];
+    while !remaining.is_empty() {
+        let mut processor = make_processor();
+        if let Some(settings) = &settings {
+            apply_params(&processor, settings)?;
+        }
+        processor.set_sample_rate(sample_rate);
+        let group = processor.bus_layout().main_inputs.max(1).min(remaining.len());
+        let output = render(&mut processor, sample_rate, &remaining[..group], args.block_size);
+        channels.extend(output.into_iter().take(group));
+        remaining = &remaining[group..];
+    }
+    Wav::new(input.sample_rate, channels).write(&args.output).map_err(|e| format!("writing {}: {}", args.output, e))?;
+    Ok(())
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let host: Arc<dyn CarnyxHost> = Arc::new(NullCarnyxHost);
+    match args.processor.as_str() {
+        "ladder" => process_wav(|| LadderProcessor::new(Arc::clone(&host)), args),
+        other => Err(format!("unknown processor {:?}\n{}", other, USAGE).into()),
+    }
+}
+
+fn main() {
+    let result = parse_args().map_err(Box::<dyn Error>::from).and_then(|args| run(&args));
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,178 @@
+//! The window an editor is opened in, standing in for a host's plugin window.
+
+use std::time::Duration;
+
+use carnyx::carnyx::{CarnyxEditor, CarnyxWindowResizer};
+use carnyx::random::Rng;
+use druid::widget::prelude::*;
+use druid::{ExtEventSink, NativeWindowHandle, Selector, TimerToken};
+use raw_window_handle::HasRawWindowHandle;
+
+use crate::log::{LoggedResizer, OpcodeLog};
+
+pub const HOST_RESIZE: Selector<Size> = Selector::new("carnyx.devhost.host_resize");
+
+const AUTOMATION_INTERVAL: Duration = Duration::from_millis(30);
+const RESIZE_INTERVAL: Duration = Duration::from_secs(2);
+// random resizes stay within this factor of the editor's initial size
+const RESIZE_RANGE: (f64, f64) = (0.5, 1.5);
+
+/// Steps automation playback, given the seconds since it started.
+pub type Automation = Box<dyn FnMut(f64)>;
+
+struct EditorResizer {
+    ext_event_sink: ExtEventSink,
+    widget_id: WidgetId,
+}
+
+impl CarnyxWindowResizer for EditorResizer {
+    fn resize_editor_window(&self, width: usize, height: usize) -> bool {
+        self.ext_event_sink.submit_command(HOST_RESIZE, Size::new(width as f64, height as f64), self.widget_id).is_ok()
+    }
+}
+
+/// Opens an editor in a native child window when it connects, as hosts do, then plays
+/// whatever host behaviour it was built with.
+pub struct EditorHost<Editor: CarnyxEditor> {
+    editor: Editor,
+    log: OpcodeLog,
+    desired_size: Option<Size>,
+    native_child: Option<NativeWindowHandle>,
+    reopen_cycles: usize,
+    automation: Option<Automation>,
+    automation_timer: TimerToken,
+    automation_seconds: f64,
+    random_resizes: bool,
+    resize_timer: TimerToken,
+    rng: Rng,
+}
+
+impl<Editor: CarnyxEditor> EditorHost<Editor> {
+    pub fn new(editor: Editor, log: OpcodeLog) -> Self {
+        EditorHost {
+            editor,
+            log,
+            desired_size: None,
+            native_child: None,
+            reopen_cycles: 0,
+            automation: None,
+            automation_timer: TimerToken::INVALID,
+            automation_seconds: 0.,
+            random_resizes: false,
+            resize_timer: TimerToken::INVALID,
+            rng: Rng::default(),
+        }
+    }
+
+    /// Open and close the editor this many times before leaving it open, as hosts do
+    /// when the user toggles the plugin window.
+    pub fn with_reopen_cycles(mut self, cycles: usize) -> Self {
+        self.reopen_cycles = cycles;
+        self
+    }
+
+    /// Play back parameter changes while the editor is open.
+    pub fn with_automation(mut self, automation: Automation) -> Self {
+        self.automation = Some(automation);
+        self
+    }
+
+    /// Resize the window every couple of seconds, as hosts do when users drag the
+    /// plugin window or the host restores a layout.
+    pub fn with_random_resizes(mut self, random_resizes: bool) -> Self {
+        self.random_resizes = random_resizes;
+        self
+    }
+
+    fn resizer(&self, ctx: &mut EventCtx) -> Box<dyn CarnyxWindowResizer> {
+        let resizer = EditorResizer { ext_event_sink: ctx.get_external_handle(), widget_id: ctx.widget_id() };
+        Box::new(LoggedResizer::new(resizer, self.log.clone()))
+    }
+
+    fn open(&mut self, ctx: &mut EventCtx, native: &NativeWindowHandle) {
+        self.native_child = Some(native.clone());
+        let raw = native.0.raw_window_handle();
+        let (w, h) = self.editor.initial_size();
+        self.desired_size = Some(Size::new(w as f64, h as f64));
+        for cycle in 0..self.reopen_cycles {
+            let opened = self.editor.open(Some(raw), self.resizer(ctx));
+            assert!(opened && self.editor.is_open(), "editor failed to open on cycle {}", cycle);
+            self.editor.close();
+            assert!(!self.editor.is_open(), "editor still open after close on cycle {}", cycle);
+        }
+        self.editor.open(Some(raw), self.resizer(ctx));
+        if self.automation.is_some() {
+            self.automation_timer = ctx.request_timer(AUTOMATION_INTERVAL);
+        }
+        if self.random_resizes {
+            self.resize_timer = ctx.request_timer(RESIZE_INTERVAL);
+        }
+        ctx.request_layout();
+    }
+
+    fn random_resize(&mut self, ctx: &mut EventCtx) {
+        let (w, h) = self.editor.initial_size();
+        let (low, high) = RESIZE_RANGE;
+        let mut scale = || low + (high - low) * self.rng.next_f32() as f64;
+        let size = Size::new((w as f64 * scale()).round(), (h as f64 * scale()).round());
+        self.log.to_plugin("host resize", format_args!("{}x{}", size.width, size.height));
+        self.desired_size = Some(size);
+        ctx.request_layout();
+    }
+}
+
+impl<Editor: CarnyxEditor> Widget<()> for EditorHost<Editor> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut (), _env: &Env) {
+        match event {
+            Event::Command(cmd) if cmd.is(HOST_RESIZE) => {
+                if let Some(size) = cmd.get(HOST_RESIZE) {
+                    self.desired_size = Some(*size);
+                    ctx.request_layout()
+                }
+            }
+            Event::NativeWindowConnected(native) => self.open(ctx, native),
+            Event::Timer(token) if *token == self.automation_timer => {
+                self.automation_seconds += AUTOMATION_INTERVAL.as_secs_f64();
+                if let Some(automation) = &mut self.automation {
+                    automation(self.automation_seconds);
+                }
+                self.automation_timer = ctx.request_timer(AUTOMATION_INTERVAL);
+            }
+            Event::Timer(token) if *token == self.resize_timer => {
+                self.random_resize(ctx);
+                self.resize_timer = ctx.request_timer(RESIZE_INTERVAL);
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &(), _env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            let (w, h) = self.editor.initial_size();
+            ctx.request_native_window(Size::new(w as f64, h as f64))
+        }
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &(), _data: &(), _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &(), _env: &Env) -> Size {
+        let size = bc.constrain(self.desired_size.unwrap_or(Size::ZERO));
+        if let Some(nc) = &self.native_child {
+            nc.0.set_native_layout(None, self.desired_size);
+        }
+        size
+    }
+
+    fn paint(&mut self, _ctx: &mut PaintCtx, _data: &(), _env: &Env) {}
+
+    fn post_render(&mut self) {}
+}
+
+impl<Editor: CarnyxEditor> Drop for EditorHost<Editor> {
+    // the host closes the editor before destroying its window
+    fn drop(&mut self) {
+        if self.editor.is_open() {
+            self.editor.close();
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! A stand-in host for reproducing editor bugs without booting a DAW. It opens a
+//! processor's editor in a native child window as a VST host does, and can play back
+//! parameter automation, resize the window at random, and open and close the editor
+//! rapidly, logging every call between host and editor to stderr.
+//!
+//! A plugin crate runs it from an example:
+//!
+//! ```ignore
+//! fn main() {
+//!     carnyx_devhost::run(LadderProcessor::new);
+//! }
+//! ```
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use carnyx::carnyx::{CarnyxEditor, CarnyxHost, CarnyxModel, CarnyxModelListener, CarnyxParam, CarnyxProcessor, ChangeEvent, ChangeOrigin, SettableListener};
+use carnyx_druid::generic_editor;
+use druid::widget::{Button, Checkbox, Flex};
+use druid::{AppLauncher, Color, Data, Lens, Widget, WidgetExt, WindowDesc, WindowSizePolicy};
+
+mod editor_host;
+mod log;
+
+pub use editor_host::{Automation, EditorHost, HOST_RESIZE};
+pub use log::{DevHost, LoggedEditor, LoggedResizer, OpcodeLog};
+
+const RAPID_REOPEN_CYCLES: usize = 100;
+// how long playback sweeps each parameter before moving to the next
+const AUTOMATION_SECONDS_PER_PARAM: f64 = 4.;
+const AUTOMATION_SWEEP_HZ: f64 = 0.5;
+
+/// The host behaviour new editor windows get.
+#[derive(Debug, Clone, Default, Data, Lens)]
+pub struct DevHostSettings {
+    pub automation: bool,
+    pub random_resizes: bool,
+    pub rapid_reopen: bool,
+}
+
+/// Sweeps each parameter in turn as host automation would, setting values through the
+/// parameter and telling listeners the host made the change. Logs which parameter it is
+/// on rather than every value.
+pub fn automation_playback<Model: CarnyxModel>(
+    params: Vec<Box<dyn CarnyxParam<Model>>>,
+    model: Arc<Model>,
+    listener: SettableListener<Model>,
+    log: OpcodeLog,
+) -> Automation {
+    let writable: Vec<usize> = (0..params.len()).filter(|i| !params[*i].is_read_only()).collect();
+    let mut current = None;
+    Box::new(move |seconds| {
+        if writable.is_empty() {
+            return;
+        }
+        let index = writable[(seconds / AUTOMATION_SECONDS_PER_PARAM) as usize % writable.len()];
+        if current != Some(index) {
+            log.to_plugin("setParameter", format_args!("{} ({}), sweeping", index, params[index].name(&model)));
+            current = Some(index);
+        }
+        let value = 0.5 - 0.5 * (2. * PI * AUTOMATION_SWEEP_HZ * seconds).cos();
+        params[index].set_value(&model, value as f32);
+        listener.notify_change(&model, ChangeEvent::param(index, ChangeOrigin::Host));
+    })
+}
+
+fn editor_window<P: CarnyxProcessor>(processor: &P, editor: impl CarnyxEditor + 'static, settings: &DevHostSettings, log: &OpcodeLog, title: &str) -> WindowDesc<()> {
+    let mut host = EditorHost::new(LoggedEditor::new(editor, log.clone()), log.clone())
+        .with_random_resizes(settings.random_resizes);
+    if settings.rapid_reopen {
+        host = host.with_reopen_cycles(RAPID_REOPEN_CYCLES);
+    }
+    if settings.automation {
+        host = host.with_automation(automation_playback(processor.all_parameters(), processor.model(), processor.listener(), log.clone()));
+    }
+    WindowDesc::new(host.border(Color::WHITE, 1.))
+        .title(title)
+        .resizable(false)
+        .window_size_policy(WindowSizePolicy::Content)
+}
+
+fn launcher<P, F>(make_processor: Arc<F>, log: OpcodeLog) -> impl Widget<DevHostSettings>
+where
+    P: CarnyxProcessor,
+    P::Editor: 'static,
+    <P::Model as CarnyxModel>::Snap: Data,
+    F: Fn(Arc<dyn CarnyxHost>) -> P + 'static,
+{
+    let (make_custom, make_generic) = (Arc::clone(&make_processor), make_processor);
+    let (custom_log, generic_log) = (log.clone(), log);
+    let host = |log: &OpcodeLog| Arc::new(DevHost::new(log.clone())) as Arc<dyn CarnyxHost>;
+    Flex::column()
+        .with_child(Checkbox::new("Play automation").lens(DevHostSettings::automation))
+        .with_child(Checkbox::new("Random resizes").lens(DevHostSettings::random_resizes))
+        .with_child(Checkbox::new(format!("Open and close {} times first", RAPID_REOPEN_CYCLES)).lens(DevHostSettings::rapid_reopen))
+        .with_spacer(10.)
+        .with_child(Button::new("Open editor").on_click(move |ctx, settings: &mut DevHostSettings, _| {
+            let processor = make_custom(host(&custom_log));
+            match processor.editor() {
+                Some(editor) => ctx.new_window(editor_window(&processor, editor, settings, &custom_log, "Plugin Editor")),
+                None => custom_log.to_plugin("editOpen", "the processor has no editor of its own"),
+            }
+        }))
+        .with_child(Button::new("Open generic editor").on_click(move |ctx, settings: &mut DevHostSettings, _| {
+            let processor = make_generic(host(&generic_log));
+            let editor = generic_editor(host(&generic_log), &processor);
+            ctx.new_window(editor_window(&processor, editor, settings, &generic_log, "Generic Editor"));
+        }))
+}
+
+/// Run the devhost for processors made by `make_processor`, until its window is closed.
+pub fn run<P, F>(make_processor: F)
+where
+    P: CarnyxProcessor,
+    P::Editor: 'static,
+    <P::Model as CarnyxModel>::Snap: Data,
+    F: Fn(Arc<dyn CarnyxHost>) -> P + 'static,
+{
+    let log = OpcodeLog::new();
+    let window = WindowDesc::new(launcher(Arc::new(make_processor), log))
+        .title("carnyx devhost")
+        .window_size_policy(WindowSizePolicy::Content);
+    AppLauncher::with_window(window)
+        .use_env_tracing()
+        .launch(DevHostSettings::default())
+        .expect("Failed to launch application");
+}
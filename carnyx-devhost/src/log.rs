@@ -0,0 +1,144 @@
+//! Logging the calls that pass between the host and a plugin, named after the VST opcodes
+//! a real host would send.
+
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Instant;
+
+use carnyx::automation::DEFAULT_DISPLAY_INTERVAL;
+use carnyx::carnyx::{CarnyxEditor, CarnyxHost, CarnyxWindowResizer, HostInfo};
+use raw_window_handle::RawWindowHandle;
+
+/// Writes timestamped calls to stderr.
+#[derive(Debug, Clone)]
+pub struct OpcodeLog {
+    start: Arc<Instant>,
+}
+
+impl Default for OpcodeLog {
+    fn default() -> Self {
+        OpcodeLog { start: Arc::new(Instant::now()) }
+    }
+}
+
+impl OpcodeLog {
+    pub fn new() -> Self {
+        OpcodeLog::default()
+    }
+
+    /// A call from the host to the plugin.
+    pub fn to_plugin(&self, opcode: &str, detail: impl Display) {
+        self.write("host -> plugin", opcode, detail);
+    }
+
+    /// A call from the plugin to the host.
+    pub fn to_host(&self, opcode: &str, detail: impl Display) {
+        self.write("plugin -> host", opcode, detail);
+    }
+
+    fn write(&self, direction: &str, opcode: &str, detail: impl Display) {
+        eprintln!("{:>9.3}s {} {} {}", self.start.elapsed().as_secs_f64(), direction, opcode, detail);
+    }
+}
+
+/// The host processors are made with. Logs what they ask of it.
+pub struct DevHost {
+    log: OpcodeLog,
+    info: HostInfo,
+}
+
+impl DevHost {
+    pub fn new(log: OpcodeLog) -> Self {
+        let info = HostInfo {
+            vendor: "carnyx".to_string(),
+            product: "carnyx-devhost".to_string(),
+            version: 1,
+            supports_resize: true,
+            supports_automation_gestures: true,
+            display_update_interval: DEFAULT_DISPLAY_INTERVAL,
+        };
+        DevHost { log, info }
+    }
+}
+
+impl CarnyxHost for DevHost {
+    fn update_host_display(&self) {
+        self.log.to_host("updateDisplay", "");
+    }
+
+    fn host_info(&self) -> &HostInfo {
+        &self.info
+    }
+
+    fn set_parameter_automated(&self, index: usize, value: f32) -> bool {
+        self.log.to_host("automate", format_args!("{} = {:.3}", index, value));
+        true
+    }
+
+    fn begin_edit(&self, index: usize) {
+        self.log.to_host("beginEdit", index);
+    }
+
+    fn end_edit(&self, index: usize) {
+        self.log.to_host("endEdit", index);
+    }
+}
+
+/// Passes an editor's resize requests on to the devhost's window.
+pub struct LoggedResizer<R> {
+    inner: R,
+    log: OpcodeLog,
+}
+
+impl<R: CarnyxWindowResizer> LoggedResizer<R> {
+    pub fn new(inner: R, log: OpcodeLog) -> Self {
+        LoggedResizer { inner, log }
+    }
+}
+
+impl<R: CarnyxWindowResizer> CarnyxWindowResizer for LoggedResizer<R> {
+    fn resize_editor_window(&self, width: usize, height: usize) -> bool {
+        let resized = self.inner.resize_editor_window(width, height);
+        self.log.to_host("sizeWindow", format_args!("{}x{} -> {}", width, height, resized));
+        resized
+    }
+}
+
+/// An editor that logs each call the host makes to it.
+pub struct LoggedEditor<E> {
+    inner: E,
+    log: OpcodeLog,
+}
+
+impl<E: CarnyxEditor> LoggedEditor<E> {
+    pub fn new(inner: E, log: OpcodeLog) -> Self {
+        LoggedEditor { inner, log }
+    }
+}
+
+impl<E: CarnyxEditor> CarnyxEditor for LoggedEditor<E> {
+    fn initial_size(&self) -> (usize, usize) {
+        let (width, height) = self.inner.initial_size();
+        self.log.to_plugin("editGetRect", format_args!("{}x{}", width, height));
+        (width, height)
+    }
+
+    fn initial_position(&self) -> (isize, isize) {
+        self.inner.initial_position()
+    }
+
+    fn open(&mut self, handle: Option<RawWindowHandle>, window_resizer: Box<dyn CarnyxWindowResizer>) -> bool {
+        let opened = self.inner.open(handle, window_resizer);
+        self.log.to_plugin("editOpen", format_args!("-> {}", opened));
+        opened
+    }
+
+    fn close(&mut self) {
+        self.log.to_plugin("editClose", "");
+        self.inner.close();
+    }
+
+    fn is_open(&self) -> bool {
+        self.inner.is_open()
+    }
+}
@@ -0,0 +1,88 @@
+//! A light which shows the output going over full scale.
+
+use std::time::{Duration, Instant};
+
+use druid::kurbo::Circle;
+use druid::widget::prelude::*;
+use druid::{theme, Color};
+
+use carnyx::limiter::CEILING;
+use carnyx::Telemetry;
+
+use crate::frames::FRAME;
+use crate::telemetry::TELEMETRY;
+
+const DIAMETER: f64 = 12.;
+// long enough to notice a single over
+const HOLD: Duration = Duration::from_millis(1500);
+
+/// Lights up when any output channel's [`Telemetry::Peak`] goes over full scale, and
+/// stays lit for a moment afterwards, or until clicked. The peaks are from before the
+/// output limiter, so the light also shows the overs it catches.
+pub struct ClipLed {
+    lit_until: Option<Instant>,
+}
+
+impl Default for ClipLed {
+    fn default() -> Self {
+        ClipLed::new()
+    }
+}
+
+impl ClipLed {
+    pub fn new() -> Self {
+        ClipLed { lit_until: None }
+    }
+
+    fn is_lit(&self) -> bool {
+        self.lit_until.is_some()
+    }
+}
+
+impl Widget<()> for ClipLed {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut (), _env: &Env) {
+        match event {
+            Event::Command(command) if command.is(TELEMETRY) => {
+                let messages = command.get(TELEMETRY).map(|messages| messages.as_slice()).unwrap_or(&[]);
+                let clipped = messages.iter().any(|message| matches!(message, Telemetry::Peak { level, .. } if *level > CEILING));
+                if clipped {
+                    self.lit_until = Some(Instant::now() + HOLD);
+                    ctx.request_paint();
+                }
+            }
+            Event::Command(command) if command.is(FRAME) => {
+                if self.lit_until.map(|until| Instant::now() >= until).unwrap_or(false) {
+                    self.lit_until = None;
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseDown(_) if self.is_lit() => {
+                self.lit_until = None;
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &(), _env: &Env) {}
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &(), _data: &(), _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &(), _env: &Env) -> Size {
+        bc.constrain(Size::new(DIAMETER, DIAMETER))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &(), env: &Env) {
+        let size = ctx.size();
+        let light = Circle::new((size.width / 2., size.height / 2.), size.width.min(size.height) / 2. - 1.);
+        if self.is_lit() {
+            ctx.fill(light, &Color::rgb8(0xe8, 0x2c, 0x2c));
+        } else {
+            ctx.fill(light, &env.get(theme::BACKGROUND_DARK));
+        }
+        ctx.stroke(light, &env.get(theme::BORDER_DARK), 1.);
+    }
+
+    fn post_render(&mut self) {}
+}
@@ -0,0 +1,16 @@
+use druid::widget::{Button, LabelText};
+use druid::{Data, Widget};
+
+use carnyx::CommandQueue;
+
+/// A button which sends `command` to the processor when clicked. If the queue is full
+/// the click is dropped; the processor hasn't run since the last few anyway.
+pub fn command_button<T: Data, C: Clone + Send + 'static>(
+    label: impl Into<LabelText<T>>,
+    queue: CommandQueue<C>,
+    command: C,
+) -> impl Widget<T> {
+    Button::new(label).on_click(move |_ctx, _data, _env| {
+        queue.push(command.clone());
+    })
+}
@@ -14,12 +14,112 @@
 
 //! A dial widget.
 
-use druid::kurbo::{CircleSegment, Shape};
+use druid::kurbo::{CircleSegment, Line, Shape};
 use druid::widget::prelude::*;
-use druid::{theme, LinearGradient, Point, UnitPoint};
+use druid::{theme, LinearGradient, MouseButton, Point, Selector, TimerToken, UnitPoint, Vec2};
 use std::f64::consts::PI;
+use std::time::{Duration, Instant};
+
+use carnyx::automation::AutomationTracker;
+use carnyx::{ModulationAmount, Telemetry};
+
+use crate::druid_editor::HOST_PLAYING;
+use crate::reset::is_reset_click;
+use crate::telemetry::TELEMETRY;
+use crate::theme::{KNOB_FILLED, TOUCH_INPUT};
 
 const STROKE_WIDTH: f64 = 2.0;
+const INDICATOR_WIDTH: f64 = 2.0;
+// the dial runs clockwise from bottom left to bottom right
+const START_ANGLE: f64 = 0.75 * PI;
+const SWEEP_ANGLE: f64 = 1.5 * PI;
+// with touch input: the smallest a dial gets, about a fingertip
+pub(crate) const TOUCH_TARGET: f64 = 44.;
+// how far a finger drags to sweep the whole range, whatever the dial's size
+pub(crate) const TOUCH_TRAVEL: f64 = 240.;
+// how far a finger can wander before a press becomes a drag
+pub(crate) const TOUCH_SLOP: f64 = 6.;
+pub(crate) const LONG_PRESS: Duration = Duration::from_millis(500);
+
+/// Sent by a [`Dial`] or an [`XYPad`](crate::XYPad) to itself on a right click, or a long press with touch input, with
+/// the position in the window, for a controller around it to show a menu there.
+pub const CONTEXT_MENU: Selector<Point> = Selector::new("carnyx-druid.context-menu");
+
+// a touch on the dial, which drags from where it started once it moves far enough
+#[derive(Debug, Clone)]
+struct Press {
+    origin: Point,
+    window_pos: Point,
+    value: f64,
+    dragging: bool,
+    timer: TimerToken,
+}
+
+// the modulation shown around the dial, from the parameter's telemetry
+#[derive(Debug, Clone)]
+struct ModulationRing {
+    index: usize,
+    amount: ModulationAmount,
+}
+
+impl ModulationRing {
+    /// Returns true if the amount changed.
+    fn receive(&mut self, messages: &[Telemetry]) -> bool {
+        let latest = messages.iter().rev().find_map(|message| match message {
+            Telemetry::Modulation { index, amount } if *index == self.index => Some(*amount),
+            _ => None,
+        });
+        match latest {
+            Some(amount) if amount != self.amount => {
+                self.amount = amount;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Eases the dial from the value it showed to one set from outside the editor.
+#[derive(Debug, Clone)]
+struct Easing {
+    duration: Duration,
+    // the value shown when the change came, and the time since
+    from: Option<(f64, Duration)>,
+}
+
+impl Easing {
+    fn new(duration: Duration) -> Self {
+        Easing { duration, from: None }
+    }
+
+    fn start(&mut self, shown: f64) {
+        self.from = Some((shown, Duration::from_nanos(0)));
+    }
+
+    fn stop(&mut self) {
+        self.from = None;
+    }
+
+    /// Returns true if still easing.
+    fn advance(&mut self, interval: Duration) -> bool {
+        if let Some((from, elapsed)) = self.from {
+            let elapsed = elapsed + interval;
+            self.from = if elapsed < self.duration { Some((from, elapsed)) } else { None };
+        }
+        self.from.is_some()
+    }
+
+    fn value(&self, to: f64) -> f64 {
+        match self.from {
+            Some((from, elapsed)) => {
+                let t = elapsed.as_secs_f64() / self.duration.as_secs_f64();
+                // ease out, so the dial starts moving at once
+                from + (to - from) * (1. - (1. - t).powi(3))
+            }
+            None => to,
+        }
+    }
+}
 
 /// A slider, allowing interactive update of a numeric value.
 ///
@@ -29,7 +129,12 @@ const STROKE_WIDTH: f64 = 2.0;
 pub struct Dial {
     min: f64,
     max: f64,
+    default: Option<f64>,
+    automation: Option<AutomationTracker>,
+    easing: Option<Easing>,
+    modulation: Option<ModulationRing>,
     mouse_last: Option<Point>,
+    press: Option<Press>,
     hovered: bool,
 }
 
@@ -45,7 +150,12 @@ impl Dial {
         Dial {
             min: 0.,
             max: 1.,
+            default: None,
+            automation: None,
+            easing: None,
+            modulation: None,
             mouse_last: None,
+            press: None,
             hovered: false,
         }
     }
@@ -58,6 +168,36 @@ impl Dial {
         self.max = max;
         self
     }
+
+    /// Builder-style method to set the value a Cmd/Ctrl-click or double-click resets to.
+    pub fn with_default(mut self, default: f64) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Builder-style method to enable the automation overlay. While the host is playing
+    /// and the value is changing rapidly from outside the editor, the dial keeps showing
+    /// the value the user set, with the automated value as a ghost ring around it.
+    pub fn with_automation_overlay(mut self) -> Self {
+        self.automation = Some(AutomationTracker::new());
+        self
+    }
+
+    /// Builder-style method to ease the dial to values set from outside the editor, e.g.
+    /// by the host, over `duration` rather than jumping. Around 60ms is enough to follow.
+    pub fn with_animation(mut self, duration: Duration) -> Self {
+        self.easing = Some(Easing::new(duration));
+        self
+    }
+
+    /// Builder-style method to show the modulation of the parameter with this index, e.g.
+    /// from an LFO, as a shaded arc around the dial over the range the value swings
+    /// through, with a mark where it is now. Read from the processor's
+    /// [`Telemetry`](carnyx::Telemetry), in normalized units of the dial's range.
+    pub fn with_modulation(mut self, index: usize) -> Self {
+        self.modulation = Some(ModulationRing { index, amount: ModulationAmount::default() });
+        self
+    }
 }
 
 impl Dial {
@@ -65,55 +205,124 @@ impl Dial {
         (data.clamp(self.min, self.max) - self.min) / (self.max - self.min)
     }
 
+    // what the dial shows as its value, which is not the data while automation runs
+    fn shown_value(&self, data: &f64) -> f64 {
+        let eased = self.easing.as_ref().map(|e| e.value(*data)).unwrap_or(*data);
+        self.automation.as_ref().and_then(|a| a.user_value()).unwrap_or(eased)
+    }
+
     fn make_segment(&self, data: &f64, env: &Env, size: Size) -> CircleSegment {
-        let rect = size.to_rect();
-        let clamped = self.normalize(*data);
-        let center = rect.center();
-        let inset_rect = rect
-            .contained_rect_with_aspect_ratio(1.0)
-            .inset(-env.get(theme::WIDGET_CONTROL_COMPONENT_PADDING));
-
-        let start_angle = 0.75 * PI;
-        //let end_angle = 2.25 * PI;
-
-        let outer = inset_rect.height() / 2.;
-        let seg = CircleSegment::new(
-            center,
-            outer,
-            outer * 0.5,
-            start_angle,
-            2. * PI * 0.75 * clamped,
-        );
-        seg
+        self.make_ring(data, env, size, 0.5, 1.0)
+    }
+
+    // the whole range, behind the value
+    fn make_track(&self, env: &Env, size: Size) -> CircleSegment {
+        CircleSegment::new(size.to_rect().center(), radius(env, size), radius(env, size) * 0.5, START_ANGLE, SWEEP_ANGLE)
+    }
+
+    // a segment between the given fractions of the dial's radius
+    fn make_ring(&self, data: &f64, env: &Env, size: Size, inner: f64, outer: f64) -> CircleSegment {
+        let radius = radius(env, size);
+        CircleSegment::new(size.to_rect().center(), radius * outer, radius * inner, START_ANGLE, SWEEP_ANGLE * self.normalize(*data))
+    }
+
+    // across the ring, at the value's angle
+    fn make_indicator(&self, data: &f64, env: &Env, size: Size) -> Line {
+        self.make_tick(self.normalize(*data), env, size, 0.5, 1.0)
+    }
+
+    // a radial line at a fraction of the dial's sweep, between fractions of its radius
+    fn make_tick(&self, fraction: f64, env: &Env, size: Size, inner: f64, outer: f64) -> Line {
+        let (center, radius) = (size.to_rect().center(), radius(env, size));
+        let direction = Vec2::from_angle(START_ANGLE + SWEEP_ANGLE * fraction);
+        Line::new(center + direction * radius * inner, center + direction * radius * outer)
+    }
+
+    fn paint_modulation(&self, ctx: &mut PaintCtx, data: &f64, env: &Env) {
+        let amount = match &self.modulation {
+            Some(ring) if ring.amount.is_active() => ring.amount,
+            _ => return,
+        };
+        let base = self.normalize(*data);
+        let (low, high) = ((base - amount.range as f64).max(0.), (base + amount.range as f64).min(1.));
+        let (center, radius) = (ctx.size().to_rect().center(), radius(env, ctx.size()));
+        let arc = CircleSegment::new(center, radius * 1.15, radius * 1.05, START_ANGLE + SWEEP_ANGLE * low, SWEEP_ANGLE * (high - low));
+        ctx.fill(&arc, &env.get(theme::PRIMARY_DARK).with_alpha(0.5));
+        let now = (base + amount.offset as f64).clamp(0., 1.);
+        let tick = self.make_tick(now, env, ctx.size(), 1.0, 1.2);
+        ctx.stroke(tick, &env.get(theme::PRIMARY_LIGHT), INDICATOR_WIDTH);
     }
 }
 
+fn radius(env: &Env, size: Size) -> f64 {
+    let rect = size.to_rect()
+        .contained_rect_with_aspect_ratio(1.0)
+        .inset(-env.get(theme::WIDGET_CONTROL_COMPONENT_PADDING));
+    rect.height() / 2.
+}
+
 impl Widget<f64> for Dial {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut f64, env: &Env) {
         match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Right => {
+                ctx.submit_command(CONTEXT_MENU.with(mouse.window_pos).to(ctx.widget_id()));
+                ctx.set_handled();
+            }
+            Event::MouseDown(mouse) if self.default.is_some() && is_reset_click(mouse) => {
+                *data = self.default.unwrap_or(self.min).clamp(self.min, self.max);
+                if let Some(automation) = &mut self.automation {
+                    automation.user_edit(*data);
+                }
+                ctx.request_paint();
+            }
             Event::MouseDown(mouse) => {
+                // drags start from the value, not wherever easing had got to
+                if let Some(easing) = &mut self.easing {
+                    easing.stop();
+                }
                 ctx.set_active(true);
                 self.mouse_last = Some(mouse.pos);
+                if env.try_get(TOUCH_INPUT).unwrap_or(false) {
+                    let timer = ctx.request_timer(LONG_PRESS);
+                    self.press = Some(Press { origin: mouse.pos, window_pos: mouse.window_pos, value: *data, dragging: false, timer });
+                }
                 ctx.request_paint();
             }
             Event::MouseUp(_) => {
+                self.press = None;
                 if ctx.is_active() {
                     ctx.set_active(false);
                     ctx.request_paint();
                 }
             }
             Event::MouseMove(mouse) => {
-                if ctx.is_active() {
+                if let (Some(press), true) = (&mut self.press, ctx.is_active()) {
+                    let rise = press.origin.y - mouse.pos.y;
+                    press.dragging |= rise.abs() > TOUCH_SLOP;
+                    if press.dragging {
+                        // relative to where the finger came down, less the slop, so the
+                        // value never jumps, and at a fixed rate rather than the dial's size
+                        let travel = rise - TOUCH_SLOP * rise.signum();
+                        *data = (press.value + (self.max - self.min) * travel / TOUCH_TRAVEL).clamp(self.min, self.max);
+                        if let Some(automation) = &mut self.automation {
+                            automation.user_edit(*data);
+                        }
+                        ctx.request_paint();
+                    }
+                } else if ctx.is_active() {
                     if let Some(last) = self.mouse_last {
                         let y_move = last.y - mouse.pos.y;
-                        let tmp = *data + (self.max - self.min) * y_move / ctx.size().height;
+                        let tmp = self.shown_value(data) + (self.max - self.min) * y_move / ctx.size().height;
                         *data = tmp.clamp(self.min, self.max);
+                        if let Some(automation) = &mut self.automation {
+                            automation.user_edit(*data);
+                        }
                         ctx.request_paint();
                     }
                     self.mouse_last = Some(mouse.pos);
                 }
                 if ctx.is_hot() {
-                    let shape = self.make_segment(data, env, ctx.size());
+                    let shape = self.make_track(env, ctx.size());
                     let mouse_pos = mouse.pos;
                     let hover = shape.winding(mouse_pos) > 0;
                     if hover != self.hovered {
@@ -122,13 +331,63 @@ impl Widget<f64> for Dial {
                     }
                 }
             }
+            Event::Timer(token) if self.press.as_ref().map(|press| press.timer == *token).unwrap_or(false) => {
+                // held still, rather than dragged
+                let held = self.press.as_ref().filter(|press| !press.dragging).map(|press| press.window_pos);
+                if let (Some(window_pos), true) = (held, ctx.is_active()) {
+                    self.press = None;
+                    ctx.set_active(false);
+                    ctx.submit_command(CONTEXT_MENU.with(window_pos).to(ctx.widget_id()));
+                    ctx.request_paint();
+                }
+                ctx.set_handled();
+            }
+            Event::AnimFrame(interval) => {
+                if let Some(easing) = &mut self.easing {
+                    if easing.advance(Duration::from_nanos(*interval)) {
+                        ctx.request_anim_frame();
+                    }
+                    ctx.request_paint();
+                }
+                if let Some(automation) = &mut self.automation {
+                    if automation.expire(Instant::now()) {
+                        ctx.request_paint();
+                    } else if automation.is_automating() {
+                        ctx.request_anim_frame();
+                    }
+                }
+            }
+            Event::Command(command) if command.is(TELEMETRY) => {
+                if let (Some(ring), Some(messages)) = (&mut self.modulation, command.get(TELEMETRY)) {
+                    if ring.receive(messages) {
+                        ctx.request_paint();
+                    }
+                }
+            }
             _ => (),
         }
     }
 
     fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &f64, _env: &Env) {}
 
-    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &f64, _data: &f64, _env: &Env) {
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &f64, data: &f64, env: &Env) {
+        // the dial's own edits happen while it is active
+        let playing = env.try_get(HOST_PLAYING).unwrap_or(true);
+        if let (Some(automation), false, true) = (&mut self.automation, ctx.is_active(), playing) {
+            if old_data != data {
+                automation.external_change(*old_data, Instant::now());
+                if automation.is_automating() {
+                    ctx.request_anim_frame();
+                }
+            }
+        }
+        if let (Some(easing), false) = (&mut self.easing, ctx.is_active()) {
+            if old_data != data {
+                let shown = easing.value(*old_data);
+                easing.start(shown);
+                ctx.request_anim_frame();
+            }
+        }
         ctx.request_paint();
     }
 
@@ -140,11 +399,13 @@ impl Widget<f64> for Dial {
         env: &Env,
     ) -> Size {
         bc.debug_check("Dial");
-        bc.constrain_aspect_ratio(1.0, env.get(theme::WIDE_WIDGET_WIDTH)/ 2.)
+        let width = env.get(theme::WIDE_WIDGET_WIDTH) / 2.;
+        let width = if env.try_get(TOUCH_INPUT).unwrap_or(false) { width.max(TOUCH_TARGET) } else { width };
+        bc.constrain_aspect_ratio(1.0, width)
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &f64, env: &Env) {
-        let seg = self.make_segment(data, env, ctx.size());
+        let seg = self.make_segment(&self.shown_value(data), env, ctx.size());
 
         let is_active = ctx.is_active();
         let is_hovered = self.hovered;
@@ -162,8 +423,22 @@ impl Widget<f64> for Dial {
             env.get(theme::FOREGROUND_DARK)
         };
 
+        let track = self.make_track(env, ctx.size());
+        ctx.fill(&track, &env.get(theme::BACKGROUND_LIGHT));
+        ctx.stroke(&track, &env.get(theme::FOREGROUND_DARK).with_alpha(0.4), 1.0);
+
         ctx.stroke(&seg, &border_color, STROKE_WIDTH);
-        ctx.fill(&seg, &gradient);
+        if env.try_get(KNOB_FILLED).unwrap_or(true) {
+            ctx.fill(&seg, &gradient);
+        }
+        let indicator = self.make_indicator(&self.shown_value(data), env, ctx.size());
+        ctx.stroke(indicator, &env.get(theme::FOREGROUND_LIGHT), INDICATOR_WIDTH);
+
+        if self.automation.as_ref().map(|a| a.is_automating()).unwrap_or(false) {
+            let ghost = self.make_ring(data, env, ctx.size(), 1.05, 1.15);
+            ctx.fill(&ghost, &env.get(theme::PRIMARY_LIGHT).with_alpha(0.6));
+        }
+        self.paint_modulation(ctx, &self.shown_value(data), env);
     }
 
     fn post_render(&mut self) {}
@@ -0,0 +1,57 @@
+//! A button which picks one of a parameter's steps from a menu, for choices too many or
+//! too changeable for a row of radio buttons, such as the parameter an LFO modulates.
+
+use druid::widget::{Button, Controller, Flex};
+use druid::{ContextMenu, Data, Env, Event, EventCtx, Lens, LocalizedString, MenuDesc, MenuItem, Selector, Vec2, Widget, WidgetExt, WidgetId};
+
+use carnyx::carnyx::CarnyxModel;
+
+use crate::druid_editor::EditorState;
+use crate::param::{ParamHandle, ParamLens};
+
+/// Sent by a dropdown's menu to the dropdown, with the chosen step.
+pub const DROPDOWN_SELECT: Selector<usize> = Selector::new("carnyx-druid.dropdown-select");
+
+fn step_menu<Model: CarnyxModel>(names: Vec<String>, dropdown: WidgetId) -> MenuDesc<EditorState<Model>> where Model::Snap: Data {
+    names.into_iter().enumerate().fold(MenuDesc::empty(), |menu, (step, name)| {
+        menu.append(MenuItem::new(LocalizedString::new("carnyx-dropdown-step").with_placeholder(name), DROPDOWN_SELECT.with(step).to(dropdown)))
+    })
+}
+
+// writes the chosen step back through the parameter's lens
+struct SelectStep {
+    lens: ParamLens,
+    steps: usize,
+}
+
+impl<Model: CarnyxModel, W: Widget<EditorState<Model>>> Controller<EditorState<Model>, W> for SelectStep where Model::Snap: Data {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut EditorState<Model>, env: &Env) {
+        match event {
+            Event::Command(command) if command.is(DROPDOWN_SELECT) => {
+                let step = command.get(DROPDOWN_SELECT).copied().unwrap_or(0);
+                let value = step as f64 / self.steps.saturating_sub(1).max(1) as f64;
+                self.lens.with_mut(data, |normalized| *normalized = value);
+                ctx.set_handled();
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
+
+/// A labelled button showing a stepped parameter's value, which opens a menu of its steps.
+pub fn dropdown_for_param<Model: CarnyxModel>(handle: &ParamHandle<Model>) -> impl Widget<EditorState<Model>> where Model::Snap: Data {
+    let id = WidgetId::next();
+    let shown = handle.clone();
+    let listed = handle.clone();
+    let button = Button::dynamic(move |_: &EditorState<Model>, _| format!("{} \u{25be}", shown.display()))
+        .on_click(move |ctx, _, _| {
+            let menu = step_menu::<Model>(listed.step_names(), id);
+            let below = ctx.window_origin() + Vec2::new(0., ctx.size().height);
+            ctx.show_context_menu(ContextMenu::new(menu, below));
+        })
+        .controller(SelectStep { lens: handle.lens(), steps: handle.param().steps() })
+        .with_id(id);
+    handle.with_tooltip(handle.with_focus(Flex::column()
+        .with_child(handle.title())
+        .with_child(handle.guard(button))))
+}
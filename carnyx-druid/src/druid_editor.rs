@@ -1,23 +1,71 @@
-use std::sync::Arc;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
 
-use druid::{AppLauncher, Data, EmbeddedApp, Env, Event, EventCtx, Lens, Selector, Widget, WidgetExt, WindowDesc, Target, ExtEventSink, Size};
-use druid::lens::Unit;
-use druid::widget::{Controller, Flex, Label};
+use druid::{commands, AppLauncher, BoxConstraints, Color, ContextMenu, Data, EmbeddedApp, Env, Event, EventCtx, FileDialogOptions, FileSpec, Key, LayoutCtx, Lens, LifeCycle, LifeCycleCtx, PaintCtx, Point, Selector, TimerToken, UpdateCtx, Widget, WidgetExt, WidgetId, WidgetPod, WindowDesc, Target, ExtEventSink, Size, Vec2};
+use druid::widget::{Button, Checkbox, Controller, Either, EnvScope, Flex, Label, SizedBox};
 
 use raw_window_handle::RawWindowHandle;
-use crate::HostResizeDragArea;
-use carnyx::carnyx::{CarnyxModel, CarnyxModelListener, CarnyxHost, CarnyxEditor, SettableListener};
+use crate::host_resize::{size_preset_menu, HostResizeDragArea, MeasureContent, ResizeEdges, ResizePolicy, ScaleToFit, SizePreset, DEFAULT_EDGE_HIT_ZONE, SCALE_PRESETS};
+use crate::keyboard::NOTE_EVENT;
+use crate::image_panel::{Faceplate, ImagePanel};
+use crate::param::ParamValues;
+use crate::tag::instance_header;
+use crate::readout::PARAM_FOCUS;
+use crate::theme::CarnyxTheme;
+use crate::frames::{FrameRate, FrameScheduler, Presented, FRAME};
+use crate::ui_state::UiValues;
+use carnyx::automation::DisplayLimiter;
+use carnyx::carnyx::{guard, ChangeEvent, ChangeOrigin, CarnyxModel, CarnyxModelListener, CarnyxHost, CarnyxEditor, ListenerId, ParamFilter, SettableListener};
 use std::marker::PhantomData;
-use carnyx::CarnyxWindowResizer;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use carnyx::{CarnyxDescriptor, CarnyxParam, CarnyxWindowResizer, Diagnostics, LockSet, MacroParams, NoteQueue, NoteSender, ParamList, RefreshGate};
+use carnyx::fxp::{FxFile, FxProgram};
+use carnyx::audition::AuditionSettings;
+use carnyx::preset::{self, AbCompare, AbSlot};
+use carnyx::random::Rng;
 
 pub struct DruidEditor<Model: CarnyxModel> {
-    make_editor: Box<dyn Fn() -> Box<dyn Widget<EditorState<Model>>>>,
+    make_editor: Rc<dyn Fn() -> Box<dyn Widget<EditorState<Model>>>>,
     host: Arc<dyn CarnyxHost>,
     listener: SettableListener<Model>,
+    // registered weakly with `listener`, so kept alive here while the editor is open
+    ext_listener: Option<(ListenerId, Arc<dyn CarnyxModelListener<Model>>)>,
+    // set while a MODEL_CHANGED is on its way to the editor
+    refresh_pending: Arc<RefreshGate>,
     model: Arc<Model>,
+    note_queue: Option<NoteQueue>,
+    audition: Option<Arc<AuditionSettings>>,
+    compare: Arc<Mutex<AbCompare<Model::Snap>>>,
+    params: Option<ParamList<Model>>,
+    theme: CarnyxTheme,
+    faceplate: Option<Faceplate>,
+    // identifies exported preset files
+    preset_files: Option<CarnyxDescriptor>,
+    resize_policy: ResizePolicy,
+    // how close to the window's edges a drag resizes it
+    edge_hit_zone: f64,
+    size_presets: Vec<SizePreset>,
+    snap_to_size_presets: bool,
+    // the size the editor was built for, if it said
+    size: Option<Size>,
+    // what the content asked for when first laid out
+    measured: Arc<Mutex<Option<Size>>>,
+    frame_rate: FrameRate,
+    // sends frames and the processor's telemetry while open
+    frames: Option<FrameScheduler>,
+    // set whenever the editor paints, so frames slow down while it's hidden
+    painted: Arc<AtomicBool>,
     app: Option<EmbeddedApp>,
 }
 
+// until the content has been measured
+const DEFAULT_SIZE: Size = Size::new(500., 500.);
+
 impl<Model: CarnyxModel> DruidEditor<Model> where Model::Snap : Data{
     pub fn new<W: Widget<EditorState<Model>> + 'static>(
         host: Arc<dyn CarnyxHost>,
@@ -25,56 +73,408 @@ impl<Model: CarnyxModel> DruidEditor<Model> where Model::Snap : Data{
         model: Arc<Model>,
         f: impl Fn() -> W + 'static,
     ) -> Self {
+        let compare = Arc::new(Mutex::new(AbCompare::new(model.snap())));
         DruidEditor {
-            make_editor: Box::new(move || f().boxed()),
+            make_editor: Rc::new(move || f().boxed()),
             host,
             listener,
+            ext_listener: None,
+            refresh_pending: Arc::new(RefreshGate::new()),
             model,
+            note_queue: None,
+            audition: None,
+            compare,
+            params: None,
+            theme: CarnyxTheme::default(),
+            faceplate: None,
+            preset_files: None,
+            resize_policy: ResizePolicy::Reflow,
+            edge_hit_zone: DEFAULT_EDGE_HIT_ZONE,
+            size_presets: SCALE_PRESETS.to_vec(),
+            snap_to_size_presets: false,
+            size: None,
+            measured: Arc::new(Mutex::new(None)),
+            frame_rate: FrameRate::default(),
+            frames: None,
+            painted: Arc::new(AtomicBool::new(false)),
             app: None,
         }
     }
+
+    /// Forward notes played in the editor (e.g. on a [`Keyboard`](crate::Keyboard)) to the processor.
+    pub fn with_note_queue(mut self, note_queue: NoteQueue) -> Self {
+        self.note_queue = Some(note_queue);
+        self
+    }
+
+    /// How often meters, scopes and modulation rings redraw. 60 fps unless set.
+    pub fn with_frame_rate(mut self, frame_rate: FrameRate) -> Self {
+        self.frame_rate = frame_rate;
+        self
+    }
+
+    /// Show an "Audition" toggle which switches the processor's test signal on and off.
+    pub fn with_audition(mut self, audition: Arc<AuditionSettings>) -> Self {
+        self.audition = Some(audition);
+        self
+    }
+
+    /// Builder-style method to set the editor's colors, sizes and knob style.
+    pub fn with_theme(mut self, theme: CarnyxTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Builder-style method to draw an image behind the editor's controls (not the toolbar).
+    pub fn with_faceplate(mut self, faceplate: Faceplate) -> Self {
+        self.faceplate = Some(faceplate);
+        self
+    }
+
+    /// Builder-style method to open the editor at this size, rather than the size its
+    /// content asks for.
+    pub fn with_size(mut self, width: f64, height: f64) -> Self {
+        self.size = Some(Size::new(width, height));
+        self
+    }
+
+    /// Builder-style method to choose what dragging the resize corner does to the content.
+    pub fn with_resize_policy(mut self, resize_policy: ResizePolicy) -> Self {
+        self.resize_policy = resize_policy;
+        self
+    }
+
+    /// Builder-style method to set how close to the window's right and bottom edges a drag
+    /// resizes it. Zero leaves only the resize corner.
+    pub fn with_edge_hit_zone(mut self, edge_hit_zone: f64) -> Self {
+        self.edge_hit_zone = edge_hit_zone;
+        self
+    }
+
+    /// Builder-style method to set the sizes offered by the menu next to the resize corner,
+    /// as scales of the initial size. Empty hides the menu.
+    pub fn with_size_presets(mut self, size_presets: Vec<SizePreset>) -> Self {
+        self.size_presets = size_presets;
+        self
+    }
+
+    /// Builder-style method to snap resizes to the nearest size preset, for hosts which
+    /// can't resize freely.
+    pub fn with_snap_to_size_presets(mut self, snap_to_size_presets: bool) -> Self {
+        self.snap_to_size_presets = snap_to_size_presets;
+        self
+    }
+
+    /// Sends commands to the editor while it is open, e.g. to drive it from tests.
+    pub fn event_sink(&self) -> Option<ExtEventSink> {
+        self.app.as_ref().map(|app| app.sink.clone())
+    }
+
+    /// Give the editor the processor's parameters, enabling the randomize and mutate buttons.
+    pub fn with_parameters(mut self, params: Vec<Box<dyn CarnyxParam<Model>>>) -> Self {
+        self.params = Some(Arc::new(params));
+        self
+    }
+
+    /// Builder-style method to show buttons importing and exporting VST `.fxp`/`.fxb`
+    /// preset files; needs `with_parameters`. Exports are marked as the plugin `descriptor` describes.
+    pub fn with_preset_files(mut self, descriptor: CarnyxDescriptor) -> Self {
+        self.preset_files = Some(descriptor);
+        self
+    }
 }
 
-fn wrap_editor_widget<Model: CarnyxModel>(
-    host: Arc<dyn CarnyxHost>,
-    window_resizer: Box<dyn CarnyxWindowResizer>,
-    params: Arc<Model>,
-    child: impl Widget<EditorState<Model>> + 'static) -> impl Widget<EditorState<Model>> where Model::Snap : Data {
+const FXP: FileSpec = FileSpec::new("VST preset", &["fxp"]);
+const FXB: FileSpec = FileSpec::new("VST bank", &["fxb"]);
 
-    Flex::column()
-        .with_flex_child(
-            child,
-            1.0
-        )
+fn preset_file_buttons<Model: CarnyxModel>() -> impl Widget<EditorState<Model>> where Model::Snap : Data {
+    let import = FileDialogOptions::new().allowed_types(vec![FXP, FXB]).title("Import preset");
+    let export = FileDialogOptions::new().allowed_types(vec![FXP]).default_type(FXP).title("Export preset");
+    Flex::row()
+        .with_child(Button::new("Import\u{2026}").on_click(move |ctx, _, _| {
+            ctx.submit_command(commands::SHOW_OPEN_PANEL.with(import.clone()))
+        }))
+        .with_child(Button::new("Export\u{2026}").on_click(move |ctx, _, _| {
+            ctx.submit_command(commands::SHOW_SAVE_PANEL.with(export.clone()))
+        }))
+}
+
+fn ab_compare_bar<Model: CarnyxModel>() -> impl Widget<EditorState<Model>> where Model::Snap : Data {
+    Flex::row()
         .with_child(
-            Flex::row()
-                .with_flex_spacer(1.0)
-                .with_child(HostResizeDragArea::new(window_resizer).lens(Unit)),
-        ).controller(EditorController::new(host, params))
+            Button::dynamic(|data: &EditorState<Model>, _| format!("Compare: {}", data.ab_slot.name()))
+                .on_click(|ctx, _, _| ctx.submit_command(AB_TOGGLE)),
+        )
+        .with_child(Button::new("Copy A\u{2192}B").on_click(|ctx, _, _| ctx.submit_command(AB_COPY_A_TO_B)))
 }
 
+// opens the preset menu below itself
+fn size_preset_button<Model: CarnyxModel>(presets: Vec<SizePreset>, initial: Size, drag_area: WidgetId) -> impl Widget<EditorState<Model>> where Model::Snap : Data {
+    Button::new("Size \u{25be}").on_click(move |ctx, _, _| {
+        let menu = size_preset_menu(&presets, initial, drag_area);
+        let below = ctx.window_origin() + Vec2::new(0., ctx.size().height);
+        ctx.show_context_menu(ContextMenu::new(menu, below));
+    })
+}
+
+impl<Model: CarnyxModel> DruidEditor<Model> where Model::Snap : Data {
+    // e.g. "LadderFilter 2 - Bass - REAPER", from whatever the model and host know
+    fn title(&self) -> String {
+        let instance = self.model.instance().map(|instance| instance.name());
+        let context = Some(self.host.instance_context().describe()).filter(|context| !context.is_empty());
+        let parts: Vec<String> = instance.into_iter().chain(context).collect();
+        parts.join(" - ")
+    }
+
+    fn wrap_editor_widget(
+        &self,
+        window_resizer: Box<dyn CarnyxWindowResizer>,
+        child: impl Widget<EditorState<Model>> + 'static) -> impl Widget<EditorState<Model>> {
+
+        let mut toolbar = Flex::row().with_child(ab_compare_bar());
+        if self.params.is_some() {
+            toolbar.add_child(Button::new("Randomize").on_click(|ctx, _, _| ctx.submit_command(RANDOMIZE)));
+            toolbar.add_child(Button::new("Mutate").on_click(|ctx, _, _| ctx.submit_command(MUTATE)));
+            if self.preset_files.is_some() {
+                toolbar.add_child(preset_file_buttons());
+            }
+        }
+        if self.audition.is_some() {
+            toolbar.add_child(Checkbox::new("Audition").lens(EditorState::audition));
+        }
+        if self.host.diagnostics().is_some() {
+            toolbar.add_child(Checkbox::new("Log").lens(EditorState::show_diagnostics));
+        }
+        toolbar.add_flex_spacer(1.0);
+        toolbar.add_child(Label::new(self.title()));
+        let (w, h) = self.initial_size();
+        let initial = Size::new(w as f64, h as f64);
+        let drag_area = WidgetId::next();
+        if self.resize_policy != ResizePolicy::Fixed {
+            if !self.size_presets.is_empty() {
+                toolbar.add_child(size_preset_button(self.size_presets.clone(), initial, drag_area));
+            }
+            toolbar.add_child(HostResizeDragArea::new(window_resizer)
+                .with_policy(self.resize_policy, initial)
+                .with_size_presets(self.size_presets.clone())
+                .with_snap_to_presets(self.snap_to_size_presets)
+                .with_id(drag_area));
+        }
+
+        let child: Box<dyn Widget<EditorState<Model>>> = match self.faceplate.map(|f| f.to_widget()) {
+            Some(Ok(image)) => Box::new(ImagePanel::new(image, child)),
+            Some(Err(_)) => {
+                // a broken faceplate shouldn't stop the editor opening
+                if let Some(diagnostics) = self.host.diagnostics() {
+                    diagnostics.warn("editor", "faceplate could not be loaded", None);
+                }
+                Box::new(child)
+            }
+            None => Box::new(child),
+        };
+
+        let mut column = Flex::column();
+        if self.model.instance_tag().is_some() {
+            column.add_child(instance_header());
+        }
+        let column = column
+            .with_child(Either::new(
+                |data: &EditorState<Model>, _| data.failure.is_some(),
+                Label::dynamic(|data: &EditorState<Model>, _| data.failure.as_ref().map(|f| f.to_string()).unwrap_or_default())
+                    .with_text_color(Color::rgb8(0xe8, 0x2c, 0x2c))
+                    .padding(4.),
+                SizedBox::empty()))
+            .with_flex_child(
+                child,
+                1.0
+            )
+            .with_child(Either::new(
+                |data: &EditorState<Model>, _| data.show_diagnostics,
+                Label::dynamic(|data: &EditorState<Model>, _| data.diagnostics.to_string()),
+                SizedBox::empty()))
+            .with_child(toolbar)
+            .background(self.theme.background.clone());
+        let mut column = MeasureContent::new(column, Arc::clone(&self.measured), initial);
+        if self.size.is_none() && self.resize_policy != ResizePolicy::Fixed {
+            column = column.with_resize(drag_area);
+        }
+        let column: Box<dyn Widget<EditorState<Model>>> = match self.resize_policy {
+            ResizePolicy::Scale => Box::new(ScaleToFit::new(column, initial)),
+            _ => Box::new(column),
+        };
+        let column: Box<dyn Widget<EditorState<Model>>> = if self.resize_policy != ResizePolicy::Fixed && self.edge_hit_zone > 0. {
+            Box::new(ResizeEdges::new(column, drag_area).with_hit_zone(self.edge_hit_zone))
+        } else {
+            column
+        };
+        let column = Presented::new(column, Arc::clone(&self.painted));
+        let theme = self.theme.clone();
+        EnvScope::new(
+            move |env, data: &EditorState<Model>| {
+                theme.apply(env);
+                env.set(HOST_PLAYING, data.host_playing);
+            },
+            column)
+            .controller(EditorController::new(self.host.clone(), Arc::clone(&self.model), Arc::clone(&self.compare))
+                .with_note_queue(self.note_queue.clone())
+                .with_audition(self.audition.clone())
+                .with_parameters(self.params.clone())
+                .with_preset_files(self.preset_files)
+                .with_listener(self.listener.clone())
+                .with_refresh_pending(Arc::clone(&self.refresh_pending)))
+    }
+}
+
+/// The editor's content, made again from scratch on `REBUILD_UI`.
+struct Rebuild<T> {
+    make: Rc<dyn Fn() -> Box<dyn Widget<T>>>,
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+}
+
+impl<T: Data> Rebuild<T> {
+    fn new(make: Rc<dyn Fn() -> Box<dyn Widget<T>>>) -> Self {
+        let child = WidgetPod::new(make());
+        Rebuild { make, child }
+    }
+}
+
+impl<T: Data> Widget<T> for Rebuild<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::Command(cmd) = event {
+            if cmd.is(REBUILD_UI) {
+                self.child = WidgetPod::new((self.make)());
+                ctx.children_changed();
+                return;
+            }
+        }
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+    }
+
+    fn post_render(&mut self) {}
+}
+
+/// Catches panics in widget code, which runs in the host's window procedure where unwinding
+/// would take the host down. A panic fails the instance, see [`carnyx::guard`], and the
+/// child is left out from then on.
+struct Guarded<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    host: Arc<dyn CarnyxHost>,
+    broken: bool,
+}
+
+impl<T: Data> Guarded<T> {
+    fn new(child: impl Widget<T> + 'static, host: Arc<dyn CarnyxHost>) -> Self {
+        Guarded { child: WidgetPod::new(Box::new(child)), host, broken: false }
+    }
+
+    fn guard<R>(&mut self, what: &'static str, fallback: R, f: impl FnOnce(&mut WidgetPod<T, Box<dyn Widget<T>>>) -> R) -> R {
+        if self.broken {
+            return fallback;
+        }
+        let child = &mut self.child;
+        match guard(&*self.host, what, || f(child)) {
+            Some(result) => result,
+            None => {
+                self.broken = true;
+                fallback
+            }
+        }
+    }
+}
+
+impl<T: Data> Widget<T> for Guarded<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.guard("editor event", (), |child| child.event(ctx, event, data, env));
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.guard("editor lifecycle", (), |child| child.lifecycle(ctx, event, data, env));
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.guard("editor update", (), |child| child.update(ctx, data, env));
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.guard("editor layout", bc.min(), |child| {
+            let size = child.layout(ctx, bc, data, env);
+            child.set_origin(ctx, data, env, Point::ORIGIN);
+            size
+        })
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.guard("editor paint", (), |child| child.paint(ctx, data, env));
+    }
+
+    fn post_render(&mut self) {}
+}
+
+/// Forwards model changes to the editor. Dense automation would otherwise queue a
+/// command per `set_parameter`; instead at most one is in flight at a time, and the
+/// editor reads the latest state of the model when it arrives.
 struct ExtEventListener<Model: CarnyxModel>{
     sink: ExtEventSink,
+    pending: Arc<RefreshGate>,
     phantom_m: PhantomData<fn()->Model>
 }
 
 impl<Model: CarnyxModel> ExtEventListener<Model> {
-    pub fn new(sink: ExtEventSink) -> Self {
-        ExtEventListener { sink, phantom_m: PhantomData }
+    pub fn new(sink: ExtEventSink, pending: Arc<RefreshGate>) -> Self {
+        ExtEventListener { sink, pending, phantom_m: PhantomData }
+    }
+
+    /// Have the editor read the whole model, unless it is already about to.
+    fn refresh(&self, origin: ChangeOrigin) {
+        if !self.pending.claim() {
+            return;
+        }
+        if self.sink.submit_command(MODEL_CHANGED, ChangeEvent::model(origin), Target::Global).is_err() {
+            self.pending.release();
+        }
     }
 }
 
 impl <Model: CarnyxModel> CarnyxModelListener<Model> for ExtEventListener<Model>{
-    fn notify_change(&self, _model: &Model) {
-        self.sink.submit_command(MODEL_CHANGED, (), Target::Global)
-            .expect("Submit command to sink");
+    fn notify_change(&self, _model: &Model, event: ChangeEvent) {
+        // the editor's own edits are already on screen
+        if event.origin == ChangeOrigin::Editor {
+            return;
+        }
+        // never coalesced, the controls for the old parameters may be on screen
+        if event.layout {
+            let _ = self.sink.submit_command(REBUILD_UI, (), Target::Global);
+        }
+        // coalesced changes may involve other parameters, so report the whole model
+        self.refresh(event.origin);
     }
 }
 
 impl<Model: CarnyxModel> CarnyxEditor for DruidEditor<Model> where Model::Snap : Data {
 
+    // hosts ask again after opening, by when the content has usually been measured
     fn initial_size(&self) -> (usize, usize) {
-        (500, 500)
+        let measured = self.measured.lock().ok().and_then(|measured| *measured);
+        let size = self.size.or(measured).unwrap_or(DEFAULT_SIZE);
+        (size.width.round() as usize, size.height.round() as usize)
     }
 
     fn initial_position(&self) -> (isize, isize) {
@@ -82,72 +482,392 @@ impl<Model: CarnyxModel> CarnyxEditor for DruidEditor<Model> where Model::Snap :
     }
 
     fn open(&mut self, handle: Option<RawWindowHandle>, window_resizer: Box<dyn CarnyxWindowResizer>) -> bool {
+        // hosts don't always close before reopening
+        self.close();
         if let Some(raw) = handle {
-            let make_editor = &self.make_editor;
-            let snap_edit = make_editor();
-            let wrapped = wrap_editor_widget(self.host.clone(), window_resizer, Arc::clone(&self.model), snap_edit);
+            // the content apart, so the failure banner outside it still shows
+            let snap_edit = Guarded::new(Rebuild::new(Rc::clone(&self.make_editor)), Arc::clone(&self.host));
+            let wrapped = Guarded::new(self.wrap_editor_widget(window_resizer, snap_edit), Arc::clone(&self.host));
             let (w, h) = self.initial_size();
             let window_desc = WindowDesc::new(wrapped)
                 .window_size(Size::new(w as f64, h as f64))
                 .show_titlebar(false)
                 .resizable(false);
             let state = EditorState {
-                snap: self.model.snap(),
+                snap: Arc::new(self.model.snap()),
+                audition: self.audition.as_ref().map(|a| a.is_enabled()).unwrap_or(false),
+                probe: self.model.probes().and_then(|probes| probes.soloed()),
+                macro_learn: None,
+                nickname: Arc::new(self.model.instance_tag().map(|tag| tag.nickname()).unwrap_or_default()),
+                tag_color: self.model.instance_tag().and_then(|tag| tag.color()),
+                ab_slot: self.compare.lock().map(|c| c.active()).unwrap_or(AbSlot::A),
+                show_diagnostics: false,
+                diagnostics: Arc::new(String::new()),
+                failure: self.host.failure().map(Arc::new),
+                host_playing: self.host.is_playing(),
+                params: self.params.as_ref().map(|p| ParamValues::read(p, &self.model)).unwrap_or_default(),
+                ui: self.model.ui_state().map(|ui| UiValues::new(ui.values())).unwrap_or_default(),
+                locks: self.model.locks().map(|locks| locks.get()).unwrap_or_default(),
             };
 
-            self.app = AppLauncher::with_window(window_desc)
-                .launch_embedded(state, raw).ok();
+            self.app = match AppLauncher::with_window(window_desc).launch_embedded(state, raw) {
+                Ok(app) => Some(app),
+                Err(err) => {
+                    // the host shows an empty window, so leave a trace of why
+                    if let Some(diagnostics) = self.host.diagnostics() {
+                        diagnostics.error("editor", "editor window could not be created", err);
+                    }
+                    None
+                }
+            };
 
             if let Some(app) = &self.app {
                 let sink = app.sink.clone();
-                self.listener.set_listener(Box::new(ExtEventListener::new(sink)));
+                let ext = Arc::new(ExtEventListener::new(sink, Arc::clone(&self.refresh_pending)));
+                let ext_listener: Arc<dyn CarnyxModelListener<Model>> = ext.clone();
+                let id = self.listener.add_listener(&ext_listener, ParamFilter::All);
+                // automation between the snap above and registering would otherwise stay
+                // off screen until the next change
+                ext.refresh(ChangeOrigin::Host);
+                self.ext_listener = Some((id, ext_listener));
+                self.frames = FrameScheduler::start(Arc::clone(&self.model), app.sink.clone(), self.frame_rate, Arc::clone(&self.painted));
                 true
             } else {
                 false
             }
         }else{
+            if let Some(diagnostics) = self.host.diagnostics() {
+                diagnostics.error("editor", "host gave no parent window", "editOpen without a window handle");
+            }
             false
         }
     }
 
+    fn close(&mut self) {
+        // stop forwarding changes to the old app's sink before dropping it
+        if let Some((id, _)) = self.ext_listener.take() {
+            self.listener.remove_listener(id);
+        }
+        self.frames = None;
+        self.app = None;
+        self.refresh_pending.release();
+    }
+
     fn is_open(&self) -> bool {
         self.app.is_some()
     }
 }
 
+// the snap and nickname are shared, so the controller can keep the old ones to compare
+// against without copying them on every event; lens into them with `in_arc`
 #[derive(Lens)]
 pub struct EditorState<Model: CarnyxModel> {
-    snap: Model::Snap,
+    snap: Arc<Model::Snap>,
+    audition: bool,
+    // the soloed probe, see Probes
+    pub(crate) probe: Option<usize>,
+    // the macro waiting for a control to be dragged, to assign it
+    pub(crate) macro_learn: Option<usize>,
+    // the instance's tag, see InstanceTag
+    pub(crate) nickname: Arc<String>,
+    pub(crate) tag_color: Option<usize>,
+    ab_slot: AbSlot,
+    show_diagnostics: bool,
+    // the most recent diagnostic events, one per line
+    diagnostics: Arc<String>,
+    // why the processor stopped, see CarnyxHost::failure
+    failure: Option<Arc<String>>,
+    host_playing: bool,
+    // normalized parameter values, for controls built from parameters
+    pub(crate) params: ParamValues,
+    // page, scroll and similar, kept on the model between openings
+    pub(crate) ui: UiValues,
+    // parameters which ignore the host
+    pub(crate) locks: LockSet,
 }
 
 impl<Model: CarnyxModel> Clone for EditorState<Model> where Model::Snap : Clone {
     fn clone(&self) -> Self {
         EditorState {
-            snap: self.snap.clone(),
+            snap: Arc::clone(&self.snap),
+            audition: self.audition,
+            probe: self.probe,
+            macro_learn: self.macro_learn,
+            nickname: Arc::clone(&self.nickname),
+            tag_color: self.tag_color,
+            ab_slot: self.ab_slot,
+            show_diagnostics: self.show_diagnostics,
+            diagnostics: Arc::clone(&self.diagnostics),
+            failure: self.failure.clone(),
+            host_playing: self.host_playing,
+            params: self.params.clone(),
+            ui: self.ui.clone(),
+            locks: self.locks,
         }
     }
 
     fn clone_from(&mut self, source: &Self) {
-        self.snap = source.snap.clone();
+        self.snap = Arc::clone(&source.snap);
+        self.audition = source.audition;
+        self.probe = source.probe;
+        self.macro_learn = source.macro_learn;
+        self.nickname = Arc::clone(&source.nickname);
+        self.tag_color = source.tag_color;
+        self.ab_slot = source.ab_slot;
+        self.show_diagnostics = source.show_diagnostics;
+        self.diagnostics = Arc::clone(&source.diagnostics);
+        self.failure = source.failure.clone();
+        self.host_playing = source.host_playing;
+        self.params = source.params.clone();
+        self.ui = source.ui.clone();
+        self.locks = source.locks;
     }
 }
 
 impl<Model: CarnyxModel> Data for EditorState<Model> where Model::Snap : Data {
     fn same(&self, other: &Self) -> bool {
-        self.snap.same(&other.snap)
+        (Arc::ptr_eq(&self.snap, &other.snap) || self.snap.as_ref().same(&other.snap)) && self.audition == other.audition && self.probe == other.probe && self.macro_learn == other.macro_learn
+            && self.nickname == other.nickname && self.tag_color == other.tag_color && self.ab_slot == other.ab_slot
+            && self.show_diagnostics == other.show_diagnostics && self.diagnostics.same(&other.diagnostics)
+            && self.failure == other.failure && self.host_playing == other.host_playing && self.params.same(&other.params)
+            && self.ui.same(&other.ui) && self.locks == other.locks
     }
 }
 
-pub const MODEL_CHANGED: Selector = Selector::new("carnyx.model-changed");
+/// Whether the host is playing, for widgets which treat automation specially.
+pub const HOST_PLAYING: Key<bool> = Key::new("carnyx-druid.host-playing");
+
+/// Sent to the editor when the processor's parameters change, to build its controls again.
+pub const REBUILD_UI: Selector = Selector::new("carnyx.rebuild-ui");
+/// Sent to the editor when something other than the editor changes the model.
+pub const MODEL_CHANGED: Selector<ChangeEvent> = Selector::new("carnyx.model-changed");
+/// Switch between the A and B compare slots.
+pub const AB_TOGGLE: Selector = Selector::new("carnyx.ab-toggle");
+/// Copy the A compare slot over the B slot.
+pub const AB_COPY_A_TO_B: Selector = Selector::new("carnyx.ab-copy-a-to-b");
+/// Set all randomizable parameters to random values.
+pub const RANDOMIZE: Selector = Selector::new("carnyx.randomize");
+/// Nudge all randomizable parameters by a small random amount.
+pub const MUTATE: Selector = Selector::new("carnyx.mutate");
+
+const MUTATE_AMOUNT: f32 = 0.1;
+const DIAGNOSTIC_LINES: usize = 12;
+
+// about a block at common sizes
+const NOTE_RETRY_INTERVAL: Duration = Duration::from_millis(10);
 
 pub struct EditorController<Model: CarnyxModel>{
     host: Arc<dyn CarnyxHost>,
-    params: Arc<Model>
+    params: Arc<Model>,
+    compare: Arc<Mutex<AbCompare<Model::Snap>>>,
+    notes: Option<NoteSender>,
+    // retries note offs which found the queue full
+    note_retry: TimerToken,
+    audition: Option<Arc<AuditionSettings>>,
+    param_list: Option<ParamList<Model>>,
+    listener: Option<SettableListener<Model>>,
+    refresh_pending: Option<Arc<RefreshGate>>,
+    diagnostics: Option<Arc<Diagnostics>>,
+    recent_diagnostics: VecDeque<String>,
+    rng: Rng,
+    preset_files: Option<CarnyxDescriptor>,
+    // parameters being dragged, each its own gesture to the host; more than one at a time
+    // with several fingers on a touch screen
+    gestures: Vec<usize>,
+    // when the host's display was last refreshed; edited through &self
+    display: Cell<DisplayLimiter>,
 }
 
 impl <Model: CarnyxModel> EditorController<Model> {
-    pub fn new(host: Arc<dyn CarnyxHost>, params: Arc<Model>) -> Self {
-        EditorController { host, params }
+    pub fn new(host: Arc<dyn CarnyxHost>, params: Arc<Model>, compare: Arc<Mutex<AbCompare<Model::Snap>>>) -> Self {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+        let diagnostics = host.diagnostics();
+        let display = Cell::new(DisplayLimiter::new(host.host_info().display_update_interval));
+        EditorController {
+            host, params, compare, notes: None, note_retry: TimerToken::INVALID, audition: None, param_list: None, listener: None, refresh_pending: None,
+            diagnostics, recent_diagnostics: VecDeque::with_capacity(DIAGNOSTIC_LINES), rng: Rng::new(seed),
+            preset_files: None,
+            gestures: Vec::new(),
+            display,
+        }
+    }
+
+    /// Tell other listeners (e.g. other editors) about edits made here.
+    pub fn with_listener(mut self, listener: SettableListener<Model>) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// The gate an `ExtEventListener` claims when it sends `MODEL_CHANGED`, released here
+    /// once the editor has caught up.
+    pub fn with_refresh_pending(mut self, refresh_pending: Arc<RefreshGate>) -> Self {
+        self.refresh_pending = Some(refresh_pending);
+        self
+    }
+
+    /// Pull in new diagnostic events, keeping the last few lines for the log overlay.
+    fn drain_diagnostics(&mut self, data: &mut EditorState<Model>) {
+        if let Some(diagnostics) = &self.diagnostics {
+            let recent = &mut self.recent_diagnostics;
+            let mut changed = false;
+            diagnostics.drain(|diagnostic| {
+                if recent.len() == DIAGNOSTIC_LINES {
+                    recent.pop_front();
+                }
+                recent.push_back(diagnostic.to_string());
+                changed = true;
+            });
+            if changed {
+                data.diagnostics = Arc::new(recent.iter().cloned().collect::<Vec<_>>().join("\n"));
+            }
+        }
+    }
+
+    // after the model changes through its snapshot
+    fn read_params(&self, data: &mut EditorState<Model>) {
+        if let Some(param_list) = &self.param_list {
+            data.params = ParamValues::read(param_list, &self.params);
+        }
+    }
+
+    // after controls bound to parameters change them
+    // point the learning macro's next free assignment at the parameter being dragged, through
+    // the parameter values so it is saved and notified like any other edit
+    fn learn_macro(&self, control: usize, index: usize, data: &mut EditorState<Model>) {
+        if let (Some(param_list), Some(macros)) = (&self.param_list, self.params.macros()) {
+            let id = MacroParams::target_id(control, macros.control(control).learn_slot());
+            if let Some(target) = param_list.iter().position(|param| param.id() == id) {
+                // the processor's own parameters, which come first, are the only targets
+                let targets = param_list[target].steps().saturating_sub(1);
+                if index < targets {
+                    data.params.set(target, (index + 1) as f32 / targets as f32);
+                    data.macro_learn = None;
+                }
+            }
+        }
+    }
+
+    // begin or end the gesture on one parameter, leaving any others alone
+    fn track_gesture(&mut self, index: usize, dragging: bool) {
+        let position = self.gestures.iter().position(|gesture| *gesture == index);
+        match (position, dragging) {
+            (None, true) => {
+                self.gestures.push(index);
+                self.host.begin_edit(index);
+            }
+            (Some(position), false) => {
+                self.gestures.swap_remove(position);
+                // so the host shows where the drag ended before the gesture closes
+                if self.gestures.is_empty() {
+                    let mut display = self.display.get();
+                    if display.gesture_ended(Instant::now()) {
+                        self.host.update_host_display();
+                    }
+                    self.display.set(display);
+                }
+                self.host.end_edit(index);
+            }
+            _ => (),
+        }
+    }
+
+    // drags can edit on every mouse move, more often than some hosts can redraw
+    fn update_host_display(&self) {
+        let mut display = self.display.get();
+        if display.edited(Instant::now(), !self.gestures.is_empty()) {
+            self.host.update_host_display();
+        }
+        self.display.set(display);
+    }
+
+    fn params_edited(&self, old: &ParamValues, data: &mut EditorState<Model>) {
+        if let Some(param_list) = &self.param_list {
+            let (changed, read_only): (Vec<usize>, Vec<usize>) = data.params.changed(old)
+                .partition(|&index| param_list.get(index).map(|p| !p.is_read_only()).unwrap_or(true));
+            for &index in &changed {
+                if let (Some(param), Some(value)) = (param_list.get(index), data.params.get(index)) {
+                    param.set_value(&self.params, value);
+                }
+            }
+            // only the processor sets read only parameters, so put back what it set
+            if !read_only.is_empty() {
+                self.read_params(data);
+            }
+            data.snap = Arc::new(self.params.snap());
+            self.update_host_display();
+            if let Some(listener) = &self.listener {
+                for index in changed {
+                    listener.notify_change(&self.params, ChangeEvent::param(index, ChangeOrigin::Editor));
+                }
+            }
+        }
+    }
+
+    fn model_edited(&self) {
+        self.update_host_display();
+        if let Some(listener) = &self.listener {
+            listener.notify_change(&self.params, ChangeEvent::model(ChangeOrigin::Editor));
+        }
+    }
+
+    pub fn with_parameters(mut self, param_list: Option<ParamList<Model>>) -> Self {
+        self.param_list = param_list;
+        self
+    }
+
+    pub fn with_preset_files(mut self, descriptor: Option<CarnyxDescriptor>) -> Self {
+        self.preset_files = descriptor;
+        self
+    }
+
+    // load the program in an .fxp, or the selected one in an .fxb
+    fn import_preset(&self, path: &Path, data: &mut EditorState<Model>) -> io::Result<()> {
+        if let Some(param_list) = &self.param_list {
+            let file = FxFile::read(path)?;
+            let program = file.program().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty bank"))?;
+            program.apply(param_list, &self.params);
+            data.snap = Arc::new(self.params.snap());
+            self.read_params(data);
+            self.model_edited();
+        }
+        Ok(())
+    }
+
+    fn export_preset(&self, path: &Path) -> io::Result<()> {
+        if let (Some(param_list), Some(descriptor)) = (&self.param_list, &self.preset_files) {
+            let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            FxProgram::capture(name, descriptor, param_list, &self.params).write(path)?;
+        }
+        Ok(())
+    }
+
+    fn warn(&self, message: &'static str) {
+        if let Some(diagnostics) = &self.diagnostics {
+            diagnostics.warn("editor", message, None);
+        }
+    }
+
+    pub fn with_audition(mut self, audition: Option<Arc<AuditionSettings>>) -> Self {
+        self.audition = audition;
+        self
+    }
+
+    pub fn with_note_queue(mut self, note_queue: Option<NoteQueue>) -> Self {
+        self.notes = note_queue.map(NoteSender::new);
+        self
+    }
+}
+
+// a window closed mid drag would otherwise leave the host recording
+impl<Model: CarnyxModel> Drop for EditorController<Model> {
+    fn drop(&mut self) {
+        for index in self.gestures.drain(..) {
+            self.host.end_edit(index);
+        }
+        // and the processor holding notes from the keyboard
+        if let Some(notes) = &mut self.notes {
+            notes.release_all();
+        }
     }
 }
 
@@ -164,16 +884,174 @@ Controller<EditorState<Model>, W> for EditorController<Model> where Model::Snap
     ) {
         match event {
             Event::Command(cmd) if cmd.is(MODEL_CHANGED) => {
-                data.snap = self.params.snap();
+                // clear first, so a change made while snapping sends another refresh
+                if let Some(pending) = &self.refresh_pending {
+                    pending.release();
+                }
+                // Data diffing means only the controls whose values changed are updated
+                data.snap = Arc::new(self.params.snap());
+                self.read_params(data);
+                data.host_playing = self.host.is_playing();
+                // loading state brings its tag
+                if let Some(tag) = self.params.instance_tag() {
+                    data.nickname = Arc::new(tag.nickname());
+                    data.tag_color = tag.color();
+                }
+            }
+            Event::Command(cmd) if cmd.is(NOTE_EVENT) => {
+                if let (Some(notes), Some(note)) = (&mut self.notes, cmd.get(NOTE_EVENT)) {
+                    // a dropped note on is only a missed note, but a dropped note off would
+                    // leave it sounding, so those are retried until the processor catches up
+                    notes.send(*note);
+                    if notes.is_pending() && self.note_retry == TimerToken::INVALID {
+                        self.note_retry = ctx.request_timer(NOTE_RETRY_INTERVAL);
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Timer(token) if *token == self.note_retry => {
+                self.note_retry = TimerToken::INVALID;
+                if let Some(notes) = &mut self.notes {
+                    if !notes.flush() {
+                        self.note_retry = ctx.request_timer(NOTE_RETRY_INTERVAL);
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(AB_TOGGLE) || cmd.is(AB_COPY_A_TO_B) => {
+                if let Ok(mut compare) = self.compare.lock() {
+                    if cmd.is(AB_TOGGLE) {
+                        compare.toggle(Arc::make_mut(&mut data.snap));
+                    } else {
+                        compare.copy_a_to_b(Arc::make_mut(&mut data.snap));
+                    }
+                    data.ab_slot = compare.active();
+                }
+                self.params.set_snap(&data.snap);
+                self.read_params(data);
+                self.model_edited();
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(commands::OPEN_FILE) => {
+                if let Some(file) = cmd.get(commands::OPEN_FILE) {
+                    if self.import_preset(file.path(), data).is_err() {
+                        self.warn("preset file could not be imported");
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(commands::SAVE_FILE_AS) => {
+                if let Some(file) = cmd.get(commands::SAVE_FILE_AS) {
+                    if self.export_preset(file.path()).is_err() {
+                        self.warn("preset file could not be exported");
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(RANDOMIZE) || cmd.is(MUTATE) => {
+                if let Some(param_list) = &self.param_list {
+                    if cmd.is(RANDOMIZE) {
+                        preset::randomize(param_list, &self.params, &mut self.rng);
+                    } else {
+                        preset::mutate(param_list, &self.params, MUTATE_AMOUNT, &mut self.rng);
+                    }
+                    data.snap = Arc::new(self.params.snap());
+                    self.read_params(data);
+                    self.model_edited();
+                }
+                ctx.set_handled();
             }
             _ => {
-                let old_snap = data.snap.clone();
+                if let (Event::AnimFrame(_), true) = (event, data.show_diagnostics) {
+                    self.drain_diagnostics(data);
+                    ctx.request_anim_frame();
+                }
+                // the processor can't tell the editor itself, so look once a frame
+                if let (Event::Command(cmd), None) = (event, &data.failure) {
+                    if cmd.is(FRAME) {
+                        data.failure = self.host.failure().map(Arc::new);
+                    }
+                }
+                // all cheap: the shared fields are compared by pointer below, as edits
+                // through `in_arc` replace them
+                let old_snap = Arc::clone(&data.snap);
+                let old_params = data.params.clone();
+                let old_audition = data.audition;
+                let old_probe = data.probe;
+                let old_nickname = Arc::clone(&data.nickname);
+                let old_tag_color = data.tag_color;
+                let old_show_diagnostics = data.show_diagnostics;
+                let old_ui = data.ui.clone();
+                let old_locks = data.locks;
+                let focus = match event {
+                    Event::Command(cmd) => cmd.get(PARAM_FOCUS).copied(),
+                    _ => None,
+                };
+                if let Some(focus) = focus {
+                    self.track_gesture(focus.index, focus.dragging);
+                    if let (Some(control), true) = (data.macro_learn, focus.dragging) {
+                        self.learn_macro(control, focus.index, data);
+                    }
+                }
                 child.event(ctx, event, data, env);
-                if !old_snap.same(&data.snap) {
+                if !Arc::ptr_eq(&old_snap, &data.snap) {
                     self.params.set_snap(&data.snap);
-                    self.host.update_host_display();
+                    self.read_params(data);
+                    self.model_edited();
+                } else if !old_params.same(&data.params) {
+                    self.params_edited(&old_params, data);
+                }
+                if old_locks != data.locks {
+                    if let Some(locks) = self.params.locks() {
+                        locks.set(data.locks);
+                    }
+                }
+                if !old_ui.same(&data.ui) {
+                    if let Some(ui_state) = self.params.ui_state() {
+                        ui_state.set_values(data.ui.values());
+                    }
                 }
+                if data.show_diagnostics && !old_show_diagnostics {
+                    ctx.request_anim_frame();
+                }
+                if old_audition != data.audition {
+                    if let Some(audition) = &self.audition {
+                        audition.set_enabled(data.audition);
+                    }
+                }
+                if old_probe != data.probe {
+                    if let Some(probes) = self.params.probes() {
+                        probes.solo(data.probe);
+                    }
+                }
+                if !Arc::ptr_eq(&old_nickname, &data.nickname) || old_tag_color != data.tag_color {
+                    if let Some(tag) = self.params.instance_tag() {
+                        tag.set_nickname(&data.nickname);
+                        tag.set_color(data.tag_color);
+                    }
+                    // so the host saves it
+                    self.model_edited();
+                }
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &EditorState<Model>,
+        env: &Env,
+    ) {
+        // druid tells widgets nothing of window focus, so catch up whenever the pointer
+        // comes back over the editor, which it does on the way to focusing it
+        if let LifeCycle::HotChanged(true) = event {
+            let send = self.refresh_pending.as_ref().map(|gate| gate.claim()).unwrap_or(true);
+            if send {
+                ctx.submit_command(MODEL_CHANGED.with(ChangeEvent::model(ChangeOrigin::Host)));
             }
         }
+        child.lifecycle(ctx, event, data, env)
     }
 }
\ No newline at end of file
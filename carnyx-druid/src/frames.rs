@@ -0,0 +1,153 @@
+//! Frame pacing: one clock for everything the editor shows from the processor, so meters
+//! and analyzers redraw at a steady rate rather than whenever the event loop gets to them.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::{ExtEventSink, Selector, Target};
+
+use carnyx::carnyx::CarnyxModel;
+use carnyx::Telemetry;
+
+use crate::telemetry::TELEMETRY;
+
+/// Sent to every widget once a frame, after that frame's [`TELEMETRY`]. Visualization
+/// widgets refresh and repaint on it, e.g. by way of [`RedrawOnFrame`].
+pub const FRAME: Selector<()> = Selector::new("carnyx-druid.frame");
+
+// while nothing is painted between frames the window is taken to be hidden or covered
+const HIDDEN_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often the editor redraws what it shows from the processor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameRate {
+    Fps30,
+    Fps60,
+}
+
+impl Default for FrameRate {
+    fn default() -> Self {
+        FrameRate::Fps60
+    }
+}
+
+impl FrameRate {
+    pub fn interval(self) -> Duration {
+        match self {
+            FrameRate::Fps30 => Duration::from_micros(33_333),
+            FrameRate::Fps60 => Duration::from_micros(16_667),
+        }
+    }
+}
+
+/// Sends [`FRAME`], and drains the model's telemetry, on a thread of its own while the
+/// editor is open. Slows down while the editor isn't being painted.
+pub(crate) struct FrameScheduler {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FrameScheduler {
+    /// `painted` is set by [`Presented`] whenever the editor paints.
+    pub fn start<Model: CarnyxModel>(model: Arc<Model>, sink: ExtEventSink, rate: FrameRate, painted: Arc<AtomicBool>) -> Option<Self> {
+        if let Some(telemetry) = model.telemetry() {
+            telemetry.set_listening(true);
+        }
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let (running, model) = (Arc::clone(&running), Arc::clone(&model));
+            thread::Builder::new().name("carnyx-frames".to_string()).spawn(move || {
+                let mut interval = rate.interval();
+                while running.load(Ordering::Acquire) {
+                    thread::sleep(interval);
+                    let messages: Vec<Telemetry> = model.telemetry().map(|telemetry| telemetry.drain().collect()).unwrap_or_default();
+                    // the app has gone if the sink is closed
+                    if !messages.is_empty() && sink.submit_command(TELEMETRY, Arc::new(messages), Target::Global).is_err() {
+                        break;
+                    }
+                    if sink.submit_command(FRAME, (), Target::Global).is_err() {
+                        break;
+                    }
+                    interval = if painted.swap(false, Ordering::AcqRel) { rate.interval() } else { HIDDEN_INTERVAL };
+                }
+                if let Some(telemetry) = model.telemetry() {
+                    telemetry.set_listening(false);
+                }
+            })
+        };
+        match thread {
+            Ok(thread) => Some(FrameScheduler { running, thread: Some(thread) }),
+            Err(_) => {
+                if let Some(telemetry) = model.telemetry() {
+                    telemetry.set_listening(false);
+                }
+                None
+            }
+        }
+    }
+}
+
+impl Drop for FrameScheduler {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Records that the editor painted, which hidden and covered windows don't.
+pub(crate) struct Presented<W> {
+    inner: W,
+    painted: Arc<AtomicBool>,
+}
+
+impl<W> Presented<W> {
+    pub fn new(inner: W, painted: Arc<AtomicBool>) -> Self {
+        Presented { inner, painted }
+    }
+}
+
+impl<T: Data, W: Widget<T>> Widget<T> for Presented<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.inner.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.inner.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.inner.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.inner.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.painted.store(true, Ordering::Release);
+        self.inner.paint(ctx, data, env)
+    }
+
+    fn post_render(&mut self) {}
+}
+
+/// Repaints a widget every [`FRAME`], for visualizations which read the processor's state
+/// when they paint.
+pub struct RedrawOnFrame;
+
+impl<T, W: Widget<T>> Controller<T, W> for RedrawOnFrame {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::Command(command) = event {
+            if command.is(FRAME) {
+                ctx.request_paint();
+            }
+        }
+        child.event(ctx, event, data, env)
+    }
+}
@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use druid::{Data, WidgetExt};
+
+use carnyx::carnyx::{CarnyxHost, CarnyxModel, CarnyxProcessor, ParamList};
+
+use crate::druid_editor::DruidEditor;
+use crate::panel::ParamPanel;
+use crate::ui_state::RememberScroll;
+
+/// An editor with a control for each of the processor's parameters, for processors
+/// which don't provide their own.
+pub fn generic_editor<P: CarnyxProcessor>(host: Arc<dyn CarnyxHost>, processor: &P) -> DruidEditor<P::Model>
+    where <P::Model as CarnyxModel>::Snap: Data {
+    let model = processor.model();
+    let params: ParamList<P::Model> = Arc::new(processor.all_parameters());
+    let panel_model = Arc::clone(&model);
+    DruidEditor::new(
+        host,
+        processor.listener(),
+        model,
+        move || {
+            ParamPanel::new(Arc::clone(&panel_model), Arc::clone(&params))
+                .build()
+                .scroll()
+                .vertical()
+                .controller(RememberScroll::new("generic.scroll"))
+        },
+    )
+    .with_parameters(processor.all_parameters())
+    .with_preset_files(processor.descriptor())
+}
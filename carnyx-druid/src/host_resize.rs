@@ -1,13 +1,122 @@
-use druid::kurbo::Line;
+use druid::kurbo::{Affine, Line};
 use druid::widget::prelude::*;
-use druid::{theme, MouseEvent, Point, Selector, Scalable};
-use std::sync::Arc;
+use druid::{theme, ContextMenu, Cursor, KbKey, LocalizedString, MenuDesc, MenuItem, MouseButton, MouseEvent, Point, Selector, Scalable, TimerToken, WidgetPod};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use carnyx::{CarnyxHost, CarnyxWindowResizer};
 use raw_window_handle::HasRawWindowHandle;
 
+// how far editors can be shrunk or enlarged, relative to their initial size
+const MIN_SCALE: f64 = 0.5;
+const MAX_SCALE: f64 = 4.0;
+// how often, and for how long, to look for the host applying a resize
+const CONFIRM_POLL: Duration = Duration::from_millis(50);
+const CONFIRM_TIMEOUT: Duration = Duration::from_millis(500);
+// window sizes go through pixels and back, so can be a fraction out
+const SIZE_TOLERANCE: f64 = 1.0;
+// lines in the grip
+const GRIP_LINES: usize = 3;
+/// How close to the window's edge a drag resizes it, by default.
+pub const DEFAULT_EDGE_HIT_ZONE: f64 = 6.0;
+// druid has no diagonal resize cursor
+const CORNER_CURSOR: Cursor = Cursor::Crosshair;
+// content measured at this size or larger fills whatever space it is given
+const MEASURE_LIMIT: Size = Size::new(4096., 4096.);
+
+/// A size the editor can be set to from the resize corner's menu, as a fraction of its
+/// initial size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizePreset {
+    pub name: &'static str,
+    /// Chooses the preset while the resize corner has focus.
+    pub key: &'static str,
+    pub scale: f64,
+}
+
+pub const DEFAULT_SIZE_PRESETS: [SizePreset; 3] = [
+    SizePreset { name: "Small", key: "s", scale: 0.75 },
+    SizePreset { name: "Medium", key: "m", scale: 1.0 },
+    SizePreset { name: "Large", key: "l", scale: 1.5 },
+];
+
+/// Zoom levels, as editors usually offer them.
+pub const SCALE_PRESETS: [SizePreset; 3] = [
+    SizePreset { name: "100%", key: "1", scale: 1.0 },
+    SizePreset { name: "125%", key: "2", scale: 1.25 },
+    SizePreset { name: "150%", key: "3", scale: 1.5 },
+];
+
+/// A menu of `presets` for an editor opened at `initial`, choosing one sends its size to
+/// the [`HostResizeDragArea`] with id `drag_area`.
+pub fn size_preset_menu<T: Data>(presets: &[SizePreset], initial: Size, drag_area: WidgetId) -> MenuDesc<T> {
+    presets.iter().fold(MenuDesc::empty(), |menu, preset| {
+        let size = initial * preset.scale;
+        let title = format!("{} ({:.0} \u{d7} {:.0})", preset.name, size.width, size.height);
+        menu.append(
+            MenuItem::new(LocalizedString::new("carnyx-size-preset").with_placeholder(title), IDLE_RESIZE.with(size).to(drag_area))
+                .hotkey(None, preset.key),
+        )
+    })
+}
+
+/// What an editor does with its content when the window is resized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizePolicy {
+    /// Draw everything bigger or smaller. The window keeps its initial proportions.
+    Scale,
+    /// Lay the content out again at the new size, so flex children take up the space.
+    Reflow,
+    /// The window can't be resized.
+    Fixed,
+}
+
+impl ResizePolicy {
+    /// The nearest size to `desired` this policy allows, for an editor opened at `initial`.
+    pub fn constrain(self, desired: Size, initial: Size) -> Size {
+        match self {
+            ResizePolicy::Scale => {
+                let scale = (desired.width / initial.width).max(desired.height / initial.height);
+                initial * scale.max(MIN_SCALE).min(MAX_SCALE)
+            }
+            ResizePolicy::Reflow => Size::new(
+                desired.width.max(initial.width * MIN_SCALE).min(initial.width * MAX_SCALE),
+                desired.height.max(initial.height * MIN_SCALE).min(initial.height * MAX_SCALE),
+            ),
+            ResizePolicy::Fixed => initial,
+        }
+    }
+}
+
+fn same_size(a: Size, b: Size) -> bool {
+    (a.width - b.width).abs() <= SIZE_TOLERANCE && (a.height - b.height).abs() <= SIZE_TOLERANCE
+}
+
+// a resize the host said yes to, but hasn't been seen to apply yet
+struct PendingResize {
+    requested: Size,
+    // the size last confirmed, to go back to if the resize never happens
+    previous: Size,
+    deadline: Instant,
+    timer: TimerToken,
+}
+
+/// The resize corner. Dragging it asks the host to resize the editor's window, then
+/// watches for the window actually changing: hosts may apply a size late, or clamp it.
+/// A different size from the host is adopted, and a resize that never happens is rolled
+/// back. Right-clicking offers size presets, which can also be chosen by key once the
+/// corner has been clicked. With `with_snap_to_presets`, drags snap to the nearest
+/// preset, so hosts that resize awkwardly still land on predictable sizes.
+///
+/// Other widgets can resize the window by sending it [`IDLE_RESIZE`].
 pub struct HostResizeDragArea {
     resizer: Box<dyn CarnyxWindowResizer>,
     drag_start_window: Option<(Point, Size)>,
+    policy: ResizePolicy,
+    initial: Size,
+    confirmed: Option<Size>,
+    pending: Option<PendingResize>,
+    presets: Vec<SizePreset>,
+    snap_to_presets: bool,
 }
 
 impl HostResizeDragArea {
@@ -15,34 +124,131 @@ impl HostResizeDragArea {
         HostResizeDragArea {
             resizer,
             drag_start_window: None,
+            policy: ResizePolicy::Reflow,
+            initial: Size::ZERO,
+            confirmed: None,
+            pending: None,
+            presets: DEFAULT_SIZE_PRESETS.to_vec(),
+            snap_to_presets: false,
         }
     }
 
+    /// Builder-style method to replace the default small, medium and large presets.
+    pub fn with_size_presets(mut self, presets: Vec<SizePreset>) -> Self {
+        self.presets = presets;
+        self
+    }
+
+    /// Builder-style method to only ever ask for preset sizes.
+    pub fn with_snap_to_presets(mut self, snap_to_presets: bool) -> Self {
+        self.snap_to_presets = snap_to_presets;
+        self
+    }
+
+    /// Builder-style method to keep requested sizes within what `policy` allows for
+    /// an editor opened at `initial`.
+    pub fn with_policy(mut self, policy: ResizePolicy, initial: Size) -> Self {
+        self.policy = policy;
+        self.initial = initial;
+        self
+    }
+
     fn resize(&self, ctx: &mut EventCtx, mouse: &MouseEvent) {
         if let Some((start, size)) = self.drag_start_window {
             let change = mouse.window_pos - start;
             let desired_size = size + change.to_size();
-            //eprintln!("Submitting idle resize {:?}", (start, mouse.window_pos, change, size, desired_size));
             ctx.submit_command(IDLE_RESIZE.with(desired_size).to(ctx.widget_id()));
         }
     }
+
+    fn preset_size(&self, preset: &SizePreset) -> Size {
+        self.initial * preset.scale
+    }
+
+    fn nearest_preset(&self, size: Size) -> Option<Size> {
+        if self.initial == Size::ZERO {
+            return None;
+        }
+        let distance = |preset: Size| (preset.width - size.width).abs() + (preset.height - size.height).abs();
+        self.presets.iter()
+            .map(|preset| self.preset_size(preset))
+            .min_by(|a, b| distance(*a).partial_cmp(&distance(*b)).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    fn show_presets<T: Data>(&self, ctx: &mut EventCtx, mouse: &MouseEvent) {
+        let menu = size_preset_menu::<T>(&self.presets, self.initial, ctx.widget_id());
+        ctx.show_context_menu(ContextMenu::new(menu, mouse.window_pos));
+    }
+
+    fn window_size(ctx: &EventCtx) -> Option<Size> {
+        ctx.window().get_scale().ok().map(|scale| ctx.window().get_size().to_dp(scale))
+    }
+
+    fn request(&mut self, ctx: &mut EventCtx, size: Size) {
+        let size = match self.nearest_preset(size) {
+            Some(preset) if self.snap_to_presets => preset,
+            _ => size,
+        };
+        let size = if self.initial != Size::ZERO { self.policy.constrain(size, self.initial) } else { size };
+        let current = HostResizeDragArea::window_size(ctx);
+        if !self.resizer.resize_editor_window(size.width as usize, size.height as usize) {
+            return;
+        }
+        ctx.window().set_size(size);
+        if let Some(previous) = self.confirmed.or(current) {
+            self.pending = Some(PendingResize {
+                requested: size,
+                previous,
+                deadline: Instant::now() + CONFIRM_TIMEOUT,
+                timer: ctx.request_timer(CONFIRM_POLL),
+            });
+        }
+    }
+
+    fn check_pending(&mut self, ctx: &mut EventCtx) {
+        let pending = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+        let actual = match HostResizeDragArea::window_size(ctx) {
+            Some(actual) => actual,
+            None => return,
+        };
+        if same_size(actual, pending.requested) {
+            self.confirmed = Some(pending.requested);
+        } else if !same_size(actual, pending.previous) {
+            // the host chose a size of its own
+            self.confirmed = Some(actual);
+            ctx.window().set_size(actual);
+        } else if Instant::now() < pending.deadline {
+            self.pending = Some(PendingResize { timer: ctx.request_timer(CONFIRM_POLL), ..pending });
+        } else {
+            // never applied: put the window and the host back as they were
+            ctx.window().set_size(pending.previous);
+            self.resizer.resize_editor_window(pending.previous.width as usize, pending.previous.height as usize);
+        }
+    }
 }
 
 pub const IDLE_RESIZE: Selector<Size> = Selector::new("carnyx-druid.idle-resize");
-impl Widget<()> for HostResizeDragArea {
-    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut (), _env: &Env) {
+// ignores its data, but is generic over it so its menu matches the window's data
+impl<T: Data> Widget<T> for HostResizeDragArea {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut T, _env: &Env) {
         match event {
             Event::Command(cmd) if cmd.is(IDLE_RESIZE) => {
                 if let Some(size) = cmd.get(IDLE_RESIZE) {
-                    //eprintln!("idle resize {:?}", size);
-                    if self.resizer.resize_editor_window(size.width as usize, size.height as usize) {
-                        //ctx.window().set_native_layout(None, Some(*size));
-                        ctx.window().set_size(*size)
-                    }
+                    self.request(ctx, *size);
                 }
             },
+            Event::Timer(token) if self.pending.as_ref().map(|p| p.timer == *token).unwrap_or(false) => {
+                self.check_pending(ctx);
+            }
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Right => {
+                self.show_presets::<T>(ctx, mouse);
+            }
             Event::MouseDown(mouse) => {
                 ctx.set_active(true);
+                ctx.request_focus();
                 if let Ok(scale) = ctx.window().get_scale() {
                     let size = ctx.window().get_size();
                     let dp_size = size.to_dp(scale);
@@ -50,8 +256,18 @@ impl Widget<()> for HostResizeDragArea {
                 }
             }
             Event::MouseMove(mouse) => {
+                if ctx.is_hot() || ctx.is_active() {
+                    ctx.set_cursor(&CORNER_CURSOR);
+                }
                 self.resize(ctx, mouse);
             }
+            Event::KeyDown(key) if ctx.has_focus() => {
+                let preset = self.presets.iter().find(|p| matches!(&key.key, KbKey::Character(c) if c.eq_ignore_ascii_case(p.key)));
+                if let Some(size) = preset.map(|p| self.preset_size(p)) {
+                    self.request(ctx, size);
+                    ctx.set_handled();
+                }
+            }
             Event::MouseUp(mouse) => {
                 self.resize(ctx, mouse);
                 self.drag_start_window = None;
@@ -61,24 +277,297 @@ impl Widget<()> for HostResizeDragArea {
         }
     }
 
-    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &(), _env: &Env) {
-
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &T, _env: &Env) {
+        if let LifeCycle::HotChanged(_) = event {
+            ctx.request_paint();
+        }
     }
 
-    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &(), _data: &(), _env: &Env) {}
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &T, _data: &T, _env: &Env) {}
 
-    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &(), env: &Env) -> Size {
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, env: &Env) -> Size {
         let h = env.get(theme::BASIC_WIDGET_HEIGHT);
         bc.constrain(Size::new(h, h))
     }
 
-    fn paint(&mut self, ctx: &mut PaintCtx, _data: &(), env: &Env) {
+    // the usual grip: short diagonal lines filling the bottom right corner
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, env: &Env) {
         let rect = ctx
             .size()
             .to_rect()
             .inset(-env.get(theme::WIDGET_CONTROL_COMPONENT_PADDING));
-        let line = Line::new((rect.x0, rect.y1), (rect.x1, rect.y0));
-        ctx.stroke(line, &env.get(theme::FOREGROUND_DARK), 2.);
+        let color = if ctx.is_hot() || ctx.is_active() {
+            env.get(theme::FOREGROUND_LIGHT)
+        } else {
+            env.get(theme::FOREGROUND_DARK)
+        };
+        let step = rect.width().min(rect.height()) / GRIP_LINES as f64;
+        for i in 1..=GRIP_LINES {
+            let offset = step * i as f64;
+            let line = Line::new((rect.x1 - offset, rect.y1), (rect.x1, rect.y1 - offset));
+            ctx.stroke(line, &color, 1.5);
+        }
+    }
+
+    fn post_render(&mut self) {}
+}
+
+// which edges a drag from a point moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Edges {
+    right: bool,
+    bottom: bool,
+}
+
+impl Edges {
+    fn any(self) -> bool {
+        self.right || self.bottom
+    }
+
+    fn cursor(self) -> Cursor {
+        match (self.right, self.bottom) {
+            (true, true) => CORNER_CURSOR,
+            (true, false) => Cursor::ResizeLeftRight,
+            _ => Cursor::ResizeUpDown,
+        }
+    }
+}
+
+/// Lets the window be resized by dragging its right or bottom edge, within `hit_zone` of
+/// it. The host keeps the window's top left corner in place, so the other edges can't
+/// move. Sizes are passed on to the [`HostResizeDragArea`] with id `drag_area`.
+pub struct ResizeEdges<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    drag_area: WidgetId,
+    hit_zone: f64,
+    drag: Option<(Edges, Point, Size)>,
+}
+
+impl<T: Data> ResizeEdges<T> {
+    pub fn new(child: impl Widget<T> + 'static, drag_area: WidgetId) -> Self {
+        ResizeEdges { child: WidgetPod::new(Box::new(child)), drag_area, hit_zone: DEFAULT_EDGE_HIT_ZONE, drag: None }
+    }
+
+    /// Builder-style method to set how close to an edge a drag must start.
+    pub fn with_hit_zone(mut self, hit_zone: f64) -> Self {
+        self.hit_zone = hit_zone;
+        self
+    }
+
+    fn edges_at(&self, pos: Point, size: Size) -> Edges {
+        Edges { right: pos.x >= size.width - self.hit_zone, bottom: pos.y >= size.height - self.hit_zone }
+    }
+
+    fn resize(&self, ctx: &mut EventCtx, mouse: &MouseEvent) {
+        if let Some((edges, start, size)) = self.drag {
+            let change = mouse.window_pos - start;
+            let desired = Size::new(
+                if edges.right { size.width + change.x } else { size.width },
+                if edges.bottom { size.height + change.y } else { size.height },
+            );
+            ctx.submit_command(IDLE_RESIZE.with(desired).to(self.drag_area));
+        }
+    }
+}
+
+impl<T: Data> Widget<T> for ResizeEdges<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left && self.edges_at(mouse.pos, ctx.size()).any() => {
+                if let Ok(scale) = ctx.window().get_scale() {
+                    let size = ctx.window().get_size().to_dp(scale);
+                    self.drag = Some((self.edges_at(mouse.pos, ctx.size()), mouse.window_pos, size));
+                    ctx.set_active(true);
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseMove(mouse) if self.drag.is_some() => {
+                self.resize(ctx, mouse);
+                if let Some((edges, _, _)) = self.drag {
+                    ctx.set_cursor(&edges.cursor());
+                }
+            }
+            Event::MouseUp(mouse) if self.drag.is_some() => {
+                self.resize(ctx, mouse);
+                self.drag = None;
+                ctx.set_active(false);
+            }
+            Event::MouseMove(mouse) => {
+                self.child.event(ctx, event, data, env);
+                let edges = self.edges_at(mouse.pos, ctx.size());
+                if edges.any() {
+                    ctx.set_cursor(&edges.cursor());
+                }
+            }
+            _ => self.child.event(ctx, event, data, env),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+    }
+
+    fn post_render(&mut self) {}
+}
+
+/// Measures the size its child would like the first time it is laid out, so an editor
+/// can tell the host how big its window should be. In directions the child fills
+/// whatever space it gets, the size it was opened at is kept. With `with_resize`, the
+/// window is then resized to the measured size through the [`HostResizeDragArea`] with
+/// that id.
+pub struct MeasureContent<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    measured: Arc<Mutex<Option<Size>>>,
+    opened: Size,
+    drag_area: Option<WidgetId>,
+    timer: TimerToken,
+}
+
+impl<T: Data> MeasureContent<T> {
+    pub fn new(child: impl Widget<T> + 'static, measured: Arc<Mutex<Option<Size>>>, opened: Size) -> Self {
+        MeasureContent { child: WidgetPod::new(Box::new(child)), measured, opened, drag_area: None, timer: TimerToken::INVALID }
+    }
+
+    /// Builder-style method to resize the window to the measured size once it is known.
+    pub fn with_resize(mut self, drag_area: WidgetId) -> Self {
+        self.drag_area = Some(drag_area);
+        self
+    }
+
+    fn measured(&self) -> Option<Size> {
+        self.measured.lock().ok().and_then(|measured| *measured)
+    }
+
+    fn measure(&mut self, ctx: &mut LayoutCtx, data: &T, env: &Env) {
+        let natural = self.child.layout(ctx, &BoxConstraints::new(Size::ZERO, MEASURE_LIMIT), data, env);
+        let size = Size::new(
+            if natural.width < MEASURE_LIMIT.width { natural.width.ceil() } else { self.opened.width },
+            if natural.height < MEASURE_LIMIT.height { natural.height.ceil() } else { self.opened.height },
+        );
+        if let Ok(mut measured) = self.measured.lock() {
+            *measured = Some(size);
+        }
+    }
+}
+
+impl<T: Data> Widget<T> for MeasureContent<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::Timer(token) if *token == self.timer => {
+                match (self.measured(), self.drag_area) {
+                    (Some(size), Some(drag_area)) => {
+                        self.timer = TimerToken::INVALID;
+                        if !same_size(size, self.opened) {
+                            ctx.submit_command(IDLE_RESIZE.with(size).to(drag_area));
+                        }
+                    }
+                    // not laid out yet
+                    _ => self.timer = ctx.request_timer(CONFIRM_POLL),
+                }
+            }
+            _ => self.child.event(ctx, event, data, env),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let (LifeCycle::WidgetAdded, Some(_)) = (event, self.drag_area) {
+            self.timer = ctx.request_timer(CONFIRM_POLL);
+        }
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        if self.measured().is_none() {
+            self.measure(ctx, data, env);
+        }
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+    }
+
+    fn post_render(&mut self) {}
+}
+
+/// Lays its child out at a fixed size, and scales it to fit the space it is given.
+pub struct ScaleToFit<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    size: Size,
+    scale: f64,
+}
+
+impl<T: Data> ScaleToFit<T> {
+    pub fn new(child: impl Widget<T> + 'static, size: Size) -> Self {
+        ScaleToFit { child: WidgetPod::new(Box::new(child)), size, scale: 1.0 }
+    }
+
+    fn unscale(&self, mouse: &MouseEvent) -> MouseEvent {
+        let mut mouse = mouse.clone();
+        mouse.pos = Point::new(mouse.pos.x / self.scale, mouse.pos.y / self.scale);
+        mouse
+    }
+}
+
+impl<T: Data> Widget<T> for ScaleToFit<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let scaled = match event {
+            Event::MouseDown(mouse) => Some(Event::MouseDown(self.unscale(mouse))),
+            Event::MouseUp(mouse) => Some(Event::MouseUp(self.unscale(mouse))),
+            Event::MouseMove(mouse) => Some(Event::MouseMove(self.unscale(mouse))),
+            Event::Wheel(mouse) => Some(Event::Wheel(self.unscale(mouse))),
+            _ => None,
+        };
+        self.child.event(ctx, scaled.as_ref().unwrap_or(event), data, env);
+        // the child invalidates rects in its own, unscaled, coordinates
+        ctx.request_paint();
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+        ctx.request_paint();
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.child.layout(ctx, &BoxConstraints::tight(self.size), data, env);
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+        let max = bc.max();
+        let scale = (max.width / self.size.width).min(max.height / self.size.height);
+        self.scale = if scale.is_finite() && scale > 0. { scale } else { 1.0 };
+        bc.constrain(self.size * self.scale)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let scale = self.scale;
+        let child = &mut self.child;
+        ctx.with_save(|ctx| {
+            ctx.transform(Affine::scale(scale));
+            child.paint(ctx, data, env);
+        });
     }
 
     fn post_render(&mut self) {}
@@ -0,0 +1,96 @@
+//! A faceplate image drawn behind an editor's controls.
+
+use std::error::Error;
+
+use druid::widget::prelude::*;
+use druid::{Point, WidgetPod};
+
+/// An embedded image asset, usually from `include_bytes!` or `include_str!`.
+#[derive(Debug, Clone, Copy)]
+pub enum Faceplate {
+    /// PNG data; needs the `image` feature.
+    Png(&'static [u8]),
+    /// SVG source; needs the `svg` feature.
+    Svg(&'static str),
+}
+
+impl Faceplate {
+    /// A widget drawing the image stretched to fill its space. Fails if the image can't
+    /// be decoded, or support for its format isn't built in.
+    pub fn to_widget<T: Data>(self) -> Result<Box<dyn Widget<T>>, Box<dyn Error>> {
+        match self {
+            #[cfg(feature = "image")]
+            Faceplate::Png(data) => {
+                use druid::piet::InterpolationMode;
+                use druid::widget::{FillStrat, Image};
+                let image = druid::ImageBuf::from_data(data)?;
+                Ok(Box::new(Image::new(image)
+                    .fill_mode(FillStrat::Fill)
+                    .interpolation_mode(InterpolationMode::Bilinear)))
+            }
+            #[cfg(feature = "svg")]
+            Faceplate::Svg(source) => {
+                use druid::widget::{FillStrat, Svg, SvgData};
+                let svg: SvgData = source.parse()?;
+                Ok(Box::new(Svg::new(svg).fill_mode(FillStrat::Fill)))
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(format!("carnyx-druid was built without support for {:?}", self).into()),
+        }
+    }
+}
+
+/// Draws a [`Faceplate`] stretched to the size of its child, underneath the child.
+///
+/// Drawing happens in druid's display-independent units, so the image is rasterised
+/// at the window's scale factor: vector faceplates stay sharp on HiDPI screens, and
+/// bitmaps should be supplied at the largest scale they will be shown at.
+pub struct ImagePanel<T> {
+    image: WidgetPod<T, Box<dyn Widget<T>>>,
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+}
+
+impl<T: Data> ImagePanel<T> {
+    pub fn new(image: impl Widget<T> + 'static, child: impl Widget<T> + 'static) -> Self {
+        ImagePanel {
+            image: WidgetPod::new(Box::new(image)),
+            child: WidgetPod::new(Box::new(child)),
+        }
+    }
+
+    /// See [`Faceplate::to_widget`] for when this fails.
+    pub fn from_faceplate(faceplate: Faceplate, child: impl Widget<T> + 'static) -> Result<Self, Box<dyn Error>> {
+        Ok(ImagePanel::new(faceplate.to_widget()?, child))
+    }
+}
+
+impl<T: Data> Widget<T> for ImagePanel<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.image.lifecycle(ctx, event, data, env);
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.image.update(ctx, data, env);
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+        self.image.layout(ctx, &BoxConstraints::tight(size), data, env);
+        self.image.set_origin(ctx, data, env, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.image.paint(ctx, data, env);
+        self.child.paint(ctx, data, env);
+    }
+
+    fn post_render(&mut self) {}
+}
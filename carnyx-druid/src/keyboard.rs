@@ -0,0 +1,177 @@
+//! A clickable piano keyboard.
+
+use druid::widget::prelude::*;
+use druid::{theme, Color, MouseEvent, Point, Rect, Selector};
+use carnyx::NoteEvent;
+
+const WHITE_KEY_WIDTH: f64 = 16.;
+const KEYBOARD_HEIGHT: f64 = 60.;
+const BLACK_KEY_WIDTH_RATIO: f64 = 0.6;
+const BLACK_KEY_HEIGHT_RATIO: f64 = 0.6;
+
+/// Submitted by the [`Keyboard`] when a key is pressed or released. The editor forwards
+/// these into the processor's note queue.
+pub const NOTE_EVENT: Selector<NoteEvent> = Selector::new("carnyx-druid.note-event");
+
+/// A piano keyboard covering the notes `low..=high`, which emits [`NOTE_EVENT`] commands
+/// while being played with the mouse.
+pub struct Keyboard {
+    low: u8,
+    high: u8,
+    held: Option<u8>,
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Keyboard::new()
+    }
+}
+
+impl Keyboard {
+    /// Create a new `Keyboard` covering two octaves from C3.
+    pub fn new() -> Keyboard {
+        Keyboard {
+            low: 48,
+            high: 72,
+            held: None,
+        }
+    }
+
+    /// Builder-style method to set the range of notes shown.
+    pub fn with_range(mut self, low: u8, high: u8) -> Self {
+        self.low = low.min(high);
+        self.high = high.max(low);
+        self
+    }
+}
+
+fn is_black(note: u8) -> bool {
+    matches!(note % 12, 1 | 3 | 6 | 8 | 10)
+}
+
+impl Keyboard {
+    fn white_keys(&self) -> usize {
+        (self.low..=self.high).filter(|n| !is_black(*n)).count()
+    }
+
+    /// Number of white keys strictly below `note`.
+    fn white_index(&self, note: u8) -> usize {
+        (self.low..note).filter(|n| !is_black(*n)).count()
+    }
+
+    fn key_rect(&self, note: u8, size: Size) -> Rect {
+        let white_width = size.width / self.white_keys().max(1) as f64;
+        if is_black(note) {
+            let black_width = white_width * BLACK_KEY_WIDTH_RATIO;
+            let x = self.white_index(note) as f64 * white_width - black_width / 2.;
+            Rect::from_origin_size((x, 0.), (black_width, size.height * BLACK_KEY_HEIGHT_RATIO))
+        } else {
+            let x = self.white_index(note) as f64 * white_width;
+            Rect::from_origin_size((x, 0.), (white_width, size.height))
+        }
+    }
+
+    fn note_at(&self, pos: Point, size: Size) -> Option<u8> {
+        let keys = self.low..=self.high;
+        keys.clone()
+            .filter(|n| is_black(*n))
+            .chain(keys.filter(|n| !is_black(*n)))
+            .find(|n| self.key_rect(*n, size).contains(pos))
+    }
+
+    fn velocity_at(&self, pos: Point, size: Size) -> f32 {
+        // Playing further down the key is louder, as on a real keyboard.
+        (pos.y / size.height).clamp(0.1, 1.0) as f32
+    }
+
+    fn press(&mut self, ctx: &mut EventCtx, mouse: &MouseEvent) {
+        let note = self.note_at(mouse.pos, ctx.size());
+        if note != self.held {
+            self.release(ctx);
+            if let Some(note) = note {
+                let velocity = self.velocity_at(mouse.pos, ctx.size());
+                ctx.submit_command(NOTE_EVENT.with(NoteEvent::On { note, velocity }));
+                self.held = Some(note);
+            }
+            ctx.request_paint();
+        }
+    }
+
+    fn release(&mut self, ctx: &mut EventCtx) {
+        if let Some(note) = self.held.take() {
+            ctx.submit_command(NOTE_EVENT.with(NoteEvent::Off { note }));
+            ctx.request_paint();
+        }
+    }
+}
+
+impl Widget<()> for Keyboard {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut (), _env: &Env) {
+        match event {
+            Event::MouseDown(mouse) => {
+                ctx.set_active(true);
+                self.press(ctx, mouse);
+            }
+            Event::MouseMove(mouse) => {
+                if ctx.is_active() {
+                    self.press(ctx, mouse);
+                }
+            }
+            Event::MouseUp(_) => {
+                if ctx.is_active() {
+                    ctx.set_active(false);
+                    self.release(ctx);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &(), _env: &Env) {
+        // the mouse up may never come once the pointer or focus has gone elsewhere. druid
+        // says nothing when a widget is removed, so the editor releases whatever is still
+        // held when it closes
+        let lost = match event {
+            LifeCycle::HotChanged(false) => ctx.is_active(),
+            LifeCycle::FocusChanged(false) => true,
+            _ => false,
+        };
+        if lost {
+            if let Some(note) = self.held.take() {
+                ctx.submit_command(NOTE_EVENT.with(NoteEvent::Off { note }));
+                ctx.request_paint();
+            }
+        }
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &(), _data: &(), _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &(), _env: &Env) -> Size {
+        bc.debug_check("Keyboard");
+        let width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            self.white_keys() as f64 * WHITE_KEY_WIDTH
+        };
+        bc.constrain(Size::new(width, KEYBOARD_HEIGHT))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &(), env: &Env) {
+        let size = ctx.size();
+        let pressed = env.get(theme::PRIMARY_LIGHT);
+        let border = env.get(theme::FOREGROUND_DARK);
+        for note in (self.low..=self.high).filter(|n| !is_black(*n)) {
+            let rect = self.key_rect(note, size);
+            let fill = if self.held == Some(note) { pressed.clone() } else { Color::WHITE };
+            ctx.fill(rect, &fill);
+            ctx.stroke(rect, &border, 1.);
+        }
+        for note in (self.low..=self.high).filter(|n| is_black(*n)) {
+            let rect = self.key_rect(note, size);
+            let fill = if self.held == Some(note) { pressed.clone() } else { Color::BLACK };
+            ctx.fill(rect, &fill);
+        }
+    }
+
+    fn post_render(&mut self) {}
+}
@@ -1,7 +1,53 @@
+mod clip_led;
+mod command;
 mod dial;
+mod dropdown;
 mod host_resize;
+mod image_panel;
 mod druid_editor;
+mod frames;
+mod generic;
+mod keyboard;
+mod load_meter;
+mod lock;
+mod macros;
+mod oscilloscope;
+mod pages;
+mod panel;
+mod param;
+mod probe;
+mod tag;
+mod readout;
+mod reset;
+mod theme;
+mod telemetry;
+mod tooltip;
+mod ui_state;
+mod xy_pad;
 
-pub use dial::Dial;
-pub use host_resize::HostResizeDragArea;
-pub use druid_editor::{DruidEditor, EditorState};
\ No newline at end of file
+pub use clip_led::ClipLed;
+pub use command::command_button;
+pub use dial::{Dial, CONTEXT_MENU};
+pub use dropdown::{dropdown_for_param, DROPDOWN_SELECT};
+pub use host_resize::{size_preset_menu, HostResizeDragArea, MeasureContent, ResizeEdges, ResizePolicy, ScaleToFit, SizePreset, DEFAULT_EDGE_HIT_ZONE, DEFAULT_SIZE_PRESETS, IDLE_RESIZE, SCALE_PRESETS};
+pub use image_panel::{Faceplate, ImagePanel};
+pub use frames::{FrameRate, RedrawOnFrame, FRAME};
+pub use generic::generic_editor;
+pub use druid_editor::{DruidEditor, EditorState, HOST_PLAYING, AB_COPY_A_TO_B, AB_TOGGLE, MUTATE, RANDOMIZE, REBUILD_UI};
+pub use keyboard::{Keyboard, NOTE_EVENT};
+pub use load_meter::LoadMeter;
+pub use lock::LockToggle;
+pub use macros::macro_panel;
+pub use oscilloscope::Oscilloscope;
+pub use pages::{page_for_key, Pages};
+pub use panel::{ControlKind, PanelRow, ParamPanel};
+pub use param::{dial_for_param, slider_for_param, xy_pad_for_params, LockLens, ParamController, ParamHandle, ParamLens, ParamMenu, ParamPairLens, ParamValues, ReadOnly};
+pub use probe::probe_panel;
+pub use tag::instance_header;
+pub use readout::{ParamFocus, ValueReadout, PARAM_FOCUS};
+pub use reset::{is_reset_click, ResetToDefault};
+pub use theme::{CarnyxTheme, KnobStyle, KNOB_FILLED, TOUCH_INPUT};
+pub use telemetry::TELEMETRY;
+pub use tooltip::Tooltip;
+pub use ui_state::{RememberScroll, UiLens, UiValues};
+pub use xy_pad::XYPad;
\ No newline at end of file
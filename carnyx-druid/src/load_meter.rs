@@ -0,0 +1,73 @@
+//! A readout of how much CPU time processing takes.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use druid::widget::prelude::*;
+use druid::{theme, Point, TextLayout, TimerToken};
+
+use carnyx::carnyx::CarnyxModel;
+
+const REFRESH: Duration = Duration::from_millis(250);
+
+/// Shows the model's [`DspLoad`](carnyx::DspLoad) as a percentage of realtime, with the
+/// worst block since the last refresh. Blank for models which don't record load.
+pub struct LoadMeter<Model> {
+    model: Arc<Model>,
+    timer: TimerToken,
+    layout: TextLayout<String>,
+}
+
+impl<Model: CarnyxModel> LoadMeter<Model> {
+    pub fn new(model: Arc<Model>) -> Self {
+        let mut layout = TextLayout::new();
+        layout.set_text_color(theme::LABEL_COLOR);
+        LoadMeter { model, timer: TimerToken::INVALID, layout }
+    }
+
+    fn refresh(&mut self) {
+        let text = match self.model.dsp_load() {
+            Some(load) => format!("DSP {:.0}% (peak {:.0}%)", load.load() * 100., load.take_peak() * 100.),
+            None => String::new(),
+        };
+        self.layout.set_text(text);
+    }
+}
+
+impl<Model: CarnyxModel> Widget<()> for LoadMeter<Model> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut (), _env: &Env) {
+        match event {
+            // timers need an event context to start from
+            Event::AnimFrame(_) if self.timer == TimerToken::INVALID => {
+                self.refresh();
+                self.timer = ctx.request_timer(REFRESH);
+                ctx.request_layout();
+            }
+            Event::Timer(token) if *token == self.timer => {
+                self.refresh();
+                self.timer = ctx.request_timer(REFRESH);
+                ctx.request_layout();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &(), _env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &(), _data: &(), _env: &Env) {}
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &(), env: &Env) -> Size {
+        self.layout.rebuild_if_needed(ctx.text(), env);
+        bc.constrain(self.layout.size())
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &(), _env: &Env) {
+        self.layout.draw(ctx, Point::ORIGIN);
+    }
+
+    fn post_render(&mut self) {}
+}
@@ -0,0 +1,80 @@
+//! A padlock toggle for locking parameters against host automation.
+
+use druid::kurbo::{Arc as ArcShape, BezPath, Circle, Vec2};
+use druid::widget::prelude::*;
+use druid::{theme, Point, Rect};
+
+use std::f64::consts::PI;
+
+const SIZE: f64 = 12.0;
+
+/// A small padlock, closed while its parameter ignores the host. Click to toggle.
+#[derive(Default)]
+pub struct LockToggle;
+
+impl LockToggle {
+    pub fn new() -> Self {
+        LockToggle
+    }
+}
+
+impl Widget<bool> for LockToggle {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut bool, _env: &Env) {
+        match event {
+            Event::MouseDown(_) => {
+                ctx.set_active(true);
+                ctx.set_handled();
+            }
+            Event::MouseUp(_) if ctx.is_active() => {
+                ctx.set_active(false);
+                if ctx.is_hot() {
+                    *data = !*data;
+                }
+                ctx.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &bool, _env: &Env) {
+        if let LifeCycle::HotChanged(_) = event {
+            ctx.request_paint();
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &bool, data: &bool, _env: &Env) {
+        if old_data != data {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &bool, _env: &Env) -> Size {
+        bc.constrain(Size::new(SIZE, SIZE))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &bool, env: &Env) {
+        // unlocked padlocks only show on hover, to keep rows of controls quiet
+        if !*data && !ctx.is_hot() {
+            return;
+        }
+        let color = if *data { env.get(theme::PRIMARY_LIGHT) } else { env.get(theme::FOREGROUND_DARK) };
+        let body = Rect::new(1.5, SIZE / 2., SIZE - 1.5, SIZE - 0.5);
+        ctx.fill(body, &color);
+        // an open shackle is lifted to one side
+        let lift = if *data { 0. } else { -2. };
+        let center = Point::new(SIZE / 2., SIZE / 2. + lift);
+        let mut shackle = BezPath::new();
+        shackle.move_to(center + Vec2::new(-2.75, 0.));
+        shackle.extend(ArcShape {
+            center,
+            radii: Vec2::new(2.75, 3.5),
+            start_angle: PI,
+            sweep_angle: PI,
+            x_rotation: 0.,
+        }.append_iter(0.1));
+        ctx.stroke(shackle, &color, 1.5);
+        ctx.fill(Circle::new(Point::new(SIZE / 2., SIZE * 0.75), 1.), &env.get(theme::BACKGROUND_DARK));
+    }
+
+    fn post_render(&mut self) {}
+}
@@ -0,0 +1,62 @@
+//! A panel for the built in macro controls: each macro's knob, a learn toggle, and the
+//! settings of the parameters it moves.
+
+use druid::widget::{Checkbox, CrossAxisAlignment, Flex, SizedBox};
+use druid::{Data, LensExt, Widget, WidgetExt};
+
+use carnyx::carnyx::CarnyxModel;
+use carnyx::MacroParams;
+
+use crate::dropdown::dropdown_for_param;
+use crate::druid_editor::EditorState;
+use crate::param::{dial_for_param, ParamHandle};
+
+/// A row per macro, built from the macro parameters among `params`. While a macro's Learn
+/// box is ticked, the next of the processor's controls to be dragged becomes its next
+/// target. Empty for models without macros.
+pub fn macro_panel<Model: CarnyxModel>(params: &[ParamHandle<Model>]) -> Box<dyn Widget<EditorState<Model>>> where Model::Snap: Data {
+    let handles: Vec<_> = params.iter().filter(|h| h.param().id().starts_with("macro")).cloned().collect();
+    if handles.is_empty() {
+        return Box::new(SizedBox::empty());
+    }
+    // the knob, then target, start, end and curve for each assignment
+    let per_control = (handles.len() / MacroParams::CONTROLS).max(1);
+    let mut panel = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+    for (control, settings) in handles.chunks(per_control).enumerate() {
+        let mut row = Flex::row().cross_axis_alignment(CrossAxisAlignment::Start);
+        row.add_child(Flex::column()
+            .with_child(dial_for_param(&settings[0]))
+            .with_child(learn_toggle(control)));
+        row.add_spacer(10.);
+        let mut assignments = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+        for assignment in settings[1..].chunks(4) {
+            let mut line = Flex::row().cross_axis_alignment(CrossAxisAlignment::Start);
+            for handle in assignment {
+                if handle.param().steps() > 0 {
+                    line.add_child(dropdown_for_param(handle));
+                } else {
+                    line.add_child(dial_for_param(handle));
+                }
+            }
+            assignments.add_child(line);
+        }
+        row.add_child(assignments);
+        panel.add_child(row);
+        panel.add_spacer(10.);
+    }
+    Box::new(panel)
+}
+
+// on while this macro is learning; turning another on turns this one off
+fn learn_toggle<Model: CarnyxModel>(control: usize) -> impl Widget<EditorState<Model>> where Model::Snap: Data {
+    Checkbox::new("Learn").lens(EditorState::macro_learn.map(
+        move |learn| *learn == Some(control),
+        move |learn, on| {
+            if on {
+                *learn = Some(control);
+            } else if *learn == Some(control) {
+                *learn = None;
+            }
+        },
+    ))
+}
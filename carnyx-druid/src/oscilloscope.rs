@@ -0,0 +1,127 @@
+//! An oscilloscope reading from a processor's sample tap.
+
+use std::sync::Arc;
+
+use druid::kurbo::{BezPath, Line};
+use druid::widget::prelude::*;
+use druid::{theme, Point};
+use carnyx::SampleTap;
+
+use crate::frames::FRAME;
+
+const MIN_WINDOW: usize = 32;
+
+/// Draws the latest samples from a [`SampleTap`], refreshing every [`FRAME`].
+///
+/// With the trigger enabled the trace starts at a rising zero crossing, so periodic
+/// signals such as a self oscillating filter stand still on screen. Scrolling over
+/// the scope zooms the time window.
+pub struct Oscilloscope {
+    tap: Arc<SampleTap>,
+    window: usize,
+    trigger: bool,
+    scratch: Vec<f32>,
+    trace: Vec<f32>,
+}
+
+impl Oscilloscope {
+    /// Create a new `Oscilloscope` showing the last 512 samples.
+    pub fn new(tap: Arc<SampleTap>) -> Self {
+        Oscilloscope {
+            tap,
+            window: 512,
+            trigger: true,
+            scratch: Vec::new(),
+            trace: Vec::new(),
+        }
+    }
+
+    /// Builder-style method to set the number of samples across the display.
+    pub fn with_window(mut self, samples: usize) -> Self {
+        self.set_window(samples);
+        self
+    }
+
+    /// Builder-style method to enable or disable the rising-edge trigger.
+    pub fn with_trigger(mut self, trigger: bool) -> Self {
+        self.trigger = trigger;
+        self
+    }
+
+    pub fn set_window(&mut self, samples: usize) {
+        // Leave room in the tap to search for a trigger point before the window.
+        self.window = samples.clamp(MIN_WINDOW, self.tap.capacity() / 2);
+    }
+
+    fn refresh(&mut self) {
+        let wanted = if self.trigger { self.window * 2 } else { self.window };
+        self.scratch.resize(wanted, 0.);
+        let copied = self.tap.copy_latest(&mut self.scratch);
+        let samples = &self.scratch[..copied];
+
+        let start = if self.trigger && samples.len() > self.window {
+            let search = samples.len() - self.window;
+            (1..search)
+                .rev()
+                .find(|&i| samples[i - 1] <= 0. && samples[i] > 0.)
+                .unwrap_or(search)
+        } else {
+            samples.len().saturating_sub(self.window)
+        };
+        self.trace.clear();
+        self.trace.extend_from_slice(&samples[start..(start + self.window).min(samples.len())]);
+    }
+}
+
+impl Widget<()> for Oscilloscope {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut (), _env: &Env) {
+        match event {
+            Event::Command(command) if command.is(FRAME) => {
+                self.refresh();
+                ctx.request_paint();
+            }
+            Event::Wheel(mouse) => {
+                let factor = if mouse.wheel_delta.y > 0. { 1.25 } else { 0.8 };
+                self.set_window((self.window as f64 * factor) as usize);
+                ctx.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &(), _env: &Env) {}
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &(), _data: &(), _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &(), env: &Env) -> Size {
+        bc.debug_check("Oscilloscope");
+        let width = env.get(theme::WIDE_WIDGET_WIDTH) * 2.;
+        bc.constrain(Size::new(width, width / 2.))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &(), env: &Env) {
+        let size = ctx.size();
+        let rect = size.to_rect();
+        ctx.fill(rect, &env.get(theme::BACKGROUND_DARK));
+        let mid = size.height / 2.;
+        ctx.stroke(Line::new((0., mid), (size.width, mid)), &env.get(theme::BORDER_DARK), 1.);
+
+        if self.trace.len() < 2 {
+            return;
+        }
+        let step = size.width / (self.window - 1) as f64;
+        let mut path = BezPath::new();
+        for (i, sample) in self.trace.iter().enumerate() {
+            let y = mid - (sample.clamp(-1., 1.) as f64) * mid;
+            let point = Point::new(i as f64 * step, y);
+            if i == 0 {
+                path.move_to(point);
+            } else {
+                path.line_to(point);
+            }
+        }
+        ctx.stroke(path, &env.get(theme::PRIMARY_LIGHT), 1.5);
+    }
+
+    fn post_render(&mut self) {}
+}
@@ -0,0 +1,244 @@
+//! Editors split across pages, switched with a strip of tabs along the top.
+
+use druid::kurbo::Line;
+use druid::widget::prelude::*;
+use druid::{theme, Data, KbKey, KeyEvent, Point, Rect, TextLayout, WidgetExt, WidgetPod};
+
+use carnyx::carnyx::CarnyxModel;
+
+use crate::druid_editor::EditorState;
+use crate::ui_state::UiLens;
+
+const TAB_PADDING: f64 = 8.0;
+
+/// The page a key press inside [`Pages`] switches to, from page `selected` of `count`,
+/// or `None` if it isn't a page switching key. Switching wraps around at either end.
+pub fn page_for_key(key: &KeyEvent, selected: usize, count: usize) -> Option<usize> {
+    let last = count.checked_sub(1)?;
+    let step = match &key.key {
+        KbKey::Tab if key.mods.ctrl() => if key.mods.shift() { last } else { 1 },
+        KbKey::PageDown if key.mods.ctrl() => 1,
+        KbKey::PageUp if key.mods.ctrl() => last,
+        _ => return None,
+    };
+    Some((selected + step) % count)
+}
+
+type PageBuilder<Model> = Box<dyn Fn() -> Box<dyn Widget<EditorState<Model>>>>;
+type PagePod<Model> = WidgetPod<EditorState<Model>, Box<dyn Widget<EditorState<Model>>>>;
+
+/// Pages of controls with tabs to switch between them. Pages are built the first time
+/// they are shown, and the selected page is remembered in the model's
+/// [`UiState`](carnyx::UiState) under `key`.
+///
+/// With the focus inside the pages, ctrl+tab and ctrl+page down go to the next page, and
+/// ctrl+shift+tab and ctrl+page up to the previous one. The arrow keys do the same when
+/// the tabs have the focus.
+pub struct Pages<Model: CarnyxModel> {
+    key: String,
+    titles: Vec<String>,
+    builders: Vec<PageBuilder<Model>>,
+    pages: Vec<Option<PagePod<Model>>>,
+    tabs: Option<WidgetPod<EditorState<Model>, Box<dyn Widget<EditorState<Model>>>>>,
+}
+
+impl<Model: CarnyxModel> Pages<Model> where Model::Snap: Data {
+    pub fn new(key: &str) -> Self {
+        Pages { key: key.to_string(), titles: Vec::new(), builders: Vec::new(), pages: Vec::new(), tabs: None }
+    }
+
+    /// Builder-style method to add a page, built by `page` when it is first shown.
+    pub fn with_page<W: Widget<EditorState<Model>> + 'static>(mut self, title: &str, page: impl Fn() -> W + 'static) -> Self {
+        self.titles.push(title.to_string());
+        self.builders.push(Box::new(move || page().boxed()));
+        self.pages.push(None);
+        self
+    }
+
+    fn lens(&self) -> UiLens {
+        UiLens::new(self.key.clone(), 0.)
+    }
+
+    fn selected(&self, data: &EditorState<Model>) -> usize {
+        let index = data.ui.get(&self.key).unwrap_or(0.).round().max(0.) as usize;
+        index.min(self.pages.len().saturating_sub(1))
+    }
+
+    fn select(&self, data: &mut EditorState<Model>, index: usize) {
+        data.ui.set(&self.key, index as f64);
+    }
+
+    // true if the page had to be built
+    fn build(&mut self, index: usize) -> bool {
+        match self.pages.get_mut(index) {
+            Some(page @ None) => {
+                *page = Some(WidgetPod::new((self.builders[index])()));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn tabs(&mut self) -> &mut WidgetPod<EditorState<Model>, Box<dyn Widget<EditorState<Model>>>> {
+        let (titles, lens) = (self.titles.clone(), self.lens());
+        self.tabs.get_or_insert_with(|| WidgetPod::new(TabStrip::new(titles).lens(lens).boxed()))
+    }
+}
+
+impl<Model: CarnyxModel> Widget<EditorState<Model>> for Pages<Model> where Model::Snap: Data {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut EditorState<Model>, env: &Env) {
+        if let Event::KeyDown(key) = event {
+            if let Some(index) = page_for_key(key, self.selected(data), self.pages.len()) {
+                self.select(data, index);
+                ctx.set_handled();
+                return;
+            }
+        }
+        self.tabs().event(ctx, event, data, env);
+        let selected = self.selected(data);
+        if let Some(Some(page)) = self.pages.get_mut(selected) {
+            page.event(ctx, event, data, env);
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &EditorState<Model>, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.build(self.selected(data));
+        }
+        self.tabs().lifecycle(ctx, event, data, env);
+        for page in self.pages.iter_mut().flatten() {
+            page.lifecycle(ctx, event, data, env);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &EditorState<Model>, data: &EditorState<Model>, env: &Env) {
+        if self.selected(old_data) != self.selected(data) {
+            if self.build(self.selected(data)) {
+                ctx.children_changed();
+            }
+            ctx.request_layout();
+        }
+        self.tabs().update(ctx, data, env);
+        // hidden pages are kept up to date, so they are current when shown again
+        for page in self.pages.iter_mut().flatten() {
+            page.update(ctx, data, env);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &EditorState<Model>, env: &Env) -> Size {
+        let tabs_bc = BoxConstraints::new(Size::new(bc.min().width, 0.), Size::new(bc.max().width, f64::INFINITY));
+        let tabs = self.tabs();
+        let tabs_size = tabs.layout(ctx, &tabs_bc, data, env);
+        tabs.set_origin(ctx, data, env, Point::ORIGIN);
+        let selected = self.selected(data);
+        let page_size = match self.pages.get_mut(selected) {
+            Some(Some(page)) => {
+                let size = page.layout(ctx, &bc.shrink((0., tabs_size.height)), data, env);
+                page.set_origin(ctx, data, env, Point::new(0., tabs_size.height));
+                size
+            }
+            _ => Size::ZERO,
+        };
+        bc.constrain(Size::new(tabs_size.width.max(page_size.width), tabs_size.height + page_size.height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &EditorState<Model>, env: &Env) {
+        self.tabs().paint(ctx, data, env);
+        let selected = self.selected(data);
+        if let Some(Some(page)) = self.pages.get_mut(selected) {
+            page.paint(ctx, data, env);
+        }
+    }
+
+    fn post_render(&mut self) {}
+}
+
+// the index of the selected page, as an f64 so it can live in the UiState
+struct TabStrip {
+    titles: Vec<TextLayout<String>>,
+    tabs: Vec<Rect>,
+}
+
+impl TabStrip {
+    fn new(titles: Vec<String>) -> Self {
+        TabStrip { titles: titles.into_iter().map(TextLayout::from_text).collect(), tabs: Vec::new() }
+    }
+
+    fn select(&self, ctx: &mut EventCtx, data: &mut f64, index: usize) {
+        if index < self.titles.len() {
+            *data = index as f64;
+            ctx.request_paint();
+        }
+    }
+}
+
+impl Widget<f64> for TabStrip {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut f64, _env: &Env) {
+        match event {
+            Event::MouseDown(mouse) => {
+                if let Some(index) = self.tabs.iter().position(|tab| tab.contains(mouse.pos)) {
+                    self.select(ctx, data, index);
+                    ctx.request_focus();
+                }
+            }
+            Event::KeyDown(key) if ctx.is_focused() => {
+                let selected = data.round().max(0.) as usize;
+                match &key.key {
+                    KbKey::ArrowLeft if selected > 0 => self.select(ctx, data, selected - 1),
+                    KbKey::ArrowRight => self.select(ctx, data, selected + 1),
+                    _ => return,
+                }
+                ctx.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &f64, _env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => ctx.register_for_focus(),
+            LifeCycle::FocusChanged(_) => ctx.request_paint(),
+            _ => (),
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &f64, data: &f64, _env: &Env) {
+        if !old_data.same(data) {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &f64, env: &Env) -> Size {
+        self.tabs.clear();
+        let mut x = 0.;
+        let mut height: f64 = 0.;
+        for title in &mut self.titles {
+            title.rebuild_if_needed(ctx.text(), env);
+            let size = title.size();
+            let tab = Rect::new(x, 0., x + size.width + 2. * TAB_PADDING, size.height + 2. * TAB_PADDING);
+            x = tab.x1;
+            height = height.max(tab.y1);
+            self.tabs.push(tab);
+        }
+        let width = if bc.is_width_bounded() { bc.max().width } else { x };
+        bc.constrain(Size::new(width, height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &f64, env: &Env) {
+        let selected = data.round().max(0.) as usize;
+        let size = ctx.size();
+        ctx.stroke(Line::new((0., size.height - 0.5), (size.width, size.height - 0.5)), &env.get(theme::BORDER_DARK), 1.);
+        for (index, (title, tab)) in self.titles.iter_mut().zip(&self.tabs).enumerate() {
+            if index == selected {
+                ctx.fill(*tab, &env.get(theme::BACKGROUND_LIGHT));
+                let underline = if ctx.is_focused() { theme::PRIMARY_LIGHT } else { theme::PRIMARY_DARK };
+                ctx.stroke(Line::new((tab.x0, tab.y1 - 1.), (tab.x1, tab.y1 - 1.)), &env.get(underline), 2.);
+            }
+            title.set_text_color(if index == selected { theme::LABEL_COLOR } else { theme::PLACEHOLDER_COLOR });
+            title.rebuild_if_needed(ctx.text(), env);
+            title.draw(ctx, Point::new(tab.x0 + TAB_PADDING, tab.y0 + TAB_PADDING));
+        }
+    }
+
+    fn post_render(&mut self) {}
+}
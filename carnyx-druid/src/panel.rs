@@ -0,0 +1,160 @@
+//! Editors laid out from a list of parameters instead of a hand written widget tree.
+
+use std::sync::Arc;
+
+use druid::widget::{Axis, Checkbox, CrossAxisAlignment, Flex, Label, RadioGroup};
+use druid::{theme, Data, Insets, LensExt, Widget, WidgetExt};
+
+use carnyx::carnyx::{CarnyxModel, CarnyxParam, ParamList};
+
+use crate::druid_editor::EditorState;
+use crate::param::{dial_for_param, slider_for_param, ParamHandle};
+
+// parameters per row when no layout is given
+const AUTO_ROW_LENGTH: usize = 6;
+
+/// The widget to use for a parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlKind {
+    Dial,
+    Slider,
+    /// On above one half, off below.
+    Toggle,
+    /// One button per choice, spread evenly over the normalized range.
+    Choice(&'static [&'static str]),
+}
+
+impl ControlKind {
+    /// Buttons for parameters with choices, otherwise a dial.
+    pub fn for_param<Model: CarnyxModel>(param: &dyn CarnyxParam<Model>) -> Self {
+        match param.choices() {
+            [] => ControlKind::Dial,
+            choices => ControlKind::Choice(choices),
+        }
+    }
+}
+
+/// One row of a [`ParamPanel`]: parameter names with the control for each.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PanelRow {
+    pub title: Option<String>,
+    pub controls: Vec<(String, ControlKind)>,
+}
+
+/// Builds an editor from a model's parameters and a layout spec: rows of controls,
+/// optionally titled as groups, each naming a parameter and the kind of widget for it.
+/// Parameters are named as they are to the host. With no rows, every parameter gets
+/// the control [`ControlKind::for_param`] picks.
+///
+/// ```ignore
+/// ParamPanel::new(model, params)
+///     .group("Filter", &[("cutoff", ControlKind::Dial), ("resonance", ControlKind::Dial)])
+///     .row(&[("filter order", ControlKind::Choice(&["1", "2", "3", "4"]))])
+///     .build()
+/// ```
+pub struct ParamPanel<Model: CarnyxModel> {
+    model: Arc<Model>,
+    params: ParamList<Model>,
+    rows: Vec<PanelRow>,
+}
+
+impl<Model: CarnyxModel> ParamPanel<Model> where Model::Snap: Data {
+    pub fn new(model: Arc<Model>, params: ParamList<Model>) -> Self {
+        ParamPanel { model, params, rows: Vec::new() }
+    }
+
+    /// Builder-style method to add a row of controls.
+    pub fn row(mut self, controls: &[(&str, ControlKind)]) -> Self {
+        self.rows.push(PanelRow { title: None, controls: owned(controls) });
+        self
+    }
+
+    /// Builder-style method to add a row of controls, boxed with a title.
+    pub fn group(mut self, title: &str, controls: &[(&str, ControlKind)]) -> Self {
+        self.rows.push(PanelRow { title: Some(title.to_string()), controls: owned(controls) });
+        self
+    }
+
+    // inactive parameters are left out until the editor is rebuilt with them active
+    fn handles(&self) -> Vec<ParamHandle<Model>> {
+        let mut handles = ParamHandle::all(Arc::clone(&self.model), Arc::clone(&self.params));
+        handles.retain(|handle| handle.is_active());
+        handles
+    }
+
+    fn auto_rows(&self) -> Vec<PanelRow> {
+        let controls: Vec<(String, ControlKind)> =
+            self.handles().iter().map(|h| (h.name(), ControlKind::for_param(h.param()))).collect();
+        // choices take the width of their buttons, so get a row each
+        let (dials, choices): (Vec<_>, Vec<_>) = controls.into_iter().partition(|(_, kind)| *kind == ControlKind::Dial);
+        let mut rows: Vec<PanelRow> = dials
+            .chunks(AUTO_ROW_LENGTH)
+            .map(|chunk| PanelRow { title: None, controls: chunk.to_vec() })
+            .collect();
+        rows.extend(choices.into_iter().map(|control| PanelRow { title: None, controls: vec![control] }));
+        rows
+    }
+
+    /// The rows [`build`](ParamPanel::build) lays out. Names that don't match an active
+    /// parameter are left out rather than failing the editor.
+    pub fn layout(&self) -> Vec<PanelRow> {
+        let names: Vec<String> = self.handles().iter().map(ParamHandle::name).collect();
+        let mut rows = if self.rows.is_empty() { self.auto_rows() } else { self.rows.clone() };
+        for row in &mut rows {
+            row.controls.retain(|(name, _)| names.contains(name));
+        }
+        rows
+    }
+
+    pub fn build(self) -> impl Widget<EditorState<Model>> {
+        let handles = self.handles();
+        let mut column = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+        for row in self.layout() {
+            let mut flex = Flex::row().cross_axis_alignment(CrossAxisAlignment::End);
+            for (name, kind) in &row.controls {
+                if let Some(handle) = handles.iter().find(|h| &h.name() == name) {
+                    flex.add_child(control(handle, *kind).padding(Insets::uniform_xy(5., 0.)));
+                }
+            }
+            match row.title {
+                Some(title) => column.add_child(
+                    Flex::column()
+                        .cross_axis_alignment(CrossAxisAlignment::Start)
+                        .with_child(Label::new(title).with_text_size(theme::TEXT_SIZE_LARGE))
+                        .with_child(flex)
+                        .padding(5.)
+                        .border(theme::BORDER_DARK, 1.)
+                        .padding(Insets::uniform_xy(0., 5.)),
+                ),
+                None => column.add_child(flex.padding(Insets::uniform_xy(0., 5.))),
+            }
+        }
+        column
+    }
+}
+
+fn owned(controls: &[(&str, ControlKind)]) -> Vec<(String, ControlKind)> {
+    controls.iter().map(|(name, kind)| (name.to_string(), *kind)).collect()
+}
+
+fn control<Model: CarnyxModel>(handle: &ParamHandle<Model>, kind: ControlKind) -> Box<dyn Widget<EditorState<Model>>>
+    where Model::Snap: Data {
+    match kind {
+        ControlKind::Dial => Box::new(dial_for_param(handle)),
+        ControlKind::Slider => Box::new(slider_for_param(handle)),
+        ControlKind::Toggle => Box::new(handle.with_tooltip(handle.with_focus(handle.guard(
+            Checkbox::new(handle.name())
+                .lens(handle.lens().map(|value| *value >= 0.5, |value, on| *value = if on { 1. } else { 0. })),
+        )))),
+        ControlKind::Choice(names) => {
+            let last = names.len().saturating_sub(1).max(1) as f32;
+            // the same f32 arithmetic parameters use, so the selected button matches exactly
+            let choices = names.iter().enumerate().map(|(i, name)| (*name, (i as f32 / last) as f64));
+            Box::new(handle.with_tooltip(handle.with_focus(
+                Flex::column()
+                    .with_child(handle.title())
+                    .with_child(handle.guard(RadioGroup::for_axis(Axis::Horizontal, choices).lens(handle.lens()))),
+            )))
+        }
+    }
+}
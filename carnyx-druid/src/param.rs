@@ -0,0 +1,384 @@
+//! Controls bound to a [`CarnyxParam`] rather than to a field of the model's snapshot, so
+//! they can take their range, units and formatting from the parameter itself.
+
+use std::sync::Arc;
+
+use druid::widget::{Axis, Controller, ControllerHost, Flex, Label, Slider};
+use druid::{ContextMenu, Data, Env, Event, EventCtx, Lens, LifeCycle, LifeCycleCtx, LocalizedString, MenuDesc, MenuItem, Selector, Widget, WidgetExt};
+
+use carnyx::carnyx::{CarnyxModel, CarnyxParam, ParamList};
+use carnyx::{LockSet, Modulation};
+
+use crate::dial::CONTEXT_MENU;
+use crate::druid_editor::EditorState;
+use crate::lock::LockToggle;
+use crate::readout::{ParamFocus, PARAM_FOCUS};
+use crate::tooltip::Tooltip;
+use crate::{Dial, XYPad};
+
+/// The normalized value of every parameter, as last read from the model. Widgets edit
+/// these and the editor writes the changes back through the parameters.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParamValues(Arc<Vec<f32>>);
+
+impl ParamValues {
+    pub fn read<Model: CarnyxModel>(params: &[Box<dyn CarnyxParam<Model>>], model: &Model) -> Self {
+        ParamValues(Arc::new(params.iter().map(|p| p.get_value(model)).collect()))
+    }
+
+    pub fn get(&self, index: usize) -> Option<f32> {
+        self.0.get(index).copied()
+    }
+
+    pub fn set(&mut self, index: usize, value: f32) {
+        if let Some(slot) = Arc::make_mut(&mut self.0).get_mut(index) {
+            *slot = value;
+        }
+    }
+
+    /// Indices whose values differ between `self` and `other`.
+    pub fn changed<'a>(&'a self, other: &'a ParamValues) -> impl Iterator<Item = usize> + 'a {
+        self.0.iter().zip(other.0.iter()).enumerate().filter(|(_, (a, b))| a != b).map(|(i, _)| i)
+    }
+}
+
+impl Data for ParamValues {
+    fn same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+/// Lenses an editor's state to the normalized value of one parameter.
+#[derive(Clone, Copy, Debug)]
+pub struct ParamLens {
+    index: usize,
+}
+
+impl ParamLens {
+    pub fn new(index: usize) -> Self {
+        ParamLens { index }
+    }
+}
+
+impl<Model: CarnyxModel> Lens<EditorState<Model>, f64> for ParamLens where Model::Snap: Data {
+    fn with<V, F: FnOnce(&f64) -> V>(&self, data: &EditorState<Model>, f: F) -> V {
+        f(&(data.params.get(self.index).unwrap_or(0.) as f64))
+    }
+
+    fn with_mut<V, F: FnOnce(&mut f64) -> V>(&self, data: &mut EditorState<Model>, f: F) -> V {
+        let old = data.params.get(self.index).unwrap_or(0.) as f64;
+        let mut value = old;
+        let result = f(&mut value);
+        if value != old {
+            data.params.set(self.index, value as f32);
+        }
+        result
+    }
+}
+
+/// Lenses an editor's state to the normalized values of two parameters, for an
+/// [`XYPad`](crate::XYPad).
+#[derive(Clone, Copy, Debug)]
+pub struct ParamPairLens {
+    x: ParamLens,
+    y: ParamLens,
+}
+
+impl ParamPairLens {
+    pub fn new(x: usize, y: usize) -> Self {
+        ParamPairLens { x: ParamLens::new(x), y: ParamLens::new(y) }
+    }
+}
+
+impl<Model: CarnyxModel> Lens<EditorState<Model>, (f64, f64)> for ParamPairLens where Model::Snap: Data {
+    fn with<V, F: FnOnce(&(f64, f64)) -> V>(&self, data: &EditorState<Model>, f: F) -> V {
+        let x = self.x.with(data, |x| *x);
+        f(&(x, self.y.with(data, |y| *y)))
+    }
+
+    fn with_mut<V, F: FnOnce(&mut (f64, f64)) -> V>(&self, data: &mut EditorState<Model>, f: F) -> V {
+        let mut value = (self.x.with(data, |x| *x), self.y.with(data, |y| *y));
+        let result = f(&mut value);
+        self.x.with_mut(data, |x| *x = value.0);
+        self.y.with_mut(data, |y| *y = value.1);
+        result
+    }
+}
+
+/// Lenses an editor's state to whether one parameter is locked against the host.
+#[derive(Clone, Copy, Debug)]
+pub struct LockLens {
+    index: usize,
+}
+
+impl LockLens {
+    pub fn new(index: usize) -> Self {
+        LockLens { index }
+    }
+}
+
+impl<Model: CarnyxModel> Lens<EditorState<Model>, bool> for LockLens where Model::Snap: Data {
+    fn with<V, F: FnOnce(&bool) -> V>(&self, data: &EditorState<Model>, f: F) -> V {
+        f(&data.locks.contains(self.index))
+    }
+
+    fn with_mut<V, F: FnOnce(&mut bool) -> V>(&self, data: &mut EditorState<Model>, f: F) -> V {
+        let old = data.locks.contains(self.index);
+        let mut locked = old;
+        let result = f(&mut locked);
+        if locked != old {
+            data.locks.set(self.index, locked);
+        }
+        result
+    }
+}
+
+/// One parameter of a model, for building controls from.
+pub struct ParamHandle<Model: CarnyxModel> {
+    model: Arc<Model>,
+    params: ParamList<Model>,
+    index: usize,
+}
+
+impl<Model: CarnyxModel> Clone for ParamHandle<Model> {
+    fn clone(&self) -> Self {
+        ParamHandle { model: Arc::clone(&self.model), params: Arc::clone(&self.params), index: self.index }
+    }
+}
+
+impl<Model: CarnyxModel> ParamHandle<Model> {
+    pub fn new(model: Arc<Model>, params: ParamList<Model>, index: usize) -> Self {
+        ParamHandle { model, params, index }
+    }
+
+    /// Look a parameter up by the name it reports to the host.
+    pub fn by_name(model: Arc<Model>, params: ParamList<Model>, name: &str) -> Option<Self> {
+        let index = params.iter().position(|p| p.name(&model) == name)?;
+        Some(ParamHandle::new(model, params, index))
+    }
+
+    /// Handles for every parameter, in host order.
+    pub fn all(model: Arc<Model>, params: ParamList<Model>) -> Vec<Self> {
+        (0..params.len()).map(|index| ParamHandle::new(Arc::clone(&model), Arc::clone(&params), index)).collect()
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn param(&self) -> &dyn CarnyxParam<Model> {
+        &*self.params[self.index]
+    }
+
+    pub fn name(&self) -> String {
+        self.param().name(&self.model)
+    }
+
+    /// Whether the processor uses the parameter in its current configuration.
+    pub fn is_active(&self) -> bool {
+        self.param().is_active(&self.model)
+    }
+
+    /// Names for each of the parameter's steps, as of now.
+    pub fn step_names(&self) -> Vec<String> {
+        self.param().step_names(&self.model)
+    }
+
+    /// The current value as the host would show it, with its unit, e.g. "-12.0 dB".
+    pub fn display(&self) -> String {
+        let param = self.param();
+        let label = param.label(&self.model);
+        let formatted = param.formatted(&self.model);
+        if label.is_empty() {
+            formatted
+        } else {
+            format!("{} {}", formatted, label)
+        }
+    }
+
+    /// The description with the current value, for tooltips.
+    pub fn tooltip(&self) -> String {
+        match self.param().description() {
+            "" => self.display(),
+            description => format!("{}\n{}", description, self.display()),
+        }
+    }
+
+    /// Wrap a control for this parameter so hovering over it shows `tooltip`.
+    pub fn with_tooltip<T: Data>(&self, control: impl Widget<T> + 'static) -> Tooltip<T> {
+        let handle = self.clone();
+        Tooltip::new(control, move |_| handle.tooltip())
+    }
+
+    pub fn lens(&self) -> ParamLens {
+        ParamLens::new(self.index)
+    }
+
+    /// Whether the model supports locking parameters against the host.
+    pub fn lockable(&self) -> bool {
+        self.index < LockSet::CAPACITY && self.model.locks().is_some() && !self.param().is_read_only()
+    }
+
+    /// `control`, made to ignore input if the parameter is read only.
+    pub fn guard<T: Data>(&self, control: impl Widget<T> + 'static) -> Box<dyn Widget<T>> {
+        if self.param().is_read_only() {
+            Box::new(control.controller(ReadOnly))
+        } else {
+            Box::new(control)
+        }
+    }
+
+    /// `control`, made to tell any [`ValueReadout`](crate::ValueReadout) when it is hovered
+    /// or dragged.
+    pub fn with_focus<T: Data, W: Widget<T>>(&self, control: W) -> ControllerHost<W, ParamController> {
+        control.controller(ParamController::new(self.index))
+    }
+
+    /// Whether the processor can modulate the parameter and tell the editor about it, for
+    /// [`Dial::with_modulation`].
+    pub fn is_modulatable(&self) -> bool {
+        self.model.modulation().is_some() && self.model.telemetry().is_some() && self.index < Modulation::CAPACITY
+    }
+
+    pub fn lock_lens(&self) -> LockLens {
+        LockLens::new(self.index)
+    }
+
+    /// The parameter's name, with a padlock if it can be locked.
+    pub fn title(&self) -> impl Widget<EditorState<Model>> where Model::Snap: Data {
+        let mut row = Flex::row().with_child(Label::new(self.name()));
+        if self.lockable() {
+            row.add_child(LockToggle::new().lens(self.lock_lens()));
+        }
+        row
+    }
+}
+
+/// Lets a control show a value without the mouse or keyboard changing it.
+pub struct ReadOnly;
+
+impl<T, W: Widget<T>> Controller<T, W> for ReadOnly {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(_) | Event::MouseUp(_) | Event::MouseMove(_) | Event::Wheel(_)
+            | Event::KeyDown(_) | Event::KeyUp(_) => (),
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
+
+/// Sent by a parameter's menu to its control, to reset it.
+const RESET_PARAM: Selector = Selector::new("carnyx-druid.reset-param");
+
+/// Shows a menu for a parameter when its control sends [`CONTEXT_MENU`], as a [`Dial`] does
+/// on a right click or a long press, offering what a double click does with a mouse.
+pub struct ParamMenu {
+    // each parameter the control sets, with its default
+    params: Vec<(ParamLens, f64)>,
+}
+
+impl ParamMenu {
+    pub fn new<Model: CarnyxModel>(handle: &ParamHandle<Model>) -> Self {
+        ParamMenu { params: vec![(handle.lens(), handle.param().default_value() as f64)] }
+    }
+
+    /// A menu for a control setting two parameters, which resets both.
+    pub fn pair<Model: CarnyxModel>(x: &ParamHandle<Model>, y: &ParamHandle<Model>) -> Self {
+        let mut menu = ParamMenu::new(x);
+        menu.params.push((y.lens(), y.param().default_value() as f64));
+        menu
+    }
+}
+
+impl<Model: CarnyxModel, W: Widget<EditorState<Model>>> Controller<EditorState<Model>, W> for ParamMenu where Model::Snap: Data {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut EditorState<Model>, env: &Env) {
+        match event {
+            Event::Command(command) if command.is(CONTEXT_MENU) => {
+                let position = command.get(CONTEXT_MENU).copied().unwrap_or_default();
+                let reset = LocalizedString::new("carnyx-param-reset").with_placeholder("Reset to default");
+                let menu = MenuDesc::<EditorState<Model>>::empty().append(MenuItem::new(reset, RESET_PARAM.to(ctx.widget_id())));
+                ctx.show_context_menu(ContextMenu::new(menu, position));
+                ctx.set_handled();
+            }
+            Event::Command(command) if command.is(RESET_PARAM) => {
+                for (lens, default) in &self.params {
+                    lens.with_mut(data, |value| *value = *default);
+                }
+                ctx.set_handled();
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
+
+/// Sends [`PARAM_FOCUS`] as the pointer enters and leaves a parameter's control, and as
+/// dragging it starts and stops.
+pub struct ParamController {
+    focus: ParamFocus,
+}
+
+impl ParamController {
+    pub fn new(index: usize) -> Self {
+        ParamController { focus: ParamFocus { index, hovered: false, dragging: false } }
+    }
+}
+
+impl<T, W: Widget<T>> Controller<T, W> for ParamController {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let dragging = match event {
+            Event::MouseDown(_) => true,
+            Event::MouseUp(_) => false,
+            _ => self.focus.dragging,
+        };
+        if dragging != self.focus.dragging {
+            self.focus.dragging = dragging;
+            ctx.submit_command(PARAM_FOCUS.with(self.focus));
+        }
+        child.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, child: &mut W, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::HotChanged(hot) = event {
+            self.focus.hovered = *hot;
+            ctx.submit_command(PARAM_FOCUS.with(self.focus));
+        }
+        child.lifecycle(ctx, event, data, env)
+    }
+}
+
+fn readout<Model: CarnyxModel>(handle: &ParamHandle<Model>) -> impl Widget<EditorState<Model>> where Model::Snap: Data {
+    // the model is written before the editor state changes, so this is never stale
+    let handle = handle.clone();
+    Label::dynamic(move |_: &EditorState<Model>, _| handle.display())
+}
+
+/// A labelled dial for a parameter, showing its value in the parameter's units.
+pub fn dial_for_param<Model: CarnyxModel>(handle: &ParamHandle<Model>) -> impl Widget<EditorState<Model>> where Model::Snap: Data {
+    let mut dial = Dial::new().with_default(handle.param().default_value() as f64);
+    if handle.is_modulatable() {
+        dial = dial.with_modulation(handle.index());
+    }
+    handle.with_tooltip(handle.with_focus(Flex::column()
+        .with_child(handle.title())
+        .with_child(handle.guard(dial.lens(handle.lens()).controller(ParamMenu::new(handle))))
+        .with_child(readout(handle))))
+}
+
+/// A pad for two parameters, `x` across and `y` up, labelled with both and showing both
+/// values in their units.
+pub fn xy_pad_for_params<Model: CarnyxModel>(x: &ParamHandle<Model>, y: &ParamHandle<Model>) -> impl Widget<EditorState<Model>> where Model::Snap: Data {
+    let pad = XYPad::new().lens(ParamPairLens::new(x.index(), y.index())).controller(ParamMenu::pair(x, y));
+    let pad = x.guard(y.guard(pad));
+    Flex::column()
+        .with_child(Flex::row().with_child(x.title()).with_spacer(8.).with_child(y.title()))
+        .with_child(y.with_focus(x.with_focus(pad)))
+        .with_child(Flex::row().with_child(readout(x)).with_spacer(8.).with_child(readout(y)))
+}
+
+/// A labelled vertical slider for a parameter, showing its value in the parameter's units.
+pub fn slider_for_param<Model: CarnyxModel>(handle: &ParamHandle<Model>) -> impl Widget<EditorState<Model>> where Model::Snap: Data {
+    handle.with_tooltip(handle.with_focus(Flex::column()
+        .with_child(handle.title())
+        .with_child(handle.guard(Slider::for_axis(Axis::Vertical).lens(handle.lens())))
+        .with_child(readout(handle))))
+}
@@ -0,0 +1,24 @@
+//! A debugging panel which solos one of the processor's probe points to the output.
+
+use druid::widget::{Axis, CrossAxisAlignment, Flex, Label, RadioGroup, SizedBox};
+use druid::{Data, Widget, WidgetExt};
+
+use carnyx::carnyx::CarnyxModel;
+
+use crate::druid_editor::EditorState;
+
+/// A choice of the model's [`Probes`](carnyx::Probes), or the usual output. Empty for
+/// models without probes.
+pub fn probe_panel<Model: CarnyxModel>(model: &Model) -> Box<dyn Widget<EditorState<Model>>> where Model::Snap: Data {
+    let names = match model.probes() {
+        Some(probes) => probes.names(),
+        None => return Box::new(SizedBox::empty()),
+    };
+    let choices = std::iter::once(("Off", None)).chain(names.iter().enumerate().map(|(i, name)| (*name, Some(i))));
+    Box::new(
+        Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(Label::new("Solo probe"))
+            .with_child(RadioGroup::for_axis(Axis::Vertical, choices).lens(EditorState::probe)),
+    )
+}
@@ -0,0 +1,122 @@
+//! One place showing the value of whichever parameter is being pointed at, instead of a
+//! label under every control.
+
+use std::sync::Arc;
+
+use druid::widget::prelude::*;
+use druid::{theme, Point, Selector, TextLayout};
+
+use carnyx::carnyx::{CarnyxModel, ParamList};
+
+use crate::druid_editor::EditorState;
+use crate::param::ParamHandle;
+
+/// Sent by a [`ParamController`](crate::ParamController) when its control is hovered or
+/// dragged, or stops being.
+pub const PARAM_FOCUS: Selector<ParamFocus> = Selector::new("carnyx-druid.param-focus");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamFocus {
+    pub index: usize,
+    pub hovered: bool,
+    pub dragging: bool,
+}
+
+type Format<Model> = Box<dyn Fn(&ParamHandle<Model>) -> String>;
+
+/// Shows the name and value of the parameter being dragged, or failing that the one under
+/// the pointer. Controls built by [`dial_for_param`](crate::dial_for_param) and the like
+/// report themselves; others can be wrapped with [`ParamHandle::with_focus`].
+pub struct ValueReadout<Model: CarnyxModel> {
+    handles: Vec<ParamHandle<Model>>,
+    format: Format<Model>,
+    placeholder: String,
+    hovered: Option<usize>,
+    dragging: Option<usize>,
+    text: TextLayout<String>,
+}
+
+impl<Model: CarnyxModel> ValueReadout<Model> where Model::Snap: Data {
+    pub fn new(model: Arc<Model>, params: ParamList<Model>) -> Self {
+        ValueReadout {
+            handles: ParamHandle::all(model, params),
+            format: Box::new(|handle| format!("{}: {}", handle.name(), handle.display())),
+            placeholder: String::new(),
+            hovered: None,
+            dragging: None,
+            text: TextLayout::new(),
+        }
+    }
+
+    /// Builder-style method to set how a parameter is shown. The default is "name: value".
+    pub fn with_format(mut self, format: impl Fn(&ParamHandle<Model>) -> String + 'static) -> Self {
+        self.format = Box::new(format);
+        self
+    }
+
+    /// Builder-style method to set what is shown when no parameter is.
+    pub fn with_placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = placeholder.into();
+        self
+    }
+
+    fn focus(&mut self, focus: &ParamFocus) {
+        if focus.dragging {
+            self.dragging = Some(focus.index);
+        } else if self.dragging == Some(focus.index) {
+            self.dragging = None;
+        }
+        if focus.hovered {
+            self.hovered = Some(focus.index);
+        } else if self.hovered == Some(focus.index) {
+            self.hovered = None;
+        }
+    }
+
+    fn refresh(&mut self) {
+        let shown = self.dragging.or(self.hovered).and_then(|index| self.handles.get(index));
+        let text = shown.map(|handle| (self.format)(handle)).unwrap_or_else(|| self.placeholder.clone());
+        self.text.set_text(text);
+    }
+}
+
+impl<Model: CarnyxModel> Widget<EditorState<Model>> for ValueReadout<Model> where Model::Snap: Data {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut EditorState<Model>, _env: &Env) {
+        if let Event::Command(cmd) = event {
+            if let Some(focus) = cmd.get(PARAM_FOCUS) {
+                self.focus(focus);
+                self.refresh();
+                ctx.request_layout();
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &EditorState<Model>, _env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.text.set_text_color(theme::LABEL_COLOR);
+            self.refresh();
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &EditorState<Model>, data: &EditorState<Model>, _env: &Env) {
+        // the model is written before the editor state changes, so the handles are current
+        if !old_data.same(data) {
+            self.refresh();
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &EditorState<Model>, env: &Env) -> Size {
+        self.text.rebuild_if_needed(ctx.text(), env);
+        let size = self.text.size();
+        // take the width offered, so the readout doesn't shift its neighbours as it changes
+        let width = if bc.max().width.is_finite() { bc.max().width } else { size.width };
+        bc.constrain(Size::new(width, size.height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &EditorState<Model>, _env: &Env) {
+        self.text.draw(ctx, Point::ORIGIN);
+    }
+
+    fn post_render(&mut self) {}
+}
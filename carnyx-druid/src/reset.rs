@@ -0,0 +1,35 @@
+//! Returning controls to their default value.
+
+use druid::widget::prelude::*;
+use druid::widget::Controller;
+use druid::MouseEvent;
+
+/// Cmd/Ctrl-click or double-click, the usual gesture for resetting a control.
+pub fn is_reset_click(mouse: &MouseEvent) -> bool {
+    mouse.count == 2 || mouse.mods.ctrl() || mouse.mods.meta()
+}
+
+/// A [`Controller`] that resets a numeric control to a default value on a reset click,
+/// for widgets such as `Slider` which don't support it themselves.
+pub struct ResetToDefault {
+    default: f64,
+}
+
+impl ResetToDefault {
+    pub fn new(default: f64) -> Self {
+        ResetToDefault { default }
+    }
+}
+
+impl<W: Widget<f64>> Controller<f64, W> for ResetToDefault {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut f64, env: &Env) {
+        if let Event::MouseDown(mouse) = event {
+            if is_reset_click(mouse) {
+                *data = self.default;
+                ctx.set_handled();
+                return;
+            }
+        }
+        child.event(ctx, event, data, env)
+    }
+}
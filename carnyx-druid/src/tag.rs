@@ -0,0 +1,50 @@
+//! A header bar naming the instance, with a color swatch, so instances of the same plugin
+//! can be told apart at a glance.
+
+use druid::kurbo::Circle;
+use druid::widget::{CrossAxisAlignment, Flex, Painter, TextBox};
+use druid::{theme, Color, Data, LensExt, RenderContext, Widget, WidgetExt};
+
+use carnyx::carnyx::CarnyxModel;
+use carnyx::TAG_COLORS;
+
+use crate::druid_editor::EditorState;
+
+const SWATCH: f64 = 16.;
+
+fn tag_color(color: Option<usize>) -> Option<Color> {
+    color.and_then(|color| TAG_COLORS.get(color)).map(|(_, rgb)| Color::from_rgba32_u32(rgb << 8 | 0xff))
+}
+
+/// An editable nickname, and a swatch which steps through the [`TAG_COLORS`] and back to
+/// none when clicked. Both are saved with the plugin's state.
+pub fn instance_header<Model: CarnyxModel>() -> impl Widget<EditorState<Model>> where Model::Snap: Data {
+    let swatch = Painter::new(|ctx, color: &Option<usize>, env| {
+        let size = ctx.size();
+        let dot = Circle::new((size.width / 2., size.height / 2.), SWATCH / 2. - 1.);
+        match tag_color(*color) {
+            Some(fill) => ctx.fill(dot, &fill),
+            None => ctx.fill(dot, &env.get(theme::BACKGROUND_DARK)),
+        }
+        ctx.stroke(dot, &env.get(theme::BORDER_DARK), 1.);
+    })
+    .fix_size(SWATCH, SWATCH)
+    .on_click(|_, color: &mut Option<usize>, _| {
+        *color = match *color {
+            None => Some(0),
+            Some(color) if color + 1 < TAG_COLORS.len() => Some(color + 1),
+            Some(_) => None,
+        };
+    })
+    .lens(EditorState::tag_color);
+    let nickname = TextBox::new()
+        .with_placeholder("Name this instance")
+        .fix_width(200.)
+        .lens(EditorState::nickname.in_arc());
+    Flex::row()
+        .cross_axis_alignment(CrossAxisAlignment::Center)
+        .with_child(swatch)
+        .with_spacer(6.)
+        .with_child(nickname)
+        .padding(4.)
+}
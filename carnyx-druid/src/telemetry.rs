@@ -0,0 +1,12 @@
+//! Delivers the processor's [`Telemetry`](carnyx::Telemetry) to the editor's widgets, once
+//! a frame; see [`FRAME`](crate::FRAME).
+
+use std::sync::Arc;
+
+use druid::Selector;
+
+use carnyx::Telemetry;
+
+/// Sent to every widget with the telemetry which arrived since the last frame, oldest
+/// first. Widgets pick out the messages they show.
+pub const TELEMETRY: Selector<Arc<Vec<Telemetry>>> = Selector::new("carnyx-druid.telemetry");
@@ -0,0 +1,117 @@
+//! Editor themes, applied as `Env` overrides so the stock druid widgets follow them too.
+
+use druid::{theme, Color, Env, Key};
+
+/// Whether a [`Dial`](crate::Dial) fills its value arc or only outlines it.
+pub const KNOB_FILLED: Key<bool> = Key::new("carnyx-druid.knob-filled");
+/// Whether controls are worked by touch or pen, so a [`Dial`](crate::Dial) or an
+/// [`XYPad`](crate::XYPad) takes more room, drags without jumping and opens its menu on a
+/// long press.
+pub const TOUCH_INPUT: Key<bool> = Key::new("carnyx-druid.touch-input");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KnobStyle {
+    Filled,
+    Outline,
+}
+
+/// Colors and sizes for an editor. Start from [`CarnyxTheme::dark`] or
+/// [`CarnyxTheme::light`] and change what the plugin's branding needs.
+#[derive(Debug, Clone)]
+pub struct CarnyxTheme {
+    pub background: Color,
+    /// Behind controls, e.g. the scope and slider tracks.
+    pub background_dark: Color,
+    pub accent: Color,
+    pub accent_dark: Color,
+    /// Knob and handle gradients run from light to dark.
+    pub foreground_light: Color,
+    pub foreground_dark: Color,
+    pub border: Color,
+    pub text: Color,
+    pub text_size: f64,
+    pub heading_size: f64,
+    pub knob_style: KnobStyle,
+    /// Druid doesn't say whether a pointer is a mouse, a finger or a pen, so editors for
+    /// touch screens say so here.
+    pub touch_input: bool,
+}
+
+impl Default for CarnyxTheme {
+    fn default() -> Self {
+        CarnyxTheme::dark()
+    }
+}
+
+impl CarnyxTheme {
+    pub fn dark() -> Self {
+        CarnyxTheme {
+            background: Color::rgb8(0x29, 0x29, 0x29),
+            background_dark: Color::rgb8(0x1a, 0x1a, 0x1a),
+            accent: Color::rgb8(0x5c, 0xc4, 0xff),
+            accent_dark: Color::rgb8(0x00, 0x8d, 0xdd),
+            foreground_light: Color::rgb8(0xf0, 0xf0, 0xea),
+            foreground_dark: Color::rgb8(0x9e, 0x9e, 0x9e),
+            border: Color::rgb8(0x3a, 0x3a, 0x3a),
+            text: Color::rgb8(0xf0, 0xf0, 0xea),
+            text_size: 15.,
+            heading_size: 24.,
+            knob_style: KnobStyle::Filled,
+            touch_input: false,
+        }
+    }
+
+    pub fn light() -> Self {
+        CarnyxTheme {
+            background: Color::rgb8(0xf2, 0xf0, 0xeb),
+            background_dark: Color::rgb8(0xd8, 0xd5, 0xcc),
+            accent: Color::rgb8(0xe0, 0x6c, 0x1f),
+            accent_dark: Color::rgb8(0xa8, 0x4a, 0x0c),
+            foreground_light: Color::rgb8(0x5a, 0x5a, 0x5a),
+            foreground_dark: Color::rgb8(0x2a, 0x2a, 0x2a),
+            border: Color::rgb8(0xb8, 0xb4, 0xaa),
+            text: Color::rgb8(0x20, 0x20, 0x20),
+            text_size: 15.,
+            heading_size: 24.,
+            knob_style: KnobStyle::Outline,
+            touch_input: false,
+        }
+    }
+
+    /// Builder-style method to set the accent colors.
+    pub fn with_accent(mut self, accent: Color, accent_dark: Color) -> Self {
+        self.accent = accent;
+        self.accent_dark = accent_dark;
+        self
+    }
+
+    /// Builder-style method to set the knob style.
+    pub fn with_knob_style(mut self, knob_style: KnobStyle) -> Self {
+        self.knob_style = knob_style;
+        self
+    }
+
+    /// Builder-style method to set up controls for touch or pen input.
+    pub fn with_touch_input(mut self, touch_input: bool) -> Self {
+        self.touch_input = touch_input;
+        self
+    }
+
+    /// Override the druid theme keys in `env`.
+    pub fn apply(&self, env: &mut Env) {
+        env.set(theme::WINDOW_BACKGROUND_COLOR, self.background.clone());
+        env.set(theme::BACKGROUND_LIGHT, self.background.clone());
+        env.set(theme::BACKGROUND_DARK, self.background_dark.clone());
+        env.set(theme::PRIMARY_LIGHT, self.accent.clone());
+        env.set(theme::PRIMARY_DARK, self.accent_dark.clone());
+        env.set(theme::FOREGROUND_LIGHT, self.foreground_light.clone());
+        env.set(theme::FOREGROUND_DARK, self.foreground_dark.clone());
+        env.set(theme::BORDER_DARK, self.border.clone());
+        env.set(theme::BORDER_LIGHT, self.foreground_dark.clone());
+        env.set(theme::LABEL_COLOR, self.text.clone());
+        env.set(theme::TEXT_SIZE_NORMAL, self.text_size);
+        env.set(theme::TEXT_SIZE_LARGE, self.heading_size);
+        env.set(KNOB_FILLED, self.knob_style == KnobStyle::Filled);
+        env.set(TOUCH_INPUT, self.touch_input);
+    }
+}
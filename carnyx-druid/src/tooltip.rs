@@ -0,0 +1,107 @@
+//! Hover tooltips.
+
+use std::time::Duration;
+
+use druid::kurbo::Vec2;
+use druid::widget::prelude::*;
+use druid::{theme, Point, TextLayout, TimerToken, WidgetPod};
+
+const HOVER_DELAY: Duration = Duration::from_millis(600);
+const PADDING: f64 = 4.0;
+// below and to the right of the pointer, clear of the cursor image
+const OFFSET: Vec2 = Vec2::new(12.0, 18.0);
+
+/// Wraps a widget, showing some text after the pointer has rested over it a moment.
+/// The text is produced when the tooltip appears, so it can include current values.
+pub struct Tooltip<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    text: Box<dyn Fn(&T) -> String>,
+    timer: TimerToken,
+    mouse: Point,
+    shown: Option<TextLayout<String>>,
+}
+
+impl<T: Data> Tooltip<T> {
+    pub fn new(child: impl Widget<T> + 'static, text: impl Fn(&T) -> String + 'static) -> Self {
+        Tooltip {
+            child: WidgetPod::new(Box::new(child)),
+            text: Box::new(text),
+            timer: TimerToken::INVALID,
+            mouse: Point::ORIGIN,
+            shown: None,
+        }
+    }
+
+    fn hide(&mut self, ctx: &mut EventCtx) {
+        self.timer = TimerToken::INVALID;
+        if self.shown.take().is_some() {
+            ctx.request_paint();
+        }
+    }
+}
+
+impl<T: Data> Widget<T> for Tooltip<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseMove(mouse) if ctx.is_hot() => {
+                self.hide(ctx);
+                self.mouse = mouse.pos;
+                self.timer = ctx.request_timer(HOVER_DELAY);
+            }
+            Event::MouseDown(_) | Event::Wheel(_) => self.hide(ctx),
+            Event::Timer(token) if *token == self.timer => {
+                self.timer = TimerToken::INVALID;
+                let text = (self.text)(data);
+                if ctx.is_hot() && !text.is_empty() {
+                    let mut layout = TextLayout::from_text(text);
+                    layout.set_text_color(theme::LABEL_COLOR);
+                    self.shown = Some(layout);
+                    ctx.request_paint();
+                }
+                return;
+            }
+            _ => (),
+        }
+        self.child.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::HotChanged(false) = event {
+            self.timer = TimerToken::INVALID;
+            if self.shown.take().is_some() {
+                ctx.request_paint();
+            }
+        }
+        self.child.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, data, env);
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+        if let Some(layout) = &mut self.shown {
+            layout.rebuild_if_needed(ctx.text(), env);
+            let layout = layout.clone();
+            let origin = self.mouse + OFFSET;
+            let background = env.get(theme::BACKGROUND_DARK);
+            let border = env.get(theme::BORDER_LIGHT);
+            // above sibling widgets, which paint after this one
+            ctx.paint_with_z_index(1, move |ctx| {
+                let rect = layout.size().to_rect().inset(PADDING).with_origin(origin);
+                ctx.fill(rect, &background);
+                ctx.stroke(rect, &border, 1.0);
+                layout.draw(ctx, origin + Vec2::new(PADDING, PADDING));
+            });
+        }
+    }
+
+    fn post_render(&mut self) {}
+}
@@ -0,0 +1,127 @@
+//! Editor UI state, such as scroll offsets, restored when an editor is reopened.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use druid::kurbo::Vec2;
+use druid::widget::{Controller, Scroll};
+use druid::{Data, Env, Event, EventCtx, LifeCycle, LifeCycleCtx, Lens, Widget};
+
+use carnyx::carnyx::CarnyxModel;
+
+use crate::druid_editor::EditorState;
+
+// frames to keep trying to restore a scroll offset, while the content is laid out
+const RESTORE_ATTEMPTS: usize = 4;
+
+/// The editor's copy of the model's [`UiState`](carnyx::UiState). Changes are written back
+/// to the model as they happen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UiValues(Arc<BTreeMap<String, f64>>);
+
+impl UiValues {
+    pub fn new(values: BTreeMap<String, f64>) -> Self {
+        UiValues(Arc::new(values))
+    }
+
+    pub fn get(&self, key: &str) -> Option<f64> {
+        self.0.get(key).copied()
+    }
+
+    pub fn set(&mut self, key: &str, value: f64) {
+        if self.get(key) != Some(value) {
+            Arc::make_mut(&mut self.0).insert(key.to_string(), value);
+        }
+    }
+
+    pub fn values(&self) -> BTreeMap<String, f64> {
+        (*self.0).clone()
+    }
+}
+
+impl Data for UiValues {
+    fn same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+/// Lenses an editor's state to one remembered value, e.g. the selected page.
+#[derive(Clone, Debug)]
+pub struct UiLens {
+    key: String,
+    default: f64,
+}
+
+impl UiLens {
+    pub fn new(key: impl Into<String>, default: f64) -> Self {
+        UiLens { key: key.into(), default }
+    }
+}
+
+impl<Model: CarnyxModel> Lens<EditorState<Model>, f64> for UiLens where Model::Snap: Data {
+    fn with<V, F: FnOnce(&f64) -> V>(&self, data: &EditorState<Model>, f: F) -> V {
+        f(&data.ui.get(&self.key).unwrap_or(self.default))
+    }
+
+    fn with_mut<V, F: FnOnce(&mut f64) -> V>(&self, data: &mut EditorState<Model>, f: F) -> V {
+        let old = data.ui.get(&self.key).unwrap_or(self.default);
+        let mut value = old;
+        let result = f(&mut value);
+        if value != old {
+            data.ui.set(&self.key, value);
+        }
+        result
+    }
+}
+
+/// Remembers the offset of a [`Scroll`] under `key`, and scrolls back there when the
+/// editor is reopened.
+pub struct RememberScroll {
+    key_x: String,
+    key_y: String,
+    restoring: usize,
+}
+
+impl RememberScroll {
+    pub fn new(key: &str) -> Self {
+        RememberScroll { key_x: format!("{}.x", key), key_y: format!("{}.y", key), restoring: 0 }
+    }
+
+    fn saved<Model: CarnyxModel>(&self, data: &EditorState<Model>) -> Vec2 where Model::Snap: Data {
+        Vec2::new(data.ui.get(&self.key_x).unwrap_or(0.), data.ui.get(&self.key_y).unwrap_or(0.))
+    }
+}
+
+impl<Model: CarnyxModel, W: Widget<EditorState<Model>>> Controller<EditorState<Model>, Scroll<EditorState<Model>, W>>
+    for RememberScroll where Model::Snap: Data {
+    fn event(&mut self, child: &mut Scroll<EditorState<Model>, W>, ctx: &mut EventCtx, event: &Event,
+             data: &mut EditorState<Model>, env: &Env) {
+        if let (Event::AnimFrame(_), true) = (event, self.restoring > 0) {
+            let target = self.saved(data);
+            child.scroll_by(target - child.offset());
+            ctx.request_paint();
+            // the content may not be big enough to scroll that far until it has been laid out
+            self.restoring = if (child.offset() - target).hypot() < 0.5 { 0 } else { self.restoring - 1 };
+            if self.restoring > 0 {
+                ctx.request_anim_frame();
+            }
+        }
+        child.event(ctx, event, data, env);
+        if self.restoring == 0 {
+            let offset = child.offset();
+            data.ui.set(&self.key_x, offset.x);
+            data.ui.set(&self.key_y, offset.y);
+        }
+    }
+
+    fn lifecycle(&mut self, child: &mut Scroll<EditorState<Model>, W>, ctx: &mut LifeCycleCtx, event: &LifeCycle,
+                 data: &EditorState<Model>, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            if self.saved(data) != Vec2::ZERO {
+                self.restoring = RESTORE_ATTEMPTS;
+                ctx.request_anim_frame();
+            }
+        }
+        child.lifecycle(ctx, event, data, env);
+    }
+}
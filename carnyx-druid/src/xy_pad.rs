@@ -0,0 +1,136 @@
+//! A pad setting two values at once, one across and one up.
+
+use druid::kurbo::Circle;
+use druid::widget::prelude::*;
+use druid::{theme, MouseButton, Point, TimerToken};
+
+use crate::dial::{CONTEXT_MENU, LONG_PRESS, TOUCH_SLOP, TOUCH_TARGET, TOUCH_TRAVEL};
+use crate::theme::TOUCH_INPUT;
+
+const HANDLE_RADIUS: f64 = 5.;
+// with touch input the handle is drawn bigger, as a finger covers it
+const TOUCH_HANDLE_RADIUS: f64 = 10.;
+
+// a touch on the pad, which drags from where it started once it moves far enough
+#[derive(Debug, Clone)]
+struct Press {
+    origin: Point,
+    window_pos: Point,
+    value: (f64, f64),
+    dragging: bool,
+    timer: TimerToken,
+}
+
+/// A square pad for two values in `0.0..=1.0`, the first increasing to the right and the
+/// second upwards, such as cutoff and resonance.
+///
+/// With a mouse a click jumps the handle to the pointer. With [`TOUCH_INPUT`] it behaves
+/// like a [`Dial`](crate::Dial): bigger, dragged relative to where the finger came down, and
+/// sending [`CONTEXT_MENU`] on a long press as well as a right click.
+#[derive(Debug, Clone, Default)]
+pub struct XYPad {
+    press: Option<Press>,
+}
+
+impl XYPad {
+    /// Create a new `XYPad`
+    pub fn new() -> XYPad {
+        XYPad { press: None }
+    }
+}
+
+fn value_at(pos: Point, size: Size) -> (f64, f64) {
+    let x = pos.x / size.width.max(1.);
+    let y = 1. - pos.y / size.height.max(1.);
+    (x.clamp(0., 1.), y.clamp(0., 1.))
+}
+
+fn handle_center(data: &(f64, f64), size: Size) -> Point {
+    Point::new(data.0.clamp(0., 1.) * size.width, (1. - data.1.clamp(0., 1.)) * size.height)
+}
+
+impl Widget<(f64, f64)> for XYPad {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut (f64, f64), env: &Env) {
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Right => {
+                ctx.submit_command(CONTEXT_MENU.with(mouse.window_pos).to(ctx.widget_id()));
+                ctx.set_handled();
+            }
+            Event::MouseDown(mouse) => {
+                ctx.set_active(true);
+                if env.try_get(TOUCH_INPUT).unwrap_or(false) {
+                    let timer = ctx.request_timer(LONG_PRESS);
+                    self.press = Some(Press { origin: mouse.pos, window_pos: mouse.window_pos, value: *data, dragging: false, timer });
+                } else {
+                    *data = value_at(mouse.pos, ctx.size());
+                }
+                ctx.request_paint();
+            }
+            Event::MouseUp(_) => {
+                self.press = None;
+                if ctx.is_active() {
+                    ctx.set_active(false);
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseMove(mouse) if ctx.is_active() => {
+                if let Some(press) = &mut self.press {
+                    let (across, rise) = (mouse.pos.x - press.origin.x, press.origin.y - mouse.pos.y);
+                    press.dragging |= across.hypot(rise) > TOUCH_SLOP;
+                    if press.dragging {
+                        // as the dial: from where the finger came down at a fixed rate, so
+                        // neither value jumps
+                        let x = press.value.0 + across / TOUCH_TRAVEL;
+                        let y = press.value.1 + rise / TOUCH_TRAVEL;
+                        *data = (x.clamp(0., 1.), y.clamp(0., 1.));
+                        ctx.request_paint();
+                    }
+                } else {
+                    *data = value_at(mouse.pos, ctx.size());
+                    ctx.request_paint();
+                }
+            }
+            Event::Timer(token) if self.press.as_ref().map(|press| press.timer == *token).unwrap_or(false) => {
+                // held still, rather than dragged
+                let held = self.press.as_ref().filter(|press| !press.dragging).map(|press| press.window_pos);
+                if let (Some(window_pos), true) = (held, ctx.is_active()) {
+                    self.press = None;
+                    ctx.set_active(false);
+                    ctx.submit_command(CONTEXT_MENU.with(window_pos).to(ctx.widget_id()));
+                    ctx.request_paint();
+                }
+                ctx.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &(f64, f64), _env: &Env) {}
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &(f64, f64), data: &(f64, f64), _env: &Env) {
+        if old_data != data {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &(f64, f64), env: &Env) -> Size {
+        bc.debug_check("XYPad");
+        let width = env.get(theme::WIDE_WIDGET_WIDTH);
+        let width = if env.try_get(TOUCH_INPUT).unwrap_or(false) { width.max(3. * TOUCH_TARGET) } else { width };
+        bc.constrain_aspect_ratio(1.0, width)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &(f64, f64), env: &Env) {
+        let rect = ctx.size().to_rect();
+        ctx.fill(rect, &env.get(theme::BACKGROUND_LIGHT));
+        ctx.stroke(rect.inset(-0.5), &env.get(theme::FOREGROUND_DARK).with_alpha(0.4), 1.0);
+
+        let radius = if env.try_get(TOUCH_INPUT).unwrap_or(false) { TOUCH_HANDLE_RADIUS } else { HANDLE_RADIUS };
+        let handle = Circle::new(handle_center(data, ctx.size()), radius);
+        let fill = if ctx.is_active() { env.get(theme::PRIMARY_LIGHT) } else { env.get(theme::PRIMARY_DARK) };
+        ctx.fill(handle, &fill);
+        ctx.stroke(handle, &env.get(theme::FOREGROUND_LIGHT), 1.0);
+    }
+
+    fn post_render(&mut self) {}
+}
@@ -0,0 +1,37 @@
+use carnyx_druid::Faceplate;
+
+const PANEL: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="400" height="300" viewBox="0 0 400 300">
+  <rect width="400" height="300" fill="#223"/>
+  <circle cx="200" cy="150" r="60" fill="none" stroke="#889" stroke-width="4"/>
+</svg>"##;
+
+// the png signature, then nothing
+const TRUNCATED_PNG: &[u8] = &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+#[test]
+#[cfg(feature = "svg")]
+fn svg_faceplates_parse_or_fail() {
+    assert!(Faceplate::Svg(PANEL).to_widget::<()>().is_ok());
+    assert!(Faceplate::Svg("<svg").to_widget::<()>().is_err());
+}
+
+#[test]
+#[cfg(not(feature = "svg"))]
+fn svg_faceplates_need_the_svg_feature() {
+    let err = Faceplate::Svg(PANEL).to_widget::<()>().err().unwrap();
+    assert!(err.to_string().contains("without support for Svg"), "{}", err);
+}
+
+#[test]
+#[cfg(feature = "image")]
+fn broken_png_faceplates_fail() {
+    assert!(Faceplate::Png(TRUNCATED_PNG).to_widget::<()>().is_err());
+    assert!(Faceplate::Png(PANEL.as_bytes()).to_widget::<()>().is_err());
+}
+
+#[test]
+#[cfg(not(feature = "image"))]
+fn png_faceplates_need_the_image_feature() {
+    let err = Faceplate::Png(TRUNCATED_PNG).to_widget::<()>().err().unwrap();
+    assert!(err.to_string().contains("without support for Png"), "{}", err);
+}
@@ -0,0 +1,37 @@
+use carnyx_druid::page_for_key;
+use druid::{KbKey, KeyEvent, Modifiers};
+
+// an editor with oscillator, filter, envelope and effects pages
+const PAGES: usize = 4;
+
+fn press(mods: Modifiers, key: KbKey) -> KeyEvent {
+    KeyEvent::for_test(mods, key)
+}
+
+#[test]
+fn ctrl_tab_and_page_down_go_forwards_and_wrap() {
+    let next = press(Modifiers::CONTROL, KbKey::Tab);
+    assert_eq!(page_for_key(&next, 0, PAGES), Some(1));
+    assert_eq!(page_for_key(&next, 3, PAGES), Some(0));
+    let page_down = press(Modifiers::CONTROL, KbKey::PageDown);
+    assert_eq!(page_for_key(&page_down, 2, PAGES), Some(3));
+}
+
+#[test]
+fn ctrl_shift_tab_and_page_up_go_backwards_and_wrap() {
+    let previous = press(Modifiers::CONTROL | Modifiers::SHIFT, KbKey::Tab);
+    assert_eq!(page_for_key(&previous, 0, PAGES), Some(3));
+    assert_eq!(page_for_key(&previous, 2, PAGES), Some(1));
+    let page_up = press(Modifiers::CONTROL, KbKey::PageUp);
+    assert_eq!(page_for_key(&page_up, 1, PAGES), Some(0));
+}
+
+#[test]
+fn other_keys_and_empty_editors_switch_nothing() {
+    // plain tab moves the focus, as usual
+    assert_eq!(page_for_key(&press(Modifiers::empty(), KbKey::Tab), 1, PAGES), None);
+    assert_eq!(page_for_key(&press(Modifiers::CONTROL, KbKey::ArrowRight), 1, PAGES), None);
+    assert_eq!(page_for_key(&press(Modifiers::CONTROL, KbKey::Tab), 0, 0), None);
+    // a single page stays put
+    assert_eq!(page_for_key(&press(Modifiers::CONTROL, KbKey::PageDown), 0, 1), Some(0));
+}
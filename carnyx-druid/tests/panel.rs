@@ -0,0 +1,108 @@
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use carnyx::{BasicParam, CarnyxModel, CarnyxParam, DiscreteParam, ParamList};
+use carnyx_druid::{ControlKind, PanelRow, ParamPanel};
+
+const DIALS: &[&str] = &["rate", "depth", "delay", "feedback", "spread", "tone", "mix", "width"];
+const MODES: &[&str] = &["modern", "vintage"];
+const VOICES: &[&str] = &["2", "3", "4"];
+
+// a chorus, with a tone control only its modern mode has
+struct Chorus {
+    values: Vec<AtomicU32>,
+    mode: AtomicUsize,
+}
+
+impl Chorus {
+    fn new() -> Arc<Self> {
+        Arc::new(Chorus {
+            values: (0..=DIALS.len()).map(|_| AtomicU32::new(0.5f32.to_bits())).collect(),
+            mode: AtomicUsize::new(0),
+        })
+    }
+
+    fn get(&self, index: usize) -> f32 {
+        f32::from_bits(self.values[index].load(Ordering::Relaxed))
+    }
+
+    fn set(&self, index: usize, value: f32) {
+        self.values[index].store(value.to_bits(), Ordering::Relaxed)
+    }
+}
+
+impl CarnyxModel for Chorus {
+    type Snap = f32;
+
+    fn snap(&self) -> f32 {
+        self.get(0)
+    }
+
+    fn set_snap(&self, snap: &f32) {
+        self.set(0, *snap)
+    }
+}
+
+fn value(name: &'static str, index: usize) -> BasicParam<Chorus> {
+    BasicParam::new(name, "%", move |m: &Chorus| m.get(index), move |m: &Chorus, v| m.set(index, v), move |m: &Chorus| {
+        format!("{:.0}", m.get(index) * 100.)
+    })
+}
+
+fn params() -> ParamList<Chorus> {
+    let mut params: Vec<Box<dyn CarnyxParam<Chorus>>> = DIALS
+        .iter()
+        .enumerate()
+        .map(|(index, name)| -> Box<dyn CarnyxParam<Chorus>> {
+            match *name {
+                "tone" => Box::new(value(name, index).with_active(|m: &Chorus| m.mode.load(Ordering::Relaxed) == 0)),
+                _ => Box::new(value(name, index)),
+            }
+        })
+        .collect();
+    params.push(Box::new(DiscreteParam::new("mode", MODES,
+        |m: &Chorus| m.mode.load(Ordering::Relaxed),
+        |m: &Chorus, i| m.mode.store(i, Ordering::Relaxed))));
+    params.push(Box::new(value("voices", DIALS.len()).with_choices(VOICES)));
+    Arc::new(params)
+}
+
+fn names(row: &PanelRow) -> Vec<&str> {
+    row.controls.iter().map(|(name, _)| name.as_str()).collect()
+}
+
+#[test]
+fn with_no_layout_dials_fill_rows_and_choices_get_their_own() {
+    let model = Chorus::new();
+    let rows = ParamPanel::new(Arc::clone(&model), params()).layout();
+    assert_eq!(rows.len(), 4);
+    assert_eq!(names(&rows[0]), &DIALS[..6]);
+    assert_eq!(names(&rows[1]), &DIALS[6..]);
+    assert!(rows[..2].iter().all(|row| row.controls.iter().all(|(_, kind)| *kind == ControlKind::Dial)));
+    assert_eq!(rows[2].controls, [("mode".to_string(), ControlKind::Choice(MODES))]);
+    assert_eq!(rows[3].controls, [("voices".to_string(), ControlKind::Choice(VOICES))]);
+    assert!(rows.iter().all(|row| row.title.is_none()));
+
+    // in vintage mode there is no tone to show
+    model.mode.store(1, Ordering::Relaxed);
+    let rows = ParamPanel::new(model, params()).layout();
+    assert_eq!(names(&rows[0]), ["rate", "depth", "delay", "feedback", "spread", "mix"]);
+    assert_eq!(names(&rows[1]), ["width"]);
+}
+
+#[test]
+fn a_layout_keeps_its_rows_and_drops_unknown_names() {
+    let model = Chorus::new();
+    model.mode.store(1, Ordering::Relaxed);
+    let rows = ParamPanel::new(model, params())
+        .group("Motion", &[("rate", ControlKind::Dial), ("depth", ControlKind::Slider), ("wobble", ControlKind::Dial)])
+        .row(&[("tone", ControlKind::Dial), ("mode", ControlKind::Toggle)])
+        .layout();
+    assert_eq!(rows, [
+        PanelRow {
+            title: Some("Motion".to_string()),
+            controls: vec![("rate".to_string(), ControlKind::Dial), ("depth".to_string(), ControlKind::Slider)],
+        },
+        PanelRow { title: None, controls: vec![("mode".to_string(), ControlKind::Toggle)] },
+    ]);
+}
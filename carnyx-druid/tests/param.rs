@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use carnyx::{BasicParam, CarnyxModel, CarnyxParam, DiscreteParam, ParamList, ParamLocks};
+use carnyx_druid::{ParamHandle, ParamValues};
+use druid::Data;
+
+const SHAPES: &[&str] = &["soft", "hard", "fold"];
+
+// a waveshaper: a level in dB, a shape, and a meter the processor writes
+struct Shaper {
+    level: AtomicU32,
+    shape: AtomicUsize,
+    meter: AtomicU32,
+    locks: ParamLocks,
+}
+
+impl Shaper {
+    fn new() -> Arc<Self> {
+        Arc::new(Shaper {
+            level: AtomicU32::new(0.75f32.to_bits()),
+            shape: AtomicUsize::new(1),
+            meter: AtomicU32::new(0f32.to_bits()),
+            locks: ParamLocks::new(),
+        })
+    }
+
+    fn level(&self) -> f32 {
+        f32::from_bits(self.level.load(Ordering::Relaxed))
+    }
+}
+
+impl CarnyxModel for Shaper {
+    type Snap = f32;
+
+    fn snap(&self) -> f32 {
+        self.level()
+    }
+
+    fn set_snap(&self, snap: &f32) {
+        self.level.store(snap.to_bits(), Ordering::Relaxed)
+    }
+
+    fn locks(&self) -> Option<&ParamLocks> {
+        Some(&self.locks)
+    }
+}
+
+fn params() -> ParamList<Shaper> {
+    let params: Vec<Box<dyn CarnyxParam<Shaper>>> = vec![
+        Box::new(
+            BasicParam::new("level", "dB",
+                |m: &Shaper| m.level(),
+                |m: &Shaper, v| m.level.store(v.to_bits(), Ordering::Relaxed),
+                |m: &Shaper| format!("{:.1}", (m.level() - 1.) * 24.))
+                .with_description("How hard the shaper is driven"),
+        ),
+        Box::new(DiscreteParam::new("shape", SHAPES,
+            |m: &Shaper| m.shape.load(Ordering::Relaxed),
+            |m: &Shaper, i| m.shape.store(i, Ordering::Relaxed))),
+        Box::new(
+            BasicParam::new("meter", "",
+                |m: &Shaper| f32::from_bits(m.meter.load(Ordering::Relaxed)),
+                |_: &Shaper, _| {},
+                |m: &Shaper| format!("{:.2}", f32::from_bits(m.meter.load(Ordering::Relaxed))))
+                .read_only(),
+        ),
+    ];
+    Arc::new(params)
+}
+
+#[test]
+fn values_are_read_from_the_model_and_edited_copy_on_write() {
+    let model = Shaper::new();
+    let params = params();
+    let read = ParamValues::read(&params, &*model);
+    assert_eq!(read.get(0), Some(0.75));
+    assert_eq!(read.get(1), Some(0.5));
+    assert_eq!(read.get(3), None);
+
+    // an edit leaves the values it was cloned from alone
+    let mut edited = read.clone();
+    assert!(edited.same(&read));
+    edited.set(1, 1.);
+    assert_eq!(read.get(1), Some(0.5));
+    assert_eq!(edited.get(1), Some(1.));
+    assert!(!edited.same(&read));
+    assert_eq!(edited.changed(&read).collect::<Vec<_>>(), [1]);
+
+    // setting past the end changes nothing, and equal values are the same
+    edited.set(7, 1.);
+    edited.set(1, 0.5);
+    assert!(edited.same(&read));
+    assert_eq!(edited.changed(&read).count(), 0);
+}
+
+#[test]
+fn handles_show_values_with_their_units() {
+    let model = Shaper::new();
+    let level = ParamHandle::by_name(Arc::clone(&model), params(), "level").unwrap();
+    assert_eq!(level.index(), 0);
+    assert_eq!(level.display(), "-6.0 dB");
+    assert_eq!(level.tooltip(), "How hard the shaper is driven\n-6.0 dB");
+
+    // the handle reads the model as it is now
+    level.param().set_value(&*model, 0.5);
+    assert_eq!(level.display(), "-12.0 dB");
+
+    // no label, no trailing space; no description, just the value
+    let shape = ParamHandle::by_name(Arc::clone(&model), params(), "shape").unwrap();
+    assert_eq!(shape.display(), "hard");
+    assert_eq!(shape.tooltip(), "hard");
+    assert_eq!(shape.step_names(), SHAPES);
+    assert!(ParamHandle::by_name(model, params(), "drive").is_none());
+}
+
+#[test]
+fn only_writable_parameters_can_be_locked() {
+    let handles = ParamHandle::all(Shaper::new(), params());
+    assert_eq!(handles.iter().map(ParamHandle::name).collect::<Vec<_>>(), ["level", "shape", "meter"]);
+    assert_eq!(handles.iter().map(ParamHandle::lockable).collect::<Vec<_>>(), [true, true, false]);
+}
@@ -0,0 +1,40 @@
+use carnyx_druid::{ResizePolicy, DEFAULT_SIZE_PRESETS, SCALE_PRESETS};
+use druid::Size;
+
+const OPENED: Size = Size::new(640., 360.);
+
+#[test]
+fn scaling_keeps_the_proportions_and_follows_the_larger_stretch() {
+    // wider by half, taller by a quarter: everything is drawn half as big again
+    let size = ResizePolicy::Scale.constrain(Size::new(960., 450.), OPENED);
+    assert_eq!(size, Size::new(960., 540.));
+    // dragged inwards, it follows the side that shrank least
+    let size = ResizePolicy::Scale.constrain(Size::new(320., 300.), OPENED);
+    assert!((size.width - 533.33).abs() < 0.01 && (size.height - 300.).abs() < 1e-9, "{:?}", size);
+}
+
+#[test]
+fn scaling_stops_at_half_and_four_times_the_size() {
+    assert_eq!(ResizePolicy::Scale.constrain(Size::new(10., 10.), OPENED), Size::new(320., 180.));
+    assert_eq!(ResizePolicy::Scale.constrain(Size::new(9000., 100.), OPENED), Size::new(2560., 1440.));
+}
+
+#[test]
+fn reflow_takes_each_side_as_dragged_within_the_limits() {
+    assert_eq!(ResizePolicy::Reflow.constrain(Size::new(1000., 300.), OPENED), Size::new(1000., 300.));
+    assert_eq!(ResizePolicy::Reflow.constrain(Size::new(100., 2000.), OPENED), Size::new(320., 1440.));
+}
+
+#[test]
+fn a_fixed_editor_stays_as_opened() {
+    assert_eq!(ResizePolicy::Fixed.constrain(Size::new(1000., 300.), OPENED), OPENED);
+    assert_eq!(ResizePolicy::Fixed.constrain(Size::ZERO, OPENED), OPENED);
+}
+
+#[test]
+fn presets_scale_within_what_scaling_allows() {
+    for preset in DEFAULT_SIZE_PRESETS.iter().chain(SCALE_PRESETS.iter()) {
+        let size = OPENED * preset.scale;
+        assert_eq!(ResizePolicy::Scale.constrain(size, OPENED), size, "{}", preset.name);
+    }
+}
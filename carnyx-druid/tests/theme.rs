@@ -0,0 +1,46 @@
+use carnyx_druid::{CarnyxTheme, KnobStyle, KNOB_FILLED, TOUCH_INPUT};
+use druid::{theme, Color, Env};
+
+fn themed(theme: &CarnyxTheme) -> Env {
+    let mut env = Env::default();
+    theme.apply(&mut env);
+    env
+}
+
+#[test]
+fn a_theme_overrides_the_stock_widget_colors() {
+    let light = CarnyxTheme::light();
+    let env = themed(&light);
+    assert_eq!(env.get(theme::WINDOW_BACKGROUND_COLOR).as_rgba_u32(), light.background.as_rgba_u32());
+    assert_eq!(env.get(theme::PRIMARY_LIGHT).as_rgba_u32(), light.accent.as_rgba_u32());
+    assert_eq!(env.get(theme::LABEL_COLOR).as_rgba_u32(), light.text.as_rgba_u32());
+    assert_eq!(env.get(theme::TEXT_SIZE_LARGE), light.heading_size);
+    assert!(!env.get(KNOB_FILLED));
+
+    // the dark theme puts back everything the light one changed
+    let mut env = env;
+    CarnyxTheme::dark().apply(&mut env);
+    assert_eq!(env.get(theme::WINDOW_BACKGROUND_COLOR).as_rgba_u32(), CarnyxTheme::dark().background.as_rgba_u32());
+    assert!(env.get(KNOB_FILLED));
+    assert!(!env.get(TOUCH_INPUT));
+}
+
+#[test]
+fn branding_changes_only_what_it_says() {
+    let orange = Color::rgb8(0xff, 0x80, 0x00);
+    let brown = Color::rgb8(0x80, 0x40, 0x00);
+    let branded = CarnyxTheme::default()
+        .with_accent(orange.clone(), brown.clone())
+        .with_knob_style(KnobStyle::Outline)
+        .with_touch_input(true);
+    let env = themed(&branded);
+    assert_eq!(env.get(theme::PRIMARY_LIGHT).as_rgba_u32(), orange.as_rgba_u32());
+    assert_eq!(env.get(theme::PRIMARY_DARK).as_rgba_u32(), brown.as_rgba_u32());
+    assert!(!env.get(KNOB_FILLED));
+    assert!(env.get(TOUCH_INPUT));
+
+    // the rest is the dark theme it started from
+    let dark = CarnyxTheme::dark();
+    assert_eq!(branded.background.as_rgba_u32(), dark.background.as_rgba_u32());
+    assert_eq!(env.get(theme::TEXT_SIZE_NORMAL), dark.text_size);
+}
@@ -0,0 +1,45 @@
+use std::collections::BTreeMap;
+
+use carnyx::UiState;
+use carnyx_druid::UiValues;
+use druid::Data;
+
+// what an editor does with the model's state when it opens
+fn open(state: &UiState) -> UiValues {
+    UiValues::new(state.values())
+}
+
+#[test]
+fn the_editor_finds_its_place_again_after_reopening() {
+    let state = UiState::new();
+    let mut ui = open(&state);
+    assert_eq!(ui.get("pages.selected"), None);
+
+    ui.set("pages.selected", 2.);
+    ui.set("generic.scroll.y", 340.5);
+    // written back as they change, and kept on the model while the editor is closed
+    state.set_values(ui.values());
+    drop(ui);
+
+    let ui = open(&state);
+    assert_eq!(ui.get("pages.selected"), Some(2.));
+    assert_eq!(ui.get("generic.scroll.y"), Some(340.5));
+    assert_eq!(state.get("generic.scroll.x"), None);
+}
+
+#[test]
+fn only_real_changes_count_as_changes() {
+    let mut values = BTreeMap::new();
+    values.insert("group.filter.collapsed".to_string(), 1.);
+    let ui = UiValues::new(values);
+
+    let mut unchanged = ui.clone();
+    unchanged.set("group.filter.collapsed", 1.);
+    assert!(unchanged.same(&ui));
+
+    let mut changed = ui.clone();
+    changed.set("group.filter.collapsed", 0.);
+    assert!(!changed.same(&ui));
+    // the editor's copy doesn't touch the one it was cloned from
+    assert_eq!(ui.get("group.filter.collapsed"), Some(1.));
+}
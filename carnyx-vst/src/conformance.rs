@@ -0,0 +1,82 @@
+//! Checks of how a plugin's parameters behave when driven the way hosts drive them,
+//! including the odd values hosts really send.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use carnyx::random::Rng;
+use vst::plugin::PluginParameters;
+
+// how far a value may move when set to what was read back
+const EPSILON: f32 = 1e-5;
+
+const EDGE_VALUES: [f32; 7] = [0., 1., 0.5, -1., 2., f32::NAN, f32::INFINITY];
+
+fn describe(params: &dyn PluginParameters, index: i32) -> String {
+    format!("parameter {} ({})", index, params.get_parameter_name(index))
+}
+
+// hosts only see text, so formatting must never panic
+fn check_text(params: &dyn PluginParameters, index: i32, problems: &mut Vec<String>) {
+    let text = catch_unwind(AssertUnwindSafe(|| {
+        (params.get_parameter_text(index), params.get_parameter_label(index))
+    }));
+    if text.is_err() {
+        problems.push(format!("{}: formatting panicked at {}", describe(params, index), params.get_parameter(index)));
+    }
+}
+
+fn check_value(params: &dyn PluginParameters, index: i32, set: f32, problems: &mut Vec<String>) {
+    if catch_unwind(AssertUnwindSafe(|| params.set_parameter(index, set))).is_err() {
+        problems.push(format!("{}: set_parameter({}) panicked", describe(params, index), set));
+        return;
+    }
+    let read = params.get_parameter(index);
+    if !(0. ..=1.).contains(&read) {
+        problems.push(format!("{}: set {} read back {}, outside 0 to 1", describe(params, index), set, read));
+        return;
+    }
+    check_text(params, index, problems);
+    // what was read must be a value the parameter holds, or hosts' automation drifts
+    params.set_parameter(index, read);
+    let again = params.get_parameter(index);
+    if (again - read).abs() > EPSILON {
+        problems.push(format!(
+            "{}: set {} read {}, but setting {} reads {}",
+            describe(params, index), set, read, read, again,
+        ));
+    }
+}
+
+/// Drive `count` parameters with edge case and random values, returning a description
+/// of each problem found: panics, values read back outside 0 to 1, and values which
+/// change when set to what was read back. Parameters the host can't automate are only
+/// checked for panics. Leaves the parameters in a random state.
+pub fn check_parameters(params: &dyn PluginParameters, count: usize, seed: u32, rounds: usize) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut rng = Rng::new(seed);
+    for index in 0..count as i32 {
+        if !params.can_be_automated(index) {
+            check_text(params, index, &mut problems);
+            continue;
+        }
+        for &value in EDGE_VALUES.iter() {
+            check_value(params, index, value, &mut problems);
+        }
+        for _ in 0..rounds {
+            check_value(params, index, rng.next_f32(), &mut problems);
+        }
+    }
+    // indices hosts shouldn't send, but do
+    for &index in [-1, count as i32, count as i32 + 100, i32::MAX].iter() {
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            params.set_parameter(index, rng.next_f32());
+            params.get_parameter(index);
+            params.get_parameter_text(index);
+            params.get_parameter_name(index);
+        }));
+        if result.is_err() {
+            problems.push(format!("parameter index {} out of range panicked", index));
+        }
+    }
+    problems
+}
@@ -1,2 +1,10 @@
+pub mod conformance;
+mod plugin;
+pub mod quirks;
+pub mod speakers;
 mod vst_bridge;
-pub use vst_bridge::*;
\ No newline at end of file
+pub use plugin::*;
+pub use vst_bridge::*;
+
+// for carnyx_vst_plugin!
+pub use vst;
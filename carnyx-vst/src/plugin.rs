@@ -0,0 +1,334 @@
+//! A whole VST plugin for a carnyx processor. [`carnyx_vst_plugin!`] wraps this in a
+//! type the `vst` crate can export.
+
+use std::sync::Arc;
+
+use carnyx::audit;
+use carnyx::buffer::{AudioBuffer, BusLayout};
+use carnyx::carnyx::{guard, CarnyxHost, CarnyxModel, CarnyxModelListener, CarnyxProcessor, ChangeEvent, ChangeOrigin, SettableListener};
+use carnyx::preset::PresetBank;
+use carnyx::{Diagnostics, PendingChanges};
+#[cfg(feature = "gui")]
+use carnyx_druid::generic_editor;
+#[cfg(feature = "gui")]
+use druid::Data;
+use vst::api::{Events, Supported};
+use vst::channels::ChannelInfo;
+use vst::editor::Editor;
+use vst::plugin::{CanDo, HostCallback, Info, PluginParameters};
+
+use crate::speakers::{self, SpeakerRequest};
+use crate::vst_bridge::{can_do, input_channel_info, plugin_info, processing_mode, VstCarnyxEditor, VstCarnyxHost, VstParams, VstProcessState};
+
+/// What a generic editor needs of a model's snapshot: druid's `Data`, or nothing without
+/// the `gui` feature.
+#[cfg(feature = "gui")]
+pub trait EditorSnap: Data {}
+#[cfg(feature = "gui")]
+impl<T: Data> EditorSnap for T {}
+#[cfg(not(feature = "gui"))]
+pub trait EditorSnap {}
+#[cfg(not(feature = "gui"))]
+impl<T> EditorSnap for T {}
+
+/// Everything a VST plugin does, for any processor: the methods match `vst::plugin::Plugin`
+/// and the macro's impl forwards to them.
+pub struct CarnyxVstPlugin<P: CarnyxProcessor> {
+    processor: P,
+    // kept to record DSP load without touching the Arc's count each block
+    model: Arc<P::Model>,
+    state: VstProcessState,
+    diagnostics: Option<Arc<Diagnostics>>,
+    host: Arc<VstCarnyxHost>,
+    host_callback: HostCallback,
+    // host automation from the audio thread, not yet passed on to listeners
+    pending: Arc<PendingChanges>,
+    // the layout the host asked for with its speaker arrangement
+    speakers: Option<Arc<SpeakerRequest>>,
+}
+
+impl<P: CarnyxProcessor> CarnyxVstPlugin<P>
+    where P::Editor: 'static, <P::Model as CarnyxModel>::Snap: EditorSnap + Send + Sync {
+    pub fn new(host_callback: HostCallback, make_processor: impl FnOnce(Arc<dyn CarnyxHost>) -> P) -> Self {
+        let host = Arc::new(VstCarnyxHost::new(host_callback));
+        let diagnostics = host.diagnostics();
+        let mut processor = make_processor(Arc::clone(&host) as Arc<dyn CarnyxHost>);
+        host.set_param_ids(&processor.param_ids());
+        // hosts which never send a speaker arrangement connect as many channels as declared
+        let supported = processor.supported_layouts();
+        if let Some(widest) = BusLayout::widest(&supported) {
+            processor.set_layout(widest);
+        }
+        let speakers = speakers::register(host_callback.raw_effect(), supported);
+        let sends_midi = processor.capabilities().sends_midi;
+        let pending = Arc::new(PendingChanges::new(processor.all_parameters().len()));
+        CarnyxVstPlugin {
+            model: processor.model(),
+            processor,
+            state: VstProcessState::default()
+                .with_diagnostics(diagnostics.clone())
+                .with_midi_output(sends_midi),
+            diagnostics,
+            host,
+            host_callback,
+            pending,
+            speakers,
+        }
+    }
+
+    /// Without a host, e.g. for tests. Calls to the host do nothing, and time info and
+    /// host info are unavailable.
+    pub fn headless(make_processor: impl FnOnce(Arc<dyn CarnyxHost>) -> P) -> Self {
+        CarnyxVstPlugin::new(HostCallback::default(), make_processor)
+    }
+
+    pub fn processor(&self) -> &P {
+        &self.processor
+    }
+
+    /// Load any preset a MIDI program change asked for, and tell the processor's listeners
+    /// about it and the parameters the host has set since the last call. The editor does
+    /// this when the host gives it idle time; not for the audio thread.
+    pub fn notify_host_changes(&self) {
+        pass_on_changes(&self.pending, self.processor.presets().as_deref(), &self.processor.listener(), &*self.model);
+    }
+
+    // the same, for the editor to run on idle
+    fn host_changes_notifier(&self) -> Box<dyn Fn() + Send> {
+        let (pending, presets, listener, model) = (Arc::clone(&self.pending), self.processor.presets(), self.processor.listener(), Arc::clone(&self.model));
+        Box::new(move || pass_on_changes(&pending, presets.as_deref(), &listener, &*model))
+    }
+
+    // a host callback which may run the processor's code, see carnyx::guard
+    fn guard<R>(&mut self, what: &'static str, fallback: R, f: impl FnOnce(&mut Self) -> R) -> R {
+        let host = Arc::clone(&self.host);
+        guard(&*host, what, || f(self)).unwrap_or(fallback)
+    }
+
+    pub fn get_info(&self) -> Info {
+        guard(&*self.host, "get_info", || plugin_info(&self.processor)).unwrap_or_default()
+    }
+
+    pub fn get_input_info(&self, input: i32) -> ChannelInfo {
+        guard(&*self.host, "get_input_info", || input_channel_info(&self.processor.bus_layout(), input))
+            .unwrap_or_else(|| ChannelInfo::new(String::new(), None, true, None))
+    }
+
+    pub fn set_sample_rate(&mut self, rate: f32) {
+        self.guard("set_sample_rate", (), |plugin| {
+            let old_rate = plugin.state.sample_rate();
+            plugin.processor.set_sample_rate(rate);
+            plugin.state.set_sample_rate(rate);
+            if rate != old_rate {
+                plugin.processor.on_sample_rate_changed(old_rate, rate);
+            }
+        })
+    }
+
+    pub fn set_block_size(&mut self, size: i64) {
+        self.state.set_block_size(size)
+    }
+
+    pub fn resume(&mut self) {
+        self.guard("resume", (), |plugin| {
+            if let Some(layout) = plugin.speakers.as_ref().and_then(|speakers| speakers.take()) {
+                plugin.set_layout(layout);
+            }
+            plugin.processor.set_processing_mode(processing_mode(&plugin.host_callback));
+            plugin.state.prepare_scratch(plugin.processor.block_scratch_spec(plugin.state.max_block_size()));
+            plugin.processor.activate();
+        })
+    }
+
+    fn set_layout(&mut self, layout: BusLayout) {
+        if layout == self.processor.bus_layout() {
+            return;
+        }
+        if self.processor.set_layout(layout) {
+            // which parameters apply may depend on the channels
+            self.processor.parameter_layout_changed(&*self.host);
+        } else if let Some(diagnostics) = &self.diagnostics {
+            diagnostics.warn("vst", "speaker arrangement refused", Some(layout.main_inputs as f64));
+        }
+    }
+
+    pub fn suspend(&mut self) {
+        self.guard("suspend", (), |plugin| plugin.processor.deactivate())
+    }
+
+    pub fn process_events(&mut self, events: &Events) {
+        let (state, host) = (&mut self.state, &self.host);
+        guard(&**host, "process_events", || state.process_events(events));
+    }
+
+    pub fn can_do(&self, query: CanDo) -> Supported {
+        guard(&*self.host, "can_do", || can_do(self.processor.capabilities(), query)).unwrap_or(Supported::Maybe)
+    }
+
+    /// A panic here, or in any callback, is caught rather than unwinding into the host, and
+    /// mutes the instance from then on, see [`carnyx::guard`].
+    pub fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
+        if self.host.has_failed() {
+            mute(buffer);
+            return;
+        }
+        let CarnyxVstPlugin { processor, state, host, host_callback, model, .. } = self;
+        let load = model.dsp_load();
+        // outside the audit, as failing allocates
+        let processed = guard(&**host, "process", || state.process(host_callback, buffer, |buffer, context| {
+            host.set_transport(context.transport.as_ref());
+            audit::realtime("process", || match load {
+                Some(load) => load.measure(context.block_size, context.sample_rate, || processor.process_block(buffer, context)),
+                None => processor.process_block(buffer, context),
+            })
+        }));
+        if processed.is_none() {
+            mute(buffer);
+        }
+    }
+
+    pub fn get_parameter_object(&mut self) -> Arc<dyn PluginParameters> {
+        Arc::new(VstParams::new(
+            self.processor.all_parameters(),
+            self.processor.model(),
+            self.processor.listener(),
+            Arc::clone(&self.host))
+            .with_param_ids(self.processor.param_ids())
+            .with_presets(self.processor.presets())
+            .with_diagnostics(self.diagnostics.clone())
+            .with_pending_changes(Arc::clone(&self.pending))
+        ) as Arc<dyn PluginParameters>
+    }
+
+    /// The processor's editor, or a generic one if it has none. Without the `gui` feature
+    /// there is no generic editor.
+    pub fn get_editor(&mut self) -> Option<Box<dyn Editor>> {
+        self.guard("get_editor", None, |plugin| {
+            let host = Arc::clone(&plugin.host);
+            match plugin.processor.editor() {
+                Some(editor) => Some(Box::new(VstCarnyxEditor::new(editor, host).with_idle(plugin.host_changes_notifier())) as Box<dyn Editor>),
+                None => plugin.generic_editor(host),
+            }
+        })
+    }
+
+    #[cfg(feature = "gui")]
+    fn generic_editor(&self, host: Arc<VstCarnyxHost>) -> Option<Box<dyn Editor>> {
+        let editor = generic_editor(Arc::clone(&host) as Arc<dyn CarnyxHost>, &self.processor);
+        Some(Box::new(VstCarnyxEditor::new(editor, host).with_idle(self.host_changes_notifier())))
+    }
+
+    #[cfg(not(feature = "gui"))]
+    fn generic_editor(&self, _host: Arc<VstCarnyxHost>) -> Option<Box<dyn Editor>> {
+        None
+    }
+}
+
+fn pass_on_changes<Model: CarnyxModel>(pending: &PendingChanges, presets: Option<&PresetBank<Model::Snap>>,
+                                        listener: &SettableListener<Model>, model: &Model) {
+    if presets.map(|presets| presets.apply_requested(model)).unwrap_or(false) {
+        listener.notify_change(model, ChangeEvent::model(ChangeOrigin::Processor));
+    }
+    pending.notify(listener, model, ChangeOrigin::Host);
+}
+
+// whatever the processor got as far as writing may be garbage
+fn mute(buffer: &mut AudioBuffer<f32>) {
+    let (_, outputs) = buffer.split();
+    for output in outputs.into_iter() {
+        for sample in output.iter_mut() {
+            *sample = 0.;
+        }
+    }
+}
+
+/// Defines a VST plugin type named `$plugin` for a processor and exports it, so a plugin's
+/// VST crate needs nothing else. The processor is made with `$processor::new(host)`, taking
+/// an `Arc<dyn CarnyxHost>`. `Default` makes a headless plugin, see [`CarnyxVstPlugin::headless`],
+/// for the scanners and validators which make plugins that way.
+///
+/// ```ignore
+/// carnyx_vst::carnyx_vst_plugin!(LadderFilterVST, LadderProcessor);
+/// ```
+#[macro_export]
+macro_rules! carnyx_vst_plugin {
+    ($plugin:ident, $processor:ty) => {
+        pub struct $plugin($crate::CarnyxVstPlugin<$processor>);
+
+        impl Default for $plugin {
+            fn default() -> $plugin {
+                $plugin($crate::CarnyxVstPlugin::headless(<$processor>::new))
+            }
+        }
+
+        impl $crate::vst::plugin::Plugin for $plugin {
+            fn get_info(&self) -> $crate::vst::plugin::Info {
+                self.0.get_info()
+            }
+
+            fn new(host: $crate::vst::plugin::HostCallback) -> Self {
+                $plugin($crate::CarnyxVstPlugin::new(host, <$processor>::new))
+            }
+
+            fn get_input_info(&self, input: i32) -> $crate::vst::channels::ChannelInfo {
+                self.0.get_input_info(input)
+            }
+
+            fn set_sample_rate(&mut self, rate: f32) {
+                self.0.set_sample_rate(rate)
+            }
+
+            fn set_block_size(&mut self, size: i64) {
+                self.0.set_block_size(size)
+            }
+
+            fn resume(&mut self) {
+                self.0.resume()
+            }
+
+            fn suspend(&mut self) {
+                self.0.suspend()
+            }
+
+            fn process_events(&mut self, events: &$crate::vst::api::Events) {
+                self.0.process_events(events)
+            }
+
+            fn can_do(&self, query: $crate::vst::plugin::CanDo) -> $crate::vst::api::Supported {
+                self.0.can_do(query)
+            }
+
+            fn process(&mut self, buffer: &mut $crate::vst::buffer::AudioBuffer<f32>) {
+                self.0.process(buffer)
+            }
+
+            fn get_parameter_object(&mut self) -> ::std::sync::Arc<dyn $crate::vst::plugin::PluginParameters> {
+                self.0.get_parameter_object()
+            }
+
+            fn get_editor(&mut self) -> Option<Box<dyn $crate::vst::editor::Editor>> {
+                self.0.get_editor()
+            }
+        }
+
+        // as `vst::plugin_main!`, but catching speaker arrangements on the way in
+        #[cfg(target_os = "macos")]
+        #[no_mangle]
+        pub extern "system" fn main_macho(callback: $crate::vst::api::HostCallbackProc) -> *mut $crate::vst::api::AEffect {
+            VSTPluginMain(callback)
+        }
+
+        #[cfg(target_os = "windows")]
+        #[allow(non_snake_case)]
+        #[no_mangle]
+        pub extern "system" fn MAIN(callback: $crate::vst::api::HostCallbackProc) -> *mut $crate::vst::api::AEffect {
+            VSTPluginMain(callback)
+        }
+
+        #[allow(non_snake_case)]
+        #[no_mangle]
+        pub extern "C" fn VSTPluginMain(callback: $crate::vst::api::HostCallbackProc) -> *mut $crate::vst::api::AEffect {
+            $crate::speakers::wrap_dispatcher($crate::vst::main::<$plugin>(callback))
+        }
+    };
+}
@@ -0,0 +1,104 @@
+//! Hosts that don't do what the VST spec says, and what to do instead. Matched on the
+//! vendor, product and version the host reports, so workarounds live in one table
+//! rather than wherever the misbehaviour shows up.
+
+use std::time::Duration;
+
+/// How the editor's window is resized when the editor asks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeStrategy {
+    /// The host can't resize the window.
+    Unsupported,
+    /// Ask with `sizeWindow`.
+    SizeWindow,
+    /// `sizeWindow`, then `ioChanged` so the host picks up the new size.
+    SizeWindowThenIoChanged,
+    /// `sizeWindow`, then resize the window the host gave us ourselves.
+    SizeWindowThenParent,
+}
+
+impl ResizeStrategy {
+    /// For diagnostics.
+    pub fn describe(self) -> &'static str {
+        match self {
+            ResizeStrategy::Unsupported => "resize strategy: unsupported",
+            ResizeStrategy::SizeWindow => "resize strategy: sizeWindow",
+            ResizeStrategy::SizeWindowThenIoChanged => "resize strategy: sizeWindow then ioChanged",
+            ResizeStrategy::SizeWindowThenParent => "resize strategy: sizeWindow then resize the parent",
+        }
+    }
+}
+
+/// A host's known misbehaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostQuirk {
+    pub vendor: &'static str,
+    /// Matches any product from the vendor if `None`.
+    pub product: Option<&'static str>,
+    /// Inclusive range of the vendor's version numbers affected.
+    pub versions: (i32, i32),
+    /// The host answers the `sizeWindow` can-do wrongly, so the answer is ignored.
+    pub ignores_can_do: bool,
+    pub resize: ResizeStrategy,
+    /// How often the editor may ask the host to refresh its parameter display mid drag, if
+    /// the default is too often for it.
+    pub display_interval: Option<Duration>,
+    /// Why, for diagnostics.
+    pub note: &'static str,
+}
+
+const ALL_VERSIONS: (i32, i32) = (i32::MIN, i32::MAX);
+
+pub const HOST_QUIRKS: &[HostQuirk] = &[
+    HostQuirk {
+        vendor: "Ableton",
+        product: None,
+        versions: ALL_VERSIONS,
+        ignores_can_do: true,
+        resize: ResizeStrategy::SizeWindow,
+        display_interval: None,
+        note: "Ableton resizes fine but doesn't say so",
+    },
+    HostQuirk {
+        vendor: "Cockos",
+        product: Some("REAPER"),
+        versions: ALL_VERSIONS,
+        ignores_can_do: false,
+        resize: ResizeStrategy::SizeWindowThenIoChanged,
+        display_interval: None,
+        note: "REAPER only rereads the editor size after ioChanged",
+    },
+    HostQuirk {
+        vendor: "Image-Line",
+        product: None,
+        versions: ALL_VERSIONS,
+        ignores_can_do: false,
+        resize: ResizeStrategy::SizeWindowThenParent,
+        display_interval: None,
+        note: "FL Studio resizes its wrapper window but not the parent it gave us",
+    },
+];
+
+impl HostQuirk {
+    pub fn matches(&self, vendor: &str, product: &str, version: i32) -> bool {
+        self.vendor.eq_ignore_ascii_case(vendor.trim())
+            && self.product.map(|p| p.eq_ignore_ascii_case(product.trim())).unwrap_or(true)
+            && version >= self.versions.0
+            && version <= self.versions.1
+    }
+}
+
+/// The first quirk in `quirks` the host matches.
+pub fn find_quirk<'a>(quirks: &'a [HostQuirk], vendor: &str, product: &str, version: i32) -> Option<&'a HostQuirk> {
+    quirks.iter().find(|quirk| quirk.matches(vendor, product, version))
+}
+
+/// How to resize for a host, given its quirk if it has one and whether it says it can
+/// `sizeWindow`.
+pub fn resize_strategy(quirk: Option<&HostQuirk>, can_size_window: bool) -> ResizeStrategy {
+    match quirk {
+        Some(quirk) if quirk.ignores_can_do || can_size_window => quirk.resize,
+        _ if can_size_window => ResizeStrategy::SizeWindow,
+        _ => ResizeStrategy::Unsupported,
+    }
+}
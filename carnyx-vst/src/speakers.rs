@@ -0,0 +1,100 @@
+//! Speaker arrangements, which hosts use to tell a plugin how many channels a track has.
+//! The `vst` crate doesn't pass `effSetSpeakerArrangement` on to plugins, so
+//! [`carnyx_vst_plugin!`](crate::carnyx_vst_plugin) wraps the dispatcher of every effect it
+//! makes to catch it, and the plugin picks the layout up when the host next resumes.
+
+use std::ffi::c_void;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, Weak};
+
+use carnyx::audit;
+use carnyx::buffer::BusLayout;
+use vst::api::{AEffect, DispatcherProc};
+
+const SET_SPEAKER_ARRANGEMENT: i32 = 42;
+
+// the start of a VstSpeakerArrangement; the speakers that follow aren't needed
+#[repr(C)]
+struct ArrangementHeader {
+    _arrangement_type: i32,
+    num_channels: i32,
+}
+
+/// The layouts one plugin instance supports, and the one its host last asked for.
+pub struct SpeakerRequest {
+    supported: Vec<BusLayout>,
+    requested: Mutex<Option<BusLayout>>,
+}
+
+impl SpeakerRequest {
+    /// The layout asked for since the last call, if any.
+    pub fn take(&self) -> Option<BusLayout> {
+        audit::lock_taken("speaker request lock");
+        self.requested.lock().unwrap_or_else(PoisonError::into_inner).take()
+    }
+
+    fn request(&self, inputs: usize, outputs: usize) -> bool {
+        match BusLayout::find(&self.supported, inputs, outputs) {
+            Some(layout) => {
+                audit::lock_taken("speaker request lock");
+                *self.requested.lock().unwrap_or_else(PoisonError::into_inner) = Some(layout);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// effects by address, to the instance made for them
+static REQUESTS: Mutex<Vec<(usize, Weak<SpeakerRequest>)>> = Mutex::new(Vec::new());
+// the `vst` crate's dispatcher, which is the same for every effect
+static DISPATCHER: OnceLock<DispatcherProc> = OnceLock::new();
+
+/// Register the plugin instance made for `effect`, so requests for it reach it. Headless
+/// plugins have no effect and get nothing.
+pub fn register(effect: *mut AEffect, supported: Vec<BusLayout>) -> Option<Arc<SpeakerRequest>> {
+    if effect.is_null() {
+        return None;
+    }
+    let request = Arc::new(SpeakerRequest { supported, requested: Mutex::new(None) });
+    audit::lock_taken("speaker requests lock");
+    let mut requests = REQUESTS.lock().unwrap_or_else(PoisonError::into_inner);
+    // effects are freed with their plugin, and the address may be reused
+    requests.retain(|(address, weak)| weak.strong_count() > 0 && *address != effect as usize);
+    requests.push((effect as usize, Arc::downgrade(&request)));
+    Some(request)
+}
+
+fn find(effect: *mut AEffect) -> Option<Arc<SpeakerRequest>> {
+    audit::lock_taken("speaker requests lock");
+    let requests = REQUESTS.lock().unwrap_or_else(PoisonError::into_inner);
+    requests.iter().find(|(address, _)| *address == effect as usize).and_then(|(_, weak)| weak.upgrade())
+}
+
+/// Route `effect`'s dispatcher through ours. Called by the plugin's entry point.
+pub fn wrap_dispatcher(effect: *mut AEffect) -> *mut AEffect {
+    if !effect.is_null() {
+        unsafe {
+            DISPATCHER.get_or_init(|| (*effect).dispatcher);
+            (*effect).dispatcher = dispatch;
+        }
+    }
+    effect
+}
+
+extern "C" fn dispatch(effect: *mut AEffect, opcode: i32, index: i32, value: isize, ptr: *mut c_void, opt: f32) -> isize {
+    if opcode == SET_SPEAKER_ARRANGEMENT {
+        let (inputs, outputs) = (value as *const ArrangementHeader, ptr as *const ArrangementHeader);
+        if inputs.is_null() || outputs.is_null() {
+            return 0;
+        }
+        let (inputs, outputs) = unsafe { ((*inputs).num_channels, (*outputs).num_channels) };
+        let accepted = find(effect)
+            .map(|request| request.request(inputs.max(0) as usize, outputs.max(0) as usize))
+            .unwrap_or(false);
+        return accepted as isize;
+    }
+    match DISPATCHER.get() {
+        Some(dispatcher) => dispatcher(effect, opcode, index, value, ptr, opt),
+        None => 0,
+    }
+}
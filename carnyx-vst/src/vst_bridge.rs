@@ -1,66 +1,628 @@
-use carnyx::{CarnyxModel, CarnyxParam, CarnyxHost, CarnyxEditor, CarnyxModelListener, CarnyxWindowResizer};
-use vst::plugin::{PluginParameters, HostCallback};
-use std::sync::Arc;
+use carnyx::{guard, ChangeEvent, ChangeOrigin, CarnyxDescriptor, PendingChanges, CarnyxModel, CarnyxProcessor, CarnyxParam, Capabilities, CarnyxHost, CarnyxEditor, CarnyxModelListener, CarnyxWindowResizer, Diagnostics, HostInfo, Level, InstanceContext, MidiMessage, MidiOutput, ParamIdTable, TimedMidi, ProcessingMode, ProcessContext, SilenceFlags, Transport};
+use carnyx::audit;
+use carnyx::automation::{AutomationLimiter, AUTOMATION_CAPACITY, DEFAULT_DISPLAY_INTERVAL};
+use carnyx::descriptor::PluginCategory;
+use carnyx::preset::PresetBank;
+use carnyx::state;
+use carnyx::buffer::{AudioBuffer, BusLayout, ScratchBuffers, ScratchSpec};
+use vst::api::{Events, TimeInfoFlags};
+use vst::buffer::SendEventBuffer;
+use vst::event::{Event, MidiEvent};
+use vst::channels::ChannelInfo;
+use vst::api::Supported;
+use vst::plugin::{CanDo, Category, Info, PluginParameters, HostCallback};
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use vst::host::Host;
 use std::ffi::{CString, c_void};
 use vst::editor::Editor;
 use raw_window_handle::RawWindowHandle;
 
+use crate::quirks::{find_quirk, resize_strategy, HostQuirk, ResizeStrategy, HOST_QUIRKS};
+
 
 pub struct VstParams<DP: CarnyxModel, L: CarnyxModelListener<DP> + Sync>{
     params: Vec<Box<dyn CarnyxParam<DP>>>,
+    // host indices to positions in `params`
+    ids: ParamIdTable,
     inner: Arc<DP>,
-    listener: L
+    listener: L,
+    presets: Option<Arc<PresetBank<DP::Snap>>>,
+    diagnostics: Option<Arc<Diagnostics>>,
+    // changes from set_parameter, for the editor's idle to pass on to the listener
+    pending: Arc<PendingChanges>,
+    // fails if a callback panics
+    host: Arc<VstCarnyxHost>,
 }
 
 impl<DP: CarnyxModel, L: CarnyxModelListener<DP> + Sync> VstParams<DP, L> {
-    pub fn new(params: Vec<Box<dyn CarnyxParam<DP>>>, inner: Arc<DP>, listener: L) -> Self {
-        VstParams { params, inner, listener }
+    pub fn new(params: Vec<Box<dyn CarnyxParam<DP>>>, inner: Arc<DP>, listener: L, host: Arc<VstCarnyxHost>) -> Self {
+        let ids = ParamIdTable::identity(params.len());
+        let pending = Arc::new(PendingChanges::new(params.len()));
+        VstParams { params, ids, inner, listener, presets: None, diagnostics: None, pending, host }
+    }
+
+    /// Builder-style method to mark host automation in `pending`, shared with whatever
+    /// passes it on; see [`PendingChanges`].
+    pub fn with_pending_changes(mut self, pending: Arc<PendingChanges>) -> Self {
+        self.pending = pending;
+        self
+    }
+
+    /// Builder-style method to number parameters for the host through `ids`, rather than
+    /// by position.
+    pub fn with_param_ids(mut self, ids: ParamIdTable) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    // the position of the parameter at a host index, and the parameter
+    fn param(&self, index: i32) -> Option<(usize, &dyn CarnyxParam<DP>)> {
+        let position = self.ids.param(usize::try_from(index).ok()?)?;
+        self.params.get(position).map(|param| (position, &**param))
+    }
+
+    pub fn with_presets(mut self, presets: Option<Arc<PresetBank<DP::Snap>>>) -> Self {
+        self.presets = presets;
+        self
+    }
+
+    pub fn with_diagnostics(mut self, diagnostics: Option<Arc<Diagnostics>>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// VST2 has no opcode for parameter defaults, so bridges use this to initialise or
+    /// reset the plugin themselves.
+    pub fn get_parameter_default(&self, index: i32) -> f32 {
+        self.guard("get_parameter_default", 0.0, || self.param(index).map(|(_, p)| p.default_value()).unwrap_or(0.0))
+    }
+
+    // sessions save only the current settings, so banks and presets are the same state
+    fn load_state(&self, data: &[u8]) {
+        match state::load(&self.params, &self.inner, data) {
+            Ok(()) => self.listener.notify_change(&self.inner, ChangeEvent::model(ChangeOrigin::Host)),
+            Err(_) => {
+                if let Some(diagnostics) = &self.diagnostics {
+                    diagnostics.warn("vst", "saved state could not be loaded", Some(data.len() as f64));
+                }
+            }
+        }
+    }
+
+    fn set_parameter_realtime(&self, index: i32, value: f32) {
+        // hosts may call this from the audio thread, so listeners hear later, see PendingChanges
+        audit::realtime("set_parameter", || {
+            let (position, param) = match self.param(index) {
+                Some(param) => param,
+                None => {
+                    if let Some(diagnostics) = &self.diagnostics {
+                        diagnostics.trace("vst", "set_parameter ignored, no such parameter", Some(index as f64));
+                    }
+                    return;
+                }
+            };
+            if self.inner.locks().map(|locks| locks.is_locked(position)).unwrap_or(false) {
+                if let Some(diagnostics) = &self.diagnostics {
+                    diagnostics.trace("vst", "set_parameter ignored, locked", Some(index as f64));
+                }
+                return;
+            }
+            if param.is_read_only() {
+                if let Some(diagnostics) = &self.diagnostics {
+                    diagnostics.trace("vst", "set_parameter ignored, read only", Some(index as f64));
+                }
+                return;
+            }
+            if !param.is_active(&self.inner) {
+                if let Some(diagnostics) = &self.diagnostics {
+                    diagnostics.trace("vst", "set_parameter ignored, inactive", Some(index as f64));
+                }
+                return;
+            }
+            if value.is_nan() {
+                if let Some(diagnostics) = &self.diagnostics {
+                    diagnostics.warn("vst", "set_parameter ignored, NaN", Some(index as f64));
+                }
+                return;
+            }
+            param.set_value(&self.inner, value.max(0.).min(1.));
+            if let Some(diagnostics) = &self.diagnostics {
+                diagnostics.trace("vst", "set_parameter", Some(index as f64));
+            }
+            self.pending.mark(position);
+        })
+    }
+
+    // a host callback, see carnyx::guard
+    fn guard<R>(&self, what: &'static str, fallback: R, f: impl FnOnce() -> R) -> R {
+        guard(&*self.host, what, f).unwrap_or(fallback)
+    }
+
+    pub fn reset_to_defaults(&self) {
+        self.guard("reset_to_defaults", (), || {
+            for param in &self.params {
+                param.set_value(&self.inner, param.default_value());
+            }
+            self.listener.notify_change(&self.inner, ChangeEvent::model(ChangeOrigin::Host))
+        })
     }
 }
 
-impl <DP: CarnyxModel, L: CarnyxModelListener<DP> + Sync> PluginParameters for VstParams<DP, L> {
+impl <DP: CarnyxModel, L: CarnyxModelListener<DP> + Sync> PluginParameters for VstParams<DP, L>
+    where DP::Snap: Send + Sync {
+    fn change_preset(&self, preset: i32) {
+        self.guard("change_preset", (), || {
+            if let Some(presets) = &self.presets {
+                if presets.select(preset.max(0) as usize, &self.inner) {
+                    self.listener.notify_change(&self.inner, ChangeEvent::model(ChangeOrigin::Host))
+                }
+            }
+        })
+    }
+
+    fn get_preset_num(&self) -> i32 {
+        self.guard("get_preset_num", 0, || {
+            // hosts ask while showing the program, so land MIDI program changes here too
+            if let Some(presets) = &self.presets {
+                if presets.apply_requested(&*self.inner) {
+                    self.listener.notify_change(&self.inner, ChangeEvent::model(ChangeOrigin::Processor));
+                }
+            }
+            self.presets.as_ref().map(|p| p.current() as i32).unwrap_or(0)
+        })
+    }
+
+    fn get_preset_data(&self) -> Vec<u8> {
+        self.guard("get_preset_data", Vec::new(), || state::save(&self.params, &self.inner))
+    }
+
+    fn get_bank_data(&self) -> Vec<u8> {
+        self.guard("get_bank_data", Vec::new(), || state::save(&self.params, &self.inner))
+    }
+
+    fn load_preset_data(&self, data: &[u8]) {
+        self.guard("load_preset_data", (), || self.load_state(data))
+    }
+
+    fn load_bank_data(&self, data: &[u8]) {
+        self.guard("load_bank_data", (), || self.load_state(data))
+    }
+
+    fn get_preset_name(&self, preset: i32) -> String {
+        self.guard("get_preset_name", String::new(), || {
+            self.presets.as_ref()
+                .and_then(|p| p.get(preset.max(0) as usize))
+                .map(|p| p.name.clone())
+                .unwrap_or_else(|| "".to_owned())
+        })
+    }
+
     fn get_parameter_label(&self, index: i32) -> String {
-        let param = self.params.get(index as usize);
-        param.map(|p|p.label(&self.inner)).unwrap_or_else(||"".to_owned())
+        self.guard("get_parameter_label", String::new(), || {
+            let param = self.param(index);
+            param.map(|(_, p)|p.label(&self.inner)).unwrap_or_else(||"".to_owned())
+        })
     }
 
     fn get_parameter_text(&self, index: i32) -> String {
-        let param = self.params.get(index as usize);
-        param.map(|p|p.formatted(&self.inner)).unwrap_or_else(||"".to_owned())
+        self.guard("get_parameter_text", String::new(), || {
+            let param = self.param(index).filter(|(_, p)| p.is_active(&self.inner));
+            param.map(|(_, p)|p.formatted(&self.inner)).unwrap_or_else(||"".to_owned())
+        })
     }
 
     fn get_parameter_name(&self, index: i32) -> String {
-        let param = self.params.get(index as usize);
-        param.map(|p|p.name(&self.inner)).unwrap_or_else(||"".to_owned())
+        self.guard("get_parameter_name", String::new(), || {
+            let param = self.param(index);
+            param.map(|(_, p)|p.name(&self.inner)).unwrap_or_else(||"".to_owned())
+        })
     }
 
     // get_parameter has to return the value used in set_parameter
     fn get_parameter(&self, index: i32) -> f32 {
-        let param = self.params.get(index as usize);
-        param.map(|p|p.get_value(&self.inner)).unwrap_or(0.0)
+        self.guard("get_parameter", 0.0, || {
+            let param = self.param(index);
+            param.map(|(_, p)|p.get_value(&self.inner)).unwrap_or(0.0)
+        })
+    }
+
+    fn can_be_automated(&self, index: i32) -> bool {
+        self.guard("can_be_automated", false, || {
+            self.param(index).map(|(_, p)| !p.is_read_only() && p.is_active(&self.inner)).unwrap_or(false)
+        })
+    }
+
+    fn string_to_parameter(&self, index: i32, text: String) -> bool {
+        self.guard("string_to_parameter", false, || {
+            let param = self.param(index).filter(|(_, p)| !p.is_read_only() && p.is_active(&self.inner));
+            match param.and_then(|(position, p)| p.parse(&self.inner, &text).map(|value| (position, p, value))) {
+                Some((position, p, value)) => {
+                    p.set_value(&self.inner, value);
+                    self.listener.notify_change(&self.inner, ChangeEvent::param(position, ChangeOrigin::Host));
+                    true
+                }
+                None => false,
+            }
+        })
     }
 
     fn set_parameter(&self, index: i32, value: f32) {
-        let param = self.params.get(index as usize);
-        param.map(|p|p.set_value(&self.inner, value));
-        self.listener.notify_change(&self.inner)
+        // guarded outside the audit, as failing allocates
+        self.guard("set_parameter", (), || self.set_parameter_realtime(index, value))
+    }
+}
+
+/// Decode the MIDI channel messages in a host event block, with their offsets in the
+/// block, skipping sysex.
+pub fn midi_messages(events: &Events) -> impl Iterator<Item = TimedMidi> + '_ {
+    events.events().filter_map(|event| match event {
+        Event::Midi(midi) => Some(TimedMidi::new(midi.delta_frames.max(0) as usize, MidiMessage::from_bytes(midi.data))),
+        _ => None,
+    })
+}
+
+/// Answer the host's can-do queries from a processor's capabilities. Anything carnyx
+/// doesn't know about is `Maybe`.
+pub fn can_do(capabilities: Capabilities, can_do: CanDo) -> Supported {
+    let supported = match can_do {
+        CanDo::ReceiveEvents | CanDo::ReceiveMidiEvent => capabilities.receives_midi,
+        CanDo::SendEvents | CanDo::SendMidiEvent => capabilities.sends_midi,
+        CanDo::Offline => capabilities.offline,
+        CanDo::Bypass => capabilities.bypass,
+        _ => return Supported::Maybe,
+    };
+    if supported { Supported::Yes } else { Supported::No }
+}
+
+fn vst_category(category: PluginCategory) -> Category {
+    match category {
+        PluginCategory::Effect => Category::Effect,
+        PluginCategory::Instrument => Category::Synth,
+        PluginCategory::Analysis => Category::Analysis,
+        PluginCategory::Mastering => Category::Mastering,
+        PluginCategory::Spatial => Category::Spacializer,
+        PluginCategory::Room => Category::RoomFx,
+        PluginCategory::Restoration => Category::Restoration,
+        PluginCategory::Generator => Category::Generator,
+    }
+}
+
+/// The `Info` for `Plugin::get_info`, from the processor's descriptor and capabilities.
+/// The `vst` crate also answers the host's effect name, product and vendor queries from it.
+/// Channels are declared for the widest layout the processor supports.
+pub fn plugin_info<P: CarnyxProcessor>(processor: &P) -> Info {
+    let CarnyxDescriptor { name, vendor, version, unique_id, category, bus_layout } = processor.descriptor();
+    let bus_layout = BusLayout::widest(&processor.supported_layouts()).unwrap_or(bus_layout);
+    let capabilities = processor.capabilities();
+    Info {
+        name: name.to_string(),
+        vendor: vendor.to_string(),
+        version,
+        unique_id,
+        category: vst_category(category),
+        inputs: bus_layout.total_inputs() as i32,
+        outputs: bus_layout.outputs as i32,
+        midi_inputs: capabilities.receives_midi as i32,
+        midi_outputs: capabilities.sends_midi as i32,
+        presets: processor.presets().map(|p| p.len() as i32).unwrap_or(0),
+        parameters: processor.param_ids().len() as i32,
+        // sessions save carnyx::state, which is versioned, rather than raw parameter values
+        preset_chunks: true,
+        ..Default::default()
+    }
+}
+
+/// Names input channels so hosts can show which are the sidechain.
+pub fn input_channel_info(layout: &BusLayout, input: i32) -> ChannelInfo {
+    let input = input.max(0) as usize;
+    let (name, short_name) = if layout.is_sidechain(input) {
+        (format!("Sidechain {}", input - layout.main_inputs + 1), format!("SC{}", input - layout.main_inputs + 1))
+    } else {
+        (format!("Input {}", input + 1), format!("In{}", input + 1))
+    };
+    ChannelInfo::new(name, Some(short_name), true, None)
+}
+
+// MIDI beyond this many events per block is dropped rather than allocating on the audio thread
+const MAX_BLOCK_EVENTS: usize = 512;
+
+/// Collects what the VST callbacks deliver piecemeal (sample rate, events, transport) and
+/// hands it to the processor as a [`ProcessContext`].
+pub struct VstProcessState {
+    sample_rate: f32,
+    max_block_size: usize,
+    events: Vec<TimedMidi>,
+    out_events: Vec<TimedMidi>,
+    // set up when the processor sends MIDI; allocates, so not on the audio thread
+    send_buffer: Option<SendEventBuffer>,
+    scratch: ScratchBuffers,
+    diagnostics: Option<Arc<Diagnostics>>,
+}
+
+impl Default for VstProcessState {
+    fn default() -> Self {
+        VstProcessState {
+            sample_rate: 44100.,
+            max_block_size: 1024,
+            events: Vec::with_capacity(MAX_BLOCK_EVENTS),
+            out_events: Vec::with_capacity(MAX_BLOCK_EVENTS),
+            send_buffer: None,
+            scratch: ScratchBuffers::default(),
+            diagnostics: None,
+        }
+    }
+}
+
+impl VstProcessState {
+    pub fn with_diagnostics(mut self, diagnostics: Option<Arc<Diagnostics>>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Builder-style method to pass MIDI the processor sends on to the host; see
+    /// `Capabilities::sends_midi`.
+    pub fn with_midi_output(mut self, sends_midi: bool) -> Self {
+        self.send_buffer = if sends_midi { Some(SendEventBuffer::new(MAX_BLOCK_EVENTS)) } else { None };
+        self
+    }
+
+    pub fn set_sample_rate(&mut self, rate: f32) {
+        self.sample_rate = rate;
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    pub fn set_block_size(&mut self, size: i64) {
+        self.max_block_size = size.max(1) as usize;
+    }
+
+    pub fn max_block_size(&self) -> usize {
+        self.max_block_size
+    }
+
+    /// Reallocate scratch space; call from `resume`, which is never on the audio thread.
+    pub fn prepare_scratch(&mut self, spec: ScratchSpec) {
+        if spec != self.scratch.spec() {
+            self.scratch.resize(spec);
+        }
+    }
+
+    /// Hosts call `process_events` before the `process` call the events belong to.
+    pub fn process_events(&mut self, events: &Events) {
+        let room = MAX_BLOCK_EVENTS - self.events.len();
+        let incoming = midi_messages(events).count();
+        for event in midi_messages(events).take(room) {
+            // hosts should send events in order, but a block's events may come in several calls
+            let at = self.events.iter().rposition(|e| e.offset <= event.offset).map(|i| i + 1).unwrap_or(0);
+            self.events.insert(at, event);
+        }
+        if let (Some(diagnostics), true) = (&self.diagnostics, incoming > room) {
+            diagnostics.warn("vst", "midi events dropped", Some((incoming - room) as f64));
+        }
+    }
+
+    /// Build the context for this block, run `f` with it, then forget this block's events
+    /// and send the host any MIDI the processor produced.
+    pub fn process<F>(&mut self, host: &HostCallback, buffer: &mut AudioBuffer<f32>, f: F)
+        where F: FnOnce(&mut AudioBuffer<f32>, &mut ProcessContext) {
+        // VST2 hosts don't flag silent inputs, so look for ourselves
+        let samples = buffer.samples();
+        let midi_out = match self.send_buffer {
+            Some(_) => MidiOutput::new(&mut self.out_events),
+            None => MidiOutput::disconnected(),
+        };
+        let mut context = ProcessContext::new(self.sample_rate, samples)
+            .with_transport(transport(host))
+            .with_events(&self.events)
+            .with_input_silence(SilenceFlags::detect_inputs(buffer))
+            .with_scratch(self.scratch.lend(samples))
+            .with_midi_output(midi_out);
+        f(buffer, &mut context);
+        let dropped = context.midi_out.dropped();
+        if let (Some(diagnostics), true) = (&self.diagnostics, dropped > 0 && self.send_buffer.is_some()) {
+            diagnostics.warn("vst", "midi output dropped", Some(dropped as f64));
+        }
+        self.events.clear();
+        self.send_midi(host);
+    }
+
+    fn send_midi(&mut self, host: &HostCallback) {
+        if host.raw_callback().is_none() {
+            self.out_events.clear();
+            return;
+        }
+        if let (Some(send_buffer), false) = (&mut self.send_buffer, self.out_events.is_empty()) {
+            let events = self.out_events.drain(..).map(|event| MidiEvent {
+                data: event.message.to_bytes(),
+                delta_frames: event.offset as i32,
+                live: true,
+                note_length: None,
+                note_offset: None,
+                detune: 0,
+                note_off_velocity: 0,
+            });
+            let mut host = *host;
+            send_buffer.send_events(events, &mut host);
+        }
     }
 }
 
+fn transport(host: &HostCallback) -> Option<Transport> {
+    if host.raw_callback().is_none() {
+        return None;
+    }
+    let wanted = TimeInfoFlags::TEMPO_VALID | TimeInfoFlags::PPQ_POS_VALID;
+    host.get_time_info(wanted.bits()).map(|info| {
+        let flags = TimeInfoFlags::from_bits_truncate(info.flags);
+        Transport {
+            playing: flags.contains(TimeInfoFlags::TRANSPORT_PLAYING),
+            tempo: Some(info.tempo).filter(|_| flags.contains(TimeInfoFlags::TEMPO_VALID)),
+            position_beats: Some(info.ppq_pos).filter(|_| flags.contains(TimeInfoFlags::PPQ_POS_VALID)),
+            position_samples: info.sample_pos,
+        }
+    })
+}
+
+// kVstProcessLevelOffline
+const PROCESS_LEVEL_OFFLINE: isize = 4;
+
+/// Ask the host whether it is rendering offline. vst-rs doesn't expose the
+/// process level, so this goes through the raw callback.
+pub fn processing_mode(host: &HostCallback) -> ProcessingMode {
+    if let Some(callback) = host.raw_callback() {
+        let level = callback(
+            host.raw_effect(),
+            vst::host::OpCode::GetCurrentProcessLevel.into(),
+            0,
+            0,
+            std::ptr::null_mut(),
+            0.,
+        );
+        if level == PROCESS_LEVEL_OFFLINE {
+            return ProcessingMode::Offline;
+        }
+    }
+    ProcessingMode::Realtime
+}
+
+// VST 2.3 added beginEdit/endEdit
+const GESTURES_MIN_VST_VERSION: isize = 2300;
+
+fn host_can_do(host: &HostCallback, can_do: &str) -> bool {
+    match host.raw_callback() {
+        Some(callback) => {
+            let string = CString::new(can_do).unwrap();
+            let res = callback(
+                host.raw_effect(),
+                vst::host::OpCode::CanDo.into(),
+                0,
+                0,
+                string.as_bytes().as_ptr() as *mut c_void,
+                0.,
+            );
+            res == 1
+        }
+        None => false,
+    }
+}
+
+fn host_opcode(host: &HostCallback, opcode: vst::host::OpCode) -> isize {
+    match host.raw_callback() {
+        Some(callback) => callback(host.raw_effect(), opcode.into(), 0, 0, std::ptr::null_mut(), 0.),
+        None => 0,
+    }
+}
+
+/// What probing the host found.
+#[derive(Debug, Clone)]
+pub struct HostProbe {
+    pub info: HostInfo,
+    /// The host's entry in [`HOST_QUIRKS`], if it has one.
+    pub quirk: Option<&'static HostQuirk>,
+    pub resize: ResizeStrategy,
+}
+
+/// Ask the host about itself, and look up its quirks. Without a host (e.g. when the
+/// plugin is loaded by a test harness) the info is `HostInfo::UNKNOWN`.
+pub fn probe_host(host: &HostCallback) -> HostProbe {
+    if host.raw_callback().is_none() {
+        return HostProbe { info: HostInfo::UNKNOWN, quirk: None, resize: ResizeStrategy::Unsupported };
+    }
+    let (_, vendor, product) = host.get_info();
+    let version = host_opcode(host, vst::host::OpCode::GetVendorVersion) as i32;
+    let quirk = find_quirk(HOST_QUIRKS, &vendor, &product, version);
+    let resize = resize_strategy(quirk, host_can_do(host, "sizeWindow"));
+    let info = HostInfo {
+        supports_resize: resize != ResizeStrategy::Unsupported,
+        supports_automation_gestures: host_opcode(host, vst::host::OpCode::Version) >= GESTURES_MIN_VST_VERSION,
+        display_update_interval: quirk.and_then(|quirk| quirk.display_interval).unwrap_or(DEFAULT_DISPLAY_INTERVAL),
+        version,
+        vendor,
+        product,
+    };
+    HostProbe { info, quirk, resize }
+}
+
+pub fn probe_host_info(host: &HostCallback) -> HostInfo {
+    probe_host(host).info
+}
+
 pub struct VstCarnyxHost{
-    inner: HostCallback
+    inner: HostCallback,
+    info: HostInfo,
+    resize: ResizeStrategy,
+    playing: AtomicBool,
+    diagnostics: Arc<Diagnostics>,
+    automation: AutomationLimiter,
+    // host index by parameter position, for parameters the processor reports
+    host_indices: Vec<AtomicUsize>,
+    // set once a callback has panicked; the message is only read by the editor
+    failed: AtomicBool,
+    failure: Mutex<Option<String>>,
 }
 
+// a parameter the host doesn't know
+const UNPUBLISHED: usize = usize::MAX;
+
 impl VstCarnyxHost {
     pub fn new(host_callback: HostCallback) -> Self {
-        VstCarnyxHost { inner: host_callback }
+        let HostProbe { info, quirk, resize } = probe_host(&host_callback);
+        let diagnostics = Arc::new(Diagnostics::from_env());
+        if let Some(quirk) = quirk {
+            diagnostics.record(Level::Info, "host", quirk.note, None);
+        }
+        diagnostics.record(Level::Info, "host", resize.describe(), None);
+        VstCarnyxHost {
+            inner: host_callback,
+            info,
+            resize,
+            playing: AtomicBool::new(false),
+            diagnostics,
+            automation: AutomationLimiter::default(),
+            host_indices: (0..AUTOMATION_CAPACITY).map(AtomicUsize::new).collect(),
+            failed: AtomicBool::new(false),
+            failure: Mutex::new(None),
+        }
     }
 
-    pub fn resizer(&self)->Box<dyn CarnyxWindowResizer>{
-        Box::new(VstCarnyxResizer{
-            inner: self.inner.clone()
-        })
+    pub fn has_failed(&self) -> bool {
+        self.failed.load(Ordering::Acquire)
+    }
+
+    /// Report parameter changes to the host at the indices `ids` gives them. Set before
+    /// the processor runs.
+    pub fn set_param_ids(&self, ids: &ParamIdTable) {
+        for (position, host_index) in self.host_indices.iter().enumerate() {
+            host_index.store(ids.host_index(position).unwrap_or(UNPUBLISHED), Ordering::Relaxed);
+        }
+    }
+
+    // the host's index for a parameter, if it is published and the host takes gestures
+    fn gesture_index(&self, index: usize) -> Option<usize> {
+        if !self.info.supports_automation_gestures || self.inner.raw_callback().is_none() {
+            return None;
+        }
+        self.host_indices.get(index).map(|i| i.load(Ordering::Relaxed)).filter(|host_index| *host_index != UNPUBLISHED)
+    }
+
+    /// How editor windows are resized in this host, from its quirks.
+    pub fn resize_strategy(&self) -> ResizeStrategy {
+        self.resize
+    }
+
+    /// Record the transport state for `is_playing`; call from `process`.
+    pub fn set_transport(&self, transport: Option<&Transport>) {
+        self.playing.store(transport.map(|t| t.playing).unwrap_or(false), Ordering::Relaxed);
+    }
+
+    /// A resizer for an editor opened in `parent`.
+    pub fn resizer(&self, parent: *mut c_void) -> Box<dyn CarnyxWindowResizer> {
+        Box::new(VstCarnyxResizer::new(self.inner, self.resize, parent))
     }
 }
 
@@ -70,60 +632,181 @@ impl CarnyxHost for VstCarnyxHost {
             self.inner.update_display()
         }
     }
+
+    fn host_info(&self) -> &HostInfo {
+        &self.info
+    }
+
+    fn instance_context(&self) -> InstanceContext {
+        InstanceContext {
+            host_name: Some(self.info.product.clone()).filter(|name| !name.is_empty()),
+            // VST 2.4 has no way to ask for the track name
+            track_name: None,
+        }
+    }
+
+    fn is_playing(&self) -> bool {
+        self.playing.load(Ordering::Relaxed)
+    }
+
+    fn diagnostics(&self) -> Option<Arc<Diagnostics>> {
+        Some(Arc::clone(&self.diagnostics))
+    }
+
+    // VST2 can't add or remove parameters, only ask the host to reread names and values;
+    // IOChanged would have it reread the channels and latency too, and some restart the
+    // plugin for it
+    fn parameter_layout_changed(&self) {
+        self.update_host_display()
+    }
+
+    fn io_changed(&self) {
+        if self.inner.raw_callback().is_some() {
+            host_opcode(&self.inner, vst::host::OpCode::IOChanged);
+        }
+    }
+
+    fn set_parameter_automated(&self, index: usize, value: f32) -> bool {
+        let host_index = match self.host_indices.get(index).map(|i| i.load(Ordering::Relaxed)) {
+            Some(host_index) if host_index != UNPUBLISHED => host_index,
+            _ => return false,
+        };
+        if self.inner.raw_callback().is_none() || !self.automation.allow(index, value) {
+            return false;
+        }
+        self.inner.automate(host_index as i32, value);
+        true
+    }
+
+    fn begin_edit(&self, index: usize) {
+        if let Some(host_index) = self.gesture_index(index) {
+            self.inner.begin_edit(host_index as i32);
+        }
+    }
+
+    fn end_edit(&self, index: usize) {
+        if let Some(host_index) = self.gesture_index(index) {
+            self.inner.end_edit(host_index as i32);
+        }
+    }
+
+    fn failure(&self) -> Option<String> {
+        if !self.has_failed() {
+            return None;
+        }
+        audit::lock_taken("failure lock");
+        self.failure.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    // only the first failure is kept, as later ones may follow from it
+    fn fail(&self, what: &'static str, message: &str) {
+        if self.failed.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        self.diagnostics.error(what, "panicked, output muted", message);
+        audit::lock_taken("failure lock");
+        *self.failure.lock().unwrap_or_else(PoisonError::into_inner) = Some(format!("{} panicked: {}", what, message));
+    }
 }
 
 pub struct VstCarnyxResizer {
-    inner: HostCallback
+    inner: HostCallback,
+    strategy: ResizeStrategy,
+    parent: *mut c_void,
 }
 
 impl VstCarnyxResizer {
-    pub fn new(inner: HostCallback) -> Self {
-        VstCarnyxResizer { inner }
+    pub fn new(inner: HostCallback, strategy: ResizeStrategy, parent: *mut c_void) -> Self {
+        VstCarnyxResizer { inner, strategy, parent }
     }
 }
 
 impl CarnyxWindowResizer for VstCarnyxResizer{
     fn resize_editor_window(&self, width: usize, height: usize)->bool {
-        let (_, vendor, _) = self.inner.get_info();
-        let is_ableton = "Ableton".eq(&vendor);
-
-
+        if self.strategy == ResizeStrategy::Unsupported {
+            return false;
+        }
         if let Some(callback) = self.inner.raw_callback() {
-            let effect = self.inner.raw_effect();
-            let string = CString::new("sizeWindow").unwrap();
-
             let res = callback(
-                effect,
-                vst::host::OpCode::CanDo.into(),
-                0,
-                0,
-                string.as_bytes().as_ptr() as *mut c_void,
+                self.inner.raw_effect(),
+                vst::host::OpCode::SizeWindow.into(),
+                width as i32,
+                height as isize,
+                std::ptr::null_mut(),
                 0.,
             );
-            if res == 1 || is_ableton {
-                let res = callback(
-                    effect,
-                    vst::host::OpCode::SizeWindow.into(),
-                    width as i32,
-                    height as isize,
-                    std::ptr::null_mut(),
-                    0.,
-                );
-                return res == 1
-            }
+            return match self.strategy {
+                ResizeStrategy::SizeWindowThenIoChanged => {
+                    host_opcode(&self.inner, vst::host::OpCode::IOChanged);
+                    res == 1
+                }
+                ResizeStrategy::SizeWindowThenParent => resize_parent(self.parent, width, height) || res == 1,
+                _ => res == 1,
+            };
         }
         false
     }
 }
 
+#[cfg(target_os = "windows")]
+fn resize_parent(parent: *mut c_void, width: usize, height: usize) -> bool {
+    const SWP_NOMOVE: u32 = 0x0002;
+    const SWP_NOZORDER: u32 = 0x0004;
+    const SWP_NOACTIVATE: u32 = 0x0010;
+    #[link(name = "user32")]
+    extern "system" {
+        fn SetWindowPos(hwnd: *mut c_void, insert_after: *mut c_void, x: i32, y: i32, cx: i32, cy: i32, flags: u32) -> i32;
+    }
+    if parent.is_null() {
+        return false;
+    }
+    // safe: the host's window outlives the editor opened in it
+    unsafe {
+        SetWindowPos(parent, std::ptr::null_mut(), 0, 0, width as i32, height as i32, SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE) != 0
+    }
+}
+
+// only done on Windows so far, where FL Studio needs it
+#[cfg(not(target_os = "windows"))]
+fn resize_parent(_parent: *mut c_void, _width: usize, _height: usize) -> bool {
+    false
+}
+
+/// Panics in the editor are caught here rather than unwinding into the host, and fail the
+/// instance like any other, see [`carnyx::guard`]. The editor is given up on after one.
 pub struct VstCarnyxEditor<C: CarnyxEditor>{
     inner: C,
-    host_callback: HostCallback
+    host: Arc<VstCarnyxHost>,
+    // a Cell, as the host asks for the size through &self
+    broken: Cell<bool>,
+    idle: Option<Box<dyn Fn() + Send>>,
 }
 
 impl<C: CarnyxEditor> VstCarnyxEditor<C> {
-    pub fn new(inner: C, host_callback: HostCallback) -> Self {
-        VstCarnyxEditor { inner, host_callback }
+    pub fn new(inner: C, host: Arc<VstCarnyxHost>) -> Self {
+        VstCarnyxEditor { inner, host, broken: Cell::new(false), idle: None }
+    }
+
+    /// Builder-style method to run `idle` whenever the host gives the editor time, e.g. to
+    /// pass on changes made on the audio thread.
+    pub fn with_idle(mut self, idle: Box<dyn Fn() + Send>) -> Self {
+        self.idle = Some(idle);
+        self
+    }
+
+}
+
+// an editor callback, see carnyx::guard
+fn guard_editor<R>(host: &VstCarnyxHost, broken: &Cell<bool>, what: &'static str, fallback: R, f: impl FnOnce() -> R) -> R {
+    if broken.get() {
+        return fallback;
+    }
+    match guard(host, what, f) {
+        Some(result) => result,
+        None => {
+            broken.set(true);
+            fallback
+        }
     }
 }
 
@@ -147,20 +830,33 @@ fn to_raw_window_handle(parent: *mut c_void) -> RawWindowHandle {
 
 impl <C: CarnyxEditor> Editor for VstCarnyxEditor<C>{
     fn size(&self) -> (i32, i32) {
-        let (w, h) = self.inner.initial_size();
+        let (w, h) = guard_editor(&self.host, &self.broken, "editor size", (0, 0), || self.inner.initial_size());
         (w as i32, h as i32)
     }
 
     fn position(&self) -> (i32, i32) {
-        let (x, y) = self.inner.initial_position();
+        let (x, y) = guard_editor(&self.host, &self.broken, "editor position", (0, 0), || self.inner.initial_position());
         (x as i32, y as i32)
     }
 
     fn open(&mut self, parent: *mut c_void) -> bool {
-        self.inner.open(Some(to_raw_window_handle(parent)), Box::new(VstCarnyxResizer::new( self.host_callback )))
+        let VstCarnyxEditor { inner, host, broken, .. } = self;
+        let resizer = host.resizer(parent);
+        guard_editor(host, broken, "editor open", false, || inner.open(Some(to_raw_window_handle(parent)), resizer))
+    }
+
+    fn idle(&mut self) {
+        if let Some(idle) = &self.idle {
+            guard_editor(&self.host, &self.broken, "editor idle", (), || idle());
+        }
+    }
+
+    fn close(&mut self) {
+        let VstCarnyxEditor { inner, host, broken, .. } = self;
+        guard_editor(host, broken, "editor close", (), || inner.close())
     }
 
     fn is_open(&mut self) -> bool {
-        self.inner.is_open()
+        guard_editor(&self.host, &self.broken, "editor is_open", false, || self.inner.is_open())
     }
 }
\ No newline at end of file
@@ -0,0 +1,208 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+
+use carnyx::buffer::AudioBuffer;
+use carnyx::{CarnyxDescriptor, CarnyxHost, CarnyxModel, CarnyxParam, CarnyxProcessor, NoEditor, ProcessContext, SettableListener};
+use carnyx_vst::vst::host::HostBuffer;
+use carnyx_vst::vst::plugin::PluginParameters;
+use carnyx_vst::CarnyxVstPlugin;
+
+const BLOCK_SIZE: usize = 32;
+
+// a gain, and switches to make the processor or a parameter panic
+struct Fragile {
+    gain: AtomicU32,
+    process_panics: AtomicBool,
+}
+
+impl CarnyxModel for Fragile {
+    type Snap = f32;
+
+    fn snap(&self) -> f32 {
+        f32::from_bits(self.gain.load(Ordering::Relaxed))
+    }
+
+    fn set_snap(&self, snap: &f32) {
+        self.gain.store(snap.to_bits(), Ordering::Relaxed)
+    }
+}
+
+struct Gain;
+
+impl CarnyxParam<Fragile> for Gain {
+    fn name(&self, _model: &Fragile) -> String {
+        "gain".to_owned()
+    }
+
+    fn label(&self, _model: &Fragile) -> String {
+        "".to_owned()
+    }
+
+    fn get_value(&self, model: &Fragile) -> f32 {
+        model.snap()
+    }
+
+    fn set_value(&self, model: &Fragile, val: f32) {
+        model.set_snap(&val)
+    }
+
+    fn formatted(&self, model: &Fragile) -> String {
+        format!("{:.2}", model.snap())
+    }
+}
+
+// its display is broken
+struct Broken;
+
+impl CarnyxParam<Fragile> for Broken {
+    fn name(&self, _model: &Fragile) -> String {
+        "broken".to_owned()
+    }
+
+    fn label(&self, _model: &Fragile) -> String {
+        "".to_owned()
+    }
+
+    fn get_value(&self, _model: &Fragile) -> f32 {
+        0.
+    }
+
+    fn set_value(&self, _model: &Fragile, _val: f32) {}
+
+    fn formatted(&self, _model: &Fragile) -> String {
+        panic!("no display for broken")
+    }
+}
+
+struct FragileProcessor {
+    model: Arc<Fragile>,
+    listener: SettableListener<Fragile>,
+    host: Arc<dyn CarnyxHost>,
+}
+
+impl FragileProcessor {
+    fn new(host: Arc<dyn CarnyxHost>) -> Self {
+        let model = Fragile { gain: AtomicU32::new(0.5f32.to_bits()), process_panics: AtomicBool::new(false) };
+        FragileProcessor { model: Arc::new(model), listener: SettableListener::new(), host }
+    }
+}
+
+impl CarnyxProcessor for FragileProcessor {
+    type Model = Fragile;
+    type Editor = NoEditor;
+
+    fn descriptor(&self) -> CarnyxDescriptor {
+        CarnyxDescriptor::new("Fragile", 4242)
+    }
+
+    fn model(&self) -> Arc<Fragile> {
+        Arc::clone(&self.model)
+    }
+
+    fn listener(&self) -> SettableListener<Fragile> {
+        self.listener.clone()
+    }
+
+    fn set_sample_rate(&mut self, _rate: f32) {}
+
+    fn parameters(&self) -> Vec<Box<dyn CarnyxParam<Fragile>>> {
+        vec![Box::new(Gain), Box::new(Broken)]
+    }
+
+    fn process(&mut self, buffer: &mut AudioBuffer<f32>, _context: &mut ProcessContext) {
+        if self.model.process_panics.load(Ordering::Relaxed) {
+            panic!("process gave up");
+        }
+        let gain = self.model.snap();
+        for (input, output) in buffer.zip() {
+            for (i, o) in input.iter().zip(output.iter_mut()) {
+                *o = i * gain;
+            }
+        }
+    }
+}
+
+fn plugin() -> CarnyxVstPlugin<FragileProcessor> {
+    let mut plugin = CarnyxVstPlugin::headless(FragileProcessor::new);
+    plugin.set_sample_rate(48000.);
+    plugin.set_block_size(BLOCK_SIZE as i64);
+    plugin.resume();
+    plugin
+}
+
+// what the plugin makes of a block of 1s
+fn process(plugin: &mut CarnyxVstPlugin<FragileProcessor>) -> Vec<Vec<f32>> {
+    let mut host_buffer: HostBuffer<f32> = HostBuffer::new(2, 2);
+    let inputs = vec![vec![1.; BLOCK_SIZE]; 2];
+    let mut outputs = vec![vec![0.25; BLOCK_SIZE]; 2];
+    plugin.process(&mut host_buffer.bind(&inputs, &mut outputs));
+    outputs
+}
+
+fn is_silent(outputs: &[Vec<f32>]) -> bool {
+    outputs.iter().flatten().all(|sample| *sample == 0.)
+}
+
+#[test]
+fn a_panic_in_process_mutes_the_instance() {
+    let mut plugin = plugin();
+    assert!(process(&mut plugin).iter().flatten().all(|sample| *sample == 0.5));
+    assert_eq!(plugin.processor().host.failure(), None);
+
+    plugin.processor().model.process_panics.store(true, Ordering::Relaxed);
+    assert!(is_silent(&process(&mut plugin)), "output left as the processor wrote it");
+    let failure = plugin.processor().host.failure().expect("no failure recorded");
+    assert!(failure.contains("process gave up"), "{}", failure);
+
+    // muted for good, even once the processor would work again
+    plugin.processor().model.process_panics.store(false, Ordering::Relaxed);
+    assert!(is_silent(&process(&mut plugin)));
+
+    // but the host can still talk to it
+    plugin.set_sample_rate(44100.);
+    plugin.suspend();
+    plugin.resume();
+    let params = plugin.get_parameter_object();
+    params.set_parameter(0, 0.75);
+    assert_eq!(params.get_parameter(0), 0.75);
+    assert_eq!(params.get_parameter_text(0), "0.75");
+}
+
+#[test]
+fn a_panic_in_a_parameter_callback_mutes_the_instance() {
+    let mut plugin = plugin();
+    let params = plugin.get_parameter_object();
+    assert_eq!(params.get_parameter_text(0), "0.50");
+
+    assert_eq!(params.get_parameter_text(1), "");
+    let failure = plugin.processor().host.failure().expect("no failure recorded");
+    assert!(failure.starts_with("get_parameter_text"), "{}", failure);
+    assert!(is_silent(&process(&mut plugin)));
+
+    // the other parameters still answer, and the broken one goes on failing quietly
+    assert_eq!(params.get_parameter_name(1), "broken");
+    assert_eq!(params.get_parameter_text(1), "");
+    params.set_parameter(0, 0.25);
+    assert_eq!(params.get_parameter_text(0), "0.25");
+    // the first failure is the one kept
+    assert!(plugin.processor().host.failure().unwrap().starts_with("get_parameter_text"));
+}
+
+#[test]
+#[cfg(feature = "gui")]
+fn a_processor_without_an_editor_gets_a_generic_one() {
+    use carnyx_vst::vst::editor::Editor;
+
+    let mut plugin = plugin();
+    let editor = plugin.get_editor().expect("no generic editor");
+    assert!(!editor.is_open());
+    assert_eq!(editor.size(), (500, 500));
+    // the broken parameter isn't shown until the editor opens
+    assert_eq!(plugin.processor().host.failure(), None);
+}
+
+#[test]
+#[cfg(not(feature = "gui"))]
+fn without_the_gui_a_processor_without_an_editor_has_none() {
+    assert!(plugin().get_editor().is_none());
+}
@@ -0,0 +1,106 @@
+use std::ffi::{c_void, CStr};
+use std::os::raw::c_char;
+use std::ptr;
+
+use carnyx::{CarnyxHost, HostInfo, Transport};
+use carnyx_vst::{probe_host, VstCarnyxHost};
+use carnyx_vst::quirks::ResizeStrategy;
+use carnyx_vst::vst::api::AEffect;
+use carnyx_vst::vst::host::OpCode;
+use carnyx_vst::vst::plugin::HostCallback;
+
+// what a host answers about itself
+struct FakeHost {
+    vendor: &'static str,
+    product: &'static str,
+    vendor_version: isize,
+    vst_version: isize,
+    can_size_window: bool,
+}
+
+const REAPER: FakeHost = FakeHost { vendor: "Cockos", product: "REAPER", vendor_version: 6000, vst_version: 2400, can_size_window: true };
+const OLD_HOST: FakeHost = FakeHost { vendor: "Acme", product: "Tracker", vendor_version: 3, vst_version: 2200, can_size_window: false };
+
+impl FakeHost {
+    fn answer(&self, opcode: i32, ptr: *mut c_void) -> isize {
+        let is = |code: OpCode| {
+            let code: i32 = code.into();
+            opcode == code
+        };
+        let write = |text: &str| {
+            // SAFETY: hosts are given buffers of at least 64 bytes for these strings
+            unsafe {
+                ptr::copy_nonoverlapping(text.as_ptr(), ptr as *mut u8, text.len());
+                *(ptr as *mut u8).add(text.len()) = 0;
+            }
+            1
+        };
+        if is(OpCode::GetVendorString) {
+            write(self.vendor)
+        } else if is(OpCode::GetProductString) {
+            write(self.product)
+        } else if is(OpCode::GetVendorVersion) {
+            self.vendor_version
+        } else if is(OpCode::Version) {
+            self.vst_version
+        } else if is(OpCode::CanDo) {
+            // SAFETY: can-do strings are nul terminated
+            let can_do = unsafe { CStr::from_ptr(ptr as *const c_char) };
+            (can_do.to_bytes() == b"sizeWindow" && self.can_size_window) as isize
+        } else {
+            0
+        }
+    }
+}
+
+extern "C" fn reaper(_effect: *mut AEffect, opcode: i32, _index: i32, _value: isize, ptr: *mut c_void, _opt: f32) -> isize {
+    REAPER.answer(opcode, ptr)
+}
+
+extern "C" fn old_host(_effect: *mut AEffect, opcode: i32, _index: i32, _value: isize, ptr: *mut c_void, _opt: f32) -> isize {
+    OLD_HOST.answer(opcode, ptr)
+}
+
+#[test]
+fn a_known_host_gets_its_quirk() {
+    let probe = probe_host(&HostCallback::wrap(reaper, ptr::null_mut()));
+    assert_eq!((probe.info.vendor.as_str(), probe.info.product.as_str(), probe.info.version), ("Cockos", "REAPER", 6000));
+    assert_eq!(probe.quirk.map(|quirk| quirk.product), Some(Some("REAPER")));
+    assert_eq!(probe.resize, ResizeStrategy::SizeWindowThenIoChanged);
+    assert!(probe.info.supports_resize);
+    assert!(probe.info.supports_automation_gestures);
+}
+
+#[test]
+fn an_older_host_is_taken_at_its_word() {
+    let probe = probe_host(&HostCallback::wrap(old_host, ptr::null_mut()));
+    assert_eq!(probe.info.vendor, "Acme");
+    assert!(probe.quirk.is_none());
+    assert_eq!(probe.resize, ResizeStrategy::Unsupported);
+    assert!(!probe.info.supports_resize);
+    // beginEdit and endEdit came with VST 2.3
+    assert!(!probe.info.supports_automation_gestures);
+    assert_eq!(probe.info.display_update_interval, HostInfo::UNKNOWN.display_update_interval);
+}
+
+#[test]
+fn without_a_host_nothing_is_known() {
+    let probe = probe_host(&HostCallback::default());
+    assert_eq!(probe.info, HostInfo::UNKNOWN);
+    assert!(probe.quirk.is_none());
+}
+
+#[test]
+fn the_host_is_playing_as_of_the_last_block() {
+    let host = VstCarnyxHost::new(HostCallback::default());
+    assert!(!host.is_playing());
+    let playing = Transport { playing: true, tempo: Some(120.), ..Transport::default() };
+    host.set_transport(Some(&playing));
+    assert!(host.is_playing());
+    host.set_transport(Some(&Transport::default()));
+    assert!(!host.is_playing());
+    // hosts which don't report their transport aren't taken to be playing
+    host.set_transport(Some(&playing));
+    host.set_transport(None);
+    assert!(!host.is_playing());
+}
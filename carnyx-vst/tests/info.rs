@@ -0,0 +1,96 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use carnyx::buffer::{AudioBuffer, BusLayout};
+use carnyx::{BasicParam, Capabilities, CarnyxDescriptor, CarnyxModel, CarnyxParam, CarnyxProcessor, NoEditor, PluginCategory, ProcessContext, SettableListener};
+use carnyx_vst::vst::plugin::Category;
+use carnyx_vst::{input_channel_info, plugin_info};
+
+// a drum machine: notes in, trigger notes out, a stereo mix or separate outputs
+struct Drums {
+    tune: AtomicU32,
+}
+
+impl CarnyxModel for Drums {
+    type Snap = f32;
+
+    fn snap(&self) -> f32 {
+        f32::from_bits(self.tune.load(Ordering::Relaxed))
+    }
+
+    fn set_snap(&self, snap: &f32) {
+        self.tune.store(snap.to_bits(), Ordering::Relaxed)
+    }
+}
+
+struct DrumProcessor {
+    model: Arc<Drums>,
+    listener: SettableListener<Drums>,
+}
+
+impl DrumProcessor {
+    fn new() -> Self {
+        DrumProcessor { model: Arc::new(Drums { tune: AtomicU32::new(0) }), listener: SettableListener::new() }
+    }
+}
+
+impl CarnyxProcessor for DrumProcessor {
+    type Model = Drums;
+    type Editor = NoEditor;
+
+    fn descriptor(&self) -> CarnyxDescriptor {
+        CarnyxDescriptor::new("Kit", 0x4b69_7421)
+            .with_vendor("Backbeat")
+            .with_version(1203)
+            .with_category(PluginCategory::Instrument)
+            .with_bus_layout(BusLayout::new(0, 2))
+    }
+
+    fn model(&self) -> Arc<Drums> {
+        Arc::clone(&self.model)
+    }
+
+    fn listener(&self) -> SettableListener<Drums> {
+        self.listener.clone()
+    }
+
+    fn set_sample_rate(&mut self, _rate: f32) {}
+
+    fn parameters(&self) -> Vec<Box<dyn CarnyxParam<Drums>>> {
+        vec![Box::new(BasicParam::new("tune", "",
+            |m: &Drums| m.snap(),
+            |m: &Drums, v| m.set_snap(&v),
+            |m: &Drums| format!("{:.2}", m.snap())))]
+    }
+
+    fn process(&mut self, _buffer: &mut AudioBuffer<f32>, _context: &mut ProcessContext) {}
+
+    fn supported_layouts(&self) -> Vec<BusLayout> {
+        vec![BusLayout::new(0, 2), BusLayout::new(0, 8)]
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { receives_midi: true, sends_midi: true, ..Capabilities::default() }
+    }
+}
+
+#[test]
+fn the_vst_info_comes_from_the_descriptor() {
+    let info = plugin_info(&DrumProcessor::new());
+    assert_eq!((info.name.as_str(), info.vendor.as_str()), ("Kit", "Backbeat"));
+    assert_eq!((info.version, info.unique_id), (1203, 0x4b69_7421));
+    assert!(matches!(info.category, Category::Synth));
+    assert_eq!((info.midi_inputs, info.midi_outputs), (1, 1));
+    assert_eq!((info.parameters, info.presets), (1, 0));
+    assert!(info.preset_chunks);
+    // declared with every output the widest layout has
+    assert_eq!((info.inputs, info.outputs), (0, 8));
+}
+
+#[test]
+fn sidechain_inputs_are_named_for_the_host() {
+    let layout = BusLayout::new(2, 2).with_sidechain(2);
+    let names: Vec<String> = (0..4).map(|input| input_channel_info(&layout, input).name()).collect();
+    assert_eq!(names, ["Input 1", "Input 2", "Sidechain 1", "Sidechain 2"]);
+    assert_eq!(input_channel_info(&layout, 3).short_name(), "SC2");
+}
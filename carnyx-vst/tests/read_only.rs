@@ -0,0 +1,137 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use carnyx::buffer::AudioBuffer;
+use carnyx::preset;
+use carnyx::random::Rng;
+use carnyx::{BasicParam, CarnyxDescriptor, CarnyxHost, CarnyxModel, CarnyxParam, CarnyxProcessor, NoEditor, ProcessContext, SettableListener};
+use carnyx_vst::vst::host::HostBuffer;
+use carnyx_vst::CarnyxVstPlugin;
+
+const BLOCK_SIZE: usize = 16;
+
+fn load(value: &AtomicU32) -> f32 {
+    f32::from_bits(value.load(Ordering::Relaxed))
+}
+
+fn store(value: &AtomicU32, new: f32) {
+    value.store(new.to_bits(), Ordering::Relaxed)
+}
+
+// a compressor, with its gain reduction as an output for the host to show
+struct Squash {
+    threshold: AtomicU32,
+    reduction: AtomicU32,
+}
+
+impl CarnyxModel for Squash {
+    type Snap = f32;
+
+    fn snap(&self) -> f32 {
+        load(&self.threshold)
+    }
+
+    fn set_snap(&self, snap: &f32) {
+        store(&self.threshold, *snap)
+    }
+}
+
+struct SquashProcessor {
+    model: Arc<Squash>,
+    listener: SettableListener<Squash>,
+}
+
+impl CarnyxProcessor for SquashProcessor {
+    type Model = Squash;
+    type Editor = NoEditor;
+
+    fn descriptor(&self) -> CarnyxDescriptor {
+        CarnyxDescriptor::new("Squash", 5150)
+    }
+
+    fn model(&self) -> Arc<Squash> {
+        Arc::clone(&self.model)
+    }
+
+    fn listener(&self) -> SettableListener<Squash> {
+        self.listener.clone()
+    }
+
+    fn set_sample_rate(&mut self, _rate: f32) {}
+
+    fn parameters(&self) -> Vec<Box<dyn CarnyxParam<Squash>>> {
+        vec![
+            Box::new(BasicParam::new("threshold", "",
+                |m: &Squash| load(&m.threshold),
+                |m: &Squash, v| store(&m.threshold, v),
+                |m: &Squash| format!("{:.2}", load(&m.threshold)))),
+            // only the processor writes it, through the model
+            Box::new(BasicParam::new("gain reduction", "",
+                |m: &Squash| load(&m.reduction),
+                |m: &Squash, v| store(&m.reduction, v),
+                |m: &Squash| format!("{:.2}", load(&m.reduction)))
+                .read_only()),
+        ]
+    }
+
+    // reduces by however far the peak is over the threshold
+    fn process(&mut self, buffer: &mut AudioBuffer<f32>, _context: &mut ProcessContext) {
+        let threshold = load(&self.model.threshold);
+        let mut peak: f32 = 0.;
+        for (input, output) in buffer.zip() {
+            for (i, o) in input.iter().zip(output.iter_mut()) {
+                peak = peak.max(i.abs());
+                *o = i.min(threshold);
+            }
+        }
+        store(&self.model.reduction, (peak - threshold).max(0.));
+    }
+}
+
+fn plugin() -> CarnyxVstPlugin<SquashProcessor> {
+    let mut plugin = CarnyxVstPlugin::headless(|_: Arc<dyn CarnyxHost>| SquashProcessor {
+        model: Arc::new(Squash { threshold: AtomicU32::new(0.5f32.to_bits()), reduction: AtomicU32::new(0) }),
+        listener: SettableListener::new(),
+    });
+    plugin.set_sample_rate(48000.);
+    plugin.set_block_size(BLOCK_SIZE as i64);
+    plugin.resume();
+    plugin
+}
+
+#[test]
+fn the_host_can_read_an_output_but_not_set_it() {
+    let mut plugin = plugin();
+    let params = plugin.get_parameter_object();
+    assert!(params.can_be_automated(0));
+    assert!(!params.can_be_automated(1));
+
+    let mut host_buffer: HostBuffer<f32> = HostBuffer::new(2, 2);
+    let inputs = vec![vec![0.75; BLOCK_SIZE]; 2];
+    let mut outputs = vec![vec![0.; BLOCK_SIZE]; 2];
+    plugin.process(&mut host_buffer.bind(&inputs, &mut outputs));
+    assert_eq!(params.get_parameter(1), 0.25);
+    assert_eq!(params.get_parameter_text(1), "0.25");
+
+    params.set_parameter(1, 0.9);
+    assert!(!params.string_to_parameter(1, "0.9".to_string()));
+    assert_eq!(params.get_parameter(1), 0.25);
+    // while the threshold still takes what the host types in
+    assert!(params.string_to_parameter(0, "0.6".to_string()));
+    assert_eq!(params.get_parameter(0), 0.6);
+}
+
+#[test]
+fn randomizing_leaves_outputs_alone() {
+    let plugin = plugin();
+    let processor = plugin.processor();
+    let model = processor.model();
+    store(&model.reduction, 0.125);
+    let mut rng = Rng::new(0x5150);
+    for _ in 0..10 {
+        preset::randomize(&processor.all_parameters(), &*model, &mut rng);
+        preset::mutate(&processor.all_parameters(), &*model, 0.5, &mut rng);
+    }
+    assert_ne!(load(&model.threshold), 0.5);
+    assert_eq!(load(&model.reduction), 0.125);
+}
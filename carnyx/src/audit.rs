@@ -0,0 +1,128 @@
+//! Checks that nothing allocates or takes a lock on the audio thread.
+//!
+//! With the `audit` feature, a test which installs [`AuditAllocator`] as its global
+//! allocator counts allocations made inside [`realtime`], and carnyx's own locks report
+//! themselves with [`lock_taken`]. `realtime` panics if either happened, so tests fail
+//! where a plugin would glitch. Hosts don't survive a panic, so this is for tests and
+//! debugging only. Without the feature both functions compile to nothing.
+//!
+//! The allocator is left to the test crates, as a library installing one would clash
+//! with any binary which has its own:
+//!
+//! ```ignore
+//! #[cfg(feature = "audit")]
+//! #[global_allocator]
+//! static ALLOCATOR: carnyx::audit::AuditAllocator = carnyx::audit::AuditAllocator;
+//! ```
+
+#[cfg(feature = "audit")]
+mod enabled {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    thread_local! {
+        // how many `realtime` calls deep this thread is
+        static DEPTH: Cell<usize> = const { Cell::new(0) };
+        static VIOLATIONS: Cell<usize> = const { Cell::new(0) };
+        static FIRST: Cell<Option<&'static str>> = const { Cell::new(None) };
+    }
+
+    fn record(what: &'static str) {
+        // try_with, as the allocator may be called while the thread is shutting down
+        let _ = DEPTH.try_with(|depth| {
+            if depth.get() > 0 {
+                VIOLATIONS.with(|v| v.set(v.get() + 1));
+                FIRST.with(|first| if first.get().is_none() { first.set(Some(what)) });
+            }
+        });
+    }
+
+    /// Counts allocations on threads inside [`realtime`](super::realtime), once installed
+    /// with `#[global_allocator]`.
+    pub struct AuditAllocator;
+
+    unsafe impl GlobalAlloc for AuditAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            record("allocation");
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            record("deallocation");
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            record("allocation");
+            System.alloc_zeroed(layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            record("reallocation");
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    // leaves `realtime` even if `f` panics, so a caught panic doesn't leave the thread audited
+    struct Depth {
+        outermost: bool,
+    }
+
+    impl Drop for Depth {
+        fn drop(&mut self) {
+            DEPTH.with(|depth| depth.set(depth.get() - 1));
+            if self.outermost && std::thread::panicking() {
+                // unwinding allocates; the panic says what went wrong
+                VIOLATIONS.with(|v| v.set(0));
+                FIRST.with(|first| first.set(None));
+            }
+        }
+    }
+
+    pub fn realtime<R>(context: &'static str, f: impl FnOnce() -> R) -> R {
+        let outermost = DEPTH.with(|depth| {
+            depth.set(depth.get() + 1);
+            depth.get() == 1
+        });
+        let depth = Depth { outermost };
+        let result = f();
+        drop(depth);
+        if outermost {
+            let violations = VIOLATIONS.with(|v| v.replace(0));
+            let first = FIRST.with(|first| first.take());
+            if violations > 0 {
+                panic!("{}: {} allocations or locks on the audio thread, first: {}", context, violations, first.unwrap_or("?"));
+            }
+        }
+        result
+    }
+
+    pub fn lock_taken(lock: &'static str) {
+        record(lock)
+    }
+}
+
+#[cfg(feature = "audit")]
+pub use enabled::AuditAllocator;
+
+/// Run `f`, which the audio thread calls, panicking if it allocated or took a lock.
+/// `context` names it in the panic message.
+#[inline(always)]
+pub fn realtime<R>(context: &'static str, f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "audit")]
+    return enabled::realtime(context, f);
+    #[cfg(not(feature = "audit"))]
+    {
+        let _ = context;
+        f()
+    }
+}
+
+/// Report taking a lock, which is a violation inside [`realtime`].
+#[inline(always)]
+pub fn lock_taken(lock: &'static str) {
+    #[cfg(feature = "audit")]
+    enabled::lock_taken(lock);
+    #[cfg(not(feature = "audit"))]
+    let _ = lock;
+}
@@ -0,0 +1,154 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use vst::util::AtomicFloat;
+
+use crate::events::NoteEvent;
+use crate::random::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditionWaveform {
+    Sine,
+    Saw,
+    Noise,
+}
+
+impl AuditionWaveform {
+    pub const ALL: [AuditionWaveform; 3] = [AuditionWaveform::Sine, AuditionWaveform::Saw, AuditionWaveform::Noise];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuditionWaveform::Sine => "Sine",
+            AuditionWaveform::Saw => "Saw",
+            AuditionWaveform::Noise => "Noise",
+        }
+    }
+}
+
+/// Audition settings shared between the editor and the processor.
+pub struct AuditionSettings {
+    enabled: AtomicBool,
+    waveform: AtomicUsize,
+    level: AtomicFloat,
+    frequency: AtomicFloat,
+}
+
+impl Default for AuditionSettings {
+    fn default() -> Self {
+        AuditionSettings {
+            enabled: AtomicBool::new(false),
+            waveform: AtomicUsize::new(1),
+            level: AtomicFloat::new(0.25),
+            frequency: AtomicFloat::new(110.),
+        }
+    }
+}
+
+impl AuditionSettings {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed)
+    }
+
+    pub fn waveform(&self) -> AuditionWaveform {
+        AuditionWaveform::ALL[self.waveform.load(Ordering::Relaxed).min(AuditionWaveform::ALL.len() - 1)]
+    }
+
+    pub fn set_waveform(&self, waveform: AuditionWaveform) {
+        let index = AuditionWaveform::ALL.iter().position(|w| *w == waveform).unwrap_or(0);
+        self.waveform.store(index, Ordering::Relaxed)
+    }
+
+    /// Linear gain of the generated signal.
+    pub fn level(&self) -> f32 {
+        self.level.get()
+    }
+
+    pub fn set_level(&self, level: f32) {
+        self.level.set(level.clamp(0., 1.))
+    }
+
+    /// Frequency played while the audition is enabled and no note is held.
+    pub fn frequency(&self) -> f32 {
+        self.frequency.get()
+    }
+
+    pub fn set_frequency(&self, hz: f32) {
+        self.frequency.set(hz.max(0.))
+    }
+}
+
+pub fn note_to_hz(note: u8) -> f32 {
+    440. * 2f32.powf((note as f32 - 69.) / 12.)
+}
+
+/// Processor side test signal generator. It sounds while the audition is enabled
+/// in the editor, or while a note from the note queue is held.
+pub struct AuditionGenerator {
+    settings: Arc<AuditionSettings>,
+    sample_rate: f32,
+    phase: f32,
+    rng: Rng,
+    held: Option<(u8, f32)>,
+}
+
+impl AuditionGenerator {
+    pub fn new(settings: Arc<AuditionSettings>) -> Self {
+        AuditionGenerator {
+            settings,
+            sample_rate: 44100.,
+            phase: 0.,
+            rng: Rng::default(),
+            held: None,
+        }
+    }
+
+    pub fn settings(&self) -> Arc<AuditionSettings> {
+        Arc::clone(&self.settings)
+    }
+
+    pub fn set_sample_rate(&mut self, rate: f32) {
+        self.sample_rate = rate;
+    }
+
+    pub fn note(&mut self, event: NoteEvent) {
+        match event {
+            NoteEvent::On { note, velocity } => self.held = Some((note, velocity)),
+            NoteEvent::Off { note } => {
+                if matches!(self.held, Some((held, _)) if held == note) {
+                    self.held = None
+                }
+            }
+        }
+    }
+
+    /// Release any held note.
+    pub fn all_notes_off(&mut self) {
+        self.held = None;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.held.is_some() || self.settings.is_enabled()
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        if !self.is_active() {
+            return 0.;
+        }
+        let (frequency, velocity) = match self.held {
+            Some((note, velocity)) => (note_to_hz(note), velocity),
+            None => (self.settings.frequency(), 1.),
+        };
+        self.phase = (self.phase + frequency / self.sample_rate).fract();
+        let sample = match self.settings.waveform() {
+            AuditionWaveform::Sine => (2. * PI * self.phase).sin(),
+            AuditionWaveform::Saw => 2. * self.phase - 1.,
+            AuditionWaveform::Noise => self.rng.next_bipolar(),
+        };
+        sample * velocity * self.settings.level()
+    }
+}
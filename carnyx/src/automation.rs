@@ -0,0 +1,191 @@
+//! Rate limiting for parameter changes a processor reports to the host.
+
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Parameters beyond this index are never sent.
+pub const AUTOMATION_CAPACITY: usize = 256;
+/// About 30 updates a second, plenty for meters without flooding the host's automation lanes.
+pub const DEFAULT_AUTOMATION_INTERVAL: Duration = Duration::from_millis(33);
+
+// marks a parameter which hasn't been sent yet
+const NEVER: u64 = u64::MAX;
+
+struct Sent {
+    // microseconds since the limiter was created
+    at: AtomicU64,
+    value: AtomicU32,
+}
+
+/// Decides which processor-driven parameter changes are passed on to the host: at most one
+/// per parameter per interval, and none that repeat the last value sent. Lock free, so it
+/// can be used on the audio thread. Values changing faster than the interval are thinned
+/// out, so callers should keep reporting while a value changes.
+pub struct AutomationLimiter {
+    start: Instant,
+    interval: Duration,
+    sent: Vec<Sent>,
+}
+
+impl Default for AutomationLimiter {
+    fn default() -> Self {
+        AutomationLimiter::new(DEFAULT_AUTOMATION_INTERVAL)
+    }
+}
+
+impl AutomationLimiter {
+    pub fn new(interval: Duration) -> Self {
+        AutomationLimiter {
+            start: Instant::now(),
+            interval,
+            sent: (0..AUTOMATION_CAPACITY)
+                .map(|_| Sent { at: AtomicU64::new(NEVER), value: AtomicU32::new(0) })
+                .collect(),
+        }
+    }
+
+    /// Whether to send `value` for parameter `index` now. If so, it is recorded as sent.
+    pub fn allow(&self, index: usize, value: f32) -> bool {
+        let sent = match self.sent.get(index) {
+            Some(sent) => sent,
+            None => return false,
+        };
+        let now = self.start.elapsed().as_micros() as u64;
+        let at = sent.at.load(Ordering::Relaxed);
+        if at != NEVER {
+            if sent.value.load(Ordering::Relaxed) == value.to_bits() {
+                return false;
+            }
+            if now.saturating_sub(at) < self.interval.as_micros() as u64 {
+                return false;
+            }
+        }
+        // another thread may have sent in between; losing that race only costs an update
+        if sent.at.compare_exchange(at, now, Ordering::Relaxed, Ordering::Relaxed).is_err() {
+            return false;
+        }
+        sent.value.store(value.to_bits(), Ordering::Relaxed);
+        true
+    }
+}
+
+// this many changes from outside the editor within the window means automation
+const AUTOMATION_CHANGES: usize = 3;
+const AUTOMATION_WINDOW: Duration = Duration::from_millis(500);
+// how long automation is shown after the last automated change
+const AUTOMATION_HOLD: Duration = Duration::from_millis(1000);
+
+/// Tracks changes to a control's value which didn't come from the control itself, to tell
+/// automation playing back from the odd one-off change. For the editor's thread.
+#[derive(Debug, Clone)]
+pub struct AutomationTracker {
+    window_start: Option<Instant>,
+    window_changes: usize,
+    // the value before the changes in this window started
+    window_value: f64,
+    last_change: Option<Instant>,
+    // the value the user set, shown as the control while automation runs
+    user_value: Option<f64>,
+}
+
+impl Default for AutomationTracker {
+    fn default() -> Self {
+        AutomationTracker::new()
+    }
+}
+
+impl AutomationTracker {
+    pub fn new() -> Self {
+        AutomationTracker { window_start: None, window_changes: 0, window_value: 0., last_change: None, user_value: None }
+    }
+
+    pub fn is_automating(&self) -> bool {
+        self.user_value.is_some()
+    }
+
+    /// While automating, the value the user last set, which automation moves away from.
+    pub fn user_value(&self) -> Option<f64> {
+        self.user_value
+    }
+
+    /// The value changed from outside at `now`, from `old`.
+    pub fn external_change(&mut self, old: f64, now: Instant) {
+        match self.window_start {
+            Some(start) if now.saturating_duration_since(start) < AUTOMATION_WINDOW => self.window_changes += 1,
+            _ => {
+                self.window_start = Some(now);
+                self.window_changes = 1;
+                self.window_value = old;
+            }
+        }
+        self.last_change = Some(now);
+        if self.user_value.is_none() && self.window_changes >= AUTOMATION_CHANGES {
+            self.user_value = Some(self.window_value);
+        }
+    }
+
+    pub fn user_edit(&mut self, value: f64) {
+        if self.is_automating() {
+            self.user_value = Some(value);
+        }
+    }
+
+    /// Returns true if automation has just stopped.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        match self.last_change {
+            Some(last) if self.is_automating() && now.saturating_duration_since(last) >= AUTOMATION_HOLD => {
+                *self = AutomationTracker::new();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// About 20 refreshes a second while a control is dragged.
+pub const DEFAULT_DISPLAY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Decides when the editor asks the host to refresh its parameter display. Edits outside a
+/// gesture go straight through; during one at most one refresh is sent per interval, and
+/// any held back are owed when the gesture ends. For the editor's thread.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayLimiter {
+    interval: Duration,
+    sent: Option<Instant>,
+    owed: bool,
+}
+
+impl Default for DisplayLimiter {
+    fn default() -> Self {
+        DisplayLimiter::new(DEFAULT_DISPLAY_INTERVAL)
+    }
+}
+
+impl DisplayLimiter {
+    pub fn new(interval: Duration) -> Self {
+        DisplayLimiter { interval, sent: None, owed: false }
+    }
+
+    /// Whether to refresh the host's display for an edit made at `now`, with `in_gesture`
+    /// set while any parameter is being dragged. If so, it is recorded as sent.
+    pub fn edited(&mut self, now: Instant, in_gesture: bool) -> bool {
+        let due = match self.sent {
+            Some(sent) if in_gesture => now.saturating_duration_since(sent) >= self.interval,
+            _ => true,
+        };
+        if due {
+            self.sent = Some(now);
+        }
+        self.owed = !due;
+        due
+    }
+
+    /// Whether a refresh was held back, once the last gesture has ended.
+    pub fn gesture_ended(&mut self, now: Instant) -> bool {
+        let owed = std::mem::replace(&mut self.owed, false);
+        if owed {
+            self.sent = Some(now);
+        }
+        owed
+    }
+}
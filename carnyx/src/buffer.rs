@@ -0,0 +1,243 @@
+//! Audio buffers. Re-exports the vst buffer types, plus scratch space and bus layouts for
+//! processors.
+
+pub use vst::buffer::*;
+
+/// How much scratch space a processor wants, see
+/// [`CarnyxProcessor::scratch_spec`](crate::CarnyxProcessor::scratch_spec).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScratchSpec {
+    pub buffers: usize,
+    pub samples: usize,
+}
+
+/// Preallocated temporary buffers, owned by the bridge and lent to the processor each
+/// block so it never has to allocate on the audio thread.
+#[derive(Default)]
+pub struct ScratchBuffers {
+    buffers: Vec<Vec<f32>>,
+}
+
+impl ScratchBuffers {
+    pub fn new(spec: ScratchSpec) -> Self {
+        let mut scratch = ScratchBuffers::default();
+        scratch.resize(spec);
+        scratch
+    }
+
+    /// Allocates, so call this outside of processing (e.g. on resume).
+    pub fn resize(&mut self, spec: ScratchSpec) {
+        self.buffers.resize_with(spec.buffers, Vec::new);
+        for buffer in &mut self.buffers {
+            buffer.resize(spec.samples, 0.);
+        }
+    }
+
+    pub fn spec(&self) -> ScratchSpec {
+        ScratchSpec {
+            buffers: self.buffers.len(),
+            samples: self.buffers.first().map(|b| b.len()).unwrap_or(0),
+        }
+    }
+
+    /// Lend out every buffer, each truncated to `samples`.
+    pub fn lend(&mut self, samples: usize) -> Scratch<'_> {
+        Scratch {
+            buffers: &mut self.buffers,
+            samples,
+        }
+    }
+}
+
+/// Scratch buffers lent for one block. Contents are whatever the last block left behind.
+pub struct Scratch<'a> {
+    buffers: &'a mut [Vec<f32>],
+    samples: usize,
+}
+
+impl<'a> Scratch<'a> {
+    pub fn empty() -> Self {
+        Scratch {
+            buffers: &mut [],
+            samples: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    /// Split the last `count` buffers off into their own `Scratch`, e.g. for the framework
+    /// to use around the processor. Takes fewer if there aren't enough.
+    pub fn split_off(&mut self, count: usize) -> Scratch<'a> {
+        let buffers = std::mem::take(&mut self.buffers);
+        let keep = buffers.len().saturating_sub(count);
+        let (kept, split) = buffers.split_at_mut(keep);
+        self.buffers = kept;
+        Scratch {
+            buffers: split,
+            samples: self.samples,
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut [f32]> {
+        let samples = self.samples;
+        self.buffers.get_mut(index).map(|b| {
+            let len = samples.min(b.len());
+            &mut b[..len]
+        })
+    }
+
+    /// All the buffers at once, for processors needing several simultaneously.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut [f32]> {
+        let samples = self.samples;
+        self.buffers.iter_mut().map(move |b| {
+            let len = samples.min(b.len());
+            &mut b[..len]
+        })
+    }
+}
+
+/// How a processor's input channels divide into buses. Inputs are laid out main first,
+/// then sidechain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusLayout {
+    pub main_inputs: usize,
+    pub sidechain_inputs: usize,
+    pub outputs: usize,
+}
+
+impl BusLayout {
+    pub fn new(main_inputs: usize, outputs: usize) -> Self {
+        BusLayout {
+            main_inputs,
+            sidechain_inputs: 0,
+            outputs,
+        }
+    }
+
+    pub fn with_sidechain(mut self, channels: usize) -> Self {
+        self.sidechain_inputs = channels;
+        self
+    }
+
+    pub fn total_inputs(&self) -> usize {
+        self.main_inputs + self.sidechain_inputs
+    }
+
+    pub fn is_sidechain(&self, input: usize) -> bool {
+        input >= self.main_inputs && input < self.total_inputs()
+    }
+
+    /// The layout in `layouts` with the most channels, which bridges declare to hosts that
+    /// can't be told about the others.
+    pub fn widest(layouts: &[BusLayout]) -> Option<BusLayout> {
+        layouts.iter().copied().max_by_key(|layout| (layout.total_inputs() + layout.outputs, layout.main_inputs))
+    }
+
+    /// The layout in `layouts` for a host's main bus of `inputs` and `outputs` channels.
+    pub fn find(layouts: &[BusLayout], inputs: usize, outputs: usize) -> Option<BusLayout> {
+        layouts.iter().copied().find(|layout| layout.main_inputs == inputs && layout.outputs == outputs)
+    }
+}
+
+/// An [`AudioBuffer`] split by a [`BusLayout`]. If the host connected fewer channels than
+/// the layout asks for, the missing ones are absent rather than silent.
+pub struct Buses<'a> {
+    pub main: Inputs<'a, f32>,
+    pub sidechain: Inputs<'a, f32>,
+    pub outputs: Outputs<'a, f32>,
+}
+
+impl<'a> Buses<'a> {
+    /// Pairs main inputs with outputs, like [`AudioBuffer::zip`] but ignoring the sidechain.
+    pub fn zip_main(self) -> impl Iterator<Item = (&'a [f32], &'a mut [f32])> {
+        self.main.into_iter().zip(self.outputs.into_iter())
+    }
+}
+
+/// The output channels of a buffer, each already holding its input, for processing in place.
+pub struct InPlaceChannels<'a> {
+    outputs: Outputs<'a, f32>,
+}
+
+impl<'a> InPlaceChannels<'a> {
+    pub fn len(&self) -> usize {
+        self.outputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outputs.len() == 0
+    }
+
+    pub fn get_mut(&mut self, channel: usize) -> &mut [f32] {
+        self.outputs.get_mut(channel)
+    }
+}
+
+impl<'a> IntoIterator for InPlaceChannels<'a> {
+    type Item = &'a mut [f32];
+    type IntoIter = <Outputs<'a, f32> as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.outputs.into_iter()
+    }
+}
+
+pub trait AudioBufferExt {
+    fn buses(&mut self, layout: &BusLayout) -> Buses<'_>;
+
+    /// Whether the host passed the same memory for an input and an output channel. If so,
+    /// writing an output sample overwrites the input sample with the same index, and any
+    /// later read of that input sees the processed value. Never true of an empty block,
+    /// where there is nothing to overwrite.
+    fn is_in_place(&mut self) -> bool;
+
+    /// Copy each input into its output (unless they are already the same memory) and
+    /// hand back the outputs, so processing reads and writes one slice per channel
+    /// whether or not the host processes in place. Outputs without an input are zeroed.
+    /// Hosts processing in place share an input with the output of the same number; one
+    /// shared with another output may be overwritten before it is copied.
+    fn zip_in_place(&mut self) -> InPlaceChannels<'_>;
+}
+
+impl AudioBufferExt for AudioBuffer<'_, f32> {
+    fn buses(&mut self, layout: &BusLayout) -> Buses<'_> {
+        let (inputs, outputs) = self.split();
+        let main_inputs = layout.main_inputs.min(inputs.len());
+        let (main, sidechain) = inputs.split_at(main_inputs);
+        Buses { main, sidechain, outputs }
+    }
+
+    fn is_in_place(&mut self) -> bool {
+        // empty channels may all point at the same dangling address
+        if self.samples() == 0 {
+            return false;
+        }
+        let (inputs, mut outputs) = self.split();
+        inputs.into_iter().any(|input| {
+            (0..outputs.len()).any(|channel| outputs.get_mut(channel).as_ptr() == input.as_ptr())
+        })
+    }
+
+    fn zip_in_place(&mut self) -> InPlaceChannels<'_> {
+        let (inputs, mut outputs) = self.split();
+        let copied = inputs.len().min(outputs.len());
+        for (channel, input) in inputs.into_iter().enumerate().take(copied) {
+            let output = outputs.get_mut(channel);
+            if output.as_ptr() != input.as_ptr() {
+                output.copy_from_slice(input);
+            }
+        }
+        for channel in copied..outputs.len() {
+            for sample in outputs.get_mut(channel).iter_mut() {
+                *sample = 0.;
+            }
+        }
+        InPlaceChannels { outputs }
+    }
+}
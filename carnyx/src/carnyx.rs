@@ -1,9 +1,166 @@
 use raw_window_handle::RawWindowHandle;
-use crate::buffer::AudioBuffer;
-use std::sync::{Mutex, Arc};
+use crate::automation::DEFAULT_DISPLAY_INTERVAL;
+use crate::buffer::{AudioBuffer, AudioBufferExt, BusLayout, Scratch, ScratchSpec};
+use crate::crossfade::PresetCrossfade;
+use crate::descriptor::CarnyxDescriptor;
+use crate::diagnostics::Diagnostics;
+use crate::events::{MidiMessage, TimedMidi};
+use crate::lfo::LfoParams;
+use crate::limiter::Limiter;
+use crate::load::DspLoad;
+use crate::macros::MacroParams;
+use crate::midi_routes::MidiRoutes;
+use crate::locks::ParamLocks;
+use crate::modulation::Modulation;
+use crate::param_ids::ParamIdTable;
+use crate::preset::PresetBank;
+use crate::probe::Probes;
+use crate::process::ProcessContext;
+use crate::seqlock::SeqLock;
+use crate::telemetry::TelemetryBus;
+use crate::shared::Instance;
+use crate::tag::InstanceTag;
+use crate::ui_state::UiState;
+use crate::units::parse_choice;
+use crate::utility::UtilityParams;
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, Arc, Weak};
+use std::time::Duration;
+
+/// What the host is and what it can do, probed once by the bridge. Host quirks are
+/// folded in here rather than checked for wherever they matter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostInfo {
+    pub vendor: String,
+    pub product: String,
+    /// The vendor's own version number for the host.
+    pub version: i32,
+    /// The host will resize the editor window when asked.
+    pub supports_resize: bool,
+    /// The host understands begin/end edit notifications around parameter changes.
+    pub supports_automation_gestures: bool,
+    /// The least time between the editor's requests to refresh the host's parameter display
+    /// while a control is dragged.
+    pub display_update_interval: Duration,
+}
+
+impl HostInfo {
+    pub const UNKNOWN: HostInfo = HostInfo {
+        vendor: String::new(),
+        product: String::new(),
+        version: 0,
+        supports_resize: false,
+        supports_automation_gestures: false,
+        display_update_interval: DEFAULT_DISPLAY_INTERVAL,
+    };
+}
+
+impl Default for HostInfo {
+    fn default() -> Self {
+        HostInfo::UNKNOWN
+    }
+}
+
+static UNKNOWN_HOST: HostInfo = HostInfo::UNKNOWN;
+
+/// Where an instance has been put, as far as the host tells plugins. Hosts vary in what
+/// they share, so everything here is optional.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstanceContext {
+    /// The host application, e.g. "REAPER".
+    pub host_name: Option<String>,
+    /// The track or mixer channel the instance is on.
+    pub track_name: Option<String>,
+}
+
+impl InstanceContext {
+    /// A one line description for editors, e.g. "Bass - REAPER".
+    pub fn describe(&self) -> String {
+        let parts: Vec<&str> = self.track_name.iter().chain(self.host_name.iter()).map(|s| s.as_str()).collect();
+        parts.join(" - ")
+    }
+}
 
 pub trait CarnyxHost: Sync + Send{
     fn update_host_display(&self);
+
+    fn host_info(&self) -> &HostInfo {
+        &UNKNOWN_HOST
+    }
+
+    /// What the host says about where this instance is. Not for the audio thread.
+    fn instance_context(&self) -> InstanceContext {
+        InstanceContext::default()
+    }
+
+    /// Whether the host's transport was playing as of the last processed block.
+    fn is_playing(&self) -> bool {
+        false
+    }
+
+    /// Where this plugin instance records diagnostic events, if anywhere.
+    fn diagnostics(&self) -> Option<Arc<Diagnostics>> {
+        None
+    }
+
+    /// Tell the host a parameter changed from inside the processor, e.g. a gain reduction
+    /// meter, so it can record or display it. The processor sets the value on the model
+    /// itself. Hosts may be sent only some of the changes; returns false for those that
+    /// weren't sent. Safe to call from `process`.
+    fn set_parameter_automated(&self, _index: usize, _value: f32) -> bool {
+        false
+    }
+
+    /// Tell the host which parameters are active, or what they are called, changed. Hosts
+    /// can't add or remove parameters, so inactive ones stay listed. Not for the audio thread.
+    fn parameter_layout_changed(&self) {}
+
+    /// Tell the host the channel counts or latency the plugin reports changed, so it asks
+    /// again. Hosts may restart the plugin for it, so only call when they really have. Not
+    /// for the audio thread.
+    fn io_changed(&self) {}
+
+    /// Tell the host the user has started changing a parameter, so it records the changes
+    /// until [`end_edit`](CarnyxHost::end_edit) as one gesture. Gestures on different
+    /// parameters may overlap, e.g. with a dial under each finger. Not for the audio thread.
+    fn begin_edit(&self, _index: usize) {}
+
+    fn end_edit(&self, _index: usize) {}
+
+    /// Why the instance stopped processing, if the processor panicked. Its output stays
+    /// muted from then on. Not for the audio thread.
+    fn failure(&self) -> Option<String> {
+        None
+    }
+
+    /// Stop the instance after a panic in `what`, caught by [`guard`]: hosts mute its output
+    /// from then on and report `message` as the [`failure`](CarnyxHost::failure).
+    fn fail(&self, _what: &'static str, _message: &str) {}
+}
+
+/// Run `f`, which the host called as `what`, catching a panic rather than letting it unwind
+/// into the host, which would take the host down too. A panic fails the instance, see
+/// [`CarnyxHost::fail`], and gives `None`. `fail` allocates, so on the audio thread guard
+/// outside any [`audit::realtime`](crate::audit::realtime).
+pub fn guard<R>(host: &dyn CarnyxHost, what: &'static str, f: impl FnOnce() -> R) -> Option<R> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => Some(result),
+        Err(panic) => {
+            host.fail(what, panic_message(&*panic));
+            None
+        }
+    }
+}
+
+/// The message a panic was raised with, if it had one.
+pub fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+        (Some(message), _) => message,
+        (_, Some(message)) => message,
+        _ => "no message",
+    }
 }
 
 pub trait CarnyxWindowResizer {
@@ -13,72 +170,513 @@ pub trait CarnyxWindowResizer {
 pub trait CarnyxEditor{
     fn initial_size(&self)->(usize, usize);
     fn initial_position(&self)->(isize, isize);
+    /// Open the editor in the given parent window. Opening an editor which is already
+    /// open closes it first.
     fn open(&mut self, handle: Option<RawWindowHandle>, window_resizer: Box<dyn CarnyxWindowResizer>)->bool;
+    /// Called when the host closes the editor's window. The editor may be opened again.
+    fn close(&mut self);
     fn is_open(&self)->bool;
 }
 
+/// The `Editor` type for processors which rely on a generic editor built from their
+/// parameters. It has no values, so it can never be opened.
+pub enum NoEditor {}
+
+impl CarnyxEditor for NoEditor {
+    fn initial_size(&self) -> (usize, usize) {
+        match *self {}
+    }
+
+    fn initial_position(&self) -> (isize, isize) {
+        match *self {}
+    }
+
+    fn open(&mut self, _handle: Option<RawWindowHandle>, _window_resizer: Box<dyn CarnyxWindowResizer>) -> bool {
+        match *self {}
+    }
+
+    fn close(&mut self) {
+        match *self {}
+    }
+
+    fn is_open(&self) -> bool {
+        match *self {}
+    }
+}
+
+/// Whether the host is playing live or rendering (bouncing) faster or slower than realtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessingMode {
+    Realtime,
+    Offline,
+}
+
+impl Default for ProcessingMode {
+    fn default() -> Self {
+        ProcessingMode::Realtime
+    }
+}
+
+/// Features a processor has, which bridges report to the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities {
+    /// Wants MIDI from the host, e.g. for notes or program changes.
+    pub receives_midi: bool,
+    /// Sends MIDI back to the host.
+    pub sends_midi: bool,
+    /// Implements the host's offline processing interface (not just offline rendering).
+    pub offline: bool,
+    /// Handles bypass itself, e.g. to keep latency constant or ramp out smoothly.
+    pub bypass: bool,
+}
+
 pub trait CarnyxProcessor {
     type Model: CarnyxModel;
     type Editor: CarnyxEditor;
 
+    /// Name, identity and channel layout, for the host.
+    fn descriptor(&self) -> CarnyxDescriptor;
     fn model(&self)->Arc<Self::Model>;
     fn listener(&self)->SettableListener<Self::Model>;
     fn set_sample_rate(&mut self, rate: f32);
+
+    /// Called by bridges after `set_sample_rate` when the rate really changed, before the
+    /// next `process`. Filter state computed at the old rate can be reset here.
+    fn on_sample_rate_changed(&mut self, _old_rate: f32, _new_rate: f32) {}
+
+    /// Called when the host starts processing again, e.g. after transport stops or bypass.
+    fn activate(&mut self) {}
+
+    /// Called when the host stops processing. Nothing is processed until `activate`.
+    fn deactivate(&mut self) {}
+
     fn parameters(&self)->Vec<Box<dyn CarnyxParam<Self::Model>>>;
-    fn editor(&self)->Self::Editor;
-    fn process(&mut self, buffer: &mut AudioBuffer<f32>);
+
+    /// Parameter ids in the order hosts first saw the parameters: by default those in
+    /// `all_parameters` with an id, unless [`ParamFlags::UNPUBLISHED`], in list order. Hosts
+    /// save automation by index, so once released, override this with the ids as released
+    /// and only append to them; the parameter lists themselves can then change freely.
+    fn published_param_ids(&self) -> Vec<String> {
+        self.all_parameters()
+            .iter()
+            .filter(|param| !param.id().is_empty() && !param.flags().contains(ParamFlags::UNPUBLISHED))
+            .map(|param| param.id().to_string())
+            .collect()
+    }
+
+    /// Tell the host and any open editors that which parameters are active changed. Not for
+    /// the audio thread.
+    fn parameter_layout_changed(&self, host: &dyn CarnyxHost) {
+        host.parameter_layout_changed();
+        self.listener().notify_change(&self.model(), ChangeEvent::layout(ChangeOrigin::Processor));
+    }
+
+    /// How bridges map host parameter indices to `all_parameters`.
+    fn param_ids(&self) -> ParamIdTable {
+        let published = self.published_param_ids();
+        ParamIdTable::new(&self.all_parameters(), &published.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+    fn process(&mut self, buffer: &mut AudioBuffer<f32>, context: &mut ProcessContext);
+
+    /// A custom editor. Without one, bridges show a generic editor with a control for
+    /// each parameter.
+    fn editor(&self) -> Option<Self::Editor> {
+        None
+    }
+
+    /// Called when the host starts processing. Offline rendering has no deadline, so
+    /// processors may spend more CPU on quality.
+    fn set_processing_mode(&mut self, _mode: ProcessingMode) {}
+
+    /// Input and output channels, as declared to the host in the descriptor. Processors
+    /// supporting several layouts return the one last set.
+    fn bus_layout(&self) -> BusLayout {
+        self.descriptor().bus_layout
+    }
+
+    /// The channel layouts the processor can run in, e.g. mono and stereo.
+    fn supported_layouts(&self) -> Vec<BusLayout> {
+        vec![self.descriptor().bus_layout]
+    }
+
+    /// Switch to one of `supported_layouts`. Bridges call this while the host isn't
+    /// processing, then ask for scratch buffers again. Returns false, keeping the current
+    /// layout, for layouts the processor doesn't support.
+    fn set_layout(&mut self, layout: BusLayout) -> bool {
+        layout == self.bus_layout()
+    }
+
+    /// Scratch buffers to lend the processor through the `ProcessContext`, given the
+    /// largest block the host will send. Asked for whenever the host resumes processing.
+    fn scratch_spec(&self, _max_block_size: usize) -> ScratchSpec {
+        ScratchSpec::default()
+    }
+
+    /// The processor's scratch spec plus what `process_block` needs itself: a dry copy of
+    /// the inputs for the utility mix when the host processes in place. Bridges allocate this.
+    fn block_scratch_spec(&self, max_block_size: usize) -> ScratchSpec {
+        let spec = self.scratch_spec(max_block_size);
+        if self.model().utility().is_some() {
+            ScratchSpec {
+                buffers: spec.buffers + self.bus_layout().main_inputs,
+                samples: spec.samples.max(max_block_size),
+            }
+        } else {
+            spec
+        }
+    }
+
+    /// By default processors receive MIDI if they have presets, for program change.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            receives_midi: self.presets().is_some(),
+            ..Capabilities::default()
+        }
+    }
+
+    /// Presets the bridge exposes to the host, which MIDI program change also selects.
+    fn presets(&self) -> Option<Arc<PresetBank<<Self::Model as CarnyxModel>::Snap>>> {
+        None
+    }
+
+    /// Offered every MIDI program change (with the bank last selected) before it selects a
+    /// preset. Processors using program change for something else handle it here and
+    /// return true to stop the preset changing.
+    fn program_change(&mut self, _bank: u16, _program: u8) -> bool {
+        false
+    }
+
+    /// Somewhere for the utility's brickwall limiter to keep the audio it looks ahead at.
+    /// Processors without one are hard clipped when the brickwall is chosen.
+    fn output_limiter(&mut self) -> Option<&mut Limiter> {
+        None
+    }
+
+    /// Whether, given silent input, the processor would only output silence, e.g. once a
+    /// filter's tail has decayed. Returning true lets `process_block` skip `process`.
+    fn is_silent(&self) -> bool {
+        false
+    }
+
+    /// Called by `process_block` instead of `process` for a block it skipped, to keep
+    /// control rate state such as LFO phases moving through the silence.
+    fn skipped(&mut self, _context: &mut ProcessContext) {}
+
+    /// The processor's own parameters followed by any framework provided ones.
+    fn all_parameters(&self) -> Vec<Box<dyn CarnyxParam<Self::Model>>> {
+        let mut params = self.parameters();
+        let model = self.model();
+        // modulation moves the processor's own parameters, not the framework's
+        let targets: Vec<String> = params.iter().map(|param| param.name(&*model)).collect();
+        if model.utility().is_some() {
+            params.extend(UtilityParams::parameters());
+        }
+        if model.lfo().is_some() {
+            params.extend(LfoParams::parameters(targets.clone()));
+        }
+        if model.macros().is_some() {
+            params.extend(MacroParams::parameters(targets.clone()));
+        }
+        if model.midi_routes().is_some() {
+            params.extend(MidiRoutes::parameters(targets));
+        }
+        params
+    }
+
+    /// Called by bridges instead of `process`, to run framework stages around it.
+    fn process_block(&mut self, buffer: &mut AudioBuffer<f32>, context: &mut ProcessContext) {
+        handle_program_changes(self, context.events);
+        let idle = context.events.is_empty() && context.input_silence.all_silent(buffer.input_count());
+        if idle && self.is_silent() {
+            self.skipped(context);
+            let (_, outputs) = buffer.split();
+            for output in outputs.into_iter() {
+                for sample in output.iter_mut() {
+                    *sample = 0.;
+                }
+            }
+            return;
+        }
+        // in place, the processor overwrites the inputs the utility mix needs as dry
+        let copy_dry = self.model().utility().is_some() && buffer.is_in_place();
+        let mut dry_copy = if copy_dry {
+            let mut dry_copy = context.scratch.split_off(self.bus_layout().main_inputs);
+            let (inputs, _) = buffer.split();
+            for (input, dry) in inputs.into_iter().zip(dry_copy.iter_mut()) {
+                dry.copy_from_slice(&input[..dry.len()]);
+            }
+            dry_copy
+        } else {
+            Scratch::empty()
+        };
+        self.process(buffer, context);
+        if let Some(utility) = self.model().utility() {
+            if copy_dry {
+                utility.apply_output_with_dry(buffer, &mut dry_copy);
+            } else {
+                utility.apply_output(buffer);
+            }
+        }
+        // before the limiter, so the editor shows the overs it catches
+        if let Some(telemetry) = self.model().telemetry() {
+            telemetry.send_peaks(buffer);
+        }
+        let model = self.model();
+        if let Some(utility) = model.utility() {
+            utility.limit_output(buffer, self.output_limiter(), context.sample_rate);
+        }
+    }
+}
+
+// bank select and program change handling for process_block; the preset is only requested,
+// for the bridge to load and tell listeners about off the audio thread
+fn handle_program_changes<P: CarnyxProcessor + ?Sized>(processor: &mut P, events: &[TimedMidi]) {
+    let presets = processor.presets();
+    for event in events {
+        match (&event.message, &presets) {
+            (MidiMessage::ProgramChange { program, .. }, presets) => {
+                let bank = presets.as_ref().map(|p| p.bank()).unwrap_or(0);
+                if !processor.program_change(bank, *program) {
+                    if let Some(presets) = presets {
+                        presets.request_program(*program);
+                    }
+                }
+            }
+            (message, Some(presets)) => presets.track_bank_select(message),
+            _ => (),
+        }
+    }
+}
+
+/// Properties of a parameter that hosts and editors treat it differently for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParamFlags(pub u32);
+
+impl ParamFlags {
+    pub const NONE: ParamFlags = ParamFlags(0);
+    /// An output, such as a meter: listed so hosts can show and record it, but only ever
+    /// set by the processor.
+    pub const READ_ONLY: ParamFlags = ParamFlags(1);
+    /// Left out of [`CarnyxProcessor::published_param_ids`], so hosts see it by position,
+    /// after the published parameters.
+    pub const UNPUBLISHED: ParamFlags = ParamFlags(2);
+
+    pub fn contains(&self, flags: ParamFlags) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+
+    pub fn with(self, flags: ParamFlags) -> Self {
+        ParamFlags(self.0 | flags.0)
+    }
 }
 
 pub trait CarnyxParam<Model: CarnyxModel>: Sync{
+    /// A name for the parameter which never changes, even if its display name does. Saved
+    /// state and [`ParamIdTable`](crate::param_ids::ParamIdTable)s find parameters by it;
+    /// parameters without one are known only by position.
+    fn id(&self) -> &str {
+        ""
+    }
     fn name(&self, model: &Model) ->String;
     fn label(&self, model: &Model) ->String;
     fn get_value(&self, model: &Model) ->f32;
     fn set_value(&self, model: &Model, val: f32);
     fn formatted(&self, model: &Model) ->String;
+    /// Turn text typed in by the user, in the units `formatted` displays, back into a
+    /// normalized value. By default only plain normalized numbers are understood.
+    fn parse(&self, _model: &Model, text: &str) -> Option<f32> {
+        text.trim().parse::<f32>().ok().filter(|v| (0. ..=1.).contains(v))
+    }
+    /// The normalized value a fresh instance starts with, which reset gestures return to.
+    fn default_value(&self) -> f32 {
+        0.
+    }
+    /// Whether randomize/mutate may touch this parameter.
+    fn randomizable(&self) -> bool {
+        true
+    }
+    /// What the parameter does, in a sentence, for tooltips and generated documentation.
+    fn description(&self) -> &str {
+        ""
+    }
+    /// Names for the values of a parameter which is a choice between a few options, spread
+    /// evenly over the normalized range. Empty for continuous parameters.
+    fn choices(&self) -> &'static [&'static str] {
+        &[]
+    }
+    /// How many distinct values the parameter takes, for hosts which step automation.
+    /// Zero for continuous parameters.
+    fn steps(&self) -> usize {
+        self.choices().len()
+    }
+    /// Names for each of the parameter's steps, for parameters whose choices are only
+    /// known once it's made. Defaults to the choices.
+    fn step_names(&self, _model: &Model) -> Vec<String> {
+        self.choices().iter().map(|name| name.to_string()).collect()
+    }
+    fn flags(&self) -> ParamFlags {
+        ParamFlags::NONE
+    }
+    /// Whether the parameter does anything in the model's current configuration, e.g. a
+    /// control only one mode uses. Inactive parameters ignore the host, and editors leave
+    /// them out. After changing which are active, call
+    /// [`CarnyxProcessor::parameter_layout_changed`].
+    fn is_active(&self, _model: &Model) -> bool {
+        true
+    }
+    /// Read only parameters ignore the host and the editor; the processor sets them
+    /// on the model directly.
+    fn is_read_only(&self) -> bool {
+        self.flags().contains(ParamFlags::READ_ONLY)
+    }
+}
+
+pub type ParamList<Model> = Arc<Vec<Box<dyn CarnyxParam<Model>>>>;
+
+/// Who made a change, so listeners can ignore changes they made themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOrigin {
+    Host,
+    Editor,
+    Processor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangeEvent {
+    /// The parameter that changed, or `None` if the whole model may have (presets, A/B,
+    /// randomize, state loads).
+    pub param_index: Option<usize>,
+    pub origin: ChangeOrigin,
+    /// Which parameters are [active](CarnyxParam::is_active) may have changed, so editors
+    /// rebuild their controls.
+    pub layout: bool,
+}
+
+impl ChangeEvent {
+    pub fn param(index: usize, origin: ChangeOrigin) -> Self {
+        ChangeEvent { param_index: Some(index), origin, layout: false }
+    }
+
+    pub fn model(origin: ChangeOrigin) -> Self {
+        ChangeEvent { param_index: None, origin, layout: false }
+    }
+
+    pub fn layout(origin: ChangeOrigin) -> Self {
+        ChangeEvent { param_index: None, origin, layout: true }
+    }
+}
+
+pub trait CarnyxModelListener<Model> : Send + Sync{
+    fn notify_change(&self, model: &Model, event: ChangeEvent);
+}
+
+pub type ListenerId = u64;
+
+/// Which parameter changes a listener hears about. Whole-model changes (presets, A/B,
+/// state loads) reach every listener.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamFilter {
+    All,
+    Indices(Vec<usize>),
 }
 
-pub trait CarnyxModelListener<Model> : Send{
-    fn notify_change(&self, model: &Model);
+impl ParamFilter {
+    fn accepts(&self, event: &ChangeEvent) -> bool {
+        match (self, event.param_index) {
+            (ParamFilter::Indices(indices), Some(index)) => indices.contains(&index),
+            _ => true,
+        }
+    }
 }
 
+struct ListenerEntry<Model> {
+    id: ListenerId,
+    listener: Weak<dyn CarnyxModelListener<Model>>,
+    filter: ParamFilter,
+}
+
+/// Fans model changes out to any number of registered listeners. Listeners are held
+/// weakly: they stay registered until removed or until their owner drops them.
+/// Listeners must not register or remove listeners from inside a notification.
 pub struct SettableListener<Model>{
-    listener: Arc<Mutex<Option<Box<dyn CarnyxModelListener<Model>>>>>,
+    listeners: Arc<Mutex<Vec<ListenerEntry<Model>>>>,
+    next_id: Arc<AtomicU64>,
 }
 
 impl <Model> Clone for SettableListener<Model>{
     fn clone(&self) -> Self {
         Self{
-            listener: Arc::clone(&self.listener)
+            listeners: Arc::clone(&self.listeners),
+            next_id: Arc::clone(&self.next_id),
         }
     }
 
     fn clone_from(&mut self, source: &Self) {
-        self.listener = Arc::clone(&source.listener)
+        self.listeners = Arc::clone(&source.listeners);
+        self.next_id = Arc::clone(&source.next_id);
     }
 
 }
 
+impl <Model> Default for SettableListener<Model> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl <Model> SettableListener<Model> {
     pub fn new() -> Self {
         Self {
-            listener: Arc::new(Mutex::new(None))
+            listeners: Arc::new(Mutex::new(Vec::new())),
+            next_id: Arc::new(AtomicU64::new(0)),
         }
     }
 
-    pub fn set_listener(&self, listener: Box<dyn CarnyxModelListener<Model>>) {
-        if let Ok(mut listener_opt) = self.listener.lock() {
-            *listener_opt = Some(listener);
+    pub fn add_listener(&self, listener: &Arc<dyn CarnyxModelListener<Model>>, filter: ParamFilter) -> ListenerId {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        crate::audit::lock_taken("listener lock");
+        if let Ok(mut listeners) = self.listeners.lock() {
+            listeners.push(ListenerEntry { id, listener: Arc::downgrade(listener), filter });
         }
+        id
     }
+
+    /// Returns false if there was no such listener.
+    pub fn remove_listener(&self, id: ListenerId) -> bool {
+        crate::audit::lock_taken("listener lock");
+        match self.listeners.lock() {
+            Ok(mut listeners) => {
+                let before = listeners.len();
+                listeners.retain(|entry| entry.id != id);
+                listeners.len() != before
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// The number of listeners still alive.
+    pub fn listener_count(&self) -> usize {
+        crate::audit::lock_taken("listener lock");
+        self.listeners.lock()
+            .map(|listeners| listeners.iter().filter(|e| e.listener.strong_count() > 0).count())
+            .unwrap_or(0)
+    }
+
 }
 
 impl <Model> CarnyxModelListener<Model> for SettableListener<Model>{
-    fn notify_change(&self, model: &Model) {
-        if let Ok(r) = self.listener.lock(){
-            if let Some(l) = &*r {
-                l.notify_change(model)
-            }
+    fn notify_change(&self, model: &Model, event: ChangeEvent) {
+        crate::audit::lock_taken("listener lock");
+        if let Ok(mut listeners) = self.listeners.lock() {
+            listeners.retain(|entry| match entry.listener.upgrade() {
+                Some(listener) => {
+                    if entry.filter.accepts(&event) {
+                        listener.notify_change(model, event);
+                    }
+                    true
+                }
+                None => false,
+            });
         }
     }
 }
@@ -87,14 +685,90 @@ pub trait CarnyxModel: 'static + Sync + Send {
     type Snap;
     fn snap(&self) -> Self::Snap;
     fn set_snap(&self, snap: &Self::Snap);
+    fn utility(&self) -> Option<&UtilityParams> {
+        None
+    }
+    /// Where editors keep their place between being closed and opened again.
+    fn ui_state(&self) -> Option<&UiState> {
+        None
+    }
+    /// Parameters the user has locked against host automation.
+    fn locks(&self) -> Option<&ParamLocks> {
+        None
+    }
+    /// Where bridges record how long processing takes, for editors to show.
+    fn dsp_load(&self) -> Option<&DspLoad> {
+        None
+    }
+    /// The settings of the built in LFO, for models which offer one.
+    fn lfo(&self) -> Option<&LfoParams> {
+        None
+    }
+    /// Macro controls, each moving several of the processor's parameters at once, whose
+    /// offsets go in [`modulation`](CarnyxModel::modulation) alongside the LFO's.
+    fn macros(&self) -> Option<&MacroParams> {
+        None
+    }
+    /// Routings from pitch bend and the mod wheel to the processor's parameters, whose
+    /// offsets also go in [`modulation`](CarnyxModel::modulation).
+    fn midi_routes(&self) -> Option<&MidiRoutes> {
+        None
+    }
+    /// Where modulators write offsets for the processor to add to parameter values.
+    fn modulation(&self) -> Option<&Modulation> {
+        None
+    }
+    /// Where the processor sends meter readings and the like for editors to show.
+    fn telemetry(&self) -> Option<&TelemetryBus> {
+        None
+    }
+    /// Points in the processor's signal chain editors can solo, for debugging it.
+    fn probes(&self) -> Option<&Probes> {
+        None
+    }
+    /// This instance's registration among others of the same plugin, for editors to name it.
+    fn instance(&self) -> Option<&Instance> {
+        None
+    }
+    /// A nickname and color the user gives this instance, saved with its state.
+    fn instance_tag(&self) -> Option<&InstanceTag> {
+        None
+    }
+    /// Guards the parameters `set_snap` writes, so processors can read them as one set.
+    /// Models with one write under it in `set_snap`.
+    fn seqlock(&self) -> Option<&SeqLock> {
+        None
+    }
+    /// Where loading a preset asks the processor to crossfade to it.
+    fn preset_crossfade(&self) -> Option<&PresetCrossfade> {
+        None
+    }
+    /// Saved with the model's state; bump it when parameters are reordered or change meaning.
+    fn state_version(&self) -> u32 {
+        0
+    }
+    /// Correct state saved at an older `state_version`, as [`crate::state::decode`] reads
+    /// it. Called once the saved values have loaded as usual, so the model holds them;
+    /// returns the snapshot to set instead, or `None` to keep them.
+    fn migrate(&self, _old_version: u32, _state: &[u8]) -> Option<Self::Snap> {
+        None
+    }
 }
 
 pub struct BasicParam<Params> {
+    id: &'static str,
     name: &'static str,
     label: &'static str,
     get: Box<dyn Fn(&Params)->f32 + Sync>,
     set: Box<dyn Fn(&Params, f32) + Sync>,
-    format: Box<dyn Fn(&Params)->String + Sync>
+    format: Box<dyn Fn(&Params)->String + Sync>,
+    parse: Option<Box<dyn Fn(&str)->Option<f32> + Sync>>,
+    active: Option<Box<dyn Fn(&Params)->bool + Sync>>,
+    default: f32,
+    randomizable: bool,
+    description: &'static str,
+    choices: &'static [&'static str],
+    flags: ParamFlags,
 }
 
 impl <Params> BasicParam<Params> {
@@ -102,14 +776,83 @@ impl <Params> BasicParam<Params> {
                get: impl Fn(&Params) -> f32 + 'static + Sync,
                set: impl Fn(&Params, f32) + 'static + Sync,
                format: impl Fn(&Params) -> String + 'static + Sync) -> Self {
-        BasicParam { name, label,
+        BasicParam { id: "", name, label,
             get: Box::new(get),
             set: Box::new(set),
-            format: Box::new(format) }
+            format: Box::new(format),
+            parse: None,
+            active: None,
+            default: 0.,
+            randomizable: true,
+            description: "",
+            choices: &[],
+            flags: ParamFlags::NONE }
+    }
+
+    /// Give the parameter a stable id; see [`CarnyxParam::id`].
+    pub fn with_id(mut self, id: &'static str) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Set how typed text maps to a normalized value; the inverse of `format`.
+    pub fn with_parse(mut self, parse: impl Fn(&str) -> Option<f32> + 'static + Sync) -> Self {
+        self.parse = Some(Box::new(parse));
+        self
+    }
+
+    pub fn with_default(mut self, default: f32) -> Self {
+        self.default = default;
+        self
+    }
+
+    /// Make the parameter active only when `active` says; see [`CarnyxParam::is_active`].
+    pub fn with_active(mut self, active: impl Fn(&Params) -> bool + 'static + Sync) -> Self {
+        self.active = Some(Box::new(active));
+        self
+    }
+
+    /// Take the default from what this parameter reads on a freshly constructed model.
+    pub fn with_default_from(mut self, defaults: &Params) -> Self {
+        self.default = (self.get)(defaults);
+        self
+    }
+
+    pub fn with_description(mut self, description: &'static str) -> Self {
+        self.description = description;
+        self
+    }
+
+    /// Mark this as a choice between the named options; see [`CarnyxParam::choices`].
+    pub fn with_choices(mut self, choices: &'static [&'static str]) -> Self {
+        self.choices = choices;
+        self
+    }
+
+    /// Exclude this parameter from randomize/mutate, e.g. for output levels.
+    pub fn without_randomize(mut self) -> Self {
+        self.randomizable = false;
+        self
+    }
+
+    /// Mark this as an output, e.g. a meter; see [`ParamFlags::READ_ONLY`].
+    pub fn read_only(mut self) -> Self {
+        self.flags = self.flags.with(ParamFlags::READ_ONLY);
+        self
+    }
+
+    /// Keep this out of the published ids; see [`ParamFlags::UNPUBLISHED`].
+    pub fn unpublished(mut self) -> Self {
+        self.flags = self.flags.with(ParamFlags::UNPUBLISHED);
+        self
     }
 }
 
 impl <Params: CarnyxModel> CarnyxParam<Params> for BasicParam<Params> {
+    fn id(&self) -> &str {
+        self.id
+    }
+
     fn name(&self, _params: &Params) -> String {
         self.name.to_owned()
     }
@@ -129,4 +872,168 @@ impl <Params: CarnyxModel> CarnyxParam<Params> for BasicParam<Params> {
     fn formatted(&self, params: &Params) -> String {
         (self.format)(params)
     }
-}
\ No newline at end of file
+
+    fn parse(&self, _params: &Params, text: &str) -> Option<f32> {
+        match &self.parse {
+            Some(parse) => parse(text).filter(|v| !v.is_nan()).map(|v| v.clamp(0., 1.)),
+            None => text.trim().parse::<f32>().ok().filter(|v| (0. ..=1.).contains(v)),
+        }
+    }
+
+    fn default_value(&self) -> f32 {
+        self.default
+    }
+
+    fn is_active(&self, params: &Params) -> bool {
+        self.active.as_ref().map(|active| active(params)).unwrap_or(true)
+    }
+
+    fn randomizable(&self) -> bool {
+        self.randomizable
+    }
+
+    fn description(&self) -> &str {
+        self.description
+    }
+
+    fn choices(&self) -> &'static [&'static str] {
+        self.choices
+    }
+
+    fn flags(&self) -> ParamFlags {
+        self.flags
+    }
+}
+/// A parameter which is a choice between a few named values, such as a mode switch.
+/// Step `i` of `n` is the normalized value `i / (n - 1)` and normalized values round to
+/// the nearest step, so anything a host reads back it can set again unchanged.
+pub struct DiscreteParam<Params> {
+    id: &'static str,
+    name: &'static str,
+    label: &'static str,
+    names: &'static [&'static str],
+    get: Box<dyn Fn(&Params) -> usize + Sync>,
+    set: Box<dyn Fn(&Params, usize) + Sync>,
+    active: Option<Box<dyn Fn(&Params) -> bool + Sync>>,
+    default: usize,
+    randomizable: bool,
+    description: &'static str,
+}
+
+impl<Params> DiscreteParam<Params> {
+    /// `names` has one entry per step; `get` and `set` deal in indices into it.
+    pub fn new(name: &'static str, names: &'static [&'static str],
+               get: impl Fn(&Params) -> usize + 'static + Sync,
+               set: impl Fn(&Params, usize) + 'static + Sync) -> Self {
+        DiscreteParam {
+            id: "",
+            name,
+            label: "",
+            names,
+            get: Box::new(get),
+            set: Box::new(set),
+            active: None,
+            default: 0,
+            randomizable: true,
+            description: "",
+        }
+    }
+
+    /// Give the parameter a stable id; see [`CarnyxParam::id`].
+    pub fn with_id(mut self, id: &'static str) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// The unit shown after the step's name, e.g. "poles".
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = label;
+        self
+    }
+
+    pub fn with_default(mut self, index: usize) -> Self {
+        self.default = index;
+        self
+    }
+
+    /// Make the parameter active only when `active` says; see [`CarnyxParam::is_active`].
+    pub fn with_active(mut self, active: impl Fn(&Params) -> bool + 'static + Sync) -> Self {
+        self.active = Some(Box::new(active));
+        self
+    }
+
+    pub fn with_description(mut self, description: &'static str) -> Self {
+        self.description = description;
+        self
+    }
+
+    pub fn without_randomize(mut self) -> Self {
+        self.randomizable = false;
+        self
+    }
+
+    pub fn to_normalized(&self, index: usize) -> f32 {
+        match self.names.len() {
+            0 | 1 => 0.,
+            steps => index.min(steps - 1) as f32 / (steps - 1) as f32,
+        }
+    }
+
+    pub fn from_normalized(&self, value: f32) -> usize {
+        let last = self.names.len().saturating_sub(1);
+        ((value.max(0.).min(1.) * last as f32).round() as usize).min(last)
+    }
+}
+
+impl<Params: CarnyxModel> CarnyxParam<Params> for DiscreteParam<Params> {
+    fn id(&self) -> &str {
+        self.id
+    }
+
+    fn name(&self, _params: &Params) -> String {
+        self.name.to_owned()
+    }
+
+    fn label(&self, _params: &Params) -> String {
+        self.label.to_owned()
+    }
+
+    fn get_value(&self, params: &Params) -> f32 {
+        self.to_normalized((self.get)(params))
+    }
+
+    fn set_value(&self, params: &Params, val: f32) {
+        (self.set)(params, self.from_normalized(val))
+    }
+
+    fn formatted(&self, params: &Params) -> String {
+        self.names.get((self.get)(params)).map(|name| name.to_string()).unwrap_or_default()
+    }
+
+    /// Step names, with or without the label after them.
+    fn parse(&self, _params: &Params, text: &str) -> Option<f32> {
+        let text = text.trim();
+        let text = text.strip_suffix(self.label).unwrap_or(text);
+        parse_choice(text, self.names).map(|index| self.to_normalized(index))
+    }
+
+    fn default_value(&self) -> f32 {
+        self.to_normalized(self.default)
+    }
+
+    fn is_active(&self, params: &Params) -> bool {
+        self.active.as_ref().map(|active| active(params)).unwrap_or(true)
+    }
+
+    fn randomizable(&self) -> bool {
+        self.randomizable
+    }
+
+    fn description(&self) -> &str {
+        self.description
+    }
+
+    fn choices(&self) -> &'static [&'static str] {
+        self.names
+    }
+}
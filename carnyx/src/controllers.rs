@@ -0,0 +1,182 @@
+//! MIDI controllers at more than 7 bits, and smoothing controller values so MIDI sweeps
+//! sound as smooth as host automation.
+//!
+//! Controllers 1 to 31 can be sent as 14-bit pairs, the most significant 7 bits on the
+//! controller itself and the rest on the controller 32 above it. NRPNs select one of
+//! 16384 parameters with controllers 99 and 98, then set it with data entry (6 and 38)
+//! or step it with increment and decrement (96 and 97).
+
+use crate::events::MidiMessage;
+
+// controller numbers
+const BANK_SELECT: u8 = 0;
+const DATA_ENTRY_MSB: u8 = 6;
+const DATA_ENTRY_LSB: u8 = 38;
+const LSB_OFFSET: u8 = 32;
+const DATA_INCREMENT: u8 = 96;
+const DATA_DECREMENT: u8 = 97;
+const NRPN_LSB: u8 = 98;
+const NRPN_MSB: u8 = 99;
+const RPN_LSB: u8 = 100;
+const RPN_MSB: u8 = 101;
+const NONE_SELECTED: u16 = 0x3FFF;
+const MAX_14_BIT: u16 = 0x3FFF;
+
+// the shortest ramp, for values arriving at the end of a block
+const MIN_RAMP_SAMPLES: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Controller {
+    /// A plain controller, or a 14-bit pair under the number of its first controller.
+    Cc(u8),
+    Nrpn(u16),
+}
+
+/// A controller's new value, normalized to `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControllerEvent {
+    pub channel: u8,
+    pub controller: Controller,
+    pub value: f32,
+}
+
+/// Turns control changes into controller values, pairing up 14-bit controllers and
+/// following NRPN selection and data entry. Keeps no heap state, so it can be used on
+/// the audio thread.
+#[derive(Debug, Clone)]
+pub struct ControllerDecoder {
+    high_resolution: bool,
+    // most significant bits last sent for each pairable controller
+    msb: [[u8; 32]; 16],
+    // the NRPN selected on each channel, and its value
+    nrpn: [u16; 16],
+    nrpn_value: [u16; 16],
+}
+
+impl Default for ControllerDecoder {
+    fn default() -> Self {
+        ControllerDecoder {
+            high_resolution: false,
+            msb: [[0; 32]; 16],
+            nrpn: [NONE_SELECTED; 16],
+            nrpn_value: [0; 16],
+        }
+    }
+}
+
+impl ControllerDecoder {
+    pub fn new() -> Self {
+        ControllerDecoder::default()
+    }
+
+    /// Builder-style method to pair controllers 1 to 31 with 33 to 63. Off by default, as
+    /// controllers in that range are also used on their own.
+    pub fn with_high_resolution(mut self, high_resolution: bool) -> Self {
+        self.high_resolution = high_resolution;
+        self
+    }
+
+    /// The controller value `message` sets, if any. Messages selecting an NRPN, and
+    /// data entry without one selected, give nothing.
+    pub fn decode(&mut self, message: MidiMessage) -> Option<ControllerEvent> {
+        let (channel, controller, value) = match message {
+            MidiMessage::ControlChange { channel, controller, value } => (channel, controller, value),
+            _ => return None,
+        };
+        let ch = channel as usize;
+        let event = |controller, value: u16, max: u16| ControllerEvent { channel, controller, value: value as f32 / max as f32 };
+        match controller {
+            NRPN_MSB => self.nrpn[ch] = (self.nrpn[ch] & 0x7F) | (value as u16) << 7,
+            NRPN_LSB => self.nrpn[ch] = (self.nrpn[ch] & !0x7F) | value as u16,
+            // selecting an RPN deselects the NRPN; RPNs are left to the MPE translator
+            RPN_MSB | RPN_LSB => self.nrpn[ch] = NONE_SELECTED,
+            DATA_ENTRY_MSB | DATA_ENTRY_LSB | DATA_INCREMENT | DATA_DECREMENT => {
+                let nrpn = self.nrpn[ch];
+                if nrpn == NONE_SELECTED {
+                    return None;
+                }
+                let current = self.nrpn_value[ch];
+                self.nrpn_value[ch] = match controller {
+                    DATA_ENTRY_MSB => (value as u16) << 7,
+                    DATA_ENTRY_LSB => (current & !0x7F) | value as u16,
+                    DATA_INCREMENT => (current + 1).min(MAX_14_BIT),
+                    _ => current.saturating_sub(1),
+                };
+                return Some(event(Controller::Nrpn(nrpn), self.nrpn_value[ch], MAX_14_BIT));
+            }
+            BANK_SELECT => return Some(event(Controller::Cc(controller), value as u16, 127)),
+            1..=31 if self.high_resolution => {
+                self.msb[ch][controller as usize] = value;
+                return Some(event(Controller::Cc(controller), (value as u16) << 7, MAX_14_BIT));
+            }
+            33..=63 if self.high_resolution => {
+                let paired = controller - LSB_OFFSET;
+                let msb = self.msb[ch][paired as usize] as u16;
+                return Some(event(Controller::Cc(paired), msb << 7 | value as u16, MAX_14_BIT));
+            }
+            _ => return Some(event(Controller::Cc(controller), value as u16, 127)),
+        }
+        None
+    }
+}
+
+/// A controller value ramped across the block, instead of stepping when each message
+/// arrives. Call `set` at the sample a message belongs at, e.g. from a
+/// [`BlockSplitter`](crate::BlockSplitter) sub block, and `next` once per sample.
+#[derive(Debug, Clone)]
+pub struct SmoothedController {
+    smoothing: bool,
+    current: f32,
+    target: f32,
+    step: f32,
+    remaining: usize,
+}
+
+impl SmoothedController {
+    pub fn new(value: f32) -> Self {
+        SmoothedController { smoothing: true, current: value, target: value, step: 0., remaining: 0 }
+    }
+
+    /// Builder-style method to turn smoothing off, so values apply from the sample they
+    /// arrive at.
+    pub fn with_smoothing(mut self, smoothing: bool) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Ramp to `value` from `offset`, reaching it by the end of the `block_size` sample
+    /// block, or a few samples later for values arriving at its end.
+    pub fn set(&mut self, value: f32, offset: usize, block_size: usize) {
+        self.target = value;
+        if self.smoothing {
+            self.remaining = block_size.saturating_sub(offset).max(MIN_RAMP_SAMPLES);
+            self.step = (value - self.current) / self.remaining as f32;
+        } else {
+            self.jump(value);
+        }
+    }
+
+    /// Go straight to `value`, e.g. when loading a preset.
+    pub fn jump(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.remaining = 0;
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    pub fn is_smoothing(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// The value for the next sample.
+    pub fn next(&mut self) -> f32 {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            self.current = if self.remaining == 0 { self.target } else { self.current + self.step };
+        }
+        self.current
+    }
+}
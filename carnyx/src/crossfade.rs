@@ -0,0 +1,49 @@
+//! Crossfading between settings when a preset loads, rather than jumping every parameter at
+//! once and clicking. The preset layer asks for a crossfade after loading; the processor,
+//! which still has the settings it used last block, runs with both for a short while and
+//! fades from the old output to the new.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How long processors should take over a preset crossfade.
+pub const PRESET_CROSSFADE_SECONDS: f32 = 0.01;
+
+/// Shared between the preset layer, which requests crossfades, and the processor, which
+/// takes them at the start of a block. Enabled by default.
+#[derive(Debug)]
+pub struct PresetCrossfade {
+    enabled: AtomicBool,
+    pending: AtomicBool,
+}
+
+impl Default for PresetCrossfade {
+    fn default() -> Self {
+        PresetCrossfade { enabled: AtomicBool::new(true), pending: AtomicBool::new(false) }
+    }
+}
+
+impl PresetCrossfade {
+    pub fn new() -> Self {
+        PresetCrossfade::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Called after a preset has been loaded into the model.
+    pub fn request(&self) {
+        if self.is_enabled() {
+            self.pending.store(true, Ordering::Release);
+        }
+    }
+
+    /// Whether a crossfade was requested since the last call. Realtime safe.
+    pub fn take(&self) -> bool {
+        self.pending.swap(false, Ordering::Acquire)
+    }
+}
@@ -0,0 +1,71 @@
+//! What a plugin is, as bridges describe it to hosts.
+
+use crate::buffer::BusLayout;
+
+/// Where hosts file the plugin in their browsers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginCategory {
+    Effect,
+    Instrument,
+    Analysis,
+    Mastering,
+    /// Panners and other spatialisers.
+    Spatial,
+    /// Reverbs and delays.
+    Room,
+    Restoration,
+    /// Makes sound without input, other than an instrument.
+    Generator,
+}
+
+/// The name, identity and channel layout of a plugin. Every bridge builds what its host
+/// needs to know from this, so none of it is repeated per format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CarnyxDescriptor {
+    pub name: &'static str,
+    pub vendor: &'static str,
+    /// Increased with each release, so hosts can tell saved state came from an older one.
+    pub version: i32,
+    /// How hosts recognise the plugin between sessions. Never change it once released.
+    pub unique_id: i32,
+    pub category: PluginCategory,
+    pub bus_layout: BusLayout,
+}
+
+impl CarnyxDescriptor {
+    /// A stereo effect, version 1.
+    pub fn new(name: &'static str, unique_id: i32) -> Self {
+        CarnyxDescriptor {
+            name,
+            vendor: "",
+            version: 1,
+            unique_id,
+            category: PluginCategory::Effect,
+            bus_layout: BusLayout::new(2, 2),
+        }
+    }
+
+    /// Builder-style method to set the vendor.
+    pub fn with_vendor(mut self, vendor: &'static str) -> Self {
+        self.vendor = vendor;
+        self
+    }
+
+    /// Builder-style method to set the version.
+    pub fn with_version(mut self, version: i32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Builder-style method to set the category.
+    pub fn with_category(mut self, category: PluginCategory) -> Self {
+        self.category = category;
+        self
+    }
+
+    /// Builder-style method to set the channel layout.
+    pub fn with_bus_layout(mut self, bus_layout: BusLayout) -> Self {
+        self.bus_layout = bus_layout;
+        self
+    }
+}
@@ -0,0 +1,181 @@
+//! Per-instance diagnostics. Plugins can't rely on stdout (many hosts discard it, some
+//! choke on it), so events are queued here without locking or allocating, and read
+//! from a non-realtime thread - the editor's log overlay, or a log file.
+//!
+//! Filtering is by level, read from the `CARNYX_LOG` environment variable (`trace`,
+//! `debug`, `info`, `warn`, `error` or `off`; `warn` if unset). Setting `CARNYX_LOG_FILE`
+//! also appends drained events to that file. Without the `diagnostics` feature nothing
+//! is recorded.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::queue::EventQueue;
+
+const CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    pub const ALL: [Level; 5] = [Level::Trace, Level::Debug, Level::Info, Level::Warn, Level::Error];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Level> {
+        let name = name.trim();
+        Level::ALL.iter().copied().find(|level| level.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// One recorded event. Only static strings and a number, so recording never allocates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Diagnostic {
+    pub level: Level,
+    /// The part of the plugin it came from, e.g. "vst" or "processor".
+    pub source: &'static str,
+    pub message: &'static str,
+    pub value: Option<f64>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.level.name(), self.source, self.message)?;
+        if let Some(value) = self.value {
+            write!(f, " {}", value)?;
+        }
+        Ok(())
+    }
+}
+
+// Level::ALL.len() means off
+const OFF: usize = 5;
+
+pub struct Diagnostics {
+    queue: EventQueue<Diagnostic>,
+    min_level: AtomicUsize,
+    dropped: AtomicUsize,
+    log_file: Mutex<Option<File>>,
+    last_error: Mutex<Option<String>>,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Diagnostics::new(Some(Level::Warn))
+    }
+}
+
+impl Diagnostics {
+    /// Record events at `min_level` and above; `None` records nothing.
+    pub fn new(min_level: Option<Level>) -> Self {
+        Diagnostics {
+            queue: EventQueue::new(CAPACITY),
+            min_level: AtomicUsize::new(min_level.map(|l| l as usize).unwrap_or(OFF)),
+            dropped: AtomicUsize::new(0),
+            log_file: Mutex::new(None),
+            last_error: Mutex::new(None),
+        }
+    }
+
+    /// Configure from `CARNYX_LOG` and `CARNYX_LOG_FILE`.
+    pub fn from_env() -> Self {
+        let diagnostics = match std::env::var("CARNYX_LOG") {
+            Ok(filter) if filter.trim().eq_ignore_ascii_case("off") => Diagnostics::new(None),
+            Ok(filter) => Diagnostics::new(Some(Level::from_name(&filter).unwrap_or(Level::Warn))),
+            Err(_) => Diagnostics::default(),
+        };
+        if let Ok(path) = std::env::var("CARNYX_LOG_FILE") {
+            let file = OpenOptions::new().create(true).append(true).open(path).ok();
+            *diagnostics.log_file.lock().unwrap() = file;
+        }
+        diagnostics
+    }
+
+    pub fn set_min_level(&self, min_level: Option<Level>) {
+        self.min_level.store(min_level.map(|l| l as usize).unwrap_or(OFF), Ordering::Relaxed);
+    }
+
+    pub fn enabled(&self, level: Level) -> bool {
+        cfg!(feature = "diagnostics") && level as usize >= self.min_level.load(Ordering::Relaxed)
+    }
+
+    /// Safe to call from the audio thread. If nothing has drained the queue lately the
+    /// event is dropped and counted instead.
+    pub fn record(&self, level: Level, source: &'static str, message: &'static str, value: Option<f64>) {
+        if !self.enabled(level) {
+            return;
+        }
+        if !self.queue.push(Diagnostic { level, source, message, value }) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn warn(&self, source: &'static str, message: &'static str, value: Option<f64>) {
+        self.record(Level::Warn, source, message, value)
+    }
+
+    pub fn debug(&self, source: &'static str, message: &'static str, value: Option<f64>) {
+        self.record(Level::Debug, source, message, value)
+    }
+
+    pub fn trace(&self, source: &'static str, message: &'static str, value: Option<f64>) {
+        self.record(Level::Trace, source, message, value)
+    }
+
+    /// Record a failure, keeping its details as the last error. Allocates, so not for the
+    /// audio thread.
+    pub fn error(&self, source: &'static str, message: &'static str, detail: impl fmt::Display) {
+        self.record(Level::Error, source, message, None);
+        crate::audit::lock_taken("diagnostics error lock");
+        *self.last_error.lock().unwrap() = Some(format!("{}: {}: {}", source, message, detail));
+    }
+
+    /// The details of the last `error`, e.g. why the editor didn't open, for bug reports.
+    pub fn last_error(&self) -> Option<String> {
+        crate::audit::lock_taken("diagnostics error lock");
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Events lost to a full queue since the last `drain`.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Hand queued events to `f`, oldest first, writing them to the log file if there
+    /// is one. Not for the audio thread.
+    pub fn drain(&self, mut f: impl FnMut(&Diagnostic)) {
+        crate::audit::lock_taken("diagnostics log lock");
+        let mut log_file = self.log_file.lock().unwrap();
+        let dropped = self.dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            let lost = Diagnostic { level: Level::Warn, source: "diagnostics", message: "events dropped", value: Some(dropped as f64) };
+            if let Some(file) = log_file.as_mut() {
+                let _ = writeln!(file, "{}", lost);
+            }
+            f(&lost);
+        }
+        for diagnostic in self.queue.drain() {
+            if let Some(file) = log_file.as_mut() {
+                let _ = writeln!(file, "{}", diagnostic);
+            }
+            f(&diagnostic);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Small DSP building blocks processors share: gain conversions, crossfades, clippers and
+//! a few simple filters.
+
+use std::f32::consts::{FRAC_PI_2, PI};
+
+const DC_BLOCKER_CUTOFF_HZ: f32 = 10.;
+
+pub fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.)
+}
+
+/// Negative infinity for silence.
+pub fn gain_to_db(gain: f32) -> f32 {
+    20. * gain.abs().log10()
+}
+
+/// Equal power gains for a crossfade `position` of the way from `a` to `b`, as `(a, b)`.
+pub fn equal_power(position: f32) -> (f32, f32) {
+    let angle = position.clamp(0., 1.) * FRAC_PI_2;
+    (angle.cos(), angle.sin())
+}
+
+/// A cubic soft clipper, flat from +-1 onwards, where it reaches +-1.
+pub fn soft_clip(x: f32) -> f32 {
+    let x = x.clamp(-1., 1.);
+    1.5 * (x - x * x * x / 3.)
+}
+
+/// Tanh for the positive half, and half as steep a curve reaching twice as far for the
+/// negative half, like a diode which conducts later one way. Adds even harmonics, and DC.
+pub fn asymmetric_clip(x: f32) -> f32 {
+    if x >= 0. {
+        x.tanh()
+    } else {
+        2. * (0.5 * x).tanh()
+    }
+}
+
+/// The coefficient for a [`OnePole`] which covers about two thirds of the way to its
+/// target in `seconds`.
+pub fn one_pole_coefficient(seconds: f32, sample_rate: f32) -> f32 {
+    (-1. / (seconds * sample_rate)).exp()
+}
+
+/// A one pole lowpass, for smoothing parameter changes and envelopes.
+#[derive(Debug, Clone, Copy)]
+pub struct OnePole {
+    coefficient: f32,
+    value: f32,
+}
+
+impl OnePole {
+    pub fn new(seconds: f32, sample_rate: f32) -> Self {
+        OnePole { coefficient: one_pole_coefficient(seconds, sample_rate), value: 0. }
+    }
+
+    pub fn set_time(&mut self, seconds: f32, sample_rate: f32) {
+        self.coefficient = one_pole_coefficient(seconds, sample_rate);
+    }
+
+    /// Move towards `target` by a sample, returning the new value.
+    pub fn next(&mut self, target: f32) -> f32 {
+        self.value = target + self.coefficient * (self.value - target);
+        self.value
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Go straight to `value`.
+    pub fn reset(&mut self, value: f32) {
+        self.value = value;
+    }
+}
+
+/// A one pole, one zero highpass just below the audible range, which removes the DC offset
+/// asymmetric saturation leaves behind.
+#[derive(Debug, Clone, Copy)]
+pub struct DcBlocker {
+    sample_rate: f32,
+    pole: f32,
+    last_input: f32,
+    last_output: f32,
+}
+
+impl Default for DcBlocker {
+    fn default() -> Self {
+        let mut blocker = DcBlocker { sample_rate: 0., pole: 0., last_input: 0., last_output: 0. };
+        blocker.set_sample_rate(44100.);
+        blocker
+    }
+}
+
+impl DcBlocker {
+    /// Cheap enough to call every block.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.pole = (-2. * PI * DC_BLOCKER_CUTOFF_HZ / sample_rate).exp();
+        }
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let output = input - self.last_input + self.pole * self.last_output;
+        self.last_input = input;
+        self.last_output = output;
+        output
+    }
+
+    pub fn reset(&mut self) {
+        self.last_input = 0.;
+        self.last_output = 0.;
+    }
+}
+
+/// A second order filter section, with coefficients from the RBJ audio EQ cookbook.
+/// Changing the coefficients keeps the state, so they can be changed every block.
+#[derive(Debug, Clone, Copy)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl Default for Biquad {
+    /// Passes its input through unchanged.
+    fn default() -> Self {
+        Biquad { b0: 1., b1: 0., b2: 0., a1: 0., a2: 0., z1: 0., z2: 0. }
+    }
+}
+
+impl Biquad {
+    pub fn lowpass(cutoff_hz: f32, q: f32, sample_rate: f32) -> Self {
+        let mut biquad = Biquad::default();
+        biquad.set_lowpass(cutoff_hz, q, sample_rate);
+        biquad
+    }
+
+    pub fn highpass(cutoff_hz: f32, q: f32, sample_rate: f32) -> Self {
+        let mut biquad = Biquad::default();
+        biquad.set_highpass(cutoff_hz, q, sample_rate);
+        biquad
+    }
+
+    /// Unity gain at the centre.
+    pub fn bandpass(centre_hz: f32, q: f32, sample_rate: f32) -> Self {
+        let mut biquad = Biquad::default();
+        biquad.set_bandpass(centre_hz, q, sample_rate);
+        biquad
+    }
+
+    // cos(w0) and alpha, with the frequency kept below nyquist
+    fn prepare(frequency_hz: f32, q: f32, sample_rate: f32) -> (f32, f32) {
+        let w0 = 2. * PI * frequency_hz.clamp(1., sample_rate * 0.49) / sample_rate;
+        (w0.cos(), w0.sin() / (2. * q.max(0.01)))
+    }
+
+    fn set(&mut self, b: [f32; 3], a: [f32; 3]) {
+        self.b0 = b[0] / a[0];
+        self.b1 = b[1] / a[0];
+        self.b2 = b[2] / a[0];
+        self.a1 = a[1] / a[0];
+        self.a2 = a[2] / a[0];
+    }
+
+    pub fn set_lowpass(&mut self, cutoff_hz: f32, q: f32, sample_rate: f32) {
+        let (cos, alpha) = Biquad::prepare(cutoff_hz, q, sample_rate);
+        self.set([(1. - cos) / 2., 1. - cos, (1. - cos) / 2.], [1. + alpha, -2. * cos, 1. - alpha]);
+    }
+
+    pub fn set_highpass(&mut self, cutoff_hz: f32, q: f32, sample_rate: f32) {
+        let (cos, alpha) = Biquad::prepare(cutoff_hz, q, sample_rate);
+        self.set([(1. + cos) / 2., -(1. + cos), (1. + cos) / 2.], [1. + alpha, -2. * cos, 1. - alpha]);
+    }
+
+    pub fn set_bandpass(&mut self, centre_hz: f32, q: f32, sample_rate: f32) {
+        let (cos, alpha) = Biquad::prepare(centre_hz, q, sample_rate);
+        self.set([alpha, 0., -alpha], [1. + alpha, -2. * cos, 1. - alpha]);
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        // transposed direct form II
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+
+    pub fn reset(&mut self) {
+        self.z1 = 0.;
+        self.z2 = 0.;
+    }
+}
@@ -0,0 +1,217 @@
+use crate::queue::EventQueue;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoteEvent {
+    On { note: u8, velocity: f32 },
+    Off { note: u8 },
+}
+
+impl NoteEvent {
+    pub fn note(&self) -> u8 {
+        match self {
+            NoteEvent::On { note, .. } => *note,
+            NoteEvent::Off { note } => *note,
+        }
+    }
+}
+
+/// Notes sent from the editor (e.g. an on screen keyboard) to the processor.
+pub type NoteQueue = EventQueue<NoteEvent>;
+
+pub const NOTE_QUEUE_CAPACITY: usize = 256;
+
+/// The editor's end of a [`NoteQueue`], which makes sure every note it starts is stopped.
+/// A note off that finds the queue full waits, ahead of anything sent later, until
+/// [`flush`](NoteSender::flush) finds room; a note on that finds it full is dropped.
+pub struct NoteSender {
+    queue: NoteQueue,
+    // notes sent on and not yet off
+    held: Vec<u8>,
+    // note offs waiting for room, oldest first and at most one per note
+    pending_offs: Vec<u8>,
+}
+
+impl NoteSender {
+    pub fn new(queue: NoteQueue) -> Self {
+        NoteSender { queue, held: Vec::new(), pending_offs: Vec::new() }
+    }
+
+    /// Returns whether `event` went into the queue. Note offs that didn't are kept to be
+    /// sent by a later `flush` or `send`.
+    pub fn send(&mut self, event: NoteEvent) -> bool {
+        let sent = self.flush() && self.queue.push(event);
+        match event {
+            NoteEvent::On { note, .. } if sent => {
+                if !self.held.contains(&note) {
+                    self.held.push(note);
+                }
+            }
+            NoteEvent::Off { note } => {
+                self.held.retain(|held| *held != note);
+                if !sent && !self.pending_offs.contains(&note) {
+                    self.pending_offs.push(note);
+                }
+            }
+            _ => (),
+        }
+        sent
+    }
+
+    /// Sends as many waiting note offs as there is room for, returning whether they all
+    /// went.
+    pub fn flush(&mut self) -> bool {
+        while let Some(&note) = self.pending_offs.first() {
+            if !self.queue.push(NoteEvent::Off { note }) {
+                return false;
+            }
+            self.pending_offs.remove(0);
+        }
+        true
+    }
+
+    /// Whether note offs are waiting for room in the queue.
+    pub fn is_pending(&self) -> bool {
+        !self.pending_offs.is_empty()
+    }
+
+    /// Stops every note still held, e.g. when the editor closes.
+    pub fn release_all(&mut self) {
+        for note in std::mem::take(&mut self.held) {
+            self.send(NoteEvent::Off { note });
+        }
+    }
+}
+
+/// Typed commands from the editor to the processor, for GUI actions that aren't parameter
+/// changes. Processors drain it at the start of each block.
+pub type CommandQueue<C> = EventQueue<C>;
+
+pub const COMMAND_QUEUE_CAPACITY: usize = 64;
+
+/// A MIDI channel message decoded from the host's event stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MidiMessage {
+    Note { channel: u8, event: NoteEvent },
+    ControlChange { channel: u8, controller: u8, value: u8 },
+    ProgramChange { channel: u8, program: u8 },
+    /// Normalized to `-1.0..=1.0`.
+    PitchBend { channel: u8, value: f32 },
+    ChannelPressure { channel: u8, pressure: u8 },
+    /// Aftertouch for a single note.
+    PolyPressure { channel: u8, note: u8, pressure: u8 },
+    Other([u8; 3]),
+}
+
+impl MidiMessage {
+    pub fn from_bytes(data: [u8; 3]) -> MidiMessage {
+        let channel = data[0] & 0x0F;
+        match data[0] & 0xF0 {
+            0x90 if data[2] > 0 => MidiMessage::Note {
+                channel,
+                event: NoteEvent::On { note: data[1], velocity: data[2] as f32 / 127. },
+            },
+            // Note on with zero velocity is a note off by convention
+            0x80 | 0x90 => MidiMessage::Note { channel, event: NoteEvent::Off { note: data[1] } },
+            0xB0 => MidiMessage::ControlChange { channel, controller: data[1], value: data[2] },
+            0xC0 => MidiMessage::ProgramChange { channel, program: data[1] },
+            0xA0 => MidiMessage::PolyPressure { channel, note: data[1], pressure: data[2] },
+            0xD0 => MidiMessage::ChannelPressure { channel, pressure: data[1] },
+            0xE0 => {
+                let raw = ((data[2] as i32) << 7 | data[1] as i32) - 8192;
+                MidiMessage::PitchBend { channel, value: raw as f32 / 8192. }
+            }
+            _ => MidiMessage::Other(data),
+        }
+    }
+
+    /// The channel, for everything but `Other`.
+    pub fn channel(&self) -> Option<u8> {
+        match *self {
+            MidiMessage::Note { channel, .. }
+            | MidiMessage::ControlChange { channel, .. }
+            | MidiMessage::ProgramChange { channel, .. }
+            | MidiMessage::PitchBend { channel, .. }
+            | MidiMessage::ChannelPressure { channel, .. }
+            | MidiMessage::PolyPressure { channel, .. } => Some(channel),
+            MidiMessage::Other(_) => None,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 3] {
+        match *self {
+            MidiMessage::Note { channel, event: NoteEvent::On { note, velocity } } =>
+                [0x90 | channel, note, (velocity.clamp(0., 1.) * 127.).round().max(1.) as u8],
+            MidiMessage::Note { channel, event: NoteEvent::Off { note } } => [0x80 | channel, note, 0],
+            MidiMessage::ControlChange { channel, controller, value } => [0xB0 | channel, controller, value],
+            MidiMessage::ProgramChange { channel, program } => [0xC0 | channel, program, 0],
+            MidiMessage::ChannelPressure { channel, pressure } => [0xD0 | channel, pressure, 0],
+            MidiMessage::PolyPressure { channel, note, pressure } => [0xA0 | channel, note, pressure],
+            MidiMessage::PitchBend { channel, value } => {
+                let raw = ((value.clamp(-1., 1.) * 8192.) as i32 + 8192).clamp(0, 16383);
+                [0xE0 | channel, (raw & 0x7F) as u8, (raw >> 7) as u8]
+            }
+            MidiMessage::Other(data) => data,
+        }
+    }
+}
+
+/// A MIDI message and the sample within the block it belongs at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedMidi {
+    pub offset: usize,
+    pub message: MidiMessage,
+}
+
+impl TimedMidi {
+    pub fn new(offset: usize, message: MidiMessage) -> Self {
+        TimedMidi { offset, message }
+    }
+}
+
+const NOTE_STACK_CAPACITY: usize = 16;
+
+/// Held notes with last-note priority, as used by monophonic voices. Fixed capacity,
+/// so it can be updated on the audio thread.
+#[derive(Debug, Clone)]
+pub struct NoteStack {
+    notes: [u8; NOTE_STACK_CAPACITY],
+    len: usize,
+}
+
+impl Default for NoteStack {
+    fn default() -> Self {
+        NoteStack { notes: [0; NOTE_STACK_CAPACITY], len: 0 }
+    }
+}
+
+impl NoteStack {
+    pub fn apply(&mut self, event: NoteEvent) {
+        let note = event.note();
+        self.remove(note);
+        if let NoteEvent::On { .. } = event {
+            if self.len == NOTE_STACK_CAPACITY {
+                // forget the oldest note
+                self.notes.copy_within(1.., 0);
+                self.len -= 1;
+            }
+            self.notes[self.len] = note;
+            self.len += 1;
+        }
+    }
+
+    fn remove(&mut self, note: u8) {
+        if let Some(pos) = self.notes[..self.len].iter().position(|n| *n == note) {
+            self.notes.copy_within(pos + 1..self.len, pos);
+            self.len -= 1;
+        }
+    }
+
+    /// The most recently pressed note still held.
+    pub fn current(&self) -> Option<u8> {
+        if self.len > 0 { Some(self.notes[self.len - 1]) } else { None }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
@@ -0,0 +1,246 @@
+//! VST2 preset files: `.fxp` holds one program, `.fxb` a bank of them. Only the
+//! parameter value forms are understood, not opaque chunks, which mean nothing outside
+//! the plugin that wrote them. Values are matched to parameters by index.
+
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+use crate::carnyx::{CarnyxModel, CarnyxParam};
+use crate::descriptor::CarnyxDescriptor;
+use crate::seqlock::write_params;
+
+const CHUNK_MAGIC: &[u8; 4] = b"CcnK";
+const PROGRAM_MAGIC: &[u8; 4] = b"FxCk";
+const OPAQUE_PROGRAM_MAGIC: &[u8; 4] = b"FPCh";
+const BANK_MAGIC: &[u8; 4] = b"FxBk";
+const OPAQUE_BANK_MAGIC: &[u8; 4] = b"FBCh";
+
+// magic, size, fx magic, format version, plugin id, plugin version, count
+const HEADER_LEN: usize = 28;
+const NAME_LEN: usize = 28;
+// zeroed space after a bank's header; version 2 takes the current program from the front
+const BANK_RESERVED_LEN: usize = 128;
+const BANK_VERSION: i32 = 2;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+fn i32_at(bytes: &[u8], at: usize) -> io::Result<i32> {
+    bytes.get(at..at + 4).map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(|| invalid("truncated preset file"))
+}
+
+// a count can't be negative, and taking one as empty would lose the file's contents quietly
+fn count_at(bytes: &[u8], at: usize) -> io::Result<usize> {
+    usize::try_from(i32_at(bytes, at)?).map_err(|_| invalid("negative count in preset file"))
+}
+
+fn magic_at(bytes: &[u8], at: usize) -> Option<&[u8]> {
+    bytes.get(at..at + 4)
+}
+
+fn header(bytes: &mut Vec<u8>, fx_magic: &[u8; 4], version: i32, plugin_id: i32, plugin_version: i32, count: usize) {
+    bytes.extend_from_slice(CHUNK_MAGIC);
+    // the size of everything after it, filled in by `finish`
+    bytes.extend_from_slice(&0i32.to_be_bytes());
+    bytes.extend_from_slice(fx_magic);
+    bytes.extend_from_slice(&version.to_be_bytes());
+    bytes.extend_from_slice(&plugin_id.to_be_bytes());
+    bytes.extend_from_slice(&plugin_version.to_be_bytes());
+    bytes.extend_from_slice(&(count as i32).to_be_bytes());
+}
+
+fn finish(bytes: &mut [u8], start: usize) {
+    let size = (bytes.len() - start - 8) as i32;
+    bytes[start + 4..start + 8].copy_from_slice(&size.to_be_bytes());
+}
+
+/// One program: a name and a normalized value per parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FxProgram {
+    pub name: String,
+    pub plugin_id: i32,
+    pub plugin_version: i32,
+    pub params: Vec<f32>,
+}
+
+impl FxProgram {
+    /// The model's current parameter values.
+    pub fn capture<Model: CarnyxModel>(
+        name: impl Into<String>,
+        descriptor: &CarnyxDescriptor,
+        params: &[Box<dyn CarnyxParam<Model>>],
+        model: &Model,
+    ) -> Self {
+        FxProgram {
+            name: name.into(),
+            plugin_id: descriptor.unique_id,
+            plugin_version: descriptor.version,
+            params: params.iter().map(|p| p.get_value(model)).collect(),
+        }
+    }
+
+    /// Set the model's parameters from the program, as far as both go. Read only
+    /// parameters and values which aren't numbers are skipped.
+    pub fn apply<Model: CarnyxModel>(&self, params: &[Box<dyn CarnyxParam<Model>>], model: &Model) {
+        write_params(model, || {
+            for (param, value) in params.iter().zip(&self.params) {
+                if !param.is_read_only() && !value.is_nan() {
+                    param.set_value(model, value.clamp(0., 1.));
+                }
+            }
+        });
+        if let Some(crossfade) = model.preset_crossfade() {
+            crossfade.request();
+        }
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<FxProgram> {
+        FxProgram::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<FxProgram> {
+        FxProgram::parse(bytes).map(|(program, _)| program)
+    }
+
+    // the program at the start of `bytes`, and how many bytes it took
+    fn parse(bytes: &[u8]) -> io::Result<(FxProgram, usize)> {
+        if magic_at(bytes, 0) != Some(CHUNK_MAGIC) {
+            return Err(invalid("not a VST preset file"));
+        }
+        match magic_at(bytes, 8) {
+            Some(magic) if magic == PROGRAM_MAGIC => (),
+            Some(magic) if magic == OPAQUE_PROGRAM_MAGIC => {
+                return Err(invalid("the preset is stored as plugin specific data, not parameter values"))
+            }
+            _ => return Err(invalid("not a VST program")),
+        }
+        let count = count_at(bytes, 24)?;
+        let name = bytes.get(HEADER_LEN..HEADER_LEN + NAME_LEN).ok_or_else(|| invalid("truncated preset file"))?;
+        let name = String::from_utf8_lossy(name.split(|b| *b == 0).next().unwrap_or(&[])).into_owned();
+        let values = HEADER_LEN + NAME_LEN;
+        let params = (0..count)
+            .map(|i| i32_at(bytes, values + i * 4).map(|bits| f32::from_bits(bits as u32)))
+            .collect::<io::Result<Vec<f32>>>()?;
+        let program = FxProgram { name, plugin_id: i32_at(bytes, 16)?, plugin_version: i32_at(bytes, 20)?, params };
+        Ok((program, values + count * 4))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + NAME_LEN + self.params.len() * 4);
+        self.write_to(&mut bytes);
+        bytes
+    }
+
+    fn write_to(&self, bytes: &mut Vec<u8>) {
+        let start = bytes.len();
+        header(bytes, PROGRAM_MAGIC, 1, self.plugin_id, self.plugin_version, self.params.len());
+        // names are null terminated in a fixed field, cut at a character boundary
+        let mut name_len = self.name.len().min(NAME_LEN - 1);
+        while !self.name.is_char_boundary(name_len) {
+            name_len -= 1;
+        }
+        let mut name = [0u8; NAME_LEN];
+        name[..name_len].copy_from_slice(&self.name.as_bytes()[..name_len]);
+        bytes.extend_from_slice(&name);
+        for value in &self.params {
+            bytes.extend_from_slice(&value.to_bits().to_be_bytes());
+        }
+        finish(bytes, start);
+    }
+}
+
+/// A bank of programs, and which of them the plugin had selected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FxBank {
+    pub plugin_id: i32,
+    pub plugin_version: i32,
+    pub current: usize,
+    pub programs: Vec<FxProgram>,
+}
+
+impl FxBank {
+    /// The selected program, or the first if the selection is out of range.
+    pub fn current_program(&self) -> Option<&FxProgram> {
+        self.programs.get(self.current).or_else(|| self.programs.first())
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<FxBank> {
+        FxBank::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<FxBank> {
+        if magic_at(bytes, 0) != Some(CHUNK_MAGIC) {
+            return Err(invalid("not a VST preset file"));
+        }
+        match magic_at(bytes, 8) {
+            Some(magic) if magic == BANK_MAGIC => (),
+            Some(magic) if magic == OPAQUE_BANK_MAGIC => {
+                return Err(invalid("the bank is stored as plugin specific data, not parameter values"))
+            }
+            _ => return Err(invalid("not a VST bank")),
+        }
+        let version = i32_at(bytes, 12)?;
+        let count = count_at(bytes, 24)?;
+        let current = if version >= 2 { i32_at(bytes, HEADER_LEN)?.max(0) as usize } else { 0 };
+        let mut at = HEADER_LEN + BANK_RESERVED_LEN;
+        let mut programs = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            let (program, len) = FxProgram::parse(bytes.get(at..).unwrap_or(&[]))?;
+            programs.push(program);
+            at += len;
+        }
+        Ok(FxBank { plugin_id: i32_at(bytes, 16)?, plugin_version: i32_at(bytes, 20)?, current, programs })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        header(&mut bytes, BANK_MAGIC, BANK_VERSION, self.plugin_id, self.plugin_version, self.programs.len());
+        let mut reserved = [0u8; BANK_RESERVED_LEN];
+        reserved[..4].copy_from_slice(&(self.current as i32).to_be_bytes());
+        bytes.extend_from_slice(&reserved);
+        for program in &self.programs {
+            program.write_to(&mut bytes);
+        }
+        finish(&mut bytes, 0);
+        bytes
+    }
+}
+
+/// Either kind of preset file, for importing whichever the user picks.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FxFile {
+    Program(FxProgram),
+    Bank(FxBank),
+}
+
+impl FxFile {
+    pub fn read(path: impl AsRef<Path>) -> io::Result<FxFile> {
+        FxFile::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<FxFile> {
+        match magic_at(bytes, 8) {
+            Some(magic) if magic == BANK_MAGIC || magic == OPAQUE_BANK_MAGIC => FxBank::from_bytes(bytes).map(FxFile::Bank),
+            _ => FxProgram::from_bytes(bytes).map(FxFile::Program),
+        }
+    }
+
+    /// The program to load: the file's only one, or a bank's selected one.
+    pub fn program(&self) -> Option<&FxProgram> {
+        match self {
+            FxFile::Program(program) => Some(program),
+            FxFile::Bank(bank) => bank.current_program(),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Presets as JSON, for keeping in git and editing by hand. The settings are the model's
+//! snapshot as serde writes it, wrapped with the format version and the plugin it is for:
+//!
+//! ```text
+//! {
+//!   "format": "carnyx-preset",
+//!   "version": 1,
+//!   "plugin": "LadderFilter",
+//!   "name": "Acid bass",
+//!   "settings": { "cutoff": 0.42, "res": 3.6, ... }
+//! }
+//! ```
+//!
+//! Unknown fields are ignored, so presets from newer versions of a plugin still load.
+//! Snapshots should be `#[serde(default)]` so that older presets, missing fields added
+//! since, load with defaults for them.
+
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::descriptor::CarnyxDescriptor;
+use crate::preset::Preset;
+
+pub const FORMAT: &str = "carnyx-preset";
+/// Bumped when the wrapper changes, not the settings.
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Written<'a, Snap> {
+    format: &'a str,
+    version: u32,
+    plugin: &'a str,
+    name: &'a str,
+    settings: &'a Snap,
+}
+
+#[derive(Deserialize)]
+struct Read<Snap> {
+    format: String,
+    version: u32,
+    plugin: String,
+    name: String,
+    settings: Snap,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Pretty printed, so changes diff line by line.
+pub fn to_json<Snap: Serialize>(descriptor: &CarnyxDescriptor, preset: &Preset<Snap>) -> String {
+    let written = Written {
+        format: FORMAT,
+        version: VERSION,
+        plugin: descriptor.name,
+        name: &preset.name,
+        settings: &preset.snap,
+    };
+    // serializing plain data to a string can't fail
+    serde_json::to_string_pretty(&written).unwrap_or_default()
+}
+
+/// Fails for other plugins' presets, and wrapper versions newer than this one.
+pub fn from_json<Snap: DeserializeOwned>(descriptor: &CarnyxDescriptor, text: &str) -> io::Result<Preset<Snap>> {
+    let read: Read<Snap> = serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?;
+    if read.format != FORMAT {
+        return Err(invalid(format!("not a carnyx preset: {:?}", read.format)));
+    }
+    if read.version > VERSION {
+        return Err(invalid(format!("preset format version {} is newer than this plugin understands", read.version)));
+    }
+    if read.plugin != descriptor.name {
+        return Err(invalid(format!("the preset is for {}, not {}", read.plugin, descriptor.name)));
+    }
+    Ok(Preset::new(read.name, read.settings))
+}
+
+pub fn read<Snap: DeserializeOwned>(descriptor: &CarnyxDescriptor, path: impl AsRef<Path>) -> io::Result<Preset<Snap>> {
+    from_json(descriptor, &fs::read_to_string(path)?)
+}
+
+pub fn write<Snap: Serialize>(descriptor: &CarnyxDescriptor, preset: &Preset<Snap>, path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, to_json(descriptor, preset))
+}
@@ -0,0 +1,470 @@
+//! Low frequency oscillators for modulation, free running in Hz or synced to the host's
+//! tempo in note divisions.
+
+use std::f64::consts::PI;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use vst::util::AtomicFloat;
+
+use crate::carnyx::{BasicParam, CarnyxModel, CarnyxParam, DiscreteParam};
+use crate::modulation::Modulation;
+use crate::process::{ProcessContext, Transport};
+use crate::random::Rng;
+
+// when the host doesn't say
+const DEFAULT_TEMPO: f64 = 120.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    /// Rising.
+    Saw,
+    Square,
+    /// A new random value each cycle.
+    SampleAndHold,
+}
+
+impl LfoShape {
+    pub const ALL: [LfoShape; 5] = [LfoShape::Sine, LfoShape::Triangle, LfoShape::Saw, LfoShape::Square, LfoShape::SampleAndHold];
+    pub const NAMES: [&'static str; 5] = ["Sine", "Triangle", "Saw", "Square", "S&H"];
+
+    pub fn from_index(index: usize) -> LfoShape {
+        LfoShape::ALL[index.min(LfoShape::ALL.len() - 1)]
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The shape's value, from -1 to 1, at `phase` from 0 to 1. Sample and hold has no
+    /// fixed shape, so is 0 here; an [`Lfo`] holds its random values.
+    pub fn value(self, phase: f64) -> f32 {
+        let value = match self {
+            LfoShape::Sine => (2. * PI * phase).sin(),
+            LfoShape::Triangle => 1. - 4. * ((phase + 0.25).fract() - 0.5).abs(),
+            LfoShape::Saw => 2. * phase - 1.,
+            LfoShape::Square => if phase < 0.5 { 1. } else { -1. },
+            LfoShape::SampleAndHold => 0.,
+        };
+        value as f32
+    }
+}
+
+/// A note length to sync a cycle to. Dotted notes are half as long again, triplets two
+/// thirds as long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncDivision {
+    Bars4,
+    Bars2,
+    Whole,
+    Half,
+    HalfDotted,
+    Quarter,
+    QuarterDotted,
+    QuarterTriplet,
+    Eighth,
+    EighthDotted,
+    EighthTriplet,
+    Sixteenth,
+    SixteenthDotted,
+    SixteenthTriplet,
+    ThirtySecond,
+}
+
+impl SyncDivision {
+    pub const ALL: [SyncDivision; 15] = [
+        SyncDivision::Bars4,
+        SyncDivision::Bars2,
+        SyncDivision::Whole,
+        SyncDivision::Half,
+        SyncDivision::HalfDotted,
+        SyncDivision::Quarter,
+        SyncDivision::QuarterDotted,
+        SyncDivision::QuarterTriplet,
+        SyncDivision::Eighth,
+        SyncDivision::EighthDotted,
+        SyncDivision::EighthTriplet,
+        SyncDivision::Sixteenth,
+        SyncDivision::SixteenthDotted,
+        SyncDivision::SixteenthTriplet,
+        SyncDivision::ThirtySecond,
+    ];
+    pub const NAMES: [&'static str; 15] = [
+        "4/1", "2/1", "1/1", "1/2", "1/2.", "1/4", "1/4.", "1/4T", "1/8", "1/8.", "1/8T", "1/16", "1/16.", "1/16T", "1/32",
+    ];
+
+    pub fn name(self) -> &'static str {
+        SyncDivision::NAMES[self.index()]
+    }
+
+    pub fn from_index(index: usize) -> SyncDivision {
+        SyncDivision::ALL[index.min(SyncDivision::ALL.len() - 1)]
+    }
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Length in quarter notes.
+    pub fn beats(self) -> f64 {
+        match self {
+            SyncDivision::Bars4 => 16.,
+            SyncDivision::Bars2 => 8.,
+            SyncDivision::Whole => 4.,
+            SyncDivision::Half => 2.,
+            SyncDivision::HalfDotted => 3.,
+            SyncDivision::Quarter => 1.,
+            SyncDivision::QuarterDotted => 1.5,
+            SyncDivision::QuarterTriplet => 2. / 3.,
+            SyncDivision::Eighth => 0.5,
+            SyncDivision::EighthDotted => 0.75,
+            SyncDivision::EighthTriplet => 1. / 3.,
+            SyncDivision::Sixteenth => 0.25,
+            SyncDivision::SixteenthDotted => 0.375,
+            SyncDivision::SixteenthTriplet => 1. / 6.,
+            SyncDivision::ThirtySecond => 0.125,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LfoRate {
+    Hz(f32),
+    Synced(SyncDivision),
+}
+
+/// An LFO for the audio thread. Call `start_block` with each block's transport, then
+/// `next` once per sample.
+///
+/// While the host plays, synced LFOs take their phase from the song position, so they
+/// stay on the beat through loops and jumps. When the host starts playing without saying
+/// where it is, the phase restarts from zero.
+#[derive(Debug, Clone)]
+pub struct Lfo {
+    shape: LfoShape,
+    rate: LfoRate,
+    phase: f64,
+    increment: f64,
+    was_playing: bool,
+    // the sample and hold value, drawn afresh each cycle
+    held: f32,
+    rng: Rng,
+}
+
+impl Lfo {
+    pub fn new(shape: LfoShape, rate: LfoRate) -> Self {
+        Lfo { shape, rate, phase: 0., increment: 0., was_playing: false, held: 0., rng: Rng::default() }
+    }
+
+    /// Builder-style method to seed the sample and hold values, so LFOs running side by
+    /// side don't hold the same ones.
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+
+    pub fn set_rate(&mut self, rate: LfoRate) {
+        self.rate = rate;
+    }
+
+    /// Position in the cycle, from 0 to 1.
+    pub fn phase(&self) -> f64 {
+        self.phase
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0.;
+    }
+
+    pub fn start_block(&mut self, transport: Option<&Transport>, sample_rate: f32) {
+        let sample_rate = sample_rate as f64;
+        let playing = transport.map(|t| t.playing).unwrap_or(false);
+        let tempo = transport.and_then(|t| t.tempo).unwrap_or(DEFAULT_TEMPO);
+        self.increment = match self.rate {
+            LfoRate::Hz(hz) => hz as f64 / sample_rate,
+            LfoRate::Synced(division) => tempo / 60. / division.beats() / sample_rate,
+        };
+        if let (LfoRate::Synced(division), Some(transport)) = (self.rate, transport.filter(|t| t.playing)) {
+            // hosts without a musical position still count samples
+            let beats = transport.position_beats.or_else(|| {
+                transport.tempo.map(|tempo| transport.position_samples / sample_rate * tempo / 60.)
+            });
+            match beats {
+                Some(beats) => self.phase = (beats / division.beats()).rem_euclid(1.),
+                None if !self.was_playing => self.phase = 0.,
+                None => (),
+            }
+        }
+        self.was_playing = playing;
+    }
+
+    /// The value for the next sample, from -1 to 1.
+    pub fn next(&mut self) -> f32 {
+        let value = match self.shape {
+            LfoShape::SampleAndHold => self.held,
+            shape => shape.value(self.phase),
+        };
+        self.advance(1);
+        value
+    }
+
+    /// Skip `samples` samples, e.g. to run once per block at control rate.
+    pub fn advance(&mut self, samples: usize) {
+        let phase = self.phase + self.increment * samples as f64;
+        if phase >= 1. {
+            self.held = self.rng.next_bipolar();
+        }
+        self.phase = phase.fract();
+    }
+}
+
+// free running rates, spread exponentially over the normalized range
+const RATE_RANGE_HZ: (f32, f32) = (0.01, 20.);
+
+// a closure's signature can't say its result borrows from its argument, so spell it out
+fn slot_of<Model: CarnyxModel>(slot: usize) -> impl Fn(&Model) -> Option<&LfoSlot> + Copy {
+    move |m| m.lfo().map(|lfo| lfo.slot(slot))
+}
+
+fn rate_from_normalized(value: f32) -> f32 {
+    let (low, high) = RATE_RANGE_HZ;
+    low * (high / low).powf(value.clamp(0., 1.))
+}
+
+fn rate_to_normalized(hz: f32) -> f32 {
+    let (low, high) = RATE_RANGE_HZ;
+    (hz / low).ln() / (high / low).ln()
+}
+
+// by slot: shape, rate, depth and target. The first slot keeps the ids it had when it
+// was the only one
+const SLOT_IDS: [[&str; 4]; LfoParams::SLOTS] = [
+    ["lfo.shape", "lfo.rate", "lfo.depth", "lfo.target"],
+    ["lfo2.shape", "lfo2.rate", "lfo2.depth", "lfo2.target"],
+    ["lfo3.shape", "lfo3.rate", "lfo3.depth", "lfo3.target"],
+    ["lfo4.shape", "lfo4.rate", "lfo4.depth", "lfo4.target"],
+];
+const SLOT_NAMES: [[&str; 4]; LfoParams::SLOTS] = [
+    ["lfo shape", "lfo rate", "lfo depth", "lfo target"],
+    ["lfo 2 shape", "lfo 2 rate", "lfo 2 depth", "lfo 2 target"],
+    ["lfo 3 shape", "lfo 3 rate", "lfo 3 depth", "lfo 3 target"],
+    ["lfo 4 shape", "lfo 4 rate", "lfo 4 depth", "lfo 4 target"],
+];
+
+/// One modulation routing: an LFO, and the parameter it modulates.
+pub struct LfoSlot {
+    shape: AtomicUsize,
+    rate_hz: AtomicFloat,
+    // the largest offset, in normalized units
+    depth: AtomicFloat,
+    // the index of the modulated parameter, plus one; zero for none
+    target: AtomicUsize,
+}
+
+impl Default for LfoSlot {
+    fn default() -> Self {
+        LfoSlot {
+            shape: AtomicUsize::new(LfoShape::Sine.index()),
+            rate_hz: AtomicFloat::new(1.),
+            depth: AtomicFloat::new(0.25),
+            target: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl LfoSlot {
+    pub fn shape(&self) -> LfoShape {
+        LfoShape::from_index(self.shape.load(Ordering::Relaxed))
+    }
+
+    pub fn rate_hz(&self) -> f32 {
+        self.rate_hz.get()
+    }
+
+    pub fn depth(&self) -> f32 {
+        self.depth.get()
+    }
+
+    /// The index of the modulated parameter, if any.
+    pub fn target(&self) -> Option<usize> {
+        self.target.load(Ordering::Relaxed).checked_sub(1)
+    }
+}
+
+/// The settings of the built in LFOs, each in a slot of its own which modulates one of the
+/// processor's parameters. A model opts in by returning these from [`CarnyxModel::lfo`],
+/// and one from [`CarnyxModel::modulation`] for them to write to; the parameters are then
+/// appended by [`CarnyxProcessor::all_parameters`](crate::CarnyxProcessor::all_parameters),
+/// so hosts automate and save the routings like any others, and the processor runs an
+/// [`LfoModulator`].
+pub struct LfoParams {
+    slots: Vec<LfoSlot>,
+}
+
+impl Default for LfoParams {
+    fn default() -> Self {
+        LfoParams { slots: (0..LfoParams::SLOTS).map(|_| LfoSlot::default()).collect() }
+    }
+}
+
+impl LfoParams {
+    pub const SLOTS: usize = 4;
+
+    pub fn slot(&self, slot: usize) -> &LfoSlot {
+        &self.slots[slot]
+    }
+
+    pub fn slots(&self) -> &[LfoSlot] {
+        &self.slots
+    }
+
+    /// Every slot's parameters in turn, with `targets` naming the parameters they can
+    /// modulate, which are the first of the processor's.
+    pub fn parameters<Model: CarnyxModel>(targets: Vec<String>) -> Vec<Box<dyn CarnyxParam<Model>>> {
+        (0..LfoParams::SLOTS).flat_map(|slot| LfoParams::slot_parameters(slot, targets.clone())).collect()
+    }
+
+    fn slot_parameters<Model: CarnyxModel>(slot: usize, targets: Vec<String>) -> Vec<Box<dyn CarnyxParam<Model>>> {
+        let ([shape_id, rate_id, depth_id, _], [shape, rate, depth, _]) = (SLOT_IDS[slot], SLOT_NAMES[slot]);
+        let get = slot_of::<Model>(slot);
+        vec![
+            Box::new(DiscreteParam::new(shape, &LfoShape::NAMES,
+                                        move |m: &Model| get(m).map(|s| s.shape().index()).unwrap_or(0),
+                                        move |m, index| if let Some(s) = get(m) { s.shape.store(LfoShape::from_index(index).index(), Ordering::Relaxed) })
+                .with_id(shape_id)
+                .with_description("The LFO's waveform.")),
+            Box::new(BasicParam::new(rate, "Hz",
+                                     move |m: &Model| get(m).map(|s| rate_to_normalized(s.rate_hz())).unwrap_or(0.),
+                                     move |m, val| if let Some(s) = get(m) { s.rate_hz.set(rate_from_normalized(val)) },
+                                     move |m| format!("{:.2}", get(m).map(|s| s.rate_hz()).unwrap_or(0.)))
+                .with_id(rate_id)
+                .with_parse(|text| crate::units::parse_plain(text, "Hz").map(rate_to_normalized))
+                .with_default(rate_to_normalized(1.))
+                .with_description("How many cycles the LFO makes a second.")),
+            Box::new(BasicParam::new(depth, "%",
+                                     move |m: &Model| get(m).map(|s| s.depth()).unwrap_or(0.),
+                                     move |m, val| if let Some(s) = get(m) { s.depth.set(val.clamp(0., 1.)) },
+                                     move |m| format!("{:.0}", get(m).map(|s| s.depth()).unwrap_or(0.) * 100.))
+                .with_id(depth_id)
+                .with_parse(crate::units::parse_percent)
+                .with_default(0.25)
+                .with_description("How far the LFO moves its target, as a share of the target's range.")),
+            Box::new(LfoTargetParam { slot, targets }),
+        ]
+    }
+}
+
+/// Chooses the parameter a slot's LFO modulates, from names given when it is made.
+struct LfoTargetParam {
+    slot: usize,
+    targets: Vec<String>,
+}
+
+impl LfoTargetParam {
+    // none, then each target
+    fn to_normalized(&self, choice: usize) -> f32 {
+        choice.min(self.targets.len()) as f32 / self.targets.len().max(1) as f32
+    }
+
+    fn from_normalized(&self, value: f32) -> usize {
+        (value.clamp(0., 1.) * self.targets.len() as f32).round() as usize
+    }
+}
+
+impl<Model: CarnyxModel> CarnyxParam<Model> for LfoTargetParam {
+    fn id(&self) -> &str {
+        SLOT_IDS[self.slot][3]
+    }
+
+    fn name(&self, _model: &Model) -> String {
+        SLOT_NAMES[self.slot][3].to_string()
+    }
+
+    fn label(&self, _model: &Model) -> String {
+        "".to_string()
+    }
+
+    fn get_value(&self, model: &Model) -> f32 {
+        self.to_normalized(model.lfo().map(|lfo| lfo.slot(self.slot).target.load(Ordering::Relaxed)).unwrap_or(0))
+    }
+
+    fn set_value(&self, model: &Model, val: f32) {
+        if let Some(lfo) = model.lfo() {
+            lfo.slot(self.slot).target.store(self.from_normalized(val), Ordering::Relaxed);
+        }
+    }
+
+    fn formatted(&self, model: &Model) -> String {
+        let choice = self.from_normalized(self.get_value(model));
+        self.step_names(model).swap_remove(choice)
+    }
+
+    fn parse(&self, model: &Model, text: &str) -> Option<f32> {
+        let choice = self.step_names(model).iter().position(|name| name.eq_ignore_ascii_case(text.trim()))?;
+        Some(self.to_normalized(choice))
+    }
+
+    // modulating nothing is the safe thing to land on
+    fn randomizable(&self) -> bool {
+        false
+    }
+
+    fn description(&self) -> &str {
+        "The parameter the LFO modulates."
+    }
+
+    fn steps(&self) -> usize {
+        self.targets.len() + 1
+    }
+
+    fn step_names(&self, _model: &Model) -> Vec<String> {
+        std::iter::once("None".to_string()).chain(self.targets.iter().cloned()).collect()
+    }
+}
+
+/// Runs the LFOs an [`LfoParams`] describes once per block, adding each one's output,
+/// scaled by its depth, to its target's offset in a [`Modulation`]. The processor owns one
+/// and calls `process` at the start of each block, after clearing the modulation and before
+/// reading its parameters.
+#[derive(Debug, Clone)]
+pub struct LfoModulator {
+    lfos: Vec<Lfo>,
+}
+
+impl Default for LfoModulator {
+    fn default() -> Self {
+        LfoModulator {
+            lfos: (0..LfoParams::SLOTS).map(|slot| Lfo::new(LfoShape::Sine, LfoRate::Hz(1.)).with_seed(slot as u32 + 1)).collect(),
+        }
+    }
+}
+
+impl LfoModulator {
+    /// Add each slot's output to its target. Slots, and other modulators such as
+    /// [`MacroParams`](crate::MacroParams), may share a target, so this adds to whatever is
+    /// there; the processor [`clear`](Modulation::clear)s the modulation first, which also
+    /// returns targets left behind to where the host and editor left them.
+    pub fn process(&mut self, params: &LfoParams, modulation: &Modulation, context: &ProcessContext) {
+        for (lfo, slot) in self.lfos.iter_mut().zip(params.slots()) {
+            lfo.set_shape(slot.shape());
+            lfo.set_rate(LfoRate::Hz(slot.rate_hz()));
+            lfo.start_block(context.transport.as_ref(), context.sample_rate);
+            let value = lfo.next();
+            if let Some(target) = slot.target() {
+                modulation.add(target, value * slot.depth(), slot.depth());
+            }
+            lfo.advance(context.block_size.saturating_sub(1));
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for lfo in &mut self.lfos {
+            lfo.reset();
+        }
+    }
+}
@@ -1,8 +1,66 @@
+pub mod audit;
+pub mod audition;
+pub mod automation;
+pub mod buffer;
 pub mod carnyx;
+pub mod controllers;
+pub mod crossfade;
+pub mod descriptor;
+pub mod diagnostics;
+pub mod dsp;
+pub mod events;
+pub mod fxp;
+#[cfg(feature = "json")]
+pub mod json_preset;
+pub mod lfo;
+pub mod limiter;
+pub mod load;
+pub mod locks;
+pub mod macros;
+pub mod midi_routes;
+pub mod modulation;
+pub mod mpe;
+pub mod param_ids;
+pub mod pending;
+pub mod preset;
+pub mod probe;
+pub mod process;
+pub mod queue;
+pub mod random;
+pub mod seqlock;
+pub mod shared;
+pub mod state;
+pub mod tag;
+pub mod tap;
+pub mod telemetry;
+pub mod test;
+pub mod ui_state;
+pub mod units;
+pub mod utility;
+pub mod wav;
 
 pub use carnyx::*;
-pub use vst::buffer;
-
-
-
-
+pub use controllers::{Controller, ControllerDecoder, ControllerEvent, SmoothedController};
+pub use crossfade::PresetCrossfade;
+pub use descriptor::{CarnyxDescriptor, PluginCategory};
+pub use diagnostics::{Diagnostic, Diagnostics, Level};
+pub use dsp::{Biquad, DcBlocker, OnePole};
+pub use events::*;
+pub use lfo::{Lfo, LfoModulator, LfoParams, LfoRate, LfoShape, LfoSlot, SyncDivision};
+pub use limiter::{Limiter, OutputLimiter};
+pub use load::DspLoad;
+pub use locks::{LockSet, ParamLocks};
+pub use macros::{MacroAssignment, MacroControl, MacroParams};
+pub use midi_routes::{MidiRoute, MidiRouter, MidiRoutes, MidiSource};
+pub use modulation::{Modulation, ModulationAmount};
+pub use param_ids::ParamIdTable;
+pub use pending::{PendingChanges, RefreshGate};
+pub use probe::{ProbeTap, Probes};
+pub use process::{BlockSplitter, MidiOutput, ProcessContext, SilenceFlags, SubBlock, Transport};
+pub use queue::EventQueue;
+pub use seqlock::SeqLock;
+pub use shared::{Instance, Shared};
+pub use tag::{InstanceTag, TAG_COLORS};
+pub use tap::SampleTap;
+pub use telemetry::{Telemetry, TelemetryBus};
+pub use ui_state::UiState;
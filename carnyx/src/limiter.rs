@@ -0,0 +1,183 @@
+//! A safety limiter for the very end of the output stage, so runaway drive or feedback
+//! can't reach ears and speakers far above full scale.
+
+use crate::buffer::Outputs;
+use crate::dsp::one_pole_coefficient;
+
+/// The level the limiter holds the output to: full scale.
+pub const CEILING: f32 = 1.;
+
+const LOOKAHEAD_SECONDS: f32 = 0.0015;
+const RELEASE_SECONDS: f32 = 0.1;
+// frames worked out at a time, so the gain buffer is allocated once
+const CHUNK: usize = 256;
+
+/// Which safety stage, if any, follows the output gain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputLimiter {
+    Off,
+    HardClip,
+    Brickwall,
+}
+
+impl Default for OutputLimiter {
+    fn default() -> Self {
+        OutputLimiter::Off
+    }
+}
+
+impl OutputLimiter {
+    pub const ALL: [OutputLimiter; 3] = [OutputLimiter::Off, OutputLimiter::HardClip, OutputLimiter::Brickwall];
+    pub const NAMES: [&'static str; 3] = ["Off", "Hard clip", "Brickwall"];
+
+    pub fn name(&self) -> &'static str {
+        OutputLimiter::NAMES[self.index()]
+    }
+
+    pub fn from_index(index: usize) -> OutputLimiter {
+        OutputLimiter::ALL[index.min(OutputLimiter::ALL.len() - 1)]
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// A lookahead brickwall limiter, with one gain for all channels. The output is delayed by
+/// the lookahead so the gain can ramp down before a peak arrives, rather than clipping it;
+/// whatever is still over the ceiling is clipped anyway.
+///
+/// Buffers are allocated up front for `MAX_CHANNELS`; channels beyond those are only clipped.
+pub struct Limiter {
+    delay: Vec<Vec<f32>>,
+    position: usize,
+    lookahead: usize,
+    sample_rate: f32,
+    release: f32,
+    gain: f32,
+    target: f32,
+    step: f32,
+    hold: usize,
+    gains: Vec<f32>,
+    running: bool,
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Limiter::new()
+    }
+}
+
+impl Limiter {
+    pub const MAX_CHANNELS: usize = 8;
+    pub const MAX_LOOKAHEAD: usize = 1024;
+
+    pub fn new() -> Self {
+        Limiter {
+            delay: vec![vec![0.; Limiter::MAX_LOOKAHEAD]; Limiter::MAX_CHANNELS],
+            position: 0,
+            lookahead: 1,
+            sample_rate: 0.,
+            release: 0.,
+            gain: 1.,
+            target: 1.,
+            step: 0.,
+            hold: 0,
+            gains: vec![1.; CHUNK],
+            running: false,
+        }
+    }
+
+    /// How far the output is delayed, in samples, at the sample rate it last ran at.
+    pub fn latency(&self) -> usize {
+        self.lookahead
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.lookahead = ((LOOKAHEAD_SECONDS * sample_rate) as usize).clamp(1, Limiter::MAX_LOOKAHEAD);
+        self.release = one_pole_coefficient(RELEASE_SECONDS, sample_rate);
+        self.reset();
+    }
+
+    /// Forget the audio in flight and any gain reduction.
+    pub fn reset(&mut self) {
+        for channel in &mut self.delay {
+            channel.iter_mut().for_each(|sample| *sample = 0.);
+        }
+        self.position = 0;
+        self.gain = 1.;
+        self.target = 1.;
+        self.step = 0.;
+        self.hold = 0;
+    }
+
+    /// Note that the limiter was switched out, so it starts afresh when switched back in
+    /// rather than playing out stale audio.
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    pub fn process(&mut self, outputs: &mut Outputs<f32>, sample_rate: f32) {
+        if sample_rate != self.sample_rate {
+            self.set_sample_rate(sample_rate);
+        } else if !self.running {
+            self.reset();
+        }
+        self.running = true;
+        let channels = outputs.len();
+        let samples = if channels > 0 { outputs.get_mut(0).len() } else { 0 };
+        let mut start = 0;
+        while start < samples {
+            let end = (start + CHUNK).min(samples);
+            self.compute_gains(outputs, start, end);
+            let lookahead = self.lookahead;
+            for channel in 0..channels {
+                let output = &mut outputs.get_mut(channel)[start..end];
+                match self.delay.get_mut(channel) {
+                    Some(delay) => {
+                        let mut position = self.position;
+                        for (sample, gain) in output.iter_mut().zip(&self.gains) {
+                            let delayed = std::mem::replace(&mut delay[position], *sample);
+                            *sample = (delayed * gain).clamp(-CEILING, CEILING);
+                            position = (position + 1) % lookahead;
+                        }
+                    }
+                    None => {
+                        for sample in output {
+                            *sample = sample.clamp(-CEILING, CEILING);
+                        }
+                    }
+                }
+            }
+            self.position = (self.position + end - start) % lookahead;
+            start = end;
+        }
+    }
+
+    // the gain for each frame of outputs[start..end], applied to the frame leaving the delay
+    fn compute_gains(&mut self, outputs: &mut Outputs<f32>, start: usize, end: usize) {
+        let gains = &mut self.gains[..end - start];
+        gains.iter_mut().for_each(|peak| *peak = 0.);
+        for channel in 0..outputs.len() {
+            for (peak, sample) in gains.iter_mut().zip(&outputs.get_mut(channel)[start..end]) {
+                *peak = peak.max(sample.abs());
+            }
+        }
+        for slot in gains.iter_mut() {
+            let wanted = if *slot > CEILING { CEILING / *slot } else { 1. };
+            if wanted < self.target {
+                // reach the new gain just as the peak leaves the delay, and hold it until then
+                self.target = wanted;
+                self.step = (self.gain - wanted) / self.lookahead as f32;
+                self.hold = self.lookahead;
+            } else if self.hold > 0 {
+                self.hold -= 1;
+            } else {
+                self.target = 1. - (1. - self.target) * self.release;
+            }
+            self.gain = if self.gain > self.target { (self.gain - self.step).max(self.target) } else { self.target };
+            *slot = self.gain;
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! How much of the time available for processing a block is actually spent on it.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+// time constant of the smoothed load, in seconds
+const SMOOTHING: f32 = 0.3;
+
+/// Processing time as a fraction of realtime: 1.0 means a block took as long to process
+/// as it lasts when played. Written by the audio thread, read by editors.
+#[derive(Debug, Default)]
+pub struct DspLoad {
+    // f32 bits
+    load: AtomicU32,
+    peak: AtomicU32,
+}
+
+impl DspLoad {
+    pub fn new() -> Self {
+        DspLoad::default()
+    }
+
+    /// Time `process`, recording it as a block of `samples` at `sample_rate`.
+    pub fn measure<R>(&self, samples: usize, sample_rate: f32, process: impl FnOnce() -> R) -> R {
+        let start = Instant::now();
+        let result = process();
+        self.record(start.elapsed(), samples, sample_rate);
+        result
+    }
+
+    pub fn record(&self, elapsed: Duration, samples: usize, sample_rate: f32) {
+        if samples == 0 || sample_rate <= 0. {
+            return;
+        }
+        let block_seconds = samples as f32 / sample_rate;
+        let block_load = elapsed.as_secs_f32() / block_seconds;
+        let coefficient = 1. - (-block_seconds / SMOOTHING).exp();
+        let load = self.load();
+        self.load.store((load + (block_load - load) * coefficient).to_bits(), Ordering::Relaxed);
+        if block_load > f32::from_bits(self.peak.load(Ordering::Relaxed)) {
+            self.peak.store(block_load.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    /// Smoothed over the last few hundred milliseconds.
+    pub fn load(&self) -> f32 {
+        f32::from_bits(self.load.load(Ordering::Relaxed))
+    }
+
+    /// The highest load of a single block since the last call.
+    pub fn take_peak(&self) -> f32 {
+        f32::from_bits(self.peak.swap(0f32.to_bits(), Ordering::Relaxed))
+    }
+}
@@ -0,0 +1,63 @@
+//! Parameters locked against changes from the host.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const LOCK_WORDS: usize = 4;
+
+/// Which parameters are locked, by index. Only the first `LockSet::CAPACITY` parameters
+/// can be locked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockSet([u64; LOCK_WORDS]);
+
+impl LockSet {
+    pub const CAPACITY: usize = LOCK_WORDS * 64;
+
+    pub fn contains(&self, index: usize) -> bool {
+        index < LockSet::CAPACITY && self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn set(&mut self, index: usize, locked: bool) {
+        if index < LockSet::CAPACITY {
+            if locked {
+                self.0[index / 64] |= 1 << (index % 64);
+            } else {
+                self.0[index / 64] &= !(1 << (index % 64));
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|word| *word == 0)
+    }
+}
+
+/// Parameters which ignore the host, e.g. to audition a setting against automation.
+/// The editor locks and unlocks them; the bridge checks them in `set_parameter`.
+#[derive(Debug, Default)]
+pub struct ParamLocks {
+    words: [AtomicU64; LOCK_WORDS],
+}
+
+impl ParamLocks {
+    pub fn new() -> Self {
+        ParamLocks::default()
+    }
+
+    pub fn is_locked(&self, index: usize) -> bool {
+        index < LockSet::CAPACITY && self.words[index / 64].load(Ordering::Relaxed) & (1 << (index % 64)) != 0
+    }
+
+    pub fn get(&self) -> LockSet {
+        let mut set = LockSet::default();
+        for (word, atomic) in set.0.iter_mut().zip(&self.words) {
+            *word = atomic.load(Ordering::Relaxed);
+        }
+        set
+    }
+
+    pub fn set(&self, set: LockSet) {
+        for (word, atomic) in set.0.iter().zip(&self.words) {
+            atomic.store(*word, Ordering::Relaxed);
+        }
+    }
+}
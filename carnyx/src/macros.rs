@@ -0,0 +1,343 @@
+//! Macro controls: host visible knobs which each move several of a processor's parameters
+//! at once, each over a range and along a curve of its own.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use vst::util::AtomicFloat;
+
+use crate::carnyx::{BasicParam, CarnyxModel, CarnyxParam};
+use crate::modulation::Modulation;
+
+// a curve of +-1 raises the macro's value to this power, or its inverse
+const CURVE_POWER: f32 = 4.;
+
+const MACRO_IDS: [&str; MacroParams::CONTROLS] = ["macro1", "macro2", "macro3", "macro4"];
+const MACRO_NAMES: [&str; MacroParams::CONTROLS] = ["macro 1", "macro 2", "macro 3", "macro 4"];
+const ASSIGNMENT_IDS: [[[&str; 4]; MacroParams::ASSIGNMENTS]; MacroParams::CONTROLS] = [
+    [
+        ["macro1.target1", "macro1.start1", "macro1.end1", "macro1.curve1"],
+        ["macro1.target2", "macro1.start2", "macro1.end2", "macro1.curve2"],
+        ["macro1.target3", "macro1.start3", "macro1.end3", "macro1.curve3"],
+    ],
+    [
+        ["macro2.target1", "macro2.start1", "macro2.end1", "macro2.curve1"],
+        ["macro2.target2", "macro2.start2", "macro2.end2", "macro2.curve2"],
+        ["macro2.target3", "macro2.start3", "macro2.end3", "macro2.curve3"],
+    ],
+    [
+        ["macro3.target1", "macro3.start1", "macro3.end1", "macro3.curve1"],
+        ["macro3.target2", "macro3.start2", "macro3.end2", "macro3.curve2"],
+        ["macro3.target3", "macro3.start3", "macro3.end3", "macro3.curve3"],
+    ],
+    [
+        ["macro4.target1", "macro4.start1", "macro4.end1", "macro4.curve1"],
+        ["macro4.target2", "macro4.start2", "macro4.end2", "macro4.curve2"],
+        ["macro4.target3", "macro4.start3", "macro4.end3", "macro4.curve3"],
+    ],
+];
+const ASSIGNMENT_NAMES: [[[&str; 4]; MacroParams::ASSIGNMENTS]; MacroParams::CONTROLS] = [
+    [
+        ["macro 1 target 1", "macro 1 start 1", "macro 1 end 1", "macro 1 curve 1"],
+        ["macro 1 target 2", "macro 1 start 2", "macro 1 end 2", "macro 1 curve 2"],
+        ["macro 1 target 3", "macro 1 start 3", "macro 1 end 3", "macro 1 curve 3"],
+    ],
+    [
+        ["macro 2 target 1", "macro 2 start 1", "macro 2 end 1", "macro 2 curve 1"],
+        ["macro 2 target 2", "macro 2 start 2", "macro 2 end 2", "macro 2 curve 2"],
+        ["macro 2 target 3", "macro 2 start 3", "macro 2 end 3", "macro 2 curve 3"],
+    ],
+    [
+        ["macro 3 target 1", "macro 3 start 1", "macro 3 end 1", "macro 3 curve 1"],
+        ["macro 3 target 2", "macro 3 start 2", "macro 3 end 2", "macro 3 curve 2"],
+        ["macro 3 target 3", "macro 3 start 3", "macro 3 end 3", "macro 3 curve 3"],
+    ],
+    [
+        ["macro 4 target 1", "macro 4 start 1", "macro 4 end 1", "macro 4 curve 1"],
+        ["macro 4 target 2", "macro 4 start 2", "macro 4 end 2", "macro 4 curve 2"],
+        ["macro 4 target 3", "macro 4 start 3", "macro 4 end 3", "macro 4 curve 3"],
+    ],
+];
+
+/// One parameter a macro moves: how far it is offset with the macro at either end, and
+/// how the macro's value is bent in between.
+pub struct MacroAssignment {
+    // the index of the moved parameter, plus one; zero for none
+    target: AtomicUsize,
+    // offsets in normalized units, -1 to 1
+    start: AtomicFloat,
+    end: AtomicFloat,
+    // -1 to 1, straight at zero
+    curve: AtomicFloat,
+}
+
+impl Default for MacroAssignment {
+    fn default() -> Self {
+        MacroAssignment {
+            target: AtomicUsize::new(0),
+            start: AtomicFloat::new(0.),
+            end: AtomicFloat::new(0.5),
+            curve: AtomicFloat::new(0.),
+        }
+    }
+}
+
+impl MacroAssignment {
+    /// The index of the moved parameter, if any.
+    pub fn target(&self) -> Option<usize> {
+        self.target.load(Ordering::Relaxed).checked_sub(1)
+    }
+
+    pub fn set_target(&self, target: Option<usize>) {
+        self.target.store(target.map(|target| target + 1).unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// The offset with the macro at zero.
+    pub fn start(&self) -> f32 {
+        self.start.get()
+    }
+
+    /// The offset with the macro all the way up.
+    pub fn end(&self) -> f32 {
+        self.end.get()
+    }
+
+    /// Positive curves move the target most near the start of the macro's travel,
+    /// negative ones near the end.
+    pub fn curve(&self) -> f32 {
+        self.curve.get()
+    }
+
+    /// The offset to add to the target with the macro at `value`.
+    pub fn offset(&self, value: f32) -> f32 {
+        let shaped = value.clamp(0., 1.).powf(CURVE_POWER.powf(-self.curve()));
+        self.start() + (self.end() - self.start()) * shaped
+    }
+}
+
+/// A macro knob and the parameters it moves.
+pub struct MacroControl {
+    value: AtomicFloat,
+    assignments: Vec<MacroAssignment>,
+}
+
+impl Default for MacroControl {
+    fn default() -> Self {
+        MacroControl {
+            value: AtomicFloat::new(0.),
+            assignments: (0..MacroParams::ASSIGNMENTS).map(|_| MacroAssignment::default()).collect(),
+        }
+    }
+}
+
+impl MacroControl {
+    pub fn value(&self) -> f32 {
+        self.value.get()
+    }
+
+    pub fn assignment(&self, assignment: usize) -> &MacroAssignment {
+        &self.assignments[assignment]
+    }
+
+    pub fn assignments(&self) -> &[MacroAssignment] {
+        &self.assignments
+    }
+
+    /// Where a newly learned target goes: the first unused assignment, or failing that the last.
+    pub fn learn_slot(&self) -> usize {
+        self.assignments.iter().position(|assignment| assignment.target().is_none()).unwrap_or(MacroParams::ASSIGNMENTS - 1)
+    }
+}
+
+/// The built in macro controls. A model opts in by returning these from
+/// [`CarnyxModel::macros`], and a [`Modulation`] from [`CarnyxModel::modulation`] for them
+/// to write to. Their values and assignments are all parameters, appended by
+/// [`CarnyxProcessor::all_parameters`](crate::CarnyxProcessor::all_parameters), so hosts
+/// automate the knobs and save the assignments like any others; the processor calls
+/// [`modulate`](MacroParams::modulate) each block.
+pub struct MacroParams {
+    controls: Vec<MacroControl>,
+}
+
+impl Default for MacroParams {
+    fn default() -> Self {
+        MacroParams { controls: (0..MacroParams::CONTROLS).map(|_| MacroControl::default()).collect() }
+    }
+}
+
+impl MacroParams {
+    pub const CONTROLS: usize = 4;
+    pub const ASSIGNMENTS: usize = 3;
+
+    pub fn control(&self, control: usize) -> &MacroControl {
+        &self.controls[control]
+    }
+
+    pub fn controls(&self) -> &[MacroControl] {
+        &self.controls
+    }
+
+    /// The id of the parameter choosing an assignment's target, for editors which learn them.
+    pub fn target_id(control: usize, assignment: usize) -> &'static str {
+        ASSIGNMENT_IDS[control][assignment][0]
+    }
+
+    /// Add each assignment's offset to its target. Like [`LfoModulator`](crate::LfoModulator),
+    /// this adds to whatever is there, so the processor clears the modulation first.
+    pub fn modulate(&self, modulation: &Modulation) {
+        for control in &self.controls {
+            let value = control.value();
+            for assignment in &control.assignments {
+                if let Some(target) = assignment.target() {
+                    let range = assignment.start().abs().max(assignment.end().abs());
+                    modulation.add(target, assignment.offset(value), range);
+                }
+            }
+        }
+    }
+
+    /// Every macro's value and then its assignments, in turn, with `targets` naming the
+    /// parameters they can move, which are the first of the processor's.
+    pub fn parameters<Model: CarnyxModel>(targets: Vec<String>) -> Vec<Box<dyn CarnyxParam<Model>>> {
+        (0..MacroParams::CONTROLS).flat_map(|control| MacroParams::control_parameters(control, targets.clone())).collect()
+    }
+
+    fn control_parameters<Model: CarnyxModel>(control: usize, targets: Vec<String>) -> Vec<Box<dyn CarnyxParam<Model>>> {
+        let get = control_of::<Model>(control);
+        let mut params: Vec<Box<dyn CarnyxParam<Model>>> = vec![
+            Box::new(BasicParam::new(MACRO_NAMES[control], "%",
+                                     move |m: &Model| get(m).map(|c| c.value()).unwrap_or(0.),
+                                     move |m, val| if let Some(c) = get(m) { c.value.set(val.clamp(0., 1.)) },
+                                     move |m| format!("{:.0}", get(m).map(|c| c.value()).unwrap_or(0.) * 100.))
+                .with_id(MACRO_IDS[control])
+                .with_parse(crate::units::parse_percent)
+                .with_description("Moves every parameter assigned to the macro at once.")),
+        ];
+        for assignment in 0..MacroParams::ASSIGNMENTS {
+            params.extend(MacroParams::assignment_parameters(control, assignment, targets.clone()));
+        }
+        params
+    }
+
+    fn assignment_parameters<Model: CarnyxModel>(control: usize, assignment: usize, targets: Vec<String>) -> Vec<Box<dyn CarnyxParam<Model>>> {
+        let ([_, start_id, end_id, curve_id], [_, start, end, curve]) = (ASSIGNMENT_IDS[control][assignment], ASSIGNMENT_NAMES[control][assignment]);
+        let get = assignment_of::<Model>(control, assignment);
+        vec![
+            Box::new(MacroTargetParam { control, assignment, targets }),
+            Box::new(BasicParam::new(start, "%",
+                                     move |m: &Model| get(m).map(|a| offset_to_normalized(a.start())).unwrap_or(0.5),
+                                     move |m, val| if let Some(a) = get(m) { a.start.set(offset_from_normalized(val)) },
+                                     move |m| format!("{:+.0}", get(m).map(|a| a.start()).unwrap_or(0.) * 100.))
+                .with_id(start_id)
+                .with_parse(|text| crate::units::parse_percent(text).map(offset_to_normalized))
+                .with_default(offset_to_normalized(0.))
+                .with_description("How far the target is moved with the macro at zero, as a share of its range.")
+                .without_randomize()),
+            Box::new(BasicParam::new(end, "%",
+                                     move |m: &Model| get(m).map(|a| offset_to_normalized(a.end())).unwrap_or(0.5),
+                                     move |m, val| if let Some(a) = get(m) { a.end.set(offset_from_normalized(val)) },
+                                     move |m| format!("{:+.0}", get(m).map(|a| a.end()).unwrap_or(0.) * 100.))
+                .with_id(end_id)
+                .with_parse(|text| crate::units::parse_percent(text).map(offset_to_normalized))
+                .with_default(offset_to_normalized(0.5))
+                .with_description("How far the target is moved with the macro all the way up, as a share of its range.")
+                .without_randomize()),
+            Box::new(BasicParam::new(curve, "",
+                                     move |m: &Model| get(m).map(|a| offset_to_normalized(a.curve())).unwrap_or(0.5),
+                                     move |m, val| if let Some(a) = get(m) { a.curve.set(offset_from_normalized(val)) },
+                                     move |m| format!("{:+.2}", get(m).map(|a| a.curve()).unwrap_or(0.)))
+                .with_id(curve_id)
+                .with_parse(|text| crate::units::parse_plain(text, "").map(|curve| offset_to_normalized(curve.clamp(-1., 1.))))
+                .with_default(offset_to_normalized(0.))
+                .with_description("Bends how the target follows the macro: positive moves it sooner, negative later.")
+                .without_randomize()),
+        ]
+    }
+}
+
+// a closure's signature can't say its result borrows from its argument, so spell it out
+fn control_of<Model: CarnyxModel>(control: usize) -> impl Fn(&Model) -> Option<&MacroControl> + Copy {
+    move |m| m.macros().map(|macros| macros.control(control))
+}
+
+fn assignment_of<Model: CarnyxModel>(control: usize, assignment: usize) -> impl Fn(&Model) -> Option<&MacroAssignment> + Copy {
+    move |m| m.macros().map(|macros| macros.control(control).assignment(assignment))
+}
+
+// offsets and curves run -1 to 1
+fn offset_to_normalized(offset: f32) -> f32 {
+    (offset.clamp(-1., 1.) + 1.) / 2.
+}
+
+fn offset_from_normalized(value: f32) -> f32 {
+    value.clamp(0., 1.) * 2. - 1.
+}
+
+/// Chooses the parameter an assignment moves, from names given when it is made.
+struct MacroTargetParam {
+    control: usize,
+    assignment: usize,
+    targets: Vec<String>,
+}
+
+impl MacroTargetParam {
+    // none, then each target
+    fn to_normalized(&self, choice: usize) -> f32 {
+        choice.min(self.targets.len()) as f32 / self.targets.len().max(1) as f32
+    }
+
+    fn from_normalized(&self, value: f32) -> usize {
+        (value.clamp(0., 1.) * self.targets.len() as f32).round() as usize
+    }
+}
+
+impl<Model: CarnyxModel> CarnyxParam<Model> for MacroTargetParam {
+    fn id(&self) -> &str {
+        ASSIGNMENT_IDS[self.control][self.assignment][0]
+    }
+
+    fn name(&self, _model: &Model) -> String {
+        ASSIGNMENT_NAMES[self.control][self.assignment][0].to_string()
+    }
+
+    fn label(&self, _model: &Model) -> String {
+        "".to_string()
+    }
+
+    fn get_value(&self, model: &Model) -> f32 {
+        let choice = model.macros().map(|macros| macros.control(self.control).assignment(self.assignment).target.load(Ordering::Relaxed));
+        self.to_normalized(choice.unwrap_or(0))
+    }
+
+    fn set_value(&self, model: &Model, val: f32) {
+        if let Some(macros) = model.macros() {
+            macros.control(self.control).assignment(self.assignment).target.store(self.from_normalized(val), Ordering::Relaxed);
+        }
+    }
+
+    fn formatted(&self, model: &Model) -> String {
+        let choice = self.from_normalized(self.get_value(model));
+        self.step_names(model).swap_remove(choice)
+    }
+
+    fn parse(&self, model: &Model, text: &str) -> Option<f32> {
+        let choice = self.step_names(model).iter().position(|name| name.eq_ignore_ascii_case(text.trim()))?;
+        Some(self.to_normalized(choice))
+    }
+
+    // assignments are set up by hand, not rolled
+    fn randomizable(&self) -> bool {
+        false
+    }
+
+    fn description(&self) -> &str {
+        "The parameter the macro moves."
+    }
+
+    fn steps(&self) -> usize {
+        self.targets.len() + 1
+    }
+
+    fn step_names(&self, _model: &Model) -> Vec<String> {
+        std::iter::once("None".to_string()).chain(self.targets.iter().cloned()).collect()
+    }
+}
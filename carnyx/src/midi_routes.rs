@@ -0,0 +1,244 @@
+//! Routings from a keyboard's pitch bend and mod wheel to parameters, so a processor can be
+//! played from a keyboard without setting anything up in the host.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use vst::util::AtomicFloat;
+
+use crate::carnyx::{BasicParam, CarnyxModel, CarnyxParam};
+use crate::controllers::{Controller, ControllerDecoder};
+use crate::events::{MidiMessage, TimedMidi};
+use crate::modulation::Modulation;
+
+const MOD_WHEEL: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiSource {
+    /// Centred at rest, so it moves its target either way.
+    PitchBend,
+    /// CC1, which only moves its target one way from rest.
+    ModWheel,
+}
+
+impl MidiSource {
+    pub const ALL: [MidiSource; 2] = [MidiSource::PitchBend, MidiSource::ModWheel];
+    pub const NAMES: [&'static str; 2] = ["Pitch bend", "Mod wheel"];
+
+    pub fn name(&self) -> &'static str {
+        MidiSource::NAMES[self.index()]
+    }
+
+    pub fn from_index(index: usize) -> MidiSource {
+        MidiSource::ALL[index.min(MidiSource::ALL.len() - 1)]
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+const ROUTE_IDS: [[&str; 2]; 2] = [["bend.target", "bend.depth"], ["modwheel.target", "modwheel.depth"]];
+const ROUTE_NAMES: [[&str; 2]; 2] = [["pitch bend target", "pitch bend depth"], ["mod wheel target", "mod wheel depth"]];
+
+/// Where one source goes, and how far it moves it.
+pub struct MidiRoute {
+    // the index of the moved parameter, plus one; zero for none
+    target: AtomicUsize,
+    // the offset at full bend or wheel, in normalized units, -1 to 1
+    depth: AtomicFloat,
+}
+
+impl Default for MidiRoute {
+    fn default() -> Self {
+        MidiRoute { target: AtomicUsize::new(0), depth: AtomicFloat::new(0.5) }
+    }
+}
+
+impl MidiRoute {
+    /// The index of the moved parameter, if any.
+    pub fn target(&self) -> Option<usize> {
+        self.target.load(Ordering::Relaxed).checked_sub(1)
+    }
+
+    pub fn depth(&self) -> f32 {
+        self.depth.get()
+    }
+}
+
+/// The routings of pitch bend and the mod wheel, one for each [`MidiSource`]. A model opts
+/// in by returning these from [`CarnyxModel::midi_routes`], and a [`Modulation`] from
+/// [`CarnyxModel::modulation`] for them to write to; the parameters are appended by
+/// [`CarnyxProcessor::all_parameters`](crate::CarnyxProcessor::all_parameters), and the
+/// processor runs a [`MidiRouter`].
+pub struct MidiRoutes {
+    routes: Vec<MidiRoute>,
+}
+
+impl Default for MidiRoutes {
+    fn default() -> Self {
+        MidiRoutes { routes: MidiSource::ALL.iter().map(|_| MidiRoute::default()).collect() }
+    }
+}
+
+impl MidiRoutes {
+    pub fn route(&self, source: MidiSource) -> &MidiRoute {
+        &self.routes[source.index()]
+    }
+
+    /// Each source's target and depth, with `targets` naming the parameters they can move,
+    /// which are the first of the processor's.
+    pub fn parameters<Model: CarnyxModel>(targets: Vec<String>) -> Vec<Box<dyn CarnyxParam<Model>>> {
+        MidiSource::ALL.iter().flat_map(|source| MidiRoutes::route_parameters(*source, targets.clone())).collect()
+    }
+
+    fn route_parameters<Model: CarnyxModel>(source: MidiSource, targets: Vec<String>) -> Vec<Box<dyn CarnyxParam<Model>>> {
+        let ([_, depth_id], [_, depth]) = (ROUTE_IDS[source.index()], ROUTE_NAMES[source.index()]);
+        let get = route_of::<Model>(source);
+        vec![
+            Box::new(MidiTargetParam { source, targets }),
+            Box::new(BasicParam::new(depth, "%",
+                                     move |m: &Model| get(m).map(|r| (r.depth() + 1.) / 2.).unwrap_or(0.5),
+                                     move |m, val| if let Some(r) = get(m) { r.depth.set(val.clamp(0., 1.) * 2. - 1.) },
+                                     move |m| format!("{:+.0}", get(m).map(|r| r.depth()).unwrap_or(0.) * 100.))
+                .with_id(depth_id)
+                .with_parse(|text| crate::units::parse_percent(text).map(|depth| (depth.clamp(-1., 1.) + 1.) / 2.))
+                .with_default(0.75)
+                .with_description("How far the target moves at full travel, as a share of its range. Negative moves it down.")
+                .without_randomize()),
+        ]
+    }
+}
+
+// a closure's signature can't say its result borrows from its argument, so spell it out
+fn route_of<Model: CarnyxModel>(source: MidiSource) -> impl Fn(&Model) -> Option<&MidiRoute> + Copy {
+    move |m| m.midi_routes().map(|routes| routes.route(source))
+}
+
+/// Chooses the parameter a source moves, from names given when it is made.
+struct MidiTargetParam {
+    source: MidiSource,
+    targets: Vec<String>,
+}
+
+impl MidiTargetParam {
+    // none, then each target
+    fn to_normalized(&self, choice: usize) -> f32 {
+        choice.min(self.targets.len()) as f32 / self.targets.len().max(1) as f32
+    }
+
+    fn from_normalized(&self, value: f32) -> usize {
+        (value.clamp(0., 1.) * self.targets.len() as f32).round() as usize
+    }
+}
+
+impl<Model: CarnyxModel> CarnyxParam<Model> for MidiTargetParam {
+    fn id(&self) -> &str {
+        ROUTE_IDS[self.source.index()][0]
+    }
+
+    fn name(&self, _model: &Model) -> String {
+        ROUTE_NAMES[self.source.index()][0].to_string()
+    }
+
+    fn label(&self, _model: &Model) -> String {
+        "".to_string()
+    }
+
+    fn get_value(&self, model: &Model) -> f32 {
+        self.to_normalized(model.midi_routes().map(|routes| routes.route(self.source).target.load(Ordering::Relaxed)).unwrap_or(0))
+    }
+
+    fn set_value(&self, model: &Model, val: f32) {
+        if let Some(routes) = model.midi_routes() {
+            routes.route(self.source).target.store(self.from_normalized(val), Ordering::Relaxed);
+        }
+    }
+
+    fn formatted(&self, model: &Model) -> String {
+        let choice = self.from_normalized(self.get_value(model));
+        self.step_names(model).swap_remove(choice)
+    }
+
+    fn parse(&self, model: &Model, text: &str) -> Option<f32> {
+        let choice = self.step_names(model).iter().position(|name| name.eq_ignore_ascii_case(text.trim()))?;
+        Some(self.to_normalized(choice))
+    }
+
+    fn randomizable(&self) -> bool {
+        false
+    }
+
+    fn description(&self) -> &str {
+        match self.source {
+            MidiSource::PitchBend => "The parameter the pitch bend wheel moves.",
+            MidiSource::ModWheel => "The parameter the mod wheel moves.",
+        }
+    }
+
+    fn steps(&self) -> usize {
+        self.targets.len() + 1
+    }
+
+    fn step_names(&self, _model: &Model) -> Vec<String> {
+        std::iter::once("None".to_string()).chain(self.targets.iter().cloned()).collect()
+    }
+}
+
+/// Follows pitch bend and the mod wheel on any channel, adding each, scaled by its depth,
+/// to its target's offset in a [`Modulation`] once per block. The processor owns one and
+/// calls `process` at the start of each block, alongside its other modulators.
+#[derive(Debug, Clone)]
+pub struct MidiRouter {
+    decoder: ControllerDecoder,
+    bend: f32,
+    wheel: f32,
+}
+
+impl Default for MidiRouter {
+    fn default() -> Self {
+        MidiRouter { decoder: ControllerDecoder::new(), bend: 0., wheel: 0. }
+    }
+}
+
+impl MidiRouter {
+    /// Where the pitch bend wheel is, -1 to 1.
+    pub fn bend(&self) -> f32 {
+        self.bend
+    }
+
+    /// Where the mod wheel is, 0 to 1.
+    pub fn wheel(&self) -> f32 {
+        self.wheel
+    }
+
+    /// Follow this block's MIDI, then add to the targets where the wheels ended up.
+    pub fn process(&mut self, routes: &MidiRoutes, modulation: &Modulation, events: &[TimedMidi]) {
+        for timed in events {
+            match timed.message {
+                MidiMessage::PitchBend { value, .. } => self.bend = value.clamp(-1., 1.),
+                message => {
+                    if let Some(event) = self.decoder.decode(message).filter(|event| event.controller == Controller::Cc(MOD_WHEEL)) {
+                        self.wheel = event.value;
+                    }
+                }
+            }
+        }
+        for source in MidiSource::ALL.iter() {
+            let route = routes.route(*source);
+            if let Some(target) = route.target() {
+                let value = match source {
+                    MidiSource::PitchBend => self.bend,
+                    MidiSource::ModWheel => self.wheel,
+                };
+                modulation.add(target, value * route.depth(), route.depth().abs());
+            }
+        }
+    }
+
+    /// Back to rest: bend centred, mod wheel down.
+    pub fn reset(&mut self) {
+        self.decoder = ControllerDecoder::new();
+        self.bend = 0.;
+        self.wheel = 0.;
+    }
+}
@@ -0,0 +1,104 @@
+//! Modulation: offsets modulators such as the built in [`LfoModulator`](crate::lfo::LfoModulator)
+//! add to parameters, kept apart from the values the host and editor set so modulating a
+//! parameter never overwrites or automates it.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use vst::util::AtomicFloat;
+
+use crate::telemetry::{Telemetry, TelemetryBus};
+
+/// A parameter's modulation as an editor shows it, in normalized units.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ModulationAmount {
+    /// The offset now.
+    pub offset: f32,
+    /// How far the offset swings either side of the parameter's value.
+    pub range: f32,
+}
+
+impl ModulationAmount {
+    pub fn is_active(&self) -> bool {
+        self.range != 0.
+    }
+}
+
+/// An offset per parameter, by index, in normalized units. Modulators write them on the
+/// audio thread; processors add them to the values they read with [`Modulation::apply`].
+/// Only the first `Modulation::CAPACITY` parameters can be modulated.
+///
+/// Modulators also record the range of each offset, so editors can read both without
+/// locking to show what the modulation is doing.
+pub struct Modulation {
+    offsets: Vec<AtomicFloat>,
+    ranges: Vec<AtomicFloat>,
+    // which parameters were modulated when last sent as telemetry
+    reported: Vec<AtomicBool>,
+}
+
+impl Default for Modulation {
+    fn default() -> Self {
+        let zeros = || (0..Modulation::CAPACITY).map(|_| AtomicFloat::new(0.)).collect();
+        let reported = (0..Modulation::CAPACITY).map(|_| AtomicBool::new(false)).collect();
+        Modulation { offsets: zeros(), ranges: zeros(), reported }
+    }
+}
+
+impl Modulation {
+    pub const CAPACITY: usize = 256;
+
+    pub fn new() -> Self {
+        Modulation::default()
+    }
+
+    pub fn offset(&self, index: usize) -> f32 {
+        self.offsets.get(index).map(|offset| offset.get()).unwrap_or(0.)
+    }
+
+    pub fn set_offset(&self, index: usize, offset: f32) {
+        if let Some(atomic) = self.offsets.get(index) {
+            atomic.set(offset);
+        }
+    }
+
+    pub fn set_range(&self, index: usize, range: f32) {
+        if let Some(atomic) = self.ranges.get(index) {
+            atomic.set(range.abs());
+        }
+    }
+
+    /// Add one modulator's offset and range to those already written this block, for
+    /// parameters more than one modulator targets.
+    pub fn add(&self, index: usize, offset: f32, range: f32) {
+        let amount = self.amount(index);
+        self.set_offset(index, amount.offset + offset);
+        self.set_range(index, amount.range + range.abs());
+    }
+
+    pub fn amount(&self, index: usize) -> ModulationAmount {
+        ModulationAmount { offset: self.offset(index), range: self.ranges.get(index).map(|range| range.get()).unwrap_or(0.) }
+    }
+
+    /// `value`, the parameter's normalized value, with its offset added and kept in range.
+    pub fn apply(&self, index: usize, value: f32) -> f32 {
+        (value + self.offset(index)).clamp(0., 1.)
+    }
+
+    /// Send the amounts of the first `count` parameters which are modulated, or have just
+    /// stopped being, to the editor. Call once a block, after the modulators have run.
+    pub fn send_telemetry(&self, telemetry: &TelemetryBus, count: usize) {
+        for (index, reported) in self.reported.iter().enumerate().take(count) {
+            let amount = self.amount(index);
+            if reported.swap(amount.is_active(), Ordering::Relaxed) || amount.is_active() {
+                telemetry.send(Telemetry::Modulation { index, amount });
+            }
+        }
+    }
+
+    /// Modulators add to the offsets, so processors call this at the start of each block.
+    pub fn clear(&self) {
+        for atomic in self.offsets.iter().chain(&self.ranges) {
+            atomic.set(0.);
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! MIDI Polyphonic Expression: per-note pitch bend, pressure and timbre, carried by giving
+//! each sounding note a channel of its own.
+
+use crate::events::{MidiMessage, NoteEvent};
+
+// controller numbers
+const TIMBRE: u8 = 74;
+const RPN_LSB: u8 = 100;
+const RPN_MSB: u8 = 101;
+const DATA_ENTRY: u8 = 6;
+// registered parameter numbers
+const RPN_PITCH_BEND_RANGE: u16 = 0;
+const RPN_MPE_CONFIGURATION: u16 = 6;
+const RPN_NONE: u16 = 0x3FFF;
+
+const DEFAULT_MEMBER_BEND_RANGE: f32 = 48.;
+const DEFAULT_MASTER_BEND_RANGE: f32 = 2.;
+
+/// The two zones a 16 channel port can be split into. The lower zone's master channel is
+/// the first channel and its member channels count up from the second; the upper zone's
+/// master is the last channel, with members counting down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Lower,
+    Upper,
+}
+
+impl Zone {
+    pub fn master_channel(self) -> u8 {
+        match self {
+            Zone::Lower => 0,
+            Zone::Upper => 15,
+        }
+    }
+}
+
+/// A zone in use, with how many member channels it has.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoneConfig {
+    pub members: u8,
+    /// Semitones for a full pitch bend on a member channel.
+    pub member_bend_range: f32,
+    /// Semitones for a full pitch bend on the master channel.
+    pub master_bend_range: f32,
+}
+
+impl ZoneConfig {
+    pub fn new(members: u8) -> Self {
+        ZoneConfig {
+            members: members.min(15),
+            member_bend_range: DEFAULT_MEMBER_BEND_RANGE,
+            master_bend_range: DEFAULT_MASTER_BEND_RANGE,
+        }
+    }
+}
+
+/// The controllers MPE gives each note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expression {
+    /// In semitones.
+    Pitch,
+    /// `0.0..=1.0`, from channel pressure.
+    Pressure,
+    /// `0.0..=1.0`, from CC 74.
+    Timbre,
+}
+
+/// An event after MPE's channel mapping rules have been applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MpeEvent {
+    /// A note on a member channel. Voices should be told apart by channel and note together.
+    Note { channel: u8, event: NoteEvent },
+    /// A controller for the note sounding on a member channel.
+    NoteExpression { channel: u8, note: Option<u8>, expression: Expression, value: f32 },
+    /// A controller on a master channel, applying to every note in the zone on top of
+    /// their own expression.
+    ZoneExpression { zone: Zone, expression: Expression, value: f32 },
+    /// Messages MPE doesn't change the meaning of, and everything outside the zones.
+    Midi(MidiMessage),
+}
+
+/// Turns channel messages into per-note events, following MPE's rules for which channel
+/// belongs to which zone. Zones are set up by the sender's MPE configuration messages,
+/// or in advance with [`MpeTranslator::with_zone`]. Keeps no heap state, so it can be
+/// used on the audio thread.
+#[derive(Debug, Clone)]
+pub struct MpeTranslator {
+    lower: Option<ZoneConfig>,
+    upper: Option<ZoneConfig>,
+    // the note last started on each channel, while it is held
+    notes: [Option<u8>; 16],
+    // registered parameter selected on each channel, for data entry
+    rpn: [u16; 16],
+}
+
+impl Default for MpeTranslator {
+    fn default() -> Self {
+        MpeTranslator { lower: None, upper: None, notes: [None; 16], rpn: [RPN_NONE; 16] }
+    }
+}
+
+impl MpeTranslator {
+    pub fn new() -> Self {
+        MpeTranslator::default()
+    }
+
+    /// Builder-style method to configure a zone without waiting for the sender to.
+    pub fn with_zone(mut self, zone: Zone, config: ZoneConfig) -> Self {
+        self.set_zone(zone, Some(config));
+        self
+    }
+
+    pub fn zone(&self, zone: Zone) -> Option<ZoneConfig> {
+        match zone {
+            Zone::Lower => self.lower,
+            Zone::Upper => self.upper,
+        }
+    }
+
+    /// Set up or remove a zone. The other zone gives up any channels the new one takes,
+    /// as the MPE specification requires.
+    pub fn set_zone(&mut self, zone: Zone, config: Option<ZoneConfig>) {
+        let config = config.filter(|c| c.members > 0);
+        let taken = config.map(|c| c.members as i32 + 1).unwrap_or(0);
+        let (this, other) = match zone {
+            Zone::Lower => (&mut self.lower, &mut self.upper),
+            Zone::Upper => (&mut self.upper, &mut self.lower),
+        };
+        *this = config;
+        if let Some(other_config) = other {
+            let room = 16 - taken - 1;
+            if room <= 0 {
+                *other = None;
+            } else {
+                other_config.members = other_config.members.min(room as u8);
+            }
+        }
+    }
+
+    /// The zone `channel` is a member of, if any.
+    pub fn member_zone(&self, channel: u8) -> Option<Zone> {
+        match (self.lower, self.upper) {
+            (Some(lower), _) if channel >= 1 && channel <= lower.members => Some(Zone::Lower),
+            (_, Some(upper)) if channel <= 14 && channel >= 15 - upper.members => Some(Zone::Upper),
+            _ => None,
+        }
+    }
+
+    // the zone `channel` is the master channel of, if it is in use
+    fn master_zone(&self, channel: u8) -> Option<Zone> {
+        [Zone::Lower, Zone::Upper].iter().copied()
+            .find(|zone| zone.master_channel() == channel && self.zone(*zone).is_some())
+    }
+
+    pub fn translate(&mut self, message: MidiMessage) -> MpeEvent {
+        if let MidiMessage::ControlChange { channel, controller, value } = message {
+            if let Some(event) = self.registered_parameter(channel, controller, value) {
+                return event;
+            }
+        }
+        let channel = match message.channel() {
+            Some(channel) => channel,
+            None => return MpeEvent::Midi(message),
+        };
+        if let Some(zone) = self.member_zone(channel) {
+            let note = self.notes[channel as usize];
+            let expression = |expression, value| MpeEvent::NoteExpression { channel, note, expression, value };
+            match message {
+                MidiMessage::Note { event, .. } => {
+                    self.notes[channel as usize] = match event {
+                        NoteEvent::On { note, .. } => Some(note),
+                        NoteEvent::Off { note: off } if note == Some(off) => None,
+                        NoteEvent::Off { .. } => note,
+                    };
+                    MpeEvent::Note { channel, event }
+                }
+                MidiMessage::PitchBend { value, .. } => {
+                    let range = self.zone(zone).map(|z| z.member_bend_range).unwrap_or(DEFAULT_MEMBER_BEND_RANGE);
+                    expression(Expression::Pitch, value * range)
+                }
+                MidiMessage::ChannelPressure { pressure, .. } => expression(Expression::Pressure, pressure as f32 / 127.),
+                MidiMessage::ControlChange { controller: TIMBRE, value, .. } => expression(Expression::Timbre, value as f32 / 127.),
+                other => MpeEvent::Midi(other),
+            }
+        } else if let Some(zone) = self.master_zone(channel) {
+            let expression = |expression, value| MpeEvent::ZoneExpression { zone, expression, value };
+            match message {
+                MidiMessage::PitchBend { value, .. } => {
+                    let range = self.zone(zone).map(|z| z.master_bend_range).unwrap_or(DEFAULT_MASTER_BEND_RANGE);
+                    expression(Expression::Pitch, value * range)
+                }
+                MidiMessage::ChannelPressure { pressure, .. } => expression(Expression::Pressure, pressure as f32 / 127.),
+                MidiMessage::ControlChange { controller: TIMBRE, value, .. } => expression(Expression::Timbre, value as f32 / 127.),
+                other => MpeEvent::Midi(other),
+            }
+        } else {
+            MpeEvent::Midi(message)
+        }
+    }
+
+    // RPN selection and data entry; zone configuration and bend ranges are consumed here
+    fn registered_parameter(&mut self, channel: u8, controller: u8, value: u8) -> Option<MpeEvent> {
+        let rpn = &mut self.rpn[channel as usize];
+        match controller {
+            RPN_MSB => *rpn = (*rpn & 0x7F) | (value as u16) << 7,
+            RPN_LSB => *rpn = (*rpn & !0x7F) | value as u16,
+            DATA_ENTRY => match *rpn {
+                RPN_MPE_CONFIGURATION => {
+                    let zone = match channel {
+                        0 => Zone::Lower,
+                        15 => Zone::Upper,
+                        _ => return None,
+                    };
+                    self.set_zone(zone, Some(ZoneConfig::new(value)));
+                    self.notes = [None; 16];
+                }
+                RPN_PITCH_BEND_RANGE => {
+                    let semitones = value as f32;
+                    if let Some(zone) = self.member_zone(channel) {
+                        self.update_zone(zone, |config| config.member_bend_range = semitones);
+                    } else if let Some(zone) = self.master_zone(channel) {
+                        self.update_zone(zone, |config| config.master_bend_range = semitones);
+                    } else {
+                        return None;
+                    }
+                }
+                _ => return None,
+            },
+            _ => return None,
+        }
+        Some(MpeEvent::Midi(MidiMessage::ControlChange { channel, controller, value }))
+    }
+
+    fn update_zone(&mut self, zone: Zone, f: impl FnOnce(&mut ZoneConfig)) {
+        let config = match zone {
+            Zone::Lower => &mut self.lower,
+            Zone::Upper => &mut self.upper,
+        };
+        if let Some(config) = config {
+            f(config);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Hosts know parameters only by index, and save automation and sessions that way, so a
+//! released plugin can't reorder or remove parameters without breaking them. Bridges
+//! instead give hosts indices from a table of ids, published once and only appended to,
+//! so the processor's own parameter list can change underneath.
+
+use crate::carnyx::{CarnyxModel, CarnyxParam};
+
+/// Maps the parameter indices hosts use to positions in a processor's parameter list, and
+/// back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamIdTable {
+    // by host index; None for published parameters which have since been removed
+    params: Vec<Option<usize>>,
+    // by parameter position
+    host_indices: Vec<usize>,
+}
+
+impl ParamIdTable {
+    /// Host indices the same as parameter positions.
+    pub fn identity(len: usize) -> Self {
+        ParamIdTable { params: (0..len).map(Some).collect(), host_indices: (0..len).collect() }
+    }
+
+    /// Host indices in the order of the `published` ids, then any parameters without a
+    /// published id in list order. Published ids no parameter has any more keep their
+    /// index, which does nothing.
+    pub fn new<Model: CarnyxModel>(params: &[Box<dyn CarnyxParam<Model>>], published: &[&str]) -> Self {
+        let mut by_host: Vec<Option<usize>> = Vec::with_capacity(published.len().max(params.len()));
+        for id in published {
+            let position = params.iter().position(|p| !p.id().is_empty() && p.id() == *id);
+            // a repeated id gets a slot, but only the first maps to the parameter
+            by_host.push(position.filter(|position| !by_host.contains(&Some(*position))));
+        }
+        for position in 0..params.len() {
+            if !by_host.contains(&Some(position)) {
+                by_host.push(Some(position));
+            }
+        }
+        let mut host_indices = vec![0; params.len()];
+        for (host_index, position) in by_host.iter().enumerate() {
+            if let Some(position) = position {
+                host_indices[*position] = host_index;
+            }
+        }
+        ParamIdTable { params: by_host, host_indices }
+    }
+
+    /// How many parameters hosts see.
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// The parameter at a host index, if there is one.
+    pub fn param(&self, host_index: usize) -> Option<usize> {
+        self.params.get(host_index).copied().flatten()
+    }
+
+    /// The host index of the parameter at `position`.
+    pub fn host_index(&self, position: usize) -> Option<usize> {
+        self.host_indices.get(position).copied()
+    }
+}
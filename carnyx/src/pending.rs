@@ -0,0 +1,88 @@
+//! Parameter changes made on the audio thread, held for listeners to hear about elsewhere.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::carnyx::{CarnyxModelListener, ChangeEvent, ChangeOrigin};
+
+/// Which parameters have changed since listeners were last told, as a bit each. Hosts may
+/// automate from the audio thread, where listeners can't be called: they lock and editors
+/// allocate to hear. So the change is marked here, which neither locks nor allocates, and
+/// passed on by [`notify`](PendingChanges::notify) from the editor's thread. A parameter
+/// changed many times between notifications is reported once.
+pub struct PendingChanges {
+    params: Vec<AtomicU64>,
+    // a parameter beyond the capacity changed, so report the whole model
+    model: AtomicBool,
+}
+
+impl PendingChanges {
+    pub fn new(capacity: usize) -> Self {
+        PendingChanges { params: (0..(capacity + 63) / 64).map(|_| AtomicU64::new(0)).collect(), model: AtomicBool::new(false) }
+    }
+
+    /// Safe to call from the audio thread.
+    pub fn mark(&self, index: usize) {
+        match self.params.get(index / 64) {
+            Some(word) => {
+                word.fetch_or(1 << (index % 64), Ordering::Release);
+            }
+            None => self.model.store(true, Ordering::Release),
+        }
+    }
+
+    /// Whether anything is waiting; another thread may mark straight after this returns.
+    pub fn is_empty(&self) -> bool {
+        !self.model.load(Ordering::Acquire) && self.params.iter().all(|word| word.load(Ordering::Acquire) == 0)
+    }
+
+    /// Tell `listener` about everything marked since the last call, as changes from `origin`.
+    /// Not for the audio thread.
+    pub fn notify<Model>(&self, listener: &dyn CarnyxModelListener<Model>, model: &Model, origin: ChangeOrigin) {
+        let mut whole = self.model.swap(false, Ordering::AcqRel);
+        let mut changed = Vec::new();
+        for (at, word) in self.params.iter().enumerate() {
+            let mut bits = word.swap(0, Ordering::AcqRel);
+            while bits != 0 {
+                changed.push(at * 64 + bits.trailing_zeros() as usize);
+                bits &= bits - 1;
+            }
+        }
+        // one refresh says as much as a long run of single parameters
+        whole |= changed.len() > 64;
+        if whole {
+            listener.notify_change(model, ChangeEvent::model(origin));
+            return;
+        }
+        for index in changed {
+            listener.notify_change(model, ChangeEvent::param(index, origin));
+        }
+    }
+}
+
+/// Lets at most one refresh be on its way to a listener on another thread, e.g. an editor
+/// told through its event loop. Whoever [claims](RefreshGate::claim) the gate sends the
+/// refresh; the receiver [releases](RefreshGate::release) it before reading the model, so a
+/// change made while it reads sends another.
+#[derive(Debug, Default)]
+pub struct RefreshGate {
+    pending: AtomicBool,
+}
+
+impl RefreshGate {
+    pub fn new() -> Self {
+        RefreshGate::default()
+    }
+
+    /// True if no refresh is on its way, in which case the caller should send one.
+    pub fn claim(&self) -> bool {
+        !self.pending.swap(true, Ordering::AcqRel)
+    }
+
+    pub fn release(&self) {
+        self.pending.store(false, Ordering::Release);
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.load(Ordering::Acquire)
+    }
+}
@@ -0,0 +1,205 @@
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::carnyx::{CarnyxModel, CarnyxParam};
+use crate::events::MidiMessage;
+use crate::random::Rng;
+use crate::seqlock::write_params;
+
+const BANK_SELECT_MSB: u8 = 0;
+const BANK_SELECT_LSB: u8 = 32;
+const PROGRAMS_PER_BANK: usize = 128;
+// no program change waiting
+const NO_REQUEST: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbSlot {
+    A,
+    B,
+}
+
+impl AbSlot {
+    pub fn other(self) -> AbSlot {
+        match self {
+            AbSlot::A => AbSlot::B,
+            AbSlot::B => AbSlot::A,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AbSlot::A => "A",
+            AbSlot::B => "B",
+        }
+    }
+}
+
+/// Two complete snapshots for A/B comparison. The live model always holds the active slot
+/// (so host automation and saved state see it); this only keeps the inactive one.
+#[derive(Debug, Clone)]
+pub struct AbCompare<Snap> {
+    active: AbSlot,
+    inactive: Snap,
+}
+
+impl<Snap: Clone> AbCompare<Snap> {
+    pub fn new(initial: Snap) -> Self {
+        AbCompare {
+            active: AbSlot::A,
+            inactive: initial,
+        }
+    }
+
+    pub fn active(&self) -> AbSlot {
+        self.active
+    }
+
+    /// Switch slots, swapping `current` (the live snapshot) with the stored one.
+    pub fn toggle(&mut self, current: &mut Snap) {
+        mem::swap(current, &mut self.inactive);
+        self.active = self.active.other();
+    }
+
+    /// Copy slot A over slot B, whichever of them is currently live.
+    pub fn copy_a_to_b(&mut self, current: &mut Snap) {
+        match self.active {
+            AbSlot::A => self.inactive = current.clone(),
+            AbSlot::B => *current = self.inactive.clone(),
+        }
+    }
+}
+
+pub struct Preset<Snap> {
+    pub name: String,
+    pub snap: Snap,
+}
+
+impl<Snap> Preset<Snap> {
+    pub fn new(name: impl Into<String>, snap: Snap) -> Self {
+        Preset { name: name.into(), snap }
+    }
+}
+
+/// A fixed list of presets and which one was last selected, shared between the host
+/// bridge and the audio thread. MIDI bank select (CC 0 and 32) picks which block of 128
+/// presets a program change indexes into. Loading a preset writes the whole model, which
+/// the audio thread mustn't wait on, so program changes are only requested there and
+/// applied by [`apply_requested`](PresetBank::apply_requested) from another thread.
+pub struct PresetBank<Snap> {
+    presets: Vec<Preset<Snap>>,
+    current: AtomicUsize,
+    bank_msb: AtomicUsize,
+    bank_lsb: AtomicUsize,
+    requested: AtomicUsize,
+}
+
+impl<Snap> PresetBank<Snap> {
+    pub fn new(presets: Vec<Preset<Snap>>) -> Self {
+        PresetBank {
+            presets,
+            current: AtomicUsize::new(0),
+            bank_msb: AtomicUsize::new(0),
+            bank_lsb: AtomicUsize::new(0),
+            requested: AtomicUsize::new(NO_REQUEST),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.presets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Preset<Snap>> {
+        self.presets.get(index)
+    }
+
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    /// Load a preset into the model. Returns false if there is no such preset.
+    pub fn select<Model: CarnyxModel<Snap = Snap>>(&self, index: usize, model: &Model) -> bool {
+        match self.presets.get(index) {
+            Some(preset) => {
+                model.set_snap(&preset.snap);
+                if let Some(crossfade) = model.preset_crossfade() {
+                    crossfade.request();
+                }
+                self.current.store(index, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The bank most recently chosen by MIDI bank select.
+    pub fn bank(&self) -> u16 {
+        (self.bank_msb.load(Ordering::Relaxed) * 128 + self.bank_lsb.load(Ordering::Relaxed)) as u16
+    }
+
+    /// Remember bank select controller changes; other messages are ignored.
+    pub fn track_bank_select(&self, message: &MidiMessage) {
+        match *message {
+            MidiMessage::ControlChange { controller: BANK_SELECT_MSB, value, .. } => {
+                self.bank_msb.store(value as usize, Ordering::Relaxed)
+            }
+            MidiMessage::ControlChange { controller: BANK_SELECT_LSB, value, .. } => {
+                self.bank_lsb.store(value as usize, Ordering::Relaxed)
+            }
+            _ => (),
+        }
+    }
+
+    /// Select `program` in the current bank.
+    pub fn select_program<Model: CarnyxModel<Snap = Snap>>(&self, program: u8, model: &Model) -> bool {
+        self.select(self.bank() as usize * PROGRAMS_PER_BANK + program as usize, model)
+    }
+
+    /// Ask for `program` in the current bank to be selected; the last request before
+    /// [`apply_requested`](PresetBank::apply_requested) wins. Returns false if there is no
+    /// such preset. Safe to call from the audio thread.
+    pub fn request_program(&self, program: u8) -> bool {
+        let index = self.bank() as usize * PROGRAMS_PER_BANK + program as usize;
+        if index >= self.presets.len() {
+            return false;
+        }
+        self.requested.store(index, Ordering::Release);
+        true
+    }
+
+    /// Whether a program change is waiting to be applied.
+    pub fn has_request(&self) -> bool {
+        self.requested.load(Ordering::Acquire) != NO_REQUEST
+    }
+
+    /// Load the preset last asked for with [`request_program`](PresetBank::request_program),
+    /// if any. Returns whether one was loaded. Not for the audio thread.
+    pub fn apply_requested<Model: CarnyxModel<Snap = Snap>>(&self, model: &Model) -> bool {
+        match self.requested.swap(NO_REQUEST, Ordering::AcqRel) {
+            NO_REQUEST => false,
+            index => self.select(index, model),
+        }
+    }
+}
+
+/// Set every randomizable parameter to a uniformly random value.
+pub fn randomize<Model: CarnyxModel>(params: &[Box<dyn CarnyxParam<Model>>], model: &Model, rng: &mut Rng) {
+    write_params(model, || {
+        for param in params.iter().filter(|p| p.randomizable() && !p.is_read_only()) {
+            param.set_value(model, rng.next_f32());
+        }
+    })
+}
+
+/// Nudge every randomizable parameter by up to `amount` (in normalized units) either way.
+pub fn mutate<Model: CarnyxModel>(params: &[Box<dyn CarnyxParam<Model>>], model: &Model, amount: f32, rng: &mut Rng) {
+    write_params(model, || {
+        for param in params.iter().filter(|p| p.randomizable() && !p.is_read_only()) {
+            let value = param.get_value(model) + rng.next_bipolar() * amount;
+            param.set_value(model, value.clamp(0., 1.));
+        }
+    })
+}
@@ -0,0 +1,69 @@
+//! Probes: named points in a processor's signal chain which can be soloed to the output,
+//! to hear which stage of it misbehaves.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A processor's probe points, and which of them is soloed. A model opts in by returning
+/// these from [`CarnyxModel::probes`](crate::CarnyxModel::probes); editors offer the
+/// names, and the processor reads the choice into a [`ProbeTap`] each block.
+pub struct Probes {
+    names: &'static [&'static str],
+    // the soloed probe plus one, or zero for none
+    soloed: AtomicUsize,
+}
+
+impl Probes {
+    pub fn new(names: &'static [&'static str]) -> Self {
+        Probes { names, soloed: AtomicUsize::new(0) }
+    }
+
+    pub fn names(&self) -> &'static [&'static str] {
+        self.names
+    }
+
+    pub fn soloed(&self) -> Option<usize> {
+        self.soloed.load(Ordering::Relaxed).checked_sub(1)
+    }
+
+    /// Solo a probe, by index into the names, or `None` to hear the output as usual.
+    pub fn solo(&self, probe: Option<usize>) {
+        let soloed = probe.filter(|probe| *probe < self.names.len()).map(|probe| probe + 1).unwrap_or(0);
+        self.soloed.store(soloed, Ordering::Relaxed);
+    }
+}
+
+/// The audio thread's side of [`Probes`]: the processor passes its signal through
+/// [`probe`](ProbeTap::probe) at each point, which only keeps the soloed one, and then
+/// through [`output`](ProbeTap::output) to swap it in.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProbeTap {
+    soloed: Option<usize>,
+    value: f32,
+}
+
+impl ProbeTap {
+    /// Call at the start of each block.
+    pub fn update(&mut self, probes: &Probes) {
+        self.soloed = probes.soloed();
+    }
+
+    pub fn is_soloing(&self) -> bool {
+        self.soloed.is_some()
+    }
+
+    /// Record the signal at a probe point.
+    pub fn probe(&mut self, probe: usize, value: f32) {
+        if self.soloed == Some(probe) {
+            self.value = value;
+        }
+    }
+
+    /// The soloed probe's latest value while soloing, otherwise `output`.
+    pub fn output(&self, output: f32) -> f32 {
+        if self.is_soloing() {
+            self.value
+        } else {
+            output
+        }
+    }
+}
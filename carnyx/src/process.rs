@@ -0,0 +1,212 @@
+//! Per-block information handed to [`CarnyxProcessor::process`](crate::CarnyxProcessor::process).
+
+use std::ops::Range;
+
+use crate::buffer::{AudioBuffer, Scratch};
+use crate::events::{MidiMessage, TimedMidi};
+
+// below about -140 dBFS
+const SILENCE_THRESHOLD: f32 = 1e-7;
+
+/// One bit per channel, set if that channel is silent for the whole block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SilenceFlags(pub u64);
+
+impl SilenceFlags {
+    pub const NONE: SilenceFlags = SilenceFlags(0);
+
+    /// Scan the inputs of a buffer. Channels past 64 are never considered silent.
+    pub fn detect_inputs(buffer: &mut AudioBuffer<f32>) -> SilenceFlags {
+        let (inputs, _) = buffer.split();
+        let mut flags = 0u64;
+        for (channel, samples) in inputs.into_iter().enumerate().take(64) {
+            if samples.iter().all(|s| s.abs() < SILENCE_THRESHOLD) {
+                flags |= 1 << channel;
+            }
+        }
+        SilenceFlags(flags)
+    }
+
+    pub fn is_silent(&self, channel: usize) -> bool {
+        channel < 64 && self.0 & (1 << channel) != 0
+    }
+
+    /// Whether the first `channels` channels are all silent.
+    pub fn all_silent(&self, channels: usize) -> bool {
+        (0..channels).all(|channel| self.is_silent(channel))
+    }
+}
+
+/// What the host's transport was doing at the start of the block.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Transport {
+    pub playing: bool,
+    /// Beats per minute, if the host supplied it.
+    pub tempo: Option<f64>,
+    /// Position in quarter notes, if the host supplied it.
+    pub position_beats: Option<f64>,
+    /// Position in samples since the start of the timeline.
+    pub position_samples: f64,
+}
+
+/// Everything a processor needs to know about the block it is processing, other than the
+/// audio itself. Block sizes vary from call to call, so processors should not assume
+/// `block_size` stays fixed.
+pub struct ProcessContext<'a> {
+    pub sample_rate: f32,
+    pub block_size: usize,
+    /// `None` if the host doesn't report its transport.
+    pub transport: Option<Transport>,
+    /// MIDI that arrived with this block, ordered by offset. Use a [`BlockSplitter`] to
+    /// apply events at the sample they belong at.
+    pub events: &'a [TimedMidi],
+    /// Which input channels are silent for this block.
+    pub input_silence: SilenceFlags,
+    /// Temporary buffers as requested by the processor's scratch spec.
+    pub scratch: Scratch<'a>,
+    /// Where to put MIDI the processor sends. Only connected for processors whose
+    /// capabilities include `sends_midi`.
+    pub midi_out: MidiOutput<'a>,
+}
+
+impl<'a> ProcessContext<'a> {
+    /// A context with no transport and no events, for hosts and tools which only have audio.
+    pub fn new(sample_rate: f32, block_size: usize) -> Self {
+        ProcessContext {
+            sample_rate,
+            block_size,
+            transport: None,
+            events: &[],
+            input_silence: SilenceFlags::NONE,
+            scratch: Scratch::empty(),
+            midi_out: MidiOutput::disconnected(),
+        }
+    }
+
+    pub fn with_transport(mut self, transport: Option<Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    pub fn with_events(mut self, events: &'a [TimedMidi]) -> Self {
+        self.events = events;
+        self
+    }
+
+    pub fn with_input_silence(mut self, input_silence: SilenceFlags) -> Self {
+        self.input_silence = input_silence;
+        self
+    }
+
+    pub fn with_scratch(mut self, scratch: Scratch<'a>) -> Self {
+        self.scratch = scratch;
+        self
+    }
+
+    pub fn with_midi_output(mut self, midi_out: MidiOutput<'a>) -> Self {
+        self.midi_out = midi_out;
+        self
+    }
+}
+
+/// Collects the MIDI a processor sends during a block, for the bridge to pass on to the
+/// host afterwards. Room is allocated before processing; messages beyond it are dropped.
+pub struct MidiOutput<'a> {
+    events: Option<&'a mut Vec<TimedMidi>>,
+    dropped: usize,
+}
+
+impl<'a> MidiOutput<'a> {
+    /// Collect into `events`, up to its capacity.
+    pub fn new(events: &'a mut Vec<TimedMidi>) -> Self {
+        MidiOutput { events: Some(events), dropped: 0 }
+    }
+
+    /// Drops everything sent, for hosts which can't receive MIDI.
+    pub fn disconnected() -> Self {
+        MidiOutput { events: None, dropped: 0 }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.events.is_some()
+    }
+
+    /// Send `message` at sample `offset` of the block. Returns false if it was dropped.
+    pub fn send(&mut self, offset: usize, message: MidiMessage) -> bool {
+        match &mut self.events {
+            Some(events) if events.len() < events.capacity() => {
+                // keep the events in order, as hosts expect them
+                let at = events.iter().rposition(|e| e.offset <= offset).map(|i| i + 1).unwrap_or(0);
+                events.insert(at, TimedMidi::new(offset, message));
+                true
+            }
+            _ => {
+                self.dropped += 1;
+                false
+            }
+        }
+    }
+
+    /// How many messages were dropped this block.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
+
+/// Part of a block between one event offset and the next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubBlock<'a> {
+    pub start: usize,
+    pub end: usize,
+    /// The events at `start`, to apply before processing the sub-block's samples.
+    pub events: &'a [TimedMidi],
+}
+
+impl<'a> SubBlock<'a> {
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+/// Splits a block at the offsets of its events, so each event takes effect at the
+/// sample it belongs at instead of at the start of the block:
+///
+/// ```ignore
+/// for sub_block in BlockSplitter::new(context.block_size, context.events) {
+///     for event in sub_block.events { /* apply it */ }
+///     for i in sub_block.range() { /* process sample i */ }
+/// }
+/// ```
+///
+/// Events past the end of the block are applied at its last sample.
+pub struct BlockSplitter<'a> {
+    events: &'a [TimedMidi],
+    block_size: usize,
+    start: usize,
+}
+
+impl<'a> BlockSplitter<'a> {
+    pub fn new(block_size: usize, events: &'a [TimedMidi]) -> Self {
+        BlockSplitter { events, block_size, start: 0 }
+    }
+
+    fn offset(&self, event: &TimedMidi) -> usize {
+        event.offset.min(self.block_size.saturating_sub(1))
+    }
+}
+
+impl<'a> Iterator for BlockSplitter<'a> {
+    type Item = SubBlock<'a>;
+
+    fn next(&mut self) -> Option<SubBlock<'a>> {
+        if self.start >= self.block_size {
+            return None;
+        }
+        let start = self.start;
+        let here = self.events.iter().take_while(|e| self.offset(e) <= start).count();
+        let (events, rest) = self.events.split_at(here);
+        self.events = rest;
+        self.start = rest.first().map(|e| self.offset(e)).unwrap_or(self.block_size);
+        Some(SubBlock { start, end: self.start, events })
+    }
+}
@@ -0,0 +1,37 @@
+/// Small xorshift generator: cheap, allocation free and deterministic for a given seed,
+/// which is all the audio thread (noise) and sound design helpers need.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        Rng { state: if seed == 0 { 0x9E37_79B9 } else { seed } }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Uniform in `0.0..1.0`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Uniform in `-1.0..1.0`.
+    pub fn next_bipolar(&mut self) -> f32 {
+        self.next_f32() * 2. - 1.
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::new(0)
+    }
+}
@@ -0,0 +1,88 @@
+//! Consistent reads of a model's parameters while another thread writes several of them,
+//! e.g. `set_snap` loading a preset while the audio thread starts a block.
+
+use std::hint;
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+
+use crate::carnyx::CarnyxModel;
+
+/// How many times [`SeqLock::read`] tries before taking what it got rather than spin on
+/// the audio thread, e.g. if the writer was descheduled mid write.
+pub const MAX_READ_ATTEMPTS: usize = 64;
+
+/// A sequence lock guarding a group of atomics. Writers make the count odd while they
+/// write; readers retry if it was odd or changed while they read. Readers never block.
+#[derive(Debug, Default)]
+pub struct SeqLock {
+    sequence: AtomicUsize,
+    // the thread writing, to catch nested writes which would spin forever
+    #[cfg(debug_assertions)]
+    writer: AtomicUsize,
+}
+
+// tells threads apart without allocating, by where their copy of a thread local lives
+#[cfg(debug_assertions)]
+fn this_thread() -> usize {
+    thread_local!(static MARKER: u8 = 0);
+    MARKER.with(|marker| marker as *const u8 as usize)
+}
+
+impl SeqLock {
+    pub fn new() -> Self {
+        SeqLock::default()
+    }
+
+    /// Run `write`, which stores to the guarded atomics. Writers wait for each other, so
+    /// this must not be nested.
+    pub fn write<R>(&self, write: impl FnOnce() -> R) -> R {
+        let mut sequence = self.sequence.load(Ordering::Relaxed);
+        loop {
+            if sequence & 1 == 0 {
+                match self.sequence.compare_exchange_weak(sequence, sequence + 1, Ordering::Acquire, Ordering::Relaxed) {
+                    Ok(_) => break,
+                    Err(current) => sequence = current,
+                }
+            } else {
+                #[cfg(debug_assertions)]
+                debug_assert!(self.writer.load(Ordering::Relaxed) != this_thread(), "SeqLock::write nested in a write to the same lock");
+                hint::spin_loop();
+                sequence = self.sequence.load(Ordering::Relaxed);
+            }
+        }
+        #[cfg(debug_assertions)]
+        self.writer.store(this_thread(), Ordering::Relaxed);
+        // the guarded stores can't move before the count goes odd
+        fence(Ordering::Release);
+        let result = write();
+        #[cfg(debug_assertions)]
+        self.writer.store(0, Ordering::Relaxed);
+        self.sequence.store(sequence + 2, Ordering::Release);
+        result
+    }
+
+    /// Run `read`, which loads the guarded atomics, until it sees no write in progress.
+    /// Realtime safe: gives up waiting after a few attempts and returns the last read.
+    pub fn read<R>(&self, mut read: impl FnMut() -> R) -> R {
+        let mut attempts = 0;
+        loop {
+            let before = self.sequence.load(Ordering::Acquire);
+            let result = read();
+            fence(Ordering::Acquire);
+            let after = self.sequence.load(Ordering::Relaxed);
+            attempts += 1;
+            if (before & 1 == 0 && before == after) || attempts == MAX_READ_ATTEMPTS {
+                return result;
+            }
+            hint::spin_loop();
+        }
+    }
+}
+
+/// Run `write` under the model's [`SeqLock`], if it has one, for changes to several
+/// parameters at once.
+pub fn write_params<Model: CarnyxModel + ?Sized, R>(model: &Model, write: impl FnOnce() -> R) -> R {
+    match model.seqlock() {
+        Some(seqlock) => seqlock.write(write),
+        None => write(),
+    }
+}
@@ -0,0 +1,156 @@
+//! Data shared between the instances of a plugin loaded into one host process, e.g. a
+//! preset cache or levels for cross-instance metering.
+//!
+//! Sharing is opt in: a processor resolves a [`Shared`] handle, keyed by its unique id,
+//! when it is made. Every instance asking with the same id and type gets the same value,
+//! which lives as long as any of them holds it. Resolving locks the registry, so it must
+//! not happen on the audio thread; the handle then reaches the value without locking, so
+//! using it there is realtime safe as long as the type itself is, e.g. built from atomics.
+//!
+//! ```ignore
+//! #[derive(Default)]
+//! struct Levels { peaks: [AtomicFloat; 8] }
+//!
+//! let levels = Shared::<Levels>::resolve(descriptor.unique_id);
+//! levels.peaks[0].set(peak);
+//! ```
+
+use std::any::{Any, TypeId};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+
+use crate::descriptor::CarnyxDescriptor;
+
+struct Entry {
+    unique_id: i32,
+    type_id: TypeId,
+    value: Weak<dyn Any + Send + Sync>,
+}
+
+static REGISTRY: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+/// A value shared by every instance of a plugin, looked up once and then reached without
+/// locking. Clones share the value.
+#[derive(Debug)]
+pub struct Shared<T> {
+    value: Arc<T>,
+}
+
+impl<T: Default + Send + Sync + 'static> Shared<T> {
+    /// The value shared by every instance of the plugin with `unique_id`, made with
+    /// `Default` by the first to ask. Locks the registry, so not for the audio thread.
+    pub fn resolve(unique_id: i32) -> Self {
+        Shared { value: lookup(unique_id) }
+    }
+}
+
+impl<T> Shared<T> {
+    /// Whether `a` and `b` are handles to the same value.
+    pub fn ptr_eq(a: &Shared<T>, b: &Shared<T>) -> bool {
+        Arc::ptr_eq(&a.value, &b.value)
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared { value: Arc::clone(&self.value) }
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+fn lookup<T: Default + Send + Sync + 'static>(unique_id: i32) -> Arc<T> {
+    crate::audit::lock_taken("shared registry lock");
+    let mut registry = REGISTRY.lock().unwrap_or_else(PoisonError::into_inner);
+    // values are dropped with their last instance, which leaves the entry behind
+    registry.retain(|entry| entry.value.strong_count() > 0);
+    let type_id = TypeId::of::<T>();
+    let existing = registry
+        .iter()
+        .filter(|entry| entry.unique_id == unique_id && entry.type_id == type_id)
+        .find_map(|entry| entry.value.upgrade());
+    if let Some(value) = existing.and_then(|value| value.downcast::<T>().ok()) {
+        return value;
+    }
+    let value = Arc::new(T::default());
+    let weak: Weak<T> = Arc::downgrade(&value);
+    registry.push(Entry { unique_id, type_id, value: weak });
+    value
+}
+
+/// Which instance numbers are in use, shared between the instances of a plugin.
+#[derive(Debug, Default)]
+pub struct InstanceSlots {
+    taken: AtomicU64,
+}
+
+impl InstanceSlots {
+    pub const CAPACITY: usize = 64;
+
+    /// How many instances are loaded.
+    pub fn count(&self) -> usize {
+        self.taken.load(Ordering::Relaxed).count_ones() as usize
+    }
+
+    // the lowest free slot, so numbers are reused as instances come and go
+    fn claim(&self) -> Option<usize> {
+        let mut slot = None;
+        let _ = self.taken.fetch_update(Ordering::AcqRel, Ordering::Acquire, |taken| {
+            let free = (!taken).trailing_zeros() as usize;
+            slot = if free < InstanceSlots::CAPACITY { Some(free) } else { None };
+            slot.map(|free| taken | 1 << free)
+        });
+        slot
+    }
+
+    fn release(&self, slot: usize) {
+        self.taken.fetch_and(!(1 << slot), Ordering::AcqRel);
+    }
+}
+
+/// One instance's registration, giving it a number among the instances of its plugin in
+/// this process, so editors can tell them apart. The number is freed when it is dropped.
+#[derive(Debug)]
+pub struct Instance {
+    name: &'static str,
+    slots: Shared<InstanceSlots>,
+    slot: usize,
+}
+
+impl Instance {
+    /// Register an instance of the plugin `descriptor` describes. Fails when there are
+    /// more than `InstanceSlots::CAPACITY` of them. Not for the audio thread.
+    pub fn register(descriptor: &CarnyxDescriptor) -> Option<Instance> {
+        let slots = Shared::<InstanceSlots>::resolve(descriptor.unique_id);
+        let slot = slots.claim()?;
+        Some(Instance { name: descriptor.name, slots, slot })
+    }
+
+    /// Counting from 1.
+    pub fn number(&self) -> usize {
+        self.slot + 1
+    }
+
+    /// How many instances of the plugin are loaded, including this one.
+    pub fn count(&self) -> usize {
+        self.slots.count()
+    }
+
+    /// The plugin name and instance number, e.g. "LadderFilter 2".
+    pub fn name(&self) -> String {
+        format!("{} {}", self.name, self.number())
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        self.slots.release(self.slot);
+    }
+}
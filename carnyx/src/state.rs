@@ -0,0 +1,165 @@
+//! Plugin state as hosts save it in sessions: every parameter's normalized value, by
+//! index, after a header recording the model's state version, then the parameters'
+//! [ids](CarnyxParam::id), then the instance's [tag](crate::InstanceTag) if it has one.
+//!
+//! Parameters with ids load by id, so can be reordered, added or removed freely. Those
+//! without load by index: added at the end of the list they keep their defaults. Models
+//! that reorder parameters without ids, or change what any parameter's values mean, bump
+//! [`CarnyxModel::state_version`] and correct older state in [`CarnyxModel::migrate`].
+
+use std::io::{self, ErrorKind};
+
+use crate::carnyx::{CarnyxModel, CarnyxParam};
+use crate::seqlock::write_params;
+
+const MAGIC: &[u8; 4] = b"CNXS";
+// magic, state version, value count
+const HEADER_LEN: usize = 12;
+// after the values; state from before ids stops before it
+const IDS_MAGIC: &[u8; 4] = b"CNXI";
+// after the ids
+const TAG_MAGIC: &[u8; 4] = b"CNXT";
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> io::Result<u32> {
+    bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(|| invalid("truncated state"))
+}
+
+/// State holding `values`, marked with `version`.
+pub fn encode(version: u32, values: &[f32]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + values.len() * 4);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&(values.len() as u32).to_le_bytes());
+    for value in values {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> io::Result<u16> {
+    bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]])).ok_or_else(|| invalid("truncated state"))
+}
+
+/// `encode`d state with an id for each value after it; empty ids for values without.
+pub fn encode_with_ids(version: u32, values: &[f32], ids: &[&str]) -> Vec<u8> {
+    let mut bytes = encode(version, values);
+    bytes.extend_from_slice(IDS_MAGIC);
+    for id in ids {
+        bytes.extend_from_slice(&(id.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(id.as_bytes());
+    }
+    bytes
+}
+
+// a string after its length
+fn string_at(bytes: &[u8], at: usize) -> io::Result<(String, usize)> {
+    let len = u16_at(bytes, at)? as usize;
+    let string = bytes.get(at + 2..at + 2 + len).ok_or_else(|| invalid("truncated state"))?;
+    let string = String::from_utf8(string.to_vec()).map_err(|_| invalid("string in state is not UTF-8"))?;
+    Ok((string, at + 2 + len))
+}
+
+// the ids, and where they end
+fn read_ids(bytes: &[u8]) -> io::Result<Option<(Vec<String>, usize)>> {
+    let count = u32_at(bytes, 8)? as usize;
+    let mut at = HEADER_LEN + count * 4;
+    if bytes.get(at..at + 4) != Some(IDS_MAGIC) {
+        return Ok(None);
+    }
+    at += 4;
+    let mut ids = Vec::with_capacity(count);
+    for _ in 0..count {
+        let (id, end) = string_at(bytes, at)?;
+        ids.push(id);
+        at = end;
+    }
+    Ok(Some((ids, at)))
+}
+
+/// The ids saved with the values in `decode`d state, if it has any.
+pub fn decode_ids(bytes: &[u8]) -> io::Result<Option<Vec<String>>> {
+    Ok(read_ids(bytes)?.map(|(ids, _)| ids))
+}
+
+/// Add an instance's nickname and color, as an index into [`TAG_COLORS`](crate::tag::TAG_COLORS),
+/// to state from `encode_with_ids`.
+pub fn encode_tag(bytes: &mut Vec<u8>, nickname: &str, color: Option<usize>) {
+    // cut at a character boundary, to fit the length
+    let mut len = nickname.len().min(u16::MAX as usize);
+    while !nickname.is_char_boundary(len) {
+        len -= 1;
+    }
+    bytes.extend_from_slice(TAG_MAGIC);
+    bytes.extend_from_slice(&(len as u16).to_le_bytes());
+    bytes.extend_from_slice(&nickname.as_bytes()[..len]);
+    bytes.extend_from_slice(&(color.map(|color| color + 1).unwrap_or(0) as u32).to_le_bytes());
+}
+
+/// The nickname and color saved in state, if it has them.
+pub fn decode_tag(bytes: &[u8]) -> io::Result<Option<(String, Option<usize>)>> {
+    let at = match read_ids(bytes)? {
+        Some((_, end)) if bytes.get(end..end + 4) == Some(TAG_MAGIC) => end + 4,
+        _ => return Ok(None),
+    };
+    let (nickname, at) = string_at(bytes, at)?;
+    let color = (u32_at(bytes, at)? as usize).checked_sub(1);
+    Ok(Some((nickname, color)))
+}
+
+/// The state version and parameter values in saved state.
+pub fn decode(bytes: &[u8]) -> io::Result<(u32, Vec<f32>)> {
+    if bytes.get(0..4) != Some(MAGIC) {
+        return Err(invalid("not carnyx state"));
+    }
+    let version = u32_at(bytes, 4)?;
+    let count = u32_at(bytes, 8)? as usize;
+    let values = (0..count)
+        .map(|i| u32_at(bytes, HEADER_LEN + i * 4).map(f32::from_bits))
+        .collect::<io::Result<Vec<f32>>>()?;
+    Ok((version, values))
+}
+
+pub fn save<Model: CarnyxModel>(params: &[Box<dyn CarnyxParam<Model>>], model: &Model) -> Vec<u8> {
+    let values: Vec<f32> = params.iter().map(|p| p.get_value(model)).collect();
+    let ids: Vec<&str> = params.iter().map(|p| p.id()).collect();
+    let mut bytes = encode_with_ids(model.state_version(), &values, &ids);
+    if let Some(tag) = model.instance_tag() {
+        encode_tag(&mut bytes, &tag.nickname(), tag.color());
+    }
+    bytes
+}
+
+/// Restore saved state. Values load by id, or by index for parameters without ids and
+/// state saved before them; any other parameters are reset to their defaults. State from
+/// older versions then goes through the model's `migrate`. A saved tag replaces the
+/// instance's; state without one leaves it alone.
+pub fn load<Model: CarnyxModel>(params: &[Box<dyn CarnyxParam<Model>>], model: &Model, bytes: &[u8]) -> io::Result<()> {
+    let (version, values) = decode(bytes)?;
+    if let (Some(tag), Some((nickname, color))) = (model.instance_tag(), decode_tag(bytes)?) {
+        tag.set_nickname(&nickname);
+        tag.set_color(color);
+    }
+    let ids = decode_ids(bytes)?;
+    let saved = |index: usize, param: &dyn CarnyxParam<Model>| match &ids {
+        Some(ids) if !param.id().is_empty() => ids.iter().position(|id| id == param.id()).and_then(|i| values.get(i)).copied(),
+        Some(ids) => ids.get(index).filter(|id| id.is_empty()).and(values.get(index)).copied(),
+        None => values.get(index).copied(),
+    };
+    write_params(model, || {
+        for (index, param) in params.iter().enumerate().filter(|(_, p)| !p.is_read_only()) {
+            let value = saved(index, &**param).filter(|v| !v.is_nan()).unwrap_or_else(|| param.default_value());
+            param.set_value(model, value.clamp(0., 1.));
+        }
+    });
+    // not in the write above, as set_snap takes the seqlock itself
+    if version < model.state_version() {
+        if let Some(snap) = model.migrate(version, bytes) {
+            model.set_snap(&snap);
+        }
+    }
+    Ok(())
+}
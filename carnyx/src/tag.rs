@@ -0,0 +1,56 @@
+//! A nickname and color the user gives an instance, to tell apart a dozen instances of the
+//! same plugin in a session. Saved with the plugin's state, unlike [`UiState`](crate::UiState).
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Colors an instance can be tagged with, as names and `0xRRGGBB`.
+pub const TAG_COLORS: [(&str, u32); 7] = [
+    ("Red", 0xe0_4a_4a),
+    ("Orange", 0xe8_8a_2c),
+    ("Yellow", 0xe0_c8_3a),
+    ("Green", 0x5a_b8_5a),
+    ("Blue", 0x4a_8c_e0),
+    ("Purple", 0x9a_6a_d8),
+    ("Grey", 0x9e_9e_9e),
+];
+
+/// A model opts in by returning one from [`CarnyxModel::instance_tag`](crate::CarnyxModel::instance_tag).
+/// Not for the audio thread.
+#[derive(Debug, Default)]
+pub struct InstanceTag {
+    nickname: Mutex<String>,
+    // index into TAG_COLORS plus one; zero for none
+    color: AtomicUsize,
+}
+
+impl InstanceTag {
+    /// Longer nicknames are cut short, to keep the editor's header and saved state tidy.
+    pub const MAX_NICKNAME: usize = 40;
+
+    pub fn new() -> Self {
+        InstanceTag::default()
+    }
+
+    pub fn nickname(&self) -> String {
+        crate::audit::lock_taken("nickname lock");
+        self.nickname.lock().map(|nickname| nickname.clone()).unwrap_or_default()
+    }
+
+    pub fn set_nickname(&self, nickname: &str) {
+        crate::audit::lock_taken("nickname lock");
+        if let Ok(mut current) = self.nickname.lock() {
+            *current = nickname.chars().take(InstanceTag::MAX_NICKNAME).collect();
+        }
+    }
+
+    /// The index of the color in [`TAG_COLORS`], if any.
+    pub fn color(&self) -> Option<usize> {
+        self.color.load(Ordering::Relaxed).checked_sub(1)
+    }
+
+    pub fn set_color(&self, color: Option<usize>) {
+        let color = color.filter(|color| *color < TAG_COLORS.len()).map(|color| color + 1).unwrap_or(0);
+        self.color.store(color, Ordering::Relaxed);
+    }
+}
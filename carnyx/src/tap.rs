@@ -0,0 +1,43 @@
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+/// A lock-free ring of the most recent samples written by the processor, for displays
+/// such as oscilloscopes. Writing never blocks; a reader racing the writer may see
+/// a few samples from the next lap of the ring, which is harmless for visualisation.
+pub struct SampleTap {
+    samples: Box<[AtomicU32]>,
+    mask: usize,
+    written: AtomicUsize,
+}
+
+impl SampleTap {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(2).next_power_of_two();
+        SampleTap {
+            samples: (0..capacity).map(|_| AtomicU32::new(0)).collect::<Vec<_>>().into_boxed_slice(),
+            mask: capacity - 1,
+            written: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn push(&self, sample: f32) {
+        let pos = self.written.load(Ordering::Relaxed);
+        self.samples[pos & self.mask].store(sample.to_bits(), Ordering::Relaxed);
+        self.written.store(pos.wrapping_add(1), Ordering::Release);
+    }
+
+    /// Fill `out` with the latest `out.len()` samples, oldest first. Returns how many were copied,
+    /// which is less than requested if the tap is smaller or has not been written enough yet.
+    pub fn copy_latest(&self, out: &mut [f32]) -> usize {
+        let end = self.written.load(Ordering::Acquire);
+        let count = out.len().min(self.capacity()).min(end);
+        let start = end.wrapping_sub(count);
+        for (i, sample) in out[..count].iter_mut().enumerate() {
+            *sample = f32::from_bits(self.samples[start.wrapping_add(i) & self.mask].load(Ordering::Relaxed));
+        }
+        count
+    }
+}
@@ -0,0 +1,90 @@
+//! Telemetry: small typed messages from the processor to its editor, for meters,
+//! analyzers and modulation displays.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::buffer::AudioBuffer;
+use crate::modulation::ModulationAmount;
+use crate::queue::EventQueue;
+
+/// Room for several frames' worth of messages from every block.
+pub const TELEMETRY_CAPACITY: usize = 4096;
+
+/// A message from the processor to its editor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Telemetry {
+    /// The peak level of an output channel over a block.
+    Peak { channel: usize, level: f32 },
+    /// A parameter's modulation, by index. Sent every block while it is modulated, and
+    /// once more when it stops.
+    Modulation { index: usize, amount: ModulationAmount },
+    /// Something only this processor and its editor understand.
+    Custom { tag: u32, value: f32 },
+}
+
+/// A bounded, realtime-safe channel from a processor to its editor. Sending never blocks
+/// or allocates; messages are dropped while no editor is listening, or if the editor
+/// falls behind.
+pub struct TelemetryBus {
+    queue: EventQueue<Telemetry>,
+    listening: AtomicBool,
+    dropped: AtomicUsize,
+}
+
+impl Default for TelemetryBus {
+    fn default() -> Self {
+        TelemetryBus::new(TELEMETRY_CAPACITY)
+    }
+}
+
+impl TelemetryBus {
+    pub fn new(capacity: usize) -> Self {
+        TelemetryBus { queue: EventQueue::new(capacity), listening: AtomicBool::new(false), dropped: AtomicUsize::new(0) }
+    }
+
+    /// Returns false if the message was dropped.
+    pub fn send(&self, message: Telemetry) -> bool {
+        if !self.is_listening() {
+            return false;
+        }
+        let sent = self.queue.push(message);
+        if !sent {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        sent
+    }
+
+    /// Whether an editor is draining the bus, so processors can skip working out messages
+    /// no one will read.
+    pub fn is_listening(&self) -> bool {
+        self.listening.load(Ordering::Acquire)
+    }
+
+    /// Called by editors as they open and close. Messages left from before are discarded.
+    pub fn set_listening(&self, listening: bool) {
+        self.listening.store(listening, Ordering::Release);
+        self.queue.drain().for_each(drop);
+    }
+
+    /// Send the peak level of each output channel, e.g. for a clip light. Skipped while no
+    /// editor is listening.
+    pub fn send_peaks(&self, buffer: &mut AudioBuffer<f32>) {
+        if !self.is_listening() {
+            return;
+        }
+        let (_, outputs) = buffer.split();
+        for (channel, output) in outputs.into_iter().enumerate() {
+            let level = output.iter().fold(0f32, |peak, sample| peak.max(sample.abs()));
+            self.send(Telemetry::Peak { channel, level });
+        }
+    }
+
+    pub fn drain(&self) -> impl Iterator<Item = Telemetry> + '_ {
+        self.queue.drain()
+    }
+
+    /// How many messages have been dropped because the editor fell behind.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,201 @@
+//! Running processors without a host or editor, for tests and command line tools.
+//!
+//! ```ignore
+//! let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.5)]);
+//! let output = run_block(&mut processor, &[impulse(512)]);
+//! ```
+
+use std::path::Path;
+use std::sync::Arc;
+
+use vst::host::HostBuffer;
+
+use crate::audit;
+use crate::buffer::ScratchBuffers;
+use crate::carnyx::{CarnyxHost, CarnyxProcessor};
+use crate::events::TimedMidi;
+use crate::process::{ProcessContext, SilenceFlags};
+use crate::random::Rng;
+use crate::wav::Wav;
+
+pub const TEST_SAMPLE_RATE: f32 = 44100.;
+
+/// A host which ignores everything it is told.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullCarnyxHost;
+
+impl CarnyxHost for NullCarnyxHost {
+    fn update_host_display(&self) {}
+}
+
+pub fn silence(len: usize) -> Vec<f32> {
+    vec![0.; len]
+}
+
+/// One full scale sample, then silence.
+pub fn impulse(len: usize) -> Vec<f32> {
+    let mut samples = silence(len);
+    if let Some(first) = samples.first_mut() {
+        *first = 1.;
+    }
+    samples
+}
+
+pub fn dc(value: f32, len: usize) -> Vec<f32> {
+    vec![value; len]
+}
+
+pub fn sine(frequency: f32, amplitude: f32, len: usize) -> Vec<f32> {
+    let step = 2. * std::f32::consts::PI * frequency / TEST_SAMPLE_RATE;
+    (0..len).map(|i| amplitude * (i as f32 * step).sin()).collect()
+}
+
+/// A sine sweeping exponentially from `from` to `to` Hz.
+pub fn sweep(from: f32, to: f32, amplitude: f32, len: usize) -> Vec<f32> {
+    let ratio = (to / from).ln();
+    let mut phase = 0f32;
+    (0..len)
+        .map(|i| {
+            let frequency = from * (ratio * i as f32 / len.max(1) as f32).exp();
+            phase = (phase + frequency / TEST_SAMPLE_RATE).fract();
+            amplitude * (2. * std::f32::consts::PI * phase).sin()
+        })
+        .collect()
+}
+
+/// Bursts of white noise `burst` samples long, with the same length of silence between.
+/// The same seed always gives the same noise.
+pub fn noise_bursts(seed: u32, amplitude: f32, burst: usize, len: usize) -> Vec<f32> {
+    let mut rng = Rng::new(seed);
+    (0..len)
+        .map(|i| if (i / burst.max(1)) % 2 == 0 { amplitude * rng.next_bipolar() } else { 0. })
+        .collect()
+}
+
+/// Set a parameter by the name it reports to the host. Returns false if there is none.
+pub fn set_parameter<P: CarnyxProcessor>(processor: &P, name: &str, value: f32) -> bool {
+    let model = processor.model();
+    match processor.all_parameters().iter().find(|p| p.name(&model) == name) {
+        Some(param) => {
+            param.set_value(&model, value);
+            true
+        }
+        None => false,
+    }
+}
+
+/// A processor made with a [`NullCarnyxHost`] and told [`TEST_SAMPLE_RATE`], with each named
+/// parameter set. Panics on a name it doesn't have.
+pub fn processor<P: CarnyxProcessor>(new: impl FnOnce(Arc<dyn CarnyxHost>) -> P, settings: &[(&str, f32)]) -> P {
+    let mut processor = new(Arc::new(NullCarnyxHost));
+    processor.set_sample_rate(TEST_SAMPLE_RATE);
+    for (name, value) in settings {
+        assert!(set_parameter(&processor, name, *value), "no parameter {}", name);
+    }
+    processor
+}
+
+/// Process one block at [`TEST_SAMPLE_RATE`], returning the outputs. The block is as
+/// long as the first input channel; inputs the processor has but `input` doesn't are
+/// silent.
+pub fn run_block<P: CarnyxProcessor>(processor: &mut P, input: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    run_block_with_events(processor, input, &[])
+}
+
+pub fn run_block_with_events<P: CarnyxProcessor>(processor: &mut P, input: &[Vec<f32>], events: &[TimedMidi]) -> Vec<Vec<f32>> {
+    render_block(processor, TEST_SAMPLE_RATE, input, events)
+}
+
+fn render_block<P: CarnyxProcessor>(processor: &mut P, sample_rate: f32, input: &[Vec<f32>], events: &[TimedMidi]) -> Vec<Vec<f32>> {
+    let layout = processor.bus_layout();
+    let len = input.first().map(|channel| channel.len()).unwrap_or(0);
+    let mut inputs: Vec<Vec<f32>> = input.iter().take(layout.total_inputs()).cloned().collect();
+    inputs.resize(layout.total_inputs(), silence(len));
+    let mut outputs = vec![silence(len); layout.outputs];
+    let mut scratch = ScratchBuffers::new(processor.block_scratch_spec(len));
+
+    let mut host_buffer: HostBuffer<f32> = HostBuffer::new(inputs.len(), outputs.len());
+    {
+        let mut buffer = host_buffer.bind(&inputs, &mut outputs);
+        let input_silence = SilenceFlags::detect_inputs(&mut buffer);
+        let mut context = ProcessContext::new(sample_rate, len)
+            .with_events(events)
+            .with_input_silence(input_silence)
+            .with_scratch(scratch.lend(len));
+        audit::realtime("process", || processor.process_block(&mut buffer, &mut context));
+    }
+    outputs
+}
+
+/// Process a whole signal at [`TEST_SAMPLE_RATE`] in blocks of `block_size`, returning
+/// the outputs joined up.
+pub fn run<P: CarnyxProcessor>(processor: &mut P, input: &[Vec<f32>], block_size: usize) -> Vec<Vec<f32>> {
+    render(processor, TEST_SAMPLE_RATE, input, block_size)
+}
+
+/// Like [`run`], at any sample rate. The processor should already have been told it.
+pub fn render<P: CarnyxProcessor>(processor: &mut P, sample_rate: f32, input: &[Vec<f32>], block_size: usize) -> Vec<Vec<f32>> {
+    let len = input.first().map(|channel| channel.len()).unwrap_or(0);
+    let mut outputs = vec![Vec::with_capacity(len); processor.bus_layout().outputs];
+    let mut start = 0;
+    while start < len {
+        let end = (start + block_size.max(1)).min(len);
+        let block: Vec<Vec<f32>> = input.iter().map(|channel| channel[start..end].to_vec()).collect();
+        for (output, block_output) in outputs.iter_mut().zip(render_block(processor, sample_rate, &block, &[])) {
+            output.extend(block_output);
+        }
+        start = end;
+    }
+    outputs
+}
+
+/// The largest absolute sample value, or infinity if any sample isn't finite.
+pub fn peak(samples: &[f32]) -> f32 {
+    samples.iter().fold(0., |peak: f32, s| if s.is_finite() { peak.max(s.abs()) } else { f32::INFINITY })
+}
+
+/// Root mean square level.
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+// set to record new golden files instead of checking against them
+const BLESS_VAR: &str = "CARNYX_BLESS";
+
+/// Compare a render against the golden file at `path`, allowing each sample to differ
+/// by `tolerance`. With `CARNYX_BLESS` set, `output` is written as the new golden file
+/// instead. Errors describe the first difference found, or the file being missing.
+pub fn check_golden(path: impl AsRef<Path>, output: &[Vec<f32>], tolerance: f32) -> Result<(), String> {
+    let path = path.as_ref();
+    if std::env::var_os(BLESS_VAR).is_some() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("creating {}: {}", dir.display(), e))?;
+        }
+        return Wav::new(TEST_SAMPLE_RATE as u32, output.to_vec())
+            .write(path)
+            .map_err(|e| format!("writing {}: {}", path.display(), e));
+    }
+    if !path.exists() {
+        return Err(format!("{} is missing (set {} to record it)", path.display(), BLESS_VAR));
+    }
+    let golden = Wav::read(path).map_err(|e| format!("reading {}: {}", path.display(), e))?;
+    if golden.channels.len() != output.len() {
+        return Err(format!("{} has {} channels, the render has {}", path.display(), golden.channels.len(), output.len()));
+    }
+    for (channel, (expected, actual)) in golden.channels.iter().zip(output).enumerate() {
+        if expected.len() != actual.len() {
+            return Err(format!("{} is {} samples long, the render is {}", path.display(), expected.len(), actual.len()));
+        }
+        let difference = expected.iter().zip(actual).position(|(e, a)| !((e - a).abs() <= tolerance));
+        if let Some(i) = difference {
+            return Err(format!(
+                "{} differs at channel {} sample {}: expected {}, rendered {} (set {} to accept)",
+                path.display(), channel, i, expected[i], actual[i], BLESS_VAR,
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+//! Editor state which is not part of the sound, kept on the model so it outlives the editor.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Values an editor wants back the next time it opens, such as the selected page,
+/// scroll offsets or which groups are collapsed. Keys are chosen by the editor.
+#[derive(Debug, Default)]
+pub struct UiState {
+    values: Mutex<BTreeMap<String, f64>>,
+}
+
+impl UiState {
+    pub fn new() -> Self {
+        UiState::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<f64> {
+        crate::audit::lock_taken("ui state lock");
+        self.values.lock().ok().and_then(|values| values.get(key).copied())
+    }
+
+    pub fn set(&self, key: &str, value: f64) {
+        crate::audit::lock_taken("ui state lock");
+        if let Ok(mut values) = self.values.lock() {
+            values.insert(key.to_string(), value);
+        }
+    }
+
+    /// A copy of every value, for an editor which is opening.
+    pub fn values(&self) -> BTreeMap<String, f64> {
+        crate::audit::lock_taken("ui state lock");
+        self.values.lock().map(|values| values.clone()).unwrap_or_default()
+    }
+
+    /// Replace every value with the editor's.
+    pub fn set_values(&self, new_values: BTreeMap<String, f64>) {
+        crate::audit::lock_taken("ui state lock");
+        if let Ok(mut values) = self.values.lock() {
+            *values = new_values;
+        }
+    }
+}
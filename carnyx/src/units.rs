@@ -0,0 +1,54 @@
+//! Formatting parameter values for display, and parsing values typed in by users.
+
+fn parse_number(text: &str) -> Option<f32> {
+    text.trim().parse::<f32>().ok().filter(|v| v.is_finite())
+}
+
+/// Shows e.g. "865 Hz", switching to kHz from 1000 Hz: "2.40 kHz", "12.5 kHz".
+pub fn format_hz(hz: f32) -> String {
+    // switch on the rounded value, so 999.7 shows as "1.00 kHz" rather than "1000 Hz"
+    if hz.abs().round() < 1000. {
+        format!("{:.0} Hz", hz)
+    } else if hz.abs() < 9995. {
+        format!("{:.2} kHz", hz / 1000.)
+    } else {
+        format!("{:.1} kHz", hz / 1000.)
+    }
+}
+
+/// Accepts e.g. "440", "440 Hz", "1.5k" and "1.5 kHz".
+pub fn parse_hz(text: &str) -> Option<f32> {
+    let text = text.trim().to_lowercase();
+    let text = text.strip_suffix("hz").unwrap_or(&text).trim_end();
+    match text.strip_suffix('k') {
+        Some(khz) => parse_number(khz).map(|v| v * 1000.),
+        None => parse_number(text),
+    }
+}
+
+/// Accepts e.g. "50" or "50 %", returning a fraction (0.5).
+pub fn parse_percent(text: &str) -> Option<f32> {
+    let text = text.trim();
+    let text = text.strip_suffix('%').unwrap_or(text);
+    parse_number(text).map(|v| v / 100.)
+}
+
+/// Accepts a plain number, ignoring a trailing unit label.
+pub fn parse_plain(text: &str, label: &str) -> Option<f32> {
+    let text = text.trim();
+    let has_label = !label.is_empty()
+        && text.len() >= label.len()
+        && text.is_char_boundary(text.len() - label.len())
+        && text[text.len() - label.len()..].eq_ignore_ascii_case(label);
+    if has_label {
+        parse_number(&text[..text.len() - label.len()])
+    } else {
+        parse_number(text)
+    }
+}
+
+/// Matches `text` against names, ignoring case, returning the index of the match.
+pub fn parse_choice(text: &str, names: &[&str]) -> Option<usize> {
+    let text = text.trim();
+    names.iter().position(|name| name.eq_ignore_ascii_case(text))
+}
@@ -0,0 +1,173 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use vst::util::AtomicFloat;
+
+use crate::buffer::{AudioBuffer, Scratch};
+use crate::carnyx::{BasicParam, CarnyxModel, CarnyxParam, DiscreteParam};
+use crate::dsp::equal_power;
+use crate::limiter::{Limiter, OutputLimiter, CEILING};
+use crate::units::{parse_percent, parse_plain};
+
+const GAIN_RANGE_DB: f32 = 24.;
+
+pub use crate::dsp::db_to_gain;
+
+fn db_from_normalized(value: f32) -> f32 {
+    (value.clamp(0., 1.) * 2. - 1.) * GAIN_RANGE_DB
+}
+
+fn db_to_normalized(db: f32) -> f32 {
+    (db / GAIN_RANGE_DB + 1.) / 2.
+}
+
+/// Housekeeping parameters most effects want: input trim, output gain, dry/wet mix and an
+/// optional safety limiter.
+/// A model opts in by returning these from [`CarnyxModel::utility`]; the parameters are then
+/// appended by [`CarnyxProcessor::all_parameters`](crate::CarnyxProcessor::all_parameters) and the
+/// output stage applied by [`CarnyxProcessor::process_block`](crate::CarnyxProcessor::process_block).
+pub struct UtilityParams {
+    input_trim_db: AtomicFloat,
+    output_gain_db: AtomicFloat,
+    mix: AtomicFloat,
+    limiter: AtomicUsize,
+}
+
+impl Default for UtilityParams {
+    fn default() -> Self {
+        UtilityParams {
+            input_trim_db: AtomicFloat::new(0.),
+            output_gain_db: AtomicFloat::new(0.),
+            mix: AtomicFloat::new(1.),
+            limiter: AtomicUsize::new(OutputLimiter::Off.index()),
+        }
+    }
+}
+
+impl UtilityParams {
+    /// Linear gain processors should apply to their input. The host's input buffers are
+    /// read only, so this is the one part of the block the framework can't do for them.
+    pub fn input_gain(&self) -> f32 {
+        db_to_gain(self.input_trim_db.get())
+    }
+
+    pub fn output_gain(&self) -> f32 {
+        db_to_gain(self.output_gain_db.get())
+    }
+
+    /// Equal power (dry, wet) gains for the current mix.
+    pub fn mix_gains(&self) -> (f32, f32) {
+        equal_power(self.mix.get())
+    }
+
+    pub fn limiter(&self) -> OutputLimiter {
+        OutputLimiter::from_index(self.limiter.load(Ordering::Relaxed))
+    }
+
+    pub fn set_limiter(&self, limiter: OutputLimiter) {
+        self.limiter.store(limiter.index(), Ordering::Relaxed);
+    }
+
+    /// Crossfade the (trimmed) input back in and apply the output gain, after the processor
+    /// has written its output.
+    pub fn apply_output(&self, buffer: &mut AudioBuffer<f32>) {
+        let trim = self.input_gain();
+        let gain = self.output_gain();
+        let (dry, wet) = self.mix_gains();
+        if dry == 0. && gain == 1. {
+            return;
+        }
+        for (input_buffer, output_buffer) in buffer.zip() {
+            for (input_sample, output_sample) in input_buffer.iter().zip(output_buffer) {
+                *output_sample = (*input_sample * trim * dry + *output_sample * wet) * gain;
+            }
+        }
+    }
+
+    /// As `apply_output`, but with the dry signal copied out beforehand, for when the host
+    /// processes in place and the inputs have been overwritten. Channels without a dry
+    /// copy get the output gain only.
+    pub fn apply_output_with_dry(&self, buffer: &mut AudioBuffer<f32>, dry_copy: &mut Scratch) {
+        let trim = self.input_gain();
+        let gain = self.output_gain();
+        let (dry, wet) = self.mix_gains();
+        if dry == 0. && gain == 1. {
+            return;
+        }
+        let (_, outputs) = buffer.split();
+        for (channel, output_buffer) in outputs.into_iter().enumerate() {
+            match dry_copy.get_mut(channel) {
+                Some(dry_buffer) => {
+                    for (dry_sample, output_sample) in dry_buffer.iter().zip(output_buffer) {
+                        *output_sample = (*dry_sample * trim * dry + *output_sample * wet) * gain;
+                    }
+                }
+                None => {
+                    for output_sample in output_buffer {
+                        *output_sample *= gain;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run the safety limiter over the outputs, last of all. The brickwall limiter's state
+    /// belongs to the processor, see
+    /// [`CarnyxProcessor::output_limiter`](crate::CarnyxProcessor::output_limiter); without
+    /// it the output is hard clipped instead.
+    pub fn limit_output(&self, buffer: &mut AudioBuffer<f32>, limiter: Option<&mut Limiter>, sample_rate: f32) {
+        let (_, mut outputs) = buffer.split();
+        match (self.limiter(), limiter) {
+            (OutputLimiter::Brickwall, Some(limiter)) => limiter.process(&mut outputs, sample_rate),
+            (mode, limiter) => {
+                if let Some(limiter) = limiter {
+                    limiter.stop();
+                }
+                if mode == OutputLimiter::Off {
+                    return;
+                }
+                for output in outputs.into_iter() {
+                    for sample in output.iter_mut() {
+                        *sample = sample.clamp(-CEILING, CEILING);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn parameters<Model: CarnyxModel>() -> Vec<Box<dyn CarnyxParam<Model>>> {
+        vec![
+            Box::new(BasicParam::new("input trim", "dB",
+                                     |m: &Model| m.utility().map(|u| db_to_normalized(u.input_trim_db.get())).unwrap_or(0.5),
+                                     |m, val| if let Some(u) = m.utility() { u.input_trim_db.set(db_from_normalized(val)) },
+                                     |m| format!("{:.1}", m.utility().map(|u| u.input_trim_db.get()).unwrap_or(0.)))
+                .with_id("utility.input_trim")
+                .with_parse(|text| parse_plain(text, "dB").map(db_to_normalized))
+                .with_default(0.5)
+                .with_description("Gain applied to the input before processing.")
+                .without_randomize()),
+            Box::new(BasicParam::new("output gain", "dB",
+                                     |m: &Model| m.utility().map(|u| db_to_normalized(u.output_gain_db.get())).unwrap_or(0.5),
+                                     |m, val| if let Some(u) = m.utility() { u.output_gain_db.set(db_from_normalized(val)) },
+                                     |m| format!("{:.1}", m.utility().map(|u| u.output_gain_db.get()).unwrap_or(0.)))
+                .with_id("utility.output_gain")
+                .with_parse(|text| parse_plain(text, "dB").map(db_to_normalized))
+                .with_default(0.5)
+                .with_description("Gain applied to the output, after the mix.")
+                .without_randomize()),
+            Box::new(BasicParam::new("mix", "%",
+                                     |m: &Model| m.utility().map(|u| u.mix.get()).unwrap_or(1.),
+                                     |m, val| if let Some(u) = m.utility() { u.mix.set(val.clamp(0., 1.)) },
+                                     |m| format!("{:.0}", m.utility().map(|u| u.mix.get()).unwrap_or(1.) * 100.))
+                .with_id("utility.mix")
+                .with_parse(parse_percent)
+                .with_default(1.)
+                .with_description("Balance between the unprocessed input and the processed signal.")),
+            Box::new(DiscreteParam::new("limiter", &OutputLimiter::NAMES,
+                                        |m: &Model| m.utility().map(|u| u.limiter().index()).unwrap_or(0),
+                                        |m, index| if let Some(u) = m.utility() { u.set_limiter(OutputLimiter::from_index(index)) })
+                .with_id("utility.limiter")
+                .with_description("A safety stage after the output gain. Hard clip cuts peaks off at full scale; brickwall turns them down smoothly, delaying the output by 1.5 ms to see them coming.")
+                .without_randomize()),
+        ]
+    }
+}
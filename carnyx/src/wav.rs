@@ -0,0 +1,132 @@
+//! WAV files, for offline rendering and golden tests. Reads 16 and 24 bit PCM and 32 bit
+//! float; writes 32 bit float.
+
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+// the real format is in the first two bytes of the extension's subformat GUID
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Audio as one buffer per channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wav {
+    pub sample_rate: u32,
+    pub channels: Vec<Vec<f32>>,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> io::Result<u16> {
+    bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]])).ok_or_else(|| invalid("truncated wav"))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> io::Result<u32> {
+    bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(|| invalid("truncated wav"))
+}
+
+impl Wav {
+    pub fn new(sample_rate: u32, channels: Vec<Vec<f32>>) -> Self {
+        Wav { sample_rate, channels }
+    }
+
+    /// Length in samples per channel.
+    pub fn len(&self) -> usize {
+        self.channels.first().map(|c| c.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Wav> {
+        Wav::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Wav> {
+        if bytes.get(0..4) != Some(b"RIFF") || bytes.get(8..12) != Some(b"WAVE") {
+            return Err(invalid("not a wav file"));
+        }
+        let mut format = None;
+        let mut at = 12;
+        while at + 8 <= bytes.len() {
+            let id = &bytes[at..at + 4];
+            let size = u32_at(bytes, at + 4)? as usize;
+            let body = at + 8;
+            match id {
+                b"fmt " => {
+                    let mut tag = u16_at(bytes, body)?;
+                    if tag == FORMAT_EXTENSIBLE {
+                        tag = u16_at(bytes, body + 24)?;
+                    }
+                    let channels = u16_at(bytes, body + 2)? as usize;
+                    let sample_rate = u32_at(bytes, body + 4)?;
+                    let bits = u16_at(bytes, body + 14)?;
+                    format = Some((tag, channels, sample_rate, bits));
+                }
+                b"data" => {
+                    let (tag, channels, sample_rate, bits) = format.ok_or_else(|| invalid("wav data before format"))?;
+                    let data = bytes.get(body..body + size).unwrap_or(&bytes[body.min(bytes.len())..]);
+                    return Ok(Wav { sample_rate, channels: decode(data, tag, channels, bits)? });
+                }
+                _ => (),
+            }
+            // chunks are padded to an even length
+            at = body + size + (size & 1);
+        }
+        Err(invalid("wav has no data"))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let channels = self.channels.len();
+        let data_size = (self.len() * channels * 4) as u32;
+        let mut bytes = Vec::with_capacity(44 + data_size as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&FORMAT_FLOAT.to_le_bytes());
+        bytes.extend_from_slice(&(channels as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(self.sample_rate * channels as u32 * 4).to_le_bytes());
+        bytes.extend_from_slice(&(channels as u16 * 4).to_le_bytes());
+        bytes.extend_from_slice(&32u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        for frame in 0..self.len() {
+            for channel in &self.channels {
+                bytes.extend_from_slice(&channel.get(frame).copied().unwrap_or(0.).to_le_bytes());
+            }
+        }
+        bytes
+    }
+}
+
+fn decode(data: &[u8], tag: u16, channels: usize, bits: u16) -> io::Result<Vec<Vec<f32>>> {
+    let width = (bits / 8) as usize;
+    let sample: fn(&[u8]) -> f32 = match (tag, bits) {
+        (FORMAT_FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        (FORMAT_PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.,
+        (FORMAT_PCM, 24) => |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.,
+        _ => return Err(invalid("unsupported wav sample format")),
+    };
+    if channels == 0 {
+        return Err(invalid("wav has no channels"));
+    }
+    let frames = data.len() / (width * channels);
+    let mut decoded = vec![Vec::with_capacity(frames); channels];
+    for frame in data.chunks_exact(width * channels) {
+        for (channel, bytes) in decoded.iter_mut().zip(frame.chunks_exact(width)) {
+            channel.push(sample(bytes));
+        }
+    }
+    Ok(decoded)
+}
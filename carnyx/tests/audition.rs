@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use carnyx::audition::{note_to_hz, AuditionGenerator, AuditionSettings, AuditionWaveform};
+use carnyx::test::{peak, TEST_SAMPLE_RATE};
+use carnyx::NoteEvent;
+
+fn generator(waveform: AuditionWaveform) -> AuditionGenerator {
+    let settings = AuditionSettings::default();
+    settings.set_waveform(waveform);
+    settings.set_level(1.);
+    let mut generator = AuditionGenerator::new(Arc::new(settings));
+    generator.set_sample_rate(TEST_SAMPLE_RATE);
+    generator
+}
+
+// one second of the generator
+fn second(generator: &mut AuditionGenerator) -> Vec<f32> {
+    (0..TEST_SAMPLE_RATE as usize).map(|_| generator.next_sample()).collect()
+}
+
+fn rising_zero_crossings(samples: &[f32]) -> usize {
+    samples.windows(2).filter(|pair| pair[0] < 0. && pair[1] >= 0.).count()
+}
+
+#[test]
+fn a_held_note_plays_at_its_pitch() {
+    let mut generator = generator(AuditionWaveform::Sine);
+    generator.note(NoteEvent::On { note: 69, velocity: 1. });
+    let crossings = rising_zero_crossings(&second(&mut generator));
+    assert!((crossings as i32 - 440).abs() <= 1, "{} cycles a second", crossings);
+
+    generator.note(NoteEvent::On { note: 57, velocity: 1. });
+    let crossings = rising_zero_crossings(&second(&mut generator));
+    assert!((crossings as i32 - 220).abs() <= 1, "{} cycles a second", crossings);
+    assert_eq!(note_to_hz(57), 220.);
+}
+
+#[test]
+fn velocity_and_level_scale_the_signal() {
+    let mut generator = generator(AuditionWaveform::Saw);
+    generator.settings().set_level(0.5);
+    generator.note(NoteEvent::On { note: 48, velocity: 0.5 });
+    let level = peak(&second(&mut generator));
+    assert!((level - 0.25).abs() < 0.01, "peak {}", level);
+
+    generator.settings().set_waveform(AuditionWaveform::Noise);
+    let level = peak(&second(&mut generator));
+    assert!(level > 0.2 && level <= 0.25, "noise peak {}", level);
+}
+
+#[test]
+fn sounds_only_while_enabled_or_a_note_is_held() {
+    let mut generator = generator(AuditionWaveform::Sine);
+    assert!(!generator.is_active());
+    assert_eq!(peak(&second(&mut generator)), 0.);
+
+    // enabled in the editor, it plays its own frequency
+    generator.settings().set_frequency(100.);
+    generator.settings().set_enabled(true);
+    let crossings = rising_zero_crossings(&second(&mut generator));
+    assert!((crossings as i32 - 100).abs() <= 1, "{} cycles a second", crossings);
+    generator.settings().set_enabled(false);
+
+    generator.note(NoteEvent::On { note: 60, velocity: 1. });
+    // releasing another key leaves the held one sounding
+    generator.note(NoteEvent::Off { note: 64 });
+    assert!(peak(&second(&mut generator)) > 0.9);
+    generator.note(NoteEvent::Off { note: 60 });
+    assert_eq!(peak(&second(&mut generator)), 0.);
+
+    generator.note(NoteEvent::On { note: 60, velocity: 1. });
+    generator.all_notes_off();
+    assert!(!generator.is_active());
+}
@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+use carnyx::automation::{AutomationLimiter, AutomationTracker, AUTOMATION_CAPACITY};
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn a_run_of_outside_changes_is_automation() {
+    let start = Instant::now();
+    let mut tracker = AutomationTracker::new();
+    tracker.external_change(0.2, start);
+    tracker.external_change(0.3, start + ms(100));
+    assert!(!tracker.is_automating());
+    tracker.external_change(0.4, start + ms(200));
+    assert!(tracker.is_automating());
+    // shown as where the user left it, before the automation started
+    assert_eq!(tracker.user_value(), Some(0.2));
+
+    // the user can still move it underneath
+    tracker.user_edit(0.7);
+    assert_eq!(tracker.user_value(), Some(0.7));
+    tracker.external_change(0.5, start + ms(900));
+    assert!(!tracker.expire(start + ms(1800)));
+    assert!(tracker.expire(start + ms(1900)));
+    assert!(!tracker.is_automating());
+    assert!(!tracker.expire(start + ms(5000)));
+}
+
+#[test]
+fn occasional_changes_are_not() {
+    let start = Instant::now();
+    let mut tracker = AutomationTracker::new();
+    // a preset load here and there, each in its own window
+    for n in 0..5 {
+        tracker.external_change(0.1 * n as f64, start + ms(600 * n));
+        assert!(!tracker.is_automating());
+    }
+    tracker.user_edit(0.9);
+    assert_eq!(tracker.user_value(), None);
+}
+
+// a gain reduction meter, reported every block
+#[test]
+fn a_meter_is_sent_at_most_once_an_interval() {
+    let limiter = AutomationLimiter::new(Duration::from_secs(3600));
+    assert!(limiter.allow(3, 0.8));
+    assert!(!limiter.allow(3, 0.7));
+    assert!(!limiter.allow(3, 0.6));
+    // each parameter has its own interval
+    assert!(limiter.allow(4, 0.6));
+    assert!(!limiter.allow(4, 0.5));
+}
+
+#[test]
+fn repeated_values_are_never_sent_again() {
+    let limiter = AutomationLimiter::new(Duration::from_secs(0));
+    assert!(limiter.allow(0, 0.25));
+    assert!(!limiter.allow(0, 0.25));
+    assert!(limiter.allow(0, 0.5));
+    assert!(limiter.allow(0, 0.25));
+    // the first value is sent whatever it is
+    assert!(limiter.allow(1, 0.));
+    // and parameters past the capacity never are
+    assert!(!limiter.allow(AUTOMATION_CAPACITY, 0.5));
+}
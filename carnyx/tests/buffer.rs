@@ -0,0 +1,155 @@
+use carnyx::buffer::{AudioBuffer, AudioBufferExt, BusLayout, Scratch, ScratchBuffers, ScratchSpec};
+
+// Run `f` on a buffer whose inputs and outputs are the `channels` at the given indices, so
+// an input and an output may be the same memory, as when a host processes in place.
+fn with_buffer<R>(channels: &mut [Vec<f32>], inputs: &[usize], outputs: &[usize], f: impl FnOnce(&mut AudioBuffer<f32>) -> R) -> R {
+    let samples = channels.first().map(|channel| channel.len()).unwrap_or(0);
+    assert!(channels.iter().all(|channel| channel.len() == samples));
+    let pointers: Vec<*mut f32> = channels.iter_mut().map(|channel| channel.as_mut_ptr()).collect();
+    let input_pointers: Vec<*const f32> = inputs.iter().map(|i| pointers[*i] as *const f32).collect();
+    let mut output_pointers: Vec<*mut f32> = outputs.iter().map(|i| pointers[*i]).collect();
+    // SAFETY: every pointer is to one of `channels`, all `samples` long, which outlive the buffer
+    let mut buffer = unsafe {
+        AudioBuffer::from_raw(inputs.len(), outputs.len(), input_pointers.as_ptr(), output_pointers.as_mut_ptr(), samples)
+    };
+    f(&mut buffer)
+}
+
+fn ramp(start: f32, len: usize) -> Vec<f32> {
+    (0..len).map(|i| start + i as f32).collect()
+}
+
+#[test]
+fn separate_buffers_are_copied() {
+    let mut channels = vec![ramp(1., 8), ramp(100., 8), vec![-1.; 8], vec![-1.; 8]];
+    let outputs = with_buffer(&mut channels, &[0, 1], &[2, 3], |buffer| {
+        assert!(!buffer.is_in_place());
+        buffer.zip_in_place().into_iter().map(|output| output.to_vec()).collect::<Vec<_>>()
+    });
+    assert_eq!(outputs, vec![ramp(1., 8), ramp(100., 8)]);
+    // the inputs are left alone
+    assert_eq!(channels[0], ramp(1., 8));
+    assert_eq!(channels[1], ramp(100., 8));
+}
+
+#[test]
+fn aliased_buffers_are_processed_in_place() {
+    let mut channels = vec![ramp(1., 8), ramp(100., 8)];
+    with_buffer(&mut channels, &[0, 1], &[0, 1], |buffer| {
+        assert!(buffer.is_in_place());
+        let mut outputs = buffer.zip_in_place();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs.get_mut(1), &ramp(100., 8)[..]);
+        for sample in outputs.get_mut(0).iter_mut() {
+            *sample *= -1.;
+        }
+    });
+    assert_eq!(channels[0], ramp(1., 8).iter().map(|s| -s).collect::<Vec<_>>());
+    assert_eq!(channels[1], ramp(100., 8));
+}
+
+#[test]
+fn one_aliased_channel_is_enough_to_be_in_place() {
+    let mut channels = vec![ramp(1., 4), ramp(10., 4), vec![0.; 4]];
+    let outputs = with_buffer(&mut channels, &[0, 1], &[0, 2], |buffer| {
+        assert!(buffer.is_in_place());
+        buffer.zip_in_place().into_iter().map(|output| output.to_vec()).collect::<Vec<_>>()
+    });
+    assert_eq!(outputs, vec![ramp(1., 4), ramp(10., 4)]);
+}
+
+#[test]
+fn outputs_without_inputs_are_zeroed() {
+    let mut channels = vec![ramp(1., 6), vec![7.; 6], vec![7.; 6]];
+    let outputs = with_buffer(&mut channels, &[0], &[1, 2], |buffer| {
+        buffer.zip_in_place().into_iter().map(|output| output.to_vec()).collect::<Vec<_>>()
+    });
+    assert_eq!(outputs, vec![ramp(1., 6), vec![0.; 6]]);
+}
+
+#[test]
+fn inputs_without_outputs_are_left_out() {
+    let mut channels = vec![ramp(1., 6), ramp(50., 6), vec![0.; 6]];
+    let outputs = with_buffer(&mut channels, &[0, 1], &[2], |buffer| {
+        buffer.zip_in_place().into_iter().map(|output| output.to_vec()).collect::<Vec<_>>()
+    });
+    assert_eq!(outputs, vec![ramp(1., 6)]);
+    assert_eq!(channels[1], ramp(50., 6));
+}
+
+#[test]
+fn no_outputs_at_all() {
+    let mut channels = vec![ramp(1., 6)];
+    with_buffer(&mut channels, &[0], &[], |buffer| {
+        assert!(!buffer.is_in_place());
+        assert!(buffer.zip_in_place().is_empty());
+    });
+}
+
+#[test]
+fn zero_length_blocks() {
+    // separate empty channels may still share a dangling pointer
+    let mut channels = vec![Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+    with_buffer(&mut channels, &[0, 1], &[2, 3], |buffer| {
+        assert!(!buffer.is_in_place());
+        let outputs = buffer.zip_in_place();
+        assert_eq!(outputs.len(), 2);
+        assert!(outputs.into_iter().all(|output| output.is_empty()));
+    });
+    with_buffer(&mut channels, &[0, 1], &[0, 1], |buffer| {
+        assert!(!buffer.is_in_place());
+        assert_eq!(buffer.zip_in_place().len(), 2);
+    });
+}
+
+#[test]
+fn scratch_is_lent_at_the_block_size_and_kept_between_blocks() {
+    let mut buffers = ScratchBuffers::new(ScratchSpec { buffers: 3, samples: 16 });
+    assert_eq!(buffers.spec(), ScratchSpec { buffers: 3, samples: 16 });
+    {
+        let mut scratch = buffers.lend(10);
+        assert_eq!(scratch.len(), 3);
+        for (n, buffer) in scratch.iter_mut().enumerate() {
+            assert_eq!(buffer.len(), 10);
+            buffer[0] = n as f32;
+        }
+        assert!(scratch.get_mut(3).is_none());
+    }
+    // a longer block than was asked for gets no more than was allocated
+    let mut scratch = buffers.lend(64);
+    assert_eq!(scratch.get_mut(2).unwrap().len(), 16);
+    assert_eq!(scratch.iter_mut().map(|buffer| buffer[0]).collect::<Vec<_>>(), [0., 1., 2.]);
+}
+
+#[test]
+fn split_off_scratch_is_separate_from_the_rest() {
+    let mut buffers = ScratchBuffers::new(ScratchSpec { buffers: 3, samples: 4 });
+    let mut scratch = buffers.lend(4);
+    let mut framework = scratch.split_off(1);
+    assert_eq!((scratch.len(), framework.len()), (2, 1));
+    framework.get_mut(0).unwrap().copy_from_slice(&[9.; 4]);
+    for buffer in scratch.iter_mut() {
+        assert_eq!(buffer, &[0.; 4]);
+    }
+    // asking for more than is left takes what there is
+    assert_eq!(scratch.split_off(5).len(), 2);
+    assert!(scratch.is_empty());
+    assert!(Scratch::empty().get_mut(0).is_none());
+    drop(framework);
+    assert_eq!(buffers.lend(4).get_mut(2).unwrap(), &[9.; 4]);
+}
+
+#[test]
+fn sidechain_inputs_come_after_the_main_ones() {
+    let mono = BusLayout::new(1, 1).with_sidechain(1);
+    let stereo = BusLayout::new(2, 2).with_sidechain(2);
+    assert_eq!(stereo.total_inputs(), 4);
+    assert_eq!((0..5).map(|input| stereo.is_sidechain(input)).collect::<Vec<_>>(), [false, false, true, true, false]);
+    assert!(!BusLayout::new(2, 2).is_sidechain(2));
+
+    let layouts = [mono, stereo, BusLayout::new(1, 2)];
+    assert_eq!(BusLayout::widest(&layouts), Some(stereo));
+    assert_eq!(BusLayout::find(&layouts, 1, 2), Some(BusLayout::new(1, 2)));
+    assert_eq!(BusLayout::find(&layouts, 2, 1), None);
+    assert_eq!(BusLayout::widest(&[]), None);
+}
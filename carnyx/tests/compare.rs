@@ -0,0 +1,44 @@
+use carnyx::preset::{AbCompare, AbSlot};
+
+// a cutoff and a name, as a stand in for a plugin's snap
+type Snap = (f32, &'static str);
+
+#[test]
+fn toggling_swaps_the_live_snap_with_the_other_slot() {
+    let mut live: Snap = (0.5, "init");
+    let mut compare = AbCompare::new(live);
+    assert_eq!(compare.active(), AbSlot::A);
+
+    // edit A, then compare with B, which starts as A was
+    live = (0.9, "bright");
+    compare.toggle(&mut live);
+    assert_eq!(compare.active(), AbSlot::B);
+    assert_eq!(live, (0.5, "init"));
+
+    // edits to B are kept when going back to A
+    live.0 = 0.1;
+    compare.toggle(&mut live);
+    assert_eq!(compare.active(), AbSlot::A);
+    assert_eq!(live, (0.9, "bright"));
+    compare.toggle(&mut live);
+    assert_eq!(live, (0.1, "init"));
+}
+
+#[test]
+fn copying_a_to_b_works_from_either_slot() {
+    let mut live: Snap = (0.5, "a");
+    let mut compare = AbCompare::new((0.2, "b"));
+
+    // from A, B becomes a copy and A stays live
+    compare.copy_a_to_b(&mut live);
+    assert_eq!(compare.active(), AbSlot::A);
+    compare.toggle(&mut live);
+    assert_eq!(live, (0.5, "a"));
+
+    // from B, the live snap is overwritten with A
+    live = (0.7, "b edited");
+    compare.copy_a_to_b(&mut live);
+    assert_eq!(compare.active(), AbSlot::B);
+    assert_eq!(live, (0.5, "a"));
+    assert_eq!(AbSlot::B.other().name(), "A");
+}
@@ -0,0 +1,58 @@
+// nothing is recorded without the feature
+#![cfg(feature = "diagnostics")]
+
+use carnyx::{Diagnostic, Diagnostics, Level};
+
+fn drained(diagnostics: &Diagnostics) -> Vec<Diagnostic> {
+    let mut events = Vec::new();
+    diagnostics.drain(|event| events.push(*event));
+    events
+}
+
+#[test]
+fn only_events_at_the_level_and_above_are_kept() {
+    let diagnostics = Diagnostics::new(Some(Level::Debug));
+    diagnostics.trace("processor", "denormal", None);
+    diagnostics.debug("processor", "block size", Some(512.));
+    diagnostics.warn("vst", "unknown opcode", Some(73.));
+    let messages: Vec<&str> = drained(&diagnostics).iter().map(|event| event.message).collect();
+    assert_eq!(messages, ["block size", "unknown opcode"]);
+
+    diagnostics.set_min_level(None);
+    diagnostics.warn("vst", "unknown opcode", None);
+    assert!(drained(&diagnostics).is_empty());
+    assert!(!diagnostics.enabled(Level::Error));
+}
+
+#[test]
+fn a_full_queue_counts_what_it_drops() {
+    let diagnostics = Diagnostics::default();
+    for _ in 0..1000 {
+        diagnostics.warn("processor", "overload", None);
+    }
+    let dropped = diagnostics.dropped();
+    assert!(dropped > 0);
+    let events = drained(&diagnostics);
+    // the loss is reported first, then what was kept
+    assert_eq!(events[0].message, "events dropped");
+    assert_eq!(events[0].value, Some(dropped as f64));
+    assert_eq!(events.len(), 1 + 1000 - dropped);
+    assert_eq!(diagnostics.dropped(), 0);
+    assert!(drained(&diagnostics).is_empty());
+}
+
+#[test]
+fn errors_keep_their_details() {
+    let diagnostics = Diagnostics::default();
+    assert_eq!(diagnostics.last_error(), None);
+    diagnostics.error("editor", "editor window could not be created", "no GL context");
+    assert_eq!(diagnostics.last_error().as_deref(), Some("editor: editor window could not be created: no GL context"));
+    let events = drained(&diagnostics);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].to_string(), "[error] editor: editor window could not be created");
+
+    let with_value = Diagnostic { level: Level::Warn, source: "vst", message: "latency", value: Some(64.) };
+    assert_eq!(with_value.to_string(), "[warn] vst: latency 64");
+    assert_eq!(Level::from_name(" DEBUG "), Some(Level::Debug));
+    assert_eq!(Level::from_name("verbose"), None);
+}
@@ -0,0 +1,79 @@
+use carnyx::dsp::{asymmetric_clip, db_to_gain, equal_power, gain_to_db, soft_clip, Biquad, DcBlocker, OnePole};
+use carnyx::test::{dc, peak, rms, sine, TEST_SAMPLE_RATE};
+
+fn filtered(mut filter: impl FnMut(f32) -> f32, input: &[f32]) -> Vec<f32> {
+    input.iter().map(|sample| filter(*sample)).collect()
+}
+
+#[test]
+fn db_and_gain_convert_both_ways() {
+    assert_eq!(db_to_gain(0.), 1.);
+    assert!((db_to_gain(-6.0206) - 0.5).abs() < 1e-4);
+    for &db in &[-48., -6., 0., 3., 24.] {
+        assert!((gain_to_db(db_to_gain(db)) - db).abs() < 1e-4, "{} dB came back changed", db);
+    }
+    assert_eq!(gain_to_db(0.), f32::NEG_INFINITY);
+}
+
+#[test]
+fn equal_power_keeps_the_power_constant() {
+    assert_eq!(equal_power(0.), (1., 0.));
+    for step in 0..=10 {
+        let (a, b) = equal_power(step as f32 / 10.);
+        assert!((a * a + b * b - 1.).abs() < 1e-6);
+    }
+    let (a, b) = equal_power(1.);
+    assert!(a.abs() < 1e-6 && b == 1.);
+}
+
+#[test]
+fn clippers_stay_bounded() {
+    for step in -100..=100 {
+        let x = step as f32 / 10.;
+        assert!(soft_clip(x).abs() <= 1., "soft clip of {} out of range", x);
+        assert!(asymmetric_clip(x) <= 1. && asymmetric_clip(x) >= -2., "asymmetric clip of {} out of range", x);
+    }
+    assert!((soft_clip(1.) - 1.).abs() < 1e-6 && (soft_clip(-3.) + 1.).abs() < 1e-6);
+    // small signals pass through the asymmetric clipper nearly unchanged either way
+    assert!((asymmetric_clip(0.01) - 0.01).abs() < 1e-5 && (asymmetric_clip(-0.01) + 0.01).abs() < 1e-5);
+}
+
+#[test]
+fn one_pole_settles_on_its_target() {
+    let mut smoother = OnePole::new(0.01, TEST_SAMPLE_RATE);
+    // about two thirds of the way after its time constant
+    let after = (0..441).map(|_| smoother.next(1.)).last().unwrap();
+    assert!((after - 0.632).abs() < 0.01, "{}", after);
+    let settled = (0..TEST_SAMPLE_RATE as usize / 10).map(|_| smoother.next(1.)).last().unwrap();
+    assert!((settled - 1.).abs() < 1e-4);
+    smoother.reset(0.);
+    assert_eq!(smoother.value(), 0.);
+}
+
+#[test]
+fn dc_blocker_removes_offset() {
+    let mut blocker = DcBlocker::default();
+    let output = filtered(|x| blocker.process(x), &dc(0.5, TEST_SAMPLE_RATE as usize));
+    assert!(peak(&output[TEST_SAMPLE_RATE as usize / 2..]) < 1e-3);
+    let mut blocker = DcBlocker::default();
+    let tone = sine(1000., 0.5, 4096);
+    let output = filtered(|x| blocker.process(x), &tone);
+    assert!((rms(&output[2048..]) / rms(&tone[2048..]) - 1.).abs() < 0.01);
+}
+
+#[test]
+fn biquads_pass_and_stop_where_they_should() {
+    let low = sine(100., 0.5, 8192);
+    let high = sine(10000., 0.5, 8192);
+    let gain = |mut biquad: Biquad, input: &[f32]| {
+        let output = filtered(|x| biquad.process(x), input);
+        rms(&output[4096..]) / rms(&input[4096..])
+    };
+    let lowpass = Biquad::lowpass(1000., 0.707, TEST_SAMPLE_RATE);
+    assert!(gain(lowpass, &low) > 0.95 && gain(lowpass, &high) < 0.02);
+    let highpass = Biquad::highpass(1000., 0.707, TEST_SAMPLE_RATE);
+    assert!(gain(highpass, &high) > 0.95 && gain(highpass, &low) < 0.02);
+    let bandpass = Biquad::bandpass(1000., 2., TEST_SAMPLE_RATE);
+    assert!((gain(bandpass, &sine(1000., 0.5, 8192)) - 1.).abs() < 0.02);
+    assert!(gain(bandpass, &low) < 0.1 && gain(bandpass, &high) < 0.1);
+}
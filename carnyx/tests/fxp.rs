@@ -0,0 +1,108 @@
+use carnyx::fxp::{FxBank, FxFile, FxProgram};
+
+fn program(name: &str, params: &[f32]) -> FxProgram {
+    FxProgram { name: name.to_owned(), plugin_id: 0x4c61_6464, plugin_version: 3, params: params.to_vec() }
+}
+
+fn error(result: std::io::Result<impl std::fmt::Debug>) -> String {
+    result.expect_err("should have been refused").to_string()
+}
+
+#[test]
+fn programs_round_trip() {
+    let original = program("Squelch", &[0., 0.25, 1., 0.123_456_78]);
+    let bytes = original.to_bytes();
+    assert_eq!(&bytes[0..4], b"CcnK");
+    assert_eq!(&bytes[8..12], b"FxCk");
+    // the size field counts everything after it
+    assert_eq!(i32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize, bytes.len() - 8);
+    assert_eq!(FxProgram::from_bytes(&bytes).unwrap(), original);
+    assert_eq!(FxFile::from_bytes(&bytes).unwrap(), FxFile::Program(original));
+}
+
+#[test]
+fn banks_round_trip() {
+    let bank = FxBank {
+        plugin_id: 77,
+        plugin_version: 2,
+        current: 1,
+        programs: vec![program("One", &[0.1, 0.2]), program("Two", &[0.9, 0.8]), program("", &[])],
+    };
+    let bytes = bank.to_bytes();
+    assert_eq!(&bytes[8..12], b"FxBk");
+    let read = FxBank::from_bytes(&bytes).unwrap();
+    assert_eq!(read, bank);
+    assert_eq!(read.current_program().map(|p| p.name.as_str()), Some("Two"));
+    assert_eq!(FxFile::from_bytes(&bytes).unwrap().program().map(|p| p.params.clone()), Some(vec![0.9, 0.8]));
+}
+
+#[test]
+fn long_names_are_cut_at_a_character_boundary() {
+    // 26 bytes, then a three byte character which would end past the 27 the field holds
+    let name = format!("{}\u{2603}", "a".repeat(26));
+    let read = FxProgram::from_bytes(&program(&name, &[0.5]).to_bytes()).unwrap();
+    assert_eq!(read.name, "a".repeat(26));
+}
+
+#[test]
+fn names_without_a_terminator_fill_the_field() {
+    let mut bytes = program("", &[0.5]).to_bytes();
+    for byte in &mut bytes[28..56] {
+        *byte = b'x';
+    }
+    let read = FxProgram::from_bytes(&bytes).unwrap();
+    assert_eq!(read.name, "x".repeat(28));
+    assert_eq!(read.params, vec![0.5]);
+}
+
+#[test]
+fn names_which_are_not_utf8_are_kept_as_far_as_they_can_be() {
+    let mut bytes = program("ab", &[]).to_bytes();
+    bytes[29] = 0xff;
+    assert_eq!(FxProgram::from_bytes(&bytes).unwrap().name, "a\u{fffd}");
+}
+
+#[test]
+fn truncated_files_are_refused() {
+    let program_bytes = program("Cut", &[0.1, 0.2, 0.3]).to_bytes();
+    for len in 0..program_bytes.len() {
+        assert!(FxProgram::from_bytes(&program_bytes[..len]).is_err(), "{} of {} bytes read", len, program_bytes.len());
+    }
+    let bank_bytes = FxBank { plugin_id: 1, plugin_version: 1, current: 0, programs: vec![program("Cut", &[0.5])] }.to_bytes();
+    for len in 0..bank_bytes.len() {
+        assert!(FxBank::from_bytes(&bank_bytes[..len]).is_err(), "{} of {} bytes read", len, bank_bytes.len());
+    }
+}
+
+#[test]
+fn opaque_chunks_are_refused() {
+    let mut bytes = program("Opaque", &[0.5]).to_bytes();
+    bytes[8..12].copy_from_slice(b"FPCh");
+    assert!(error(FxProgram::from_bytes(&bytes)).contains("plugin specific"));
+    assert!(error(FxFile::from_bytes(&bytes)).contains("plugin specific"));
+
+    let mut bytes = FxBank { plugin_id: 1, plugin_version: 1, current: 0, programs: vec![] }.to_bytes();
+    bytes[8..12].copy_from_slice(b"FBCh");
+    assert!(error(FxBank::from_bytes(&bytes)).contains("plugin specific"));
+    assert!(error(FxFile::from_bytes(&bytes)).contains("plugin specific"));
+}
+
+#[test]
+fn negative_counts_are_refused() {
+    let mut bytes = program("Negative", &[0.5]).to_bytes();
+    bytes[24..28].copy_from_slice(&(-1i32).to_be_bytes());
+    assert!(error(FxProgram::from_bytes(&bytes)).contains("negative count"));
+
+    let mut bytes = FxBank { plugin_id: 1, plugin_version: 1, current: 0, programs: vec![program("A", &[])] }.to_bytes();
+    bytes[24..28].copy_from_slice(&i32::MIN.to_be_bytes());
+    assert!(error(FxBank::from_bytes(&bytes)).contains("negative count"));
+}
+
+#[test]
+fn other_files_are_refused() {
+    assert!(error(FxProgram::from_bytes(b"RIFF\0\0\0\0WAVEfmt ")).contains("not a VST preset"));
+    let mut bytes = program("Odd", &[]).to_bytes();
+    bytes[8..12].copy_from_slice(b"Junk");
+    assert!(error(FxProgram::from_bytes(&bytes)).contains("not a VST program"));
+    assert!(error(FxBank::from_bytes(&bytes)).contains("not a VST bank"));
+}
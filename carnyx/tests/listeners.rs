@@ -0,0 +1,68 @@
+use std::sync::{Arc, Mutex};
+
+use carnyx::carnyx::{CarnyxModelListener, ChangeEvent, ChangeOrigin, ListenerId, ParamFilter, SettableListener};
+
+// the model is only passed through, so any type will do
+type Model = ();
+
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<ChangeEvent>>,
+}
+
+impl Recorder {
+    fn heard(&self) -> Vec<Option<usize>> {
+        self.events.lock().unwrap().drain(..).map(|event| event.param_index).collect()
+    }
+}
+
+impl CarnyxModelListener<Model> for Recorder {
+    fn notify_change(&self, _model: &Model, event: ChangeEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+fn add(listeners: &SettableListener<Model>, filter: ParamFilter) -> (Arc<Recorder>, Arc<dyn CarnyxModelListener<Model>>, ListenerId) {
+    let recorder = Arc::new(Recorder::default());
+    let listener: Arc<dyn CarnyxModelListener<Model>> = recorder.clone();
+    let id = listeners.add_listener(&listener, filter);
+    (recorder, listener, id)
+}
+
+#[test]
+fn every_listener_hears_the_parameters_it_asked_for() {
+    let listeners = SettableListener::new();
+    let (editor, _editor, _) = add(&listeners, ParamFilter::All);
+    let (meter, _meter, _) = add(&listeners, ParamFilter::Indices(vec![2, 5]));
+    for index in 0..7 {
+        listeners.notify_change(&(), ChangeEvent::param(index, ChangeOrigin::Host));
+    }
+    assert_eq!(editor.heard().len(), 7);
+    assert_eq!(meter.heard(), [Some(2), Some(5)]);
+
+    // loading a preset may change anything
+    listeners.notify_change(&(), ChangeEvent::model(ChangeOrigin::Host));
+    assert_eq!(editor.heard(), [None]);
+    assert_eq!(meter.heard(), [None]);
+}
+
+#[test]
+fn listeners_go_when_removed_or_dropped() {
+    let listeners = SettableListener::new();
+    let (kept, _kept, _) = add(&listeners, ParamFilter::All);
+    let (removed, _removed, id) = add(&listeners, ParamFilter::All);
+    let (dropped, dropped_listener, _) = add(&listeners, ParamFilter::All);
+    assert_eq!(listeners.listener_count(), 3);
+
+    assert!(listeners.remove_listener(id));
+    assert!(!listeners.remove_listener(id));
+    // held weakly, so a closed editor doesn't have to remember to unregister
+    drop(dropped_listener);
+    drop(dropped);
+    assert_eq!(listeners.listener_count(), 1);
+
+    // clones share their listeners, as the processor and the bridge do
+    listeners.clone().notify_change(&(), ChangeEvent::param(0, ChangeOrigin::Editor));
+    assert_eq!(kept.heard(), [Some(0)]);
+    assert!(removed.heard().is_empty());
+}
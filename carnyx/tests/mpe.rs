@@ -0,0 +1,79 @@
+use carnyx::mpe::{Expression, MpeEvent, MpeTranslator, Zone, ZoneConfig};
+use carnyx::{MidiMessage, NoteEvent};
+
+fn cc(channel: u8, controller: u8, value: u8) -> MidiMessage {
+    MidiMessage::ControlChange { channel, controller, value }
+}
+
+fn on(channel: u8, note: u8) -> MidiMessage {
+    MidiMessage::Note { channel, event: NoteEvent::On { note, velocity: 0.75 } }
+}
+
+// what an MPE controller sends to set up a zone from its master channel
+fn configure(mpe: &mut MpeTranslator, master: u8, members: u8) {
+    for message in [cc(master, 101, 0), cc(master, 100, 6), cc(master, 6, members)].iter() {
+        mpe.translate(*message);
+    }
+}
+
+#[test]
+fn member_channels_carry_each_notes_own_expression() {
+    let mut mpe = MpeTranslator::new().with_zone(Zone::Lower, ZoneConfig::new(7));
+    assert_eq!(mpe.translate(on(1, 60)), MpeEvent::Note { channel: 1, event: NoteEvent::On { note: 60, velocity: 0.75 } });
+    mpe.translate(on(2, 64));
+
+    // half a bend is 24 of the member channels' 48 semitones
+    assert_eq!(mpe.translate(MidiMessage::PitchBend { channel: 2, value: 0.5 }),
+               MpeEvent::NoteExpression { channel: 2, note: Some(64), expression: Expression::Pitch, value: 24. });
+    assert_eq!(mpe.translate(MidiMessage::ChannelPressure { channel: 1, pressure: 127 }),
+               MpeEvent::NoteExpression { channel: 1, note: Some(60), expression: Expression::Pressure, value: 1. });
+    assert_eq!(mpe.translate(cc(1, 74, 0)),
+               MpeEvent::NoteExpression { channel: 1, note: Some(60), expression: Expression::Timbre, value: 0. });
+
+    // once released, expression has no note to go to
+    mpe.translate(MidiMessage::Note { channel: 1, event: NoteEvent::Off { note: 60 } });
+    let released = mpe.translate(MidiMessage::ChannelPressure { channel: 1, pressure: 0 });
+    assert_eq!(released, MpeEvent::NoteExpression { channel: 1, note: None, expression: Expression::Pressure, value: 0. });
+}
+
+#[test]
+fn the_master_channel_moves_the_whole_zone() {
+    let mut mpe = MpeTranslator::new().with_zone(Zone::Upper, ZoneConfig::new(3));
+    assert_eq!(mpe.translate(MidiMessage::PitchBend { channel: 15, value: -1. }),
+               MpeEvent::ZoneExpression { zone: Zone::Upper, expression: Expression::Pitch, value: -2. });
+    // members of the upper zone count down from channel 14; below them is plain MIDI
+    assert_eq!(mpe.member_zone(12), Some(Zone::Upper));
+    assert_eq!(mpe.member_zone(11), None);
+    let outside = MidiMessage::PitchBend { channel: 11, value: 0.5 };
+    assert_eq!(mpe.translate(outside), MpeEvent::Midi(outside));
+    // the lower zone's master channel isn't one until the zone is set up
+    let bend = MidiMessage::PitchBend { channel: 0, value: 0.5 };
+    assert_eq!(mpe.translate(bend), MpeEvent::Midi(bend));
+}
+
+#[test]
+fn the_sender_sets_up_zones_and_bend_ranges() {
+    let mut mpe = MpeTranslator::new();
+    configure(&mut mpe, 0, 10);
+    assert_eq!(mpe.zone(Zone::Lower).map(|z| z.members), Some(10));
+
+    // a 12 semitone bend range for the members, sent on a member channel
+    for message in [cc(3, 101, 0), cc(3, 100, 0), cc(3, 6, 12)].iter() {
+        mpe.translate(*message);
+    }
+    mpe.translate(on(3, 48));
+    assert_eq!(mpe.translate(MidiMessage::PitchBend { channel: 3, value: 1. }),
+               MpeEvent::NoteExpression { channel: 3, note: Some(48), expression: Expression::Pitch, value: 12. });
+
+    // an upper zone of 7 leaves the lower zone with the 7 channels between the masters
+    configure(&mut mpe, 15, 7);
+    assert_eq!(mpe.zone(Zone::Upper).map(|z| z.members), Some(7));
+    assert_eq!(mpe.zone(Zone::Lower).map(|z| z.members), Some(7));
+    assert_eq!(mpe.member_zone(8), Some(Zone::Upper));
+    // and one taking every channel removes it
+    configure(&mut mpe, 15, 15);
+    assert_eq!(mpe.zone(Zone::Lower), None);
+    // no members turns a zone off
+    configure(&mut mpe, 15, 0);
+    assert_eq!(mpe.zone(Zone::Upper), None);
+}
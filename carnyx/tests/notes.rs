@@ -0,0 +1,76 @@
+use carnyx::{NoteEvent, NoteQueue, NoteSender, NOTE_QUEUE_CAPACITY};
+
+// what the keyboard sends for a press on C, dragged across to D, then let go
+fn glissando() -> Vec<NoteEvent> {
+    vec![
+        NoteEvent::On { note: 60, velocity: 0.8 },
+        NoteEvent::Off { note: 60 },
+        NoteEvent::On { note: 62, velocity: 0.5 },
+        NoteEvent::Off { note: 62 },
+    ]
+}
+
+#[test]
+fn notes_leave_the_queue_in_the_order_sent() {
+    let queue = NoteQueue::new(NOTE_QUEUE_CAPACITY);
+    let editor = queue.clone();
+    for event in glissando() {
+        assert!(editor.push(event));
+    }
+    let received: Vec<NoteEvent> = queue.drain().collect();
+    assert_eq!(received, glissando());
+    assert_eq!(received.iter().map(NoteEvent::note).collect::<Vec<_>>(), [60, 60, 62, 62]);
+    assert_eq!(queue.pop(), None);
+}
+
+#[test]
+fn notes_played_between_blocks_are_kept_up_to_capacity() {
+    let queue = NoteQueue::new(NOTE_QUEUE_CAPACITY);
+    let mut pushed = 0;
+    while queue.push(NoteEvent::On { note: (pushed % 128) as u8, velocity: 1. }) {
+        pushed += 1;
+    }
+    assert_eq!(pushed, NOTE_QUEUE_CAPACITY);
+
+    // the next block takes the lot, oldest first, leaving room for more
+    let first = queue.pop();
+    assert_eq!(first, Some(NoteEvent::On { note: 0, velocity: 1. }));
+    assert_eq!(queue.drain().count(), NOTE_QUEUE_CAPACITY - 1);
+    assert!(queue.push(NoteEvent::Off { note: 0 }));
+}
+
+#[test]
+fn note_offs_wait_for_room_rather_than_being_lost() {
+    let queue = NoteQueue::new(NOTE_QUEUE_CAPACITY);
+    let mut sender = NoteSender::new(queue.clone());
+    assert!(sender.send(NoteEvent::On { note: 60, velocity: 1. }));
+    // a burst fills the queue before the next block
+    while queue.push(NoteEvent::On { note: 0, velocity: 1. }) {}
+
+    assert!(!sender.send(NoteEvent::Off { note: 60 }));
+    assert!(!sender.send(NoteEvent::On { note: 62, velocity: 1. }));
+    assert!(sender.is_pending());
+    assert!(!sender.flush());
+
+    queue.drain().for_each(drop);
+    assert!(sender.flush());
+    assert!(!sender.is_pending());
+    // the note on played while the queue was full is dropped, not its note off
+    assert_eq!(queue.drain().collect::<Vec<_>>(), [NoteEvent::Off { note: 60 }]);
+}
+
+#[test]
+fn releasing_all_stops_only_the_held_notes() {
+    let queue = NoteQueue::new(NOTE_QUEUE_CAPACITY);
+    let mut sender = NoteSender::new(queue.clone());
+    for event in glissando() {
+        sender.send(event);
+    }
+    sender.send(NoteEvent::On { note: 64, velocity: 1. });
+    queue.drain().for_each(drop);
+
+    sender.release_all();
+    assert_eq!(queue.drain().collect::<Vec<_>>(), [NoteEvent::Off { note: 64 }]);
+    sender.release_all();
+    assert_eq!(queue.pop(), None);
+}
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+use std::thread;
+
+use carnyx::RefreshGate;
+
+#[test]
+fn dense_automation_sends_one_refresh_until_the_editor_catches_up() {
+    let gate = RefreshGate::new();
+    let sent = (0..1000).filter(|_| gate.claim()).count();
+    assert_eq!(sent, 1);
+    assert!(gate.is_pending());
+
+    // the editor releases before reading, so the next change is sent on
+    gate.release();
+    assert!(!gate.is_pending());
+    assert!(gate.claim());
+    assert!(!gate.claim());
+}
+
+#[test]
+fn only_one_thread_wins_the_claim() {
+    let gate = Arc::new(RefreshGate::new());
+    let claims: usize = (0..4)
+        .map(|_| {
+            let gate = Arc::clone(&gate);
+            thread::spawn(move || (0..1000).filter(|_| gate.claim()).count())
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|thread| thread.join().unwrap())
+        .sum();
+    assert_eq!(claims, 1);
+}
@@ -0,0 +1,92 @@
+use carnyx::process::SilenceFlags;
+use carnyx::{BlockSplitter, MidiMessage, MidiOutput, NoteEvent, SubBlock, TimedMidi};
+use vst::host::HostBuffer;
+
+fn detect(inputs: &[Vec<f32>]) -> SilenceFlags {
+    let mut outputs = vec![vec![0.; inputs[0].len()]];
+    let mut host_buffer: HostBuffer<f32> = HostBuffer::new(inputs.len(), outputs.len());
+    let mut buffer = host_buffer.bind(inputs, &mut outputs);
+    SilenceFlags::detect_inputs(&mut buffer)
+}
+
+#[test]
+fn silence_is_detected_per_channel() {
+    let quiet = vec![1e-8; 64];
+    let mut click = vec![0.; 64];
+    click[63] = 0.01;
+    let flags = detect(&[vec![0.; 64], quiet, click, vec![0.; 64]]);
+    assert_eq!(flags, SilenceFlags(0b1011));
+    assert!(flags.is_silent(0) && flags.is_silent(1) && !flags.is_silent(2));
+    assert!(flags.all_silent(2));
+    assert!(!flags.all_silent(3));
+    // no channels at all are silent
+    assert!(flags.all_silent(0));
+    assert!(!SilenceFlags(u64::MAX).is_silent(64));
+}
+
+// an envelope follower's output, as sent by an envelope to CC effect
+fn cc(value: u8) -> MidiMessage {
+    MidiMessage::ControlChange { channel: 0, controller: 74, value }
+}
+
+#[test]
+fn midi_sent_out_of_order_reaches_the_host_in_order() {
+    let mut events = Vec::with_capacity(8);
+    let mut out = MidiOutput::new(&mut events);
+    assert!(out.is_connected());
+    assert!(out.send(48, cc(90)));
+    assert!(out.send(0, cc(10)));
+    assert!(out.send(48, cc(91)));
+    assert!(out.send(16, cc(40)));
+    assert_eq!(out.dropped(), 0);
+    // messages at the same offset keep the order they were sent in
+    assert_eq!(events, [TimedMidi::new(0, cc(10)), TimedMidi::new(16, cc(40)), TimedMidi::new(48, cc(90)), TimedMidi::new(48, cc(91))]);
+    assert_eq!(events[2].message.to_bytes(), [0xB0, 74, 90]);
+}
+
+#[test]
+fn midi_beyond_the_room_allocated_is_dropped_and_counted() {
+    let mut events = Vec::with_capacity(2);
+    let capacity = events.capacity();
+    let mut out = MidiOutput::new(&mut events);
+    let sent = (0..capacity + 3).filter(|i| out.send(*i, cc(*i as u8))).count();
+    assert_eq!(sent, capacity);
+    assert_eq!(out.dropped(), 3);
+    // the block never grows the vector, which would allocate on the audio thread
+    assert_eq!(events.capacity(), capacity);
+    assert_eq!(events.last().map(|e| e.offset), Some(capacity - 1));
+
+    let mut out = MidiOutput::disconnected();
+    assert!(!out.is_connected());
+    assert!(!out.send(0, cc(1)));
+    assert_eq!(out.dropped(), 1);
+}
+
+fn note(offset: usize, note: u8) -> TimedMidi {
+    TimedMidi::new(offset, MidiMessage::Note { channel: 0, event: NoteEvent::On { note, velocity: 1. } })
+}
+
+// each sub-block's range, with the notes applied at its start
+fn split(block_size: usize, events: &[TimedMidi]) -> Vec<(usize, usize, Vec<u8>)> {
+    let notes = |sub_block: &SubBlock| -> Vec<u8> { sub_block.events.iter().map(|e| e.message.to_bytes()[1]).collect() };
+    BlockSplitter::new(block_size, events).map(|sub_block| (sub_block.start, sub_block.end, notes(&sub_block))).collect()
+}
+
+#[test]
+fn blocks_split_at_each_event() {
+    // a fast run: two notes together, then one a few samples later
+    let run = [note(10, 60), note(10, 64), note(13, 67)];
+    assert_eq!(split(32, &run), [(0, 10, vec![]), (10, 13, vec![60, 64]), (13, 32, vec![67])]);
+    // an event at the first sample needs no empty sub-block before it
+    assert_eq!(split(8, &[note(0, 48)]), [(0, 8, vec![48])]);
+    assert_eq!(split(8, &[]), [(0, 8, vec![])]);
+    let ranges: Vec<_> = BlockSplitter::new(32, &run).map(|sub_block| sub_block.range().len()).collect();
+    assert_eq!(ranges.iter().sum::<usize>(), 32);
+}
+
+#[test]
+fn late_events_land_on_the_last_sample() {
+    assert_eq!(split(16, &[note(4, 50), note(40, 52)]), [(0, 4, vec![]), (4, 15, vec![50]), (15, 16, vec![52])]);
+    // an empty block has nothing to split, or apply events to
+    assert_eq!(BlockSplitter::new(0, &[note(0, 50)]).count(), 0);
+}
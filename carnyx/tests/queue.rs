@@ -0,0 +1,147 @@
+//! The stress test is small enough to run under miri, which checks the queue's unsafe
+//! code as well: `cargo +nightly miri test -p carnyx --test queue`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+use carnyx::EventQueue;
+
+#[test]
+fn empty_queue_pops_nothing() {
+    let queue: EventQueue<u32> = EventQueue::new(4);
+    assert!(queue.is_empty());
+    assert_eq!(queue.pop(), None);
+    assert_eq!(queue.drain().count(), 0);
+
+    assert!(queue.push(1));
+    assert!(!queue.is_empty());
+    assert_eq!(queue.pop(), Some(1));
+    assert!(queue.is_empty());
+    assert_eq!(queue.pop(), None);
+}
+
+#[test]
+fn full_queue_refuses_and_drops_the_value() {
+    let queue = EventQueue::new(4);
+    let value = Arc::new(());
+    for _ in 0..4 {
+        assert!(queue.push(Arc::clone(&value)));
+    }
+    assert!(!queue.push(Arc::clone(&value)), "pushed past capacity");
+    // the refused clone went straight away
+    assert_eq!(Arc::strong_count(&value), 5);
+
+    // one out makes room for exactly one more
+    assert!(queue.pop().is_some());
+    assert!(queue.push(Arc::clone(&value)));
+    assert!(!queue.push(Arc::clone(&value)));
+    assert_eq!(Arc::strong_count(&value), 5);
+}
+
+#[test]
+fn capacity_rounds_up_to_a_power_of_two() {
+    for (asked, holds) in [(0, 2), (1, 2), (3, 4), (5, 8), (8, 8)].iter().copied() {
+        let queue = EventQueue::new(asked);
+        let pushed = (0..holds + 1).take_while(|i| queue.push(*i)).count();
+        assert_eq!(pushed, holds, "asked for {}", asked);
+    }
+}
+
+#[test]
+fn wraps_around_in_order() {
+    let queue = EventQueue::new(4);
+    let mut next_in = 0;
+    let mut next_out = 0;
+    // three in, two out, so the positions pass the capacity many times over at varying offsets
+    for _ in 0..100 {
+        for _ in 0..3 {
+            if queue.push(next_in) {
+                next_in += 1;
+            }
+        }
+        for _ in 0..2 {
+            assert_eq!(queue.pop(), Some(next_out));
+            next_out += 1;
+        }
+    }
+    let rest: Vec<_> = queue.drain().collect();
+    assert_eq!(rest, (next_out..next_in).collect::<Vec<_>>());
+    assert!(next_in > 50);
+}
+
+#[test]
+fn dropping_the_queue_drops_what_is_left() {
+    let value = Arc::new(());
+    {
+        let queue = EventQueue::new(8);
+        for _ in 0..5 {
+            queue.push(Arc::clone(&value));
+        }
+        queue.pop();
+        let _other = queue.clone();
+    }
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+const PRODUCERS: usize = 4;
+const CONSUMERS: usize = 3;
+const PER_PRODUCER: usize = if cfg!(miri) { 200 } else { 20_000 };
+
+#[test]
+fn producers_and_consumers_lose_and_duplicate_nothing() {
+    let queue = EventQueue::new(64);
+    let start = Arc::new(Barrier::new(PRODUCERS + CONSUMERS));
+    let producing = Arc::new(AtomicBool::new(true));
+
+    let producers: Vec<_> = (0..PRODUCERS)
+        .map(|producer| {
+            let (queue, start) = (queue.clone(), Arc::clone(&start));
+            thread::spawn(move || {
+                start.wait();
+                for n in 0..PER_PRODUCER {
+                    while !queue.push((producer, n)) {
+                        thread::yield_now();
+                    }
+                }
+            })
+        })
+        .collect();
+    let consumers: Vec<_> = (0..CONSUMERS)
+        .map(|_| {
+            let (queue, start, producing) = (queue.clone(), Arc::clone(&start), Arc::clone(&producing));
+            thread::spawn(move || {
+                start.wait();
+                let mut seen = Vec::new();
+                loop {
+                    match queue.pop() {
+                        Some(value) => seen.push(value),
+                        None if !producing.load(Ordering::Acquire) && queue.is_empty() => return seen,
+                        None => thread::yield_now(),
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    producing.store(false, Ordering::Release);
+    let mut counts = vec![vec![0u8; PER_PRODUCER]; PRODUCERS];
+    for consumer in consumers {
+        let seen = consumer.join().unwrap();
+        // each consumer sees any one producer's values in the order they were pushed
+        for producer in 0..PRODUCERS {
+            let from: Vec<usize> = seen.iter().filter(|(p, _)| *p == producer).map(|(_, n)| *n).collect();
+            assert!(from.windows(2).all(|pair| pair[0] < pair[1]), "producer {} out of order", producer);
+        }
+        for (producer, n) in seen {
+            counts[producer][n] += 1;
+        }
+    }
+    for (producer, counts) in counts.iter().enumerate() {
+        assert!(counts.iter().all(|count| *count == 1), "producer {}: values lost or popped twice", producer);
+    }
+    assert!(queue.is_empty());
+}
@@ -0,0 +1,119 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+use carnyx::seqlock::MAX_READ_ATTEMPTS;
+use carnyx::SeqLock;
+
+// two halves which writers always keep equal, so a torn read shows
+#[derive(Default)]
+struct Pair {
+    lock: SeqLock,
+    low: AtomicU32,
+    high: AtomicU32,
+}
+
+impl Pair {
+    fn write(&self, value: u32) {
+        self.lock.write(|| {
+            self.low.store(value, Ordering::Relaxed);
+            thread::yield_now();
+            self.high.store(value, Ordering::Relaxed);
+        })
+    }
+
+    // the pair, and how many tries it took
+    fn read(&self) -> ((u32, u32), usize) {
+        let mut attempts = 0;
+        let pair = self.lock.read(|| {
+            attempts += 1;
+            (self.low.load(Ordering::Relaxed), self.high.load(Ordering::Relaxed))
+        });
+        (pair, attempts)
+    }
+}
+
+#[test]
+fn uncontended_reads_take_one_attempt() {
+    let pair = Pair::default();
+    pair.write(7);
+    assert_eq!(pair.read(), ((7, 7), 1));
+    assert_eq!(pair.lock.write(|| 42), 42);
+}
+
+#[test]
+fn readers_never_see_a_torn_write() {
+    let pair = Arc::new(Pair::default());
+    let writing = Arc::new(AtomicBool::new(true));
+    let writer = {
+        let (pair, writing) = (Arc::clone(&pair), Arc::clone(&writing));
+        thread::spawn(move || {
+            for value in 1..=20_000 {
+                pair.write(value);
+            }
+            writing.store(false, Ordering::Release);
+        })
+    };
+    let readers: Vec<_> = (0..3)
+        .map(|_| {
+            let (pair, writing) = (Arc::clone(&pair), Arc::clone(&writing));
+            thread::spawn(move || {
+                let mut last = 0;
+                while writing.load(Ordering::Acquire) {
+                    let ((low, high), attempts) = pair.read();
+                    // only a reader which gave up may see half a write
+                    if attempts < MAX_READ_ATTEMPTS {
+                        assert_eq!(low, high, "torn read after {} attempts", attempts);
+                        assert!(low >= last, "read went back from {} to {}", last, low);
+                        last = low;
+                    }
+                }
+            })
+        })
+        .collect();
+    writer.join().unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    assert_eq!(pair.read(), ((20_000, 20_000), 1));
+}
+
+#[test]
+fn readers_give_up_on_a_stalled_writer() {
+    let pair = Arc::new(Pair::default());
+    pair.write(1);
+    let (started, wait_started) = mpsc::channel();
+    let (finish, wait_finish) = mpsc::channel::<()>();
+    let writer = {
+        let pair = Arc::clone(&pair);
+        thread::spawn(move || {
+            pair.lock.write(|| {
+                pair.low.store(2, Ordering::Relaxed);
+                started.send(()).unwrap();
+                // stalled mid write, as if descheduled
+                wait_finish.recv().unwrap();
+                pair.high.store(2, Ordering::Relaxed);
+            })
+        })
+    };
+    wait_started.recv().unwrap();
+    // the reader takes what it can get rather than wait
+    assert_eq!(pair.read(), ((2, 1), MAX_READ_ATTEMPTS));
+    finish.send(()).unwrap();
+    writer.join().unwrap();
+    assert_eq!(pair.read(), ((2, 2), 1));
+}
+
+#[test]
+fn writes_to_different_locks_may_nest() {
+    let (outer, inner) = (SeqLock::new(), SeqLock::new());
+    assert_eq!(outer.write(|| inner.write(|| 3)), 3);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "nested in a write to the same lock")]
+fn nested_writes_to_one_lock_are_caught() {
+    let lock = SeqLock::new();
+    lock.write(|| lock.write(|| ()));
+}
@@ -0,0 +1,59 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use carnyx::audit;
+use carnyx::{CarnyxDescriptor, Instance, Shared};
+
+// each test its own ids, as the registry is shared by the whole test binary
+#[derive(Debug, Default)]
+struct Levels {
+    peak: AtomicU32,
+}
+
+#[test]
+fn instances_of_one_plugin_share_a_value() {
+    let first = Shared::<Levels>::resolve(0x5e1);
+    let second = Shared::<Levels>::resolve(0x5e1);
+    assert!(Shared::ptr_eq(&first, &second));
+    first.peak.store(7, Ordering::Relaxed);
+    assert_eq!(second.peak.load(Ordering::Relaxed), 7);
+
+    // another plugin gets its own
+    let other = Shared::<Levels>::resolve(0x5e2);
+    assert!(!Shared::ptr_eq(&first, &other));
+}
+
+#[test]
+fn the_value_goes_with_its_last_handle() {
+    let first = Shared::<Levels>::resolve(0x5e3);
+    first.peak.store(3, Ordering::Relaxed);
+    drop(first);
+    assert_eq!(Shared::<Levels>::resolve(0x5e3).peak.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn a_resolved_handle_is_used_without_locking() {
+    let levels = Shared::<Levels>::resolve(0x5e4);
+    let writer = levels.clone();
+    audit::realtime("meter", || writer.peak.store(1, Ordering::Relaxed));
+    assert_eq!(levels.peak.load(Ordering::Relaxed), 1);
+}
+
+#[cfg(feature = "audit")]
+#[test]
+#[should_panic(expected = "shared registry lock")]
+fn resolving_on_the_audio_thread_is_caught() {
+    audit::realtime("meter", || Shared::<Levels>::resolve(0x5e5));
+}
+
+#[test]
+fn instances_are_numbered_from_one_and_numbers_reused() {
+    let descriptor = CarnyxDescriptor::new("Shaper", 0x5e6);
+    let first = Instance::register(&descriptor).unwrap();
+    let second = Instance::register(&descriptor).unwrap();
+    assert_eq!((first.number(), second.number()), (1, 2));
+    assert_eq!(second.count(), 2);
+    drop(first);
+    let third = Instance::register(&descriptor).unwrap();
+    assert_eq!(third.number(), 1);
+    assert_eq!(third.name(), "Shaper 1");
+}
@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use carnyx::carnyx::{BasicParam, CarnyxModel, CarnyxParam};
+use carnyx::state::{self, decode, decode_ids, decode_tag, encode, encode_tag, encode_with_ids};
+
+// three levels, each a parameter with an id
+#[derive(Default)]
+struct Levels([AtomicU32; 3]);
+
+impl Levels {
+    fn get(&self, slot: usize) -> f32 {
+        f32::from_bits(self.0[slot].load(Ordering::Relaxed))
+    }
+
+    fn set(&self, slot: usize, value: f32) {
+        self.0[slot].store(value.to_bits(), Ordering::Relaxed)
+    }
+}
+
+impl CarnyxModel for Levels {
+    type Snap = [f32; 3];
+
+    fn snap(&self) -> [f32; 3] {
+        [self.get(0), self.get(1), self.get(2)]
+    }
+
+    fn set_snap(&self, snap: &[f32; 3]) {
+        for (slot, value) in snap.iter().enumerate() {
+            self.set(slot, *value);
+        }
+    }
+}
+
+fn level(id: &'static str, slot: usize) -> Box<dyn CarnyxParam<Levels>> {
+    let param = BasicParam::new(id, "", move |m: &Levels| m.get(slot), move |m: &Levels, v| m.set(slot, v), move |m: &Levels| m.get(slot).to_string());
+    Box::new(param.with_id(id).with_default(0.5))
+}
+
+fn params(ids: &[(&'static str, usize)]) -> Vec<Box<dyn CarnyxParam<Levels>>> {
+    ids.iter().map(|(id, slot)| level(id, *slot)).collect()
+}
+
+#[test]
+fn state_from_before_ids_loads_by_index() {
+    let bytes = encode(1, &[0.1, 0.2, 0.3]);
+    assert_eq!(decode(&bytes).unwrap(), (1, vec![0.1, 0.2, 0.3]));
+    assert_eq!(decode_ids(&bytes).unwrap(), None);
+    assert_eq!(decode_tag(&bytes).unwrap(), None);
+
+    let model = Levels::default();
+    state::load(&params(&[("low", 0), ("mid", 1), ("high", 2)]), &model, &bytes).unwrap();
+    assert_eq!(model.snap(), [0.1, 0.2, 0.3]);
+}
+
+#[test]
+fn reordered_parameters_load_by_id() {
+    let saved_model = Levels::default();
+    saved_model.set_snap(&[0.1, 0.2, 0.3]);
+    let bytes = state::save(&params(&[("low", 0), ("mid", 1), ("high", 2)]), &saved_model);
+    assert_eq!(decode_ids(&bytes).unwrap(), Some(vec!["low".to_owned(), "mid".to_owned(), "high".to_owned()]));
+
+    // a later version lists them the other way round, and has dropped "mid" for "air"
+    let model = Levels::default();
+    state::load(&params(&[("high", 0), ("air", 1), ("low", 2)]), &model, &bytes).unwrap();
+    assert_eq!(model.snap(), [0.3, 0.5, 0.1]);
+}
+
+#[test]
+fn truncated_id_lists_are_refused() {
+    let bytes = encode_with_ids(1, &[0.1, 0.2], &["first", "second"]);
+    let values_end = 12 + 2 * 4;
+    // anywhere after the ids' marker, up to the last byte of the last id
+    for len in values_end + 4..bytes.len() {
+        assert!(decode_ids(&bytes[..len]).is_err(), "{} of {} bytes read", len, bytes.len());
+        assert!(state::load(&params(&[("first", 0), ("second", 1)]), &Levels::default(), &bytes[..len]).is_err());
+    }
+    // cut before the marker, it reads as state from before ids
+    assert_eq!(decode_ids(&bytes[..values_end + 2]).unwrap(), None);
+    assert!(decode(&bytes[..values_end - 1]).is_err());
+}
+
+#[test]
+fn ids_which_are_not_utf8_are_refused() {
+    let mut bytes = encode_with_ids(1, &[0.25], &["ok"]);
+    let last = bytes.len() - 1;
+    bytes[last] = 0xc3;
+    let error = decode_ids(&bytes).unwrap_err();
+    assert!(error.to_string().contains("not UTF-8"), "{}", error);
+}
+
+#[test]
+fn tags_round_trip_with_and_without_a_color() {
+    let mut bytes = encode_with_ids(1, &[0.25], &["gain"]);
+    encode_tag(&mut bytes, "Lead vox", Some(3));
+    assert_eq!(decode_tag(&bytes).unwrap(), Some(("Lead vox".to_owned(), Some(3))));
+    assert_eq!(decode(&bytes).unwrap(), (1, vec![0.25]));
+
+    // saved as 0, which reads back as no color rather than the first
+    let mut bytes = encode_with_ids(1, &[0.25], &["gain"]);
+    encode_tag(&mut bytes, "Drums", None);
+    let tail = &bytes[bytes.len() - 4..];
+    assert_eq!(tail, &[0, 0, 0, 0]);
+    assert_eq!(decode_tag(&bytes).unwrap(), Some(("Drums".to_owned(), None)));
+}
+
+#[test]
+fn long_nicknames_are_cut_at_a_character_boundary() {
+    // one byte short of the longest length that fits, then a two byte character
+    let nickname = format!("{}é", "n".repeat(u16::MAX as usize - 1));
+    let mut bytes = encode_with_ids(1, &[], &[]);
+    encode_tag(&mut bytes, &nickname, Some(1));
+    let (read, color) = decode_tag(&bytes).unwrap().unwrap();
+    assert_eq!(read, "n".repeat(u16::MAX as usize - 1));
+    assert_eq!(color, Some(1));
+}
+
+#[test]
+fn other_data_is_refused() {
+    assert!(decode(b"RIFF").is_err());
+    assert!(decode(&[]).is_err());
+    assert!(state::load(&params(&[("low", 0)]), &Levels::default(), b"CNXS").is_err());
+}
@@ -0,0 +1,41 @@
+use carnyx::SampleTap;
+
+fn ramp(tap: &SampleTap, from: usize, to: usize) {
+    for i in from..to {
+        tap.push(i as f32);
+    }
+}
+
+#[test]
+fn reads_what_little_has_been_written() {
+    let tap = SampleTap::new(8);
+    let mut out = [-1.; 4];
+    assert_eq!(tap.copy_latest(&mut out), 0);
+
+    ramp(&tap, 0, 3);
+    assert_eq!(tap.copy_latest(&mut out), 3);
+    // the rest is left as it was
+    assert_eq!(out, [0., 1., 2., -1.]);
+}
+
+#[test]
+fn reads_the_latest_samples_oldest_first_across_the_wrap() {
+    let tap = SampleTap::new(8);
+    // two and a half laps
+    ramp(&tap, 0, 20);
+    let mut out = [0.; 5];
+    assert_eq!(tap.copy_latest(&mut out), 5);
+    assert_eq!(out, [15., 16., 17., 18., 19.]);
+
+    // no more than the tap holds
+    let mut out = [0.; 12];
+    assert_eq!(tap.copy_latest(&mut out), 8);
+    assert_eq!(&out[..8], &[12., 13., 14., 15., 16., 17., 18., 19.]);
+}
+
+#[test]
+fn capacity_is_a_power_of_two() {
+    assert_eq!(SampleTap::new(0).capacity(), 2);
+    assert_eq!(SampleTap::new(1000).capacity(), 1024);
+    assert_eq!(SampleTap::new(4096).capacity(), 4096);
+}
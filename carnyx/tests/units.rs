@@ -0,0 +1,31 @@
+use carnyx::units::{format_hz, parse_choice, parse_hz, parse_percent, parse_plain};
+
+#[test]
+fn frequencies_read_back_as_shown() {
+    assert_eq!(format_hz(865.2), "865 Hz");
+    assert_eq!(format_hz(999.7), "1.00 kHz");
+    assert_eq!(format_hz(2400.), "2.40 kHz");
+    assert_eq!(format_hz(12_500.), "12.5 kHz");
+    for text in &["865 Hz", "2.40 kHz", "12.5 kHz"] {
+        let hz = parse_hz(text).unwrap();
+        assert_eq!(&format_hz(hz), text);
+    }
+    assert_eq!(parse_hz(" 1.5K "), Some(1500.));
+    assert_eq!(parse_hz("440hz"), Some(440.));
+    assert_eq!(parse_hz("kHz"), None);
+}
+
+#[test]
+fn typed_values_with_and_without_units() {
+    assert_eq!(parse_percent("50 %"), Some(0.5));
+    assert_eq!(parse_percent("-25"), Some(-0.25));
+    assert_eq!(parse_plain("-6.5 dB", "dB"), Some(-6.5));
+    assert_eq!(parse_plain("3db", "dB"), Some(3.));
+    assert_eq!(parse_plain("3", "dB"), Some(3.));
+    // only its own label is ignored
+    assert_eq!(parse_plain("3 Hz", "dB"), None);
+    assert_eq!(parse_plain("inf", ""), None);
+    assert_eq!(parse_plain("NaN", ""), None);
+    assert_eq!(parse_choice(" soft CLIP", &["Tanh", "Soft clip", "Diode"]), Some(1));
+    assert_eq!(parse_choice("diodes", &["Tanh", "Soft clip", "Diode"]), None);
+}
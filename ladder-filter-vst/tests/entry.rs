@@ -0,0 +1,50 @@
+use std::ffi::c_void;
+use std::ptr;
+
+use carnyx_vst::vst::api::AEffect;
+use carnyx_vst::vst::host::OpCode;
+use ladder_filter_vst::VSTPluginMain;
+
+const EFF_CLOSE: i32 = 1;
+const EFF_SET_SPEAKER_ARRANGEMENT: i32 = 42;
+
+// a host which says it speaks VST 2.4, and nothing else about itself
+extern "C" fn host(_effect: *mut AEffect, opcode: i32, _index: i32, _value: isize, _ptr: *mut c_void, _opt: f32) -> isize {
+    let version: i32 = OpCode::Version.into();
+    if opcode == version { 2400 } else { 0 }
+}
+
+// the start of a VstSpeakerArrangement: its type, then how many channels it has
+fn arrangement(channels: i32) -> [i32; 2] {
+    [0, channels]
+}
+
+fn set_speakers(effect: *mut AEffect, inputs: i32, outputs: i32) -> isize {
+    let (mut inputs, mut outputs) = (arrangement(inputs), arrangement(outputs));
+    // SAFETY: the effect is live until closed, and the arrangements outlive the call
+    unsafe {
+        ((*effect).dispatcher)(effect, EFF_SET_SPEAKER_ARRANGEMENT, 0,
+                               inputs.as_mut_ptr() as isize, outputs.as_mut_ptr() as *mut c_void, 0.)
+    }
+}
+
+#[test]
+fn the_entry_point_makes_an_effect_the_host_can_talk_to() {
+    let effect = VSTPluginMain(host);
+    assert!(!effect.is_null());
+    // SAFETY: just made, and not yet closed
+    let (magic, unique_id, inputs, outputs) =
+        unsafe { ((*effect).magic, (*effect).uniqueId, (*effect).numInputs, (*effect).numOutputs) };
+    assert_eq!(magic, i32::from_be_bytes(*b"VstP"));
+    assert_eq!((unique_id, inputs, outputs), (9263, 4, 2));
+
+    // speaker arrangements reach the plugin, which takes the layouts it has
+    assert_eq!(set_speakers(effect, 1, 1), 1);
+    assert_eq!(set_speakers(effect, 2, 2), 1);
+    assert_eq!(set_speakers(effect, 6, 6), 0);
+
+    // SAFETY: closing frees the effect, which isn't used again
+    unsafe {
+        ((*effect).dispatcher)(effect, EFF_CLOSE, 0, 0, ptr::null_mut(), 0.);
+    }
+}
@@ -0,0 +1,85 @@
+use carnyx::Capabilities;
+use carnyx_vst::can_do;
+use carnyx_vst::vst::api::Supported;
+use carnyx_vst::vst::host::HostBuffer;
+use carnyx_vst::vst::plugin::{CanDo, Plugin};
+use ladder_filter_vst::LadderFilterVST;
+
+const BLOCK_SIZE: usize = 64;
+
+#[test]
+fn processes_without_host_or_editor() {
+    let mut plugin = LadderFilterVST::default();
+    let info = plugin.get_info();
+    assert_eq!(info.unique_id, 9263);
+    assert_eq!((info.name.as_str(), info.vendor.as_str()), ("LadderFilter", "Robert Wittams"));
+    // stereo, with a stereo sidechain, until the host asks for mono
+    assert_eq!((info.inputs, info.outputs), (4, 2));
+
+    plugin.set_sample_rate(44100.);
+    plugin.set_block_size(BLOCK_SIZE as i64);
+    plugin.resume();
+    let params = plugin.get_parameter_object();
+    assert_eq!(params.get_parameter_name(0), "cutoff");
+
+    let mut host_buffer: HostBuffer<f32> = HostBuffer::new(4, 2);
+    let inputs = vec![vec![0.5; BLOCK_SIZE]; 4];
+    let mut outputs = vec![vec![0.; BLOCK_SIZE]; 2];
+    for _ in 0..4 {
+        let mut buffer = host_buffer.bind(&inputs, &mut outputs);
+        plugin.process(&mut buffer);
+    }
+    assert!(outputs.iter().flatten().all(|sample| sample.is_finite()));
+}
+
+#[test]
+fn can_do_answers_from_the_capabilities() {
+    let plugin = LadderFilterVST::default();
+    // notes for keytracking, and program change
+    assert!(matches!(plugin.can_do(CanDo::ReceiveMidiEvent), Supported::Yes));
+    assert!(matches!(plugin.can_do(CanDo::ReceiveEvents), Supported::Yes));
+    assert!(matches!(plugin.can_do(CanDo::SendMidiEvent), Supported::No));
+    assert!(matches!(plugin.can_do(CanDo::Bypass), Supported::No));
+    assert!(matches!(plugin.can_do(CanDo::ReceiveTimeInfo), Supported::Maybe));
+
+    let sender = Capabilities { sends_midi: true, offline: true, ..Capabilities::default() };
+    assert!(matches!(can_do(sender, CanDo::SendEvents), Supported::Yes));
+    assert!(matches!(can_do(sender, CanDo::Offline), Supported::Yes));
+    assert!(matches!(can_do(sender, CanDo::ReceiveMidiEvent), Supported::No));
+}
+
+#[test]
+fn survives_a_scan_without_a_host() {
+    // scanners make and drop instance after instance, asking each a little
+    for _ in 0..3 {
+        let mut plugin = LadderFilterVST::default();
+        let info = plugin.get_info();
+        let params = plugin.get_parameter_object();
+        for index in 0..info.parameters {
+            assert!(!params.get_parameter_name(index).is_empty());
+            params.get_parameter_text(index);
+        }
+        plugin.can_do(CanDo::Bypass);
+        plugin.can_do(CanDo::ReceiveMidiEvent);
+    }
+}
+
+#[test]
+fn suspend_and_resume_clear_ringing() {
+    let mut plugin = LadderFilterVST::default();
+    plugin.set_sample_rate(44100.);
+    plugin.set_block_size(BLOCK_SIZE as i64);
+    plugin.resume();
+
+    let mut host_buffer: HostBuffer<f32> = HostBuffer::new(4, 2);
+    let loud = vec![vec![0.5; BLOCK_SIZE]; 4];
+    let mut outputs = vec![vec![0.; BLOCK_SIZE]; 2];
+    plugin.process(&mut host_buffer.bind(&loud, &mut outputs));
+    assert!(outputs[0].iter().any(|sample| *sample != 0.));
+
+    plugin.suspend();
+    plugin.resume();
+    let silent = vec![vec![0.; BLOCK_SIZE]; 4];
+    plugin.process(&mut host_buffer.bind(&silent, &mut outputs));
+    assert!(outputs.iter().flatten().all(|sample| *sample == 0.), "filter rang on after suspend");
+}
@@ -0,0 +1,51 @@
+use carnyx::{CarnyxModel, CarnyxProcessor, LockSet};
+use carnyx_vst::CarnyxVstPlugin;
+use ladder_filter::LadderProcessor;
+
+fn lock(plugin: &CarnyxVstPlugin<LadderProcessor>, indices: &[usize]) {
+    let mut set = LockSet::default();
+    for index in indices {
+        set.set(*index, true);
+    }
+    plugin.processor().model().locks().unwrap().set(set);
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn a_locked_parameter_ignores_the_host() {
+    let mut plugin = CarnyxVstPlugin::headless(LadderProcessor::new);
+    let params = plugin.get_parameter_object();
+    assert_eq!((params.get_parameter_name(0), params.get_parameter_name(1)), ("cutoff".to_string(), "resonance".to_string()));
+    params.set_parameter(0, 0.3);
+
+    // automation plays back over the locked cutoff without moving it
+    lock(&plugin, &[0]);
+    for value in &[0.9, 0.1, 0.6] {
+        params.set_parameter(0, *value);
+        assert!(close(params.get_parameter(0), 0.3), "cutoff moved to {}", params.get_parameter(0));
+    }
+    // but not over the rest
+    params.set_parameter(1, 0.4);
+    assert!(close(params.get_parameter(1), 0.4));
+
+    // unlocked, the next automation point takes effect
+    lock(&plugin, &[]);
+    params.set_parameter(0, 0.6);
+    assert!(close(params.get_parameter(0), 0.6));
+}
+
+#[test]
+fn the_editor_can_still_move_a_locked_parameter() {
+    let mut plugin = CarnyxVstPlugin::headless(LadderProcessor::new);
+    let params = plugin.get_parameter_object();
+    lock(&plugin, &[1]);
+    // as the editor does, through the parameter itself
+    let model = plugin.processor().model();
+    plugin.processor().all_parameters()[1].set_value(&model, 0.7);
+    assert!(close(params.get_parameter(1), 0.7));
+    params.set_parameter(1, 0.2);
+    assert!(close(params.get_parameter(1), 0.7));
+}
@@ -0,0 +1,12 @@
+use carnyx_vst::conformance::check_parameters;
+use carnyx_vst::vst::plugin::Plugin;
+use ladder_filter_vst::LadderFilterVST;
+
+#[test]
+fn parameters_survive_host_values() {
+    let mut plugin = LadderFilterVST::default();
+    let count = plugin.get_info().parameters as usize;
+    let params = plugin.get_parameter_object();
+    let problems = check_parameters(&*params, count, 0xf022, 200);
+    assert!(problems.is_empty(), "{}", problems.join("\n"));
+}
@@ -0,0 +1,69 @@
+//! Run with `cargo test -p ladder-filter-vst --features audit` to check the host's audio
+//! thread entry points don't allocate or lock. Without the feature the checks do nothing.
+
+use std::sync::{Arc, Mutex};
+
+use carnyx::audit;
+use carnyx::carnyx::{CarnyxModelListener, CarnyxProcessor, ChangeEvent, ChangeOrigin, ParamFilter};
+use carnyx_vst::CarnyxVstPlugin;
+use ladder_filter::LadderProcessor;
+
+// counts allocations inside audit::realtime
+#[cfg(feature = "audit")]
+#[global_allocator]
+static ALLOCATOR: audit::AuditAllocator = audit::AuditAllocator;
+
+// stands in for an editor, which locks and allocates to hear about a change
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<ChangeEvent>>,
+}
+
+impl<Model> CarnyxModelListener<Model> for Recorder {
+    fn notify_change(&self, _model: &Model, event: ChangeEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+#[test]
+fn host_automation_is_realtime_safe() {
+    let mut plugin = CarnyxVstPlugin::headless(LadderProcessor::new);
+    let recorder = Arc::new(Recorder::default());
+    let listener: Arc<dyn CarnyxModelListener<_>> = recorder.clone();
+    plugin.processor().listener().add_listener(&listener, ParamFilter::All);
+    let count = plugin.get_info().parameters;
+    let params = plugin.get_parameter_object();
+
+    audit::realtime("set_parameter", || {
+        for index in 0..count {
+            params.set_parameter(index, 0.3);
+        }
+        params.set_parameter(0, 0.4);
+    });
+    assert!(recorder.events.lock().unwrap().is_empty(), "listeners were called from set_parameter");
+
+    // as the editor does on idle
+    plugin.notify_host_changes();
+    let events = recorder.events.lock().unwrap().clone();
+    assert!(!events.is_empty());
+    assert!(events.iter().all(|event| event.origin == ChangeOrigin::Host));
+    recorder.events.lock().unwrap().clear();
+    plugin.notify_host_changes();
+    assert!(recorder.events.lock().unwrap().is_empty(), "changes were reported twice");
+}
+
+#[test]
+fn a_few_changes_are_reported_by_parameter() {
+    let mut plugin = CarnyxVstPlugin::headless(LadderProcessor::new);
+    let recorder = Arc::new(Recorder::default());
+    let listener: Arc<dyn CarnyxModelListener<_>> = recorder.clone();
+    plugin.processor().listener().add_listener(&listener, ParamFilter::All);
+    let params = plugin.get_parameter_object();
+    params.set_parameter(0, 0.3);
+    params.set_parameter(0, 0.5);
+    params.set_parameter(1, 0.5);
+    plugin.notify_host_changes();
+    let mut changed: Vec<Option<usize>> = recorder.events.lock().unwrap().iter().map(|event| event.param_index).collect();
+    changed.sort();
+    assert_eq!(changed, vec![Some(0), Some(1)]);
+}
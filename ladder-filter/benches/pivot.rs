@@ -0,0 +1,50 @@
+//! The pivot gains at each [`PivotMode`], for how fast they are, after printing how far
+//! each strays from `tanh(x) / x`. Run with `cargo bench -p ladder-filter --bench pivot`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ladder_filter::pivot::{pivot_gain, pivot_gains, PivotMode};
+
+const MODES: [PivotMode; 3] = [PivotMode::Exact, PivotMode::Fast, PivotMode::Vectorized];
+
+// the input and stage states of a driven ladder range over a few units either side of zero
+fn inputs() -> Vec<[f32; 5]> {
+    (0..4096)
+        .map(|i| {
+            let x = (i as f32 - 2048.) / 256.;
+            [x, 0.9 * x, -0.7 * x, 0.5 * x + 1., -x]
+        })
+        .collect()
+}
+
+fn accuracy() {
+    for &mode in MODES.iter() {
+        let error = inputs()
+            .iter()
+            .flat_map(|base| {
+                let gains = pivot_gains(mode, *base);
+                (0..base.len()).map(move |n| (gains[n] - pivot_gain(base[n])).abs())
+            })
+            .fold(0f32, f32::max);
+        println!("{:?}: max error {:.1} dB", mode, 20. * error.max(1e-12).log10());
+    }
+}
+
+fn speed(c: &mut Criterion) {
+    accuracy();
+    let inputs = inputs();
+    let mut group = c.benchmark_group("pivot gains");
+    group.throughput(Throughput::Elements(inputs.len() as u64));
+    for &mode in MODES.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{:?}", mode)), &mode, |b, &mode| {
+            b.iter(|| {
+                for base in &inputs {
+                    black_box(pivot_gains(mode, black_box(*base)));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, speed);
+criterion_main!(benches);
@@ -0,0 +1,88 @@
+//! How long the ladder takes per block, across qualities, drive, block sizes and channel
+//! counts. Run with `cargo bench -p ladder-filter`, adding `--features simd` to compare.
+
+use std::sync::Arc;
+
+use carnyx::carnyx::CarnyxProcessor;
+use carnyx::random::Rng;
+use carnyx::test::{set_parameter, NullCarnyxHost};
+use carnyx::ProcessContext;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ladder_filter::{LadderProcessor, Quality};
+use vst::host::HostBuffer;
+
+const SAMPLE_RATE: f32 = 44100.;
+const BLOCK_SIZES: [usize; 4] = [32, 128, 512, 2048];
+const CHANNELS: [usize; 3] = [1, 2, 8];
+
+/// One mono processor per channel, as hosts run a mono plugin on wider tracks.
+struct Bench {
+    processors: Vec<LadderProcessor>,
+    host_buffer: HostBuffer<f32>,
+    input: Vec<Vec<f32>>,
+    output: Vec<Vec<f32>>,
+}
+
+impl Bench {
+    fn new(channels: usize, quality: Quality, drive: f32, block_size: usize) -> Self {
+        let processors = (0..channels)
+            .map(|_| {
+                let mut processor = LadderProcessor::new(Arc::new(NullCarnyxHost));
+                processor.set_sample_rate(SAMPLE_RATE);
+                processor.model().set_quality(quality);
+                set_parameter(&processor, "cutoff", 0.6);
+                set_parameter(&processor, "resonance", 0.7);
+                set_parameter(&processor, "drive", drive);
+                processor
+            })
+            .collect();
+        let mut rng = Rng::new(0xbe7c);
+        Bench {
+            processors,
+            host_buffer: HostBuffer::new(1, 1),
+            input: vec![(0..block_size).map(|_| 0.5 * rng.next_bipolar()).collect()],
+            output: vec![vec![0.; block_size]],
+        }
+    }
+
+    fn process(&mut self) {
+        let block_size = self.input[0].len();
+        for processor in &mut self.processors {
+            let mut buffer = self.host_buffer.bind(&self.input, &mut self.output);
+            processor.process(&mut buffer, &mut ProcessContext::new(SAMPLE_RATE, block_size));
+        }
+    }
+}
+
+fn block_sizes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("block size");
+    for &quality in Quality::ALL.iter() {
+        // no drive keeps the ladder close to linear
+        for &(mode, drive) in [("linear", 0.), ("driven", 0.8)].iter() {
+            for &block_size in BLOCK_SIZES.iter() {
+                group.throughput(Throughput::Elements(block_size as u64));
+                let id = BenchmarkId::new(format!("{} {}", quality.name(), mode), block_size);
+                group.bench_with_input(id, &block_size, |b, &block_size| {
+                    let mut bench = Bench::new(1, quality, drive, block_size);
+                    b.iter(|| bench.process())
+                });
+            }
+        }
+    }
+    group.finish();
+}
+
+fn channels(c: &mut Criterion) {
+    let mut group = c.benchmark_group("channels");
+    for &channels in CHANNELS.iter() {
+        group.throughput(Throughput::Elements((channels * 512) as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(channels), &channels, |b, &channels| {
+            let mut bench = Bench::new(channels, Quality::Normal, 0.8, 512);
+            b.iter(|| bench.process())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, block_sizes, channels);
+criterion_main!(benches);
@@ -0,0 +1,7 @@
+//! The ladder filter in the devhost, for working on its editor without a DAW.
+
+use ladder_filter::LadderProcessor;
+
+pub fn main() {
+    carnyx_devhost::run(LadderProcessor::new);
+}
@@ -0,0 +1,78 @@
+//! The input saturation stage in front of the ladder.
+
+#[cfg(feature = "gui")]
+use druid::Data;
+use serde::{Deserialize, Serialize};
+
+use carnyx::dsp::{asymmetric_clip, soft_clip};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gui", derive(Data))]
+pub enum DriveType {
+    Tanh,
+    SoftClip,
+    Diode,
+}
+
+impl DriveType {
+    pub const ALL: [DriveType; 3] = [DriveType::Tanh, DriveType::SoftClip, DriveType::Diode];
+    pub const NAMES: [&'static str; 3] = ["Tanh", "Soft clip", "Diode"];
+
+    pub fn name(&self) -> &'static str {
+        DriveType::NAMES[self.index()]
+    }
+
+    pub fn from_index(index: usize) -> DriveType {
+        DriveType::ALL[index.min(DriveType::ALL.len() - 1)]
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn saturate(&self, x: f32) -> f32 {
+        match self {
+            DriveType::Tanh => x.tanh(),
+            DriveType::SoftClip => soft_clip(x),
+            // the negative half conducts later, which adds even harmonics
+            DriveType::Diode => asymmetric_clip(x),
+        }
+    }
+}
+
+/// Saturates its input with a selectable curve. The output is scaled so that a full scale
+/// input comes out at full scale whatever the drive, so turning up the drive changes the
+/// tone rather than the level.
+pub struct DriveStage {
+    curve: DriveType,
+    gain: f32,
+    compensation: f32,
+}
+
+impl Default for DriveStage {
+    fn default() -> Self {
+        DriveStage::new(DriveType::Tanh, 0.)
+    }
+}
+
+impl DriveStage {
+    pub fn new(curve: DriveType, drive: f32) -> Self {
+        let gain = 1. + drive;
+        DriveStage { curve, gain, compensation: 1. / curve.saturate(gain) }
+    }
+
+    /// Only recalculates the compensation when something changed, so this can be called
+    /// every block.
+    pub fn set(&mut self, curve: DriveType, drive: f32) {
+        let gain = 1. + drive;
+        if curve != self.curve || gain != self.gain {
+            self.curve = curve;
+            self.gain = gain;
+            self.compensation = 1. / curve.saturate(gain);
+        }
+    }
+
+    pub fn process(&self, x: f32) -> f32 {
+        self.curve.saturate(x * self.gain) * self.compensation
+    }
+}
@@ -11,74 +11,459 @@
 //! Feedback is clipped independently of the input, so it doesn't disappear at high gains.
 
 use std::f32::consts::PI;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::mem;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc};
 
 use std::fmt::Debug;
 
-use carnyx::buffer::AudioBuffer;
+use carnyx::buffer::{AudioBuffer, AudioBufferExt, BusLayout, Buses};
 use vst::util::AtomicFloat;
-use carnyx::carnyx::{CarnyxModel, CarnyxParam, BasicParam, CarnyxProcessor, CarnyxHost, SettableListener};
+use carnyx::carnyx::{Capabilities, CarnyxModel, CarnyxParam, BasicParam, DiscreteParam, CarnyxProcessor, CarnyxHost, ProcessingMode, SettableListener};
+use carnyx::audition::{AuditionGenerator, AuditionSettings};
+use carnyx::crossfade::PRESET_CROSSFADE_SECONDS;
+use carnyx::preset::{Preset, PresetBank};
+use carnyx::units::{format_hz, parse_hz, parse_percent, parse_plain};
+use carnyx::utility::UtilityParams;
+use carnyx::dsp::one_pole_coefficient;
+use carnyx::{BlockSplitter, CarnyxDescriptor, CommandQueue, DcBlocker, DspLoad, Limiter, ProbeTap, Probes, Instance, LfoModulator, LfoParams, MacroParams, MidiRouter, MidiRoutes, Modulation, PresetCrossfade, TelemetryBus, SeqLock, MidiMessage, NoteQueue, NoteStack, ProcessContext, ParamLocks, SampleTap, InstanceTag, UiState, COMMAND_QUEUE_CAPACITY, NOTE_QUEUE_CAPACITY};
 
-use carnyx_druid::{Dial, DruidEditor, EditorState};
-use druid::widget::{Axis, CrossAxisAlignment, Flex, Label, LabelText, RadioGroup, Slider};
-use druid::{Data, Insets, Lens, LensExt, Widget, WidgetExt};
+use crate::drive::{DriveStage, DriveType};
+use crate::pivot::{pivot_gains, PivotMode};
+
+#[cfg(not(feature = "gui"))]
+use carnyx::carnyx::NoEditor;
+#[cfg(feature = "gui")]
+use carnyx_druid::DruidEditor;
+#[cfg(feature = "gui")]
+use druid::{Data, Lens};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gui")]
+mod editor;
 
 pub struct LadderShared {
     // the "cutoff" parameter. Determines how heavy filtering is
     cutoff: AtomicFloat,
-
-    g: AtomicFloat,
-    // needed to calculate cutoff.
-    sample_rate: AtomicFloat,
     // makes a peak at cutoff
     res: AtomicFloat,
     // used to choose where we want our output to be
     poles: AtomicUsize,
-    // pole_value is just to be able to use get_parameter on poles
-    pole_value: AtomicFloat,
-    // a drive parameter. Just used to increase the volume, which results in heavier distortion
+    // a drive parameter. Increases the gain into the saturation stage
     drive: AtomicFloat,
+    // index of the saturation curve, see DriveType
+    drive_type: AtomicUsize,
+    // index of the Quality
+    quality: AtomicUsize,
+    // how much of the bass lost to resonance is restored by feeding the input back in
+    res_comp: AtomicFloat,
+    // how far the held note moves the cutoff, 1.0 tracks the keyboard exactly
+    keytrack: AtomicFloat,
+    // how far the sidechain envelope opens the cutoff
+    sidechain: AtomicFloat,
+    // whether a stereo sidechain opens both channels together
+    stereo_link: AtomicBool,
+    // index of the ChannelMode
+    channel_mode: AtomicUsize,
+    // whether the side follows the cutoff in mid/side, rather than its own
+    side_link: AtomicBool,
+    // the side's own cutoff in mid/side, unless linked
+    side_cutoff: AtomicFloat,
+    // whether the DC the drive leaves is filtered out
+    dc_block: AtomicBool,
+    // main channels in the current layout, for which parameters apply
+    channels: AtomicUsize,
+    // input trim, output gain and dry/wet
+    utility: UtilityParams,
+    lfo: LfoParams,
+    // knobs which each move several parameters
+    macros: MacroParams,
+    // pitch bend and mod wheel routings
+    midi_routes: MidiRoutes,
+    // the LFOs' and macros' offsets to their targets, added to the parameters' values each block
+    modulation: Modulation,
+    // to the editor, for the modulation rings
+    telemetry: TelemetryBus,
+    // points in the chain the editor can solo, for debugging
+    probes: Probes,
+    // editor page and scroll positions
+    ui: UiState,
+    // parameters locked against host automation
+    locks: ParamLocks,
+    // processing time, recorded by the bridge
+    load: DspLoad,
+    // so a block never starts with half a preset loaded
+    seqlock: SeqLock,
+    crossfade: PresetCrossfade,
+    // numbers this instance among others in the host, for the editor
+    instance: Option<Instance>,
+    // the user's name and color for this instance
+    tag: InstanceTag,
+}
+
+const SCOPE_CAPACITY: usize = 4096;
+// the filter order, by how many poles the output is taken after
+const POLE_NAMES: [&str; 4] = ["1", "2", "3", "4"];
+const STEREO_LINK_NAMES: [&str; 2] = ["Dual mono", "Linked"];
+const SIDE_LINK_NAMES: [&str; 2] = ["Own cutoff", "Follows cutoff"];
+const DC_BLOCK_NAMES: [&str; 2] = ["Off", "On"];
+// the probe points, in signal order
+const PROBE_NAMES: [&str; 6] = ["Post-drive", "Stage 1", "Stage 2", "Stage 3", "Stage 4", "Output"];
+const PROBE_DRIVE: usize = 0;
+const PROBE_FIRST_STAGE: usize = 1;
+const PROBE_OUTPUT: usize = 5;
+// resonance above 4 self oscillates; the nonlinear ladder keeps that oscillation bounded
+const RES_MAX: f32 = 4.5;
+// the top of the resonance range in state before version 1
+const STATE_0_RES_MAX: f32 = 4.;
+// 1: resonance's range grew to RES_MAX
+// 2: the side's cutoff link split from stereo_link
+const STATE_VERSION: u32 = 2;
+const SELF_OSCILLATION_RES: f32 = 4.;
+// nudges a silent, self oscillating filter into oscillation
+const SELF_OSCILLATION_KICK: f32 = 1e-4;
+const SILENCE: f32 = 1e-6;
+// stage voltages beyond this are left over from a blow up rather than the signal
+const STATE_LIMIT: f32 = 4.;
+// keytracking is relative to middle C
+const KEYTRACK_CENTER_NOTE: f32 = 60.;
+// a full scale sidechain at 100% opens the cutoff this many octaves
+const SIDECHAIN_OCTAVES: f32 = 4.;
+const SIDECHAIN_ATTACK_SECONDS: f32 = 0.005;
+const SIDECHAIN_RELEASE_SECONDS: f32 = 0.1;
+// Newton-Raphson iterations run after the pivot solution at high quality
+const HIGH_QUALITY_ITERATIONS: usize = 2;
+
+/// How accurately the nonlinear ladder is solved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gui", derive(Data))]
+pub enum Quality {
+    /// Single fixed-pivot pass with a rational tanh approximation.
+    Eco,
+    /// Single fixed-pivot pass.
+    Normal,
+    /// The fixed-pivot pass refined by Newton-Raphson iterations.
+    High,
+}
+
+impl Quality {
+    pub const ALL: [Quality; 3] = [Quality::Eco, Quality::Normal, Quality::High];
+    pub const NAMES: [&'static str; 3] = ["Eco", "Normal", "High"];
+
+    pub fn name(&self) -> &'static str {
+        Quality::NAMES[self.index()]
+    }
+
+    pub fn from_index(index: usize) -> Quality {
+        Quality::ALL[index.min(Quality::ALL.len() - 1)]
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    fn pivot_mode(&self, vectorized: bool) -> PivotMode {
+        match self {
+            Quality::Eco if vectorized => PivotMode::Vectorized,
+            Quality::Eco => PivotMode::Fast,
+            _ => PivotMode::Exact,
+        }
+    }
+}
+
+/// How a stereo signal is split between the two filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "gui", derive(Data))]
+pub enum ChannelMode {
+    /// One filter per side.
+    LeftRight,
+    /// One filter for the mid, the other for the side, so the side can be filtered
+    /// without collapsing the mix.
+    MidSide,
+}
+
+impl ChannelMode {
+    pub const ALL: [ChannelMode; 2] = [ChannelMode::LeftRight, ChannelMode::MidSide];
+    pub const NAMES: [&'static str; 2] = ["Left/Right", "Mid/Side"];
+
+    pub fn name(&self) -> &'static str {
+        ChannelMode::NAMES[self.index()]
+    }
+
+    pub fn from_index(index: usize) -> ChannelMode {
+        ChannelMode::ALL[index.min(ChannelMode::ALL.len() - 1)]
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Editor actions the processor carries out at the start of the next block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LadderCommand {
+    /// Silence the filter, e.g. after it has been left screaming.
+    ResetFilter,
+    /// Forget all held notes, from MIDI or the editor keyboard.
+    AllNotesOff,
+}
+
+// a setting the modulators can move, looked up from its parameter's id once rather than
+// matched against it every block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModTarget {
+    Cutoff,
+    Resonance,
+    Poles,
+    Drive,
+    DriveType,
+    Quality,
+    ResCompensation,
+    Keytrack,
+    Sidechain,
+    StereoLink,
+    ChannelMode,
+    SideLink,
+    SideCutoff,
+    DcBlock,
+}
+
+impl ModTarget {
+    fn from_id(id: &str) -> Option<ModTarget> {
+        Some(match id {
+            "cutoff" => ModTarget::Cutoff,
+            "resonance" => ModTarget::Resonance,
+            "poles" => ModTarget::Poles,
+            "drive" => ModTarget::Drive,
+            "drive_type" => ModTarget::DriveType,
+            "quality" => ModTarget::Quality,
+            "res_compensation" => ModTarget::ResCompensation,
+            "keytrack" => ModTarget::Keytrack,
+            "sidechain" => ModTarget::Sidechain,
+            "stereo_link" => ModTarget::StereoLink,
+            "channel_mode" => ModTarget::ChannelMode,
+            "side_link" => ModTarget::SideLink,
+            "side_cutoff" => ModTarget::SideCutoff,
+            "dc_block" => ModTarget::DcBlock,
+            _ => return None,
+        })
+    }
+}
+
+// the parameters in processing units, read from the model at the start of each block
+#[derive(Debug, Clone, Copy)]
+struct LadderSettings {
+    cutoff_hz: f32,
+    res: f32,
+    poles: usize,
+    drive: f32,
+    drive_type: DriveType,
+    quality: Quality,
+    res_comp: f32,
+    keytrack: f32,
+    sidechain: f32,
+    stereo_link: bool,
+    channel_mode: ChannelMode,
+    side_link: bool,
+    side_cutoff_hz: f32,
+    dc_block: bool,
 }
 
 pub struct LadderProcessor {
     host: Arc<dyn CarnyxHost>,
     model: Arc<LadderShared>,
     listener: SettableListener<LadderShared>,
+    // notes played on the editor keyboard, used to drive the audition oscillator
+    notes: NoteQueue,
+    commands: CommandQueue<LadderCommand>,
+    audition: AuditionGenerator,
+    // output samples for the editor's oscilloscope
+    scope: Arc<SampleTap>,
+    presets: Arc<PresetBank<LadderParametersSnap>>,
+    // notes from MIDI and the editor keyboard, for keytracking
+    keys: NoteStack,
+    sidechain_envelope: EnvelopeFollower,
+    drive_stage: DriveStage,
+    // after the nonlinear ladder, which leaves DC when the drive is asymmetric
+    dc_blocker: DcBlocker,
+    layout: BusLayout,
+    settings: LadderSettings,
+    // taken from the quality parameter each block
+    quality: Quality,
+    processing_mode: ProcessingMode,
+    // whether eco quality's pivot gains are vectorized
+    vectorized: bool,
+    lfo: LfoModulator,
+    // pitch bend and the mod wheel, as last sent
+    midi: MidiRouter,
+    // the settings behind our own parameters, which the modulators target by index
+    targets: Vec<Option<ModTarget>>,
+    // for the utility's brickwall limiter
+    limiter: Limiter,
+    probe: ProbeTap,
 
     // the output of the different filter stages
     vout: [f32; 4],
     // s is the "state" parameter. In an IIR it would be the last value from the filter
     // In this we find it by trapezoidal integration to avoid the unit delay
     s: [f32; 4],
+    // set while crossfading from the settings before a preset loaded
+    fade: Option<Fade>,
+    // the second channel's filter, swapped in to run it
+    other_channel: ChannelState,
+}
+
+// a channel's filter state, which the processor's own fields hold while it runs
+#[derive(Default)]
+struct ChannelState {
+    vout: [f32; 4],
+    s: [f32; 4],
+    fade: Option<Fade>,
+    sidechain_envelope: EnvelopeFollower,
+    dc_blocker: DcBlocker,
+}
+
+// the filter as it was before a preset loaded, run alongside the new one while fading out
+struct Fade {
+    settings: LadderSettings,
+    drive_stage: DriveStage,
+    dc_blocker: DcBlocker,
+    vout: [f32; 4],
+    s: [f32; 4],
+    remaining: usize,
+    length: usize,
 }
 
 impl CarnyxProcessor for LadderProcessor {
     type Model = LadderShared;
+    #[cfg(feature = "gui")]
     type Editor = DruidEditor<Self::Model>;
+    #[cfg(not(feature = "gui"))]
+    type Editor = NoEditor;
 
     fn set_sample_rate(&mut self, rate: f32) {
-        self.model.sample_rate.set(rate);
+        self.audition.set_sample_rate(rate);
+    }
+
+    fn on_sample_rate_changed(&mut self, _old_rate: f32, _new_rate: f32) {
+        // g is worked out from the context's rate each block, so only the state needs fixing
+        self.settle_state();
+    }
+
+    // nothing rings on from before the host stopped
+    fn activate(&mut self) {
+        self.clear_state();
+    }
+
+    fn deactivate(&mut self) {
+        self.clear_state();
     }
 
     fn parameters(&self) -> Vec<Box<dyn CarnyxParam<Self::Model>>> {
+        let defaults = LadderShared::default();
+        // continuous parameters take their defaults from a fresh model
+        let basic = |param: BasicParam<LadderShared>| {
+            Box::new(param.with_default_from(&defaults)) as Box<dyn CarnyxParam<Self::Model>>
+        };
         vec![
-            Box::new( BasicParam::new("cutoff", "Hz",
-                                      |lp: &LadderShared|lp.get_cutoff(),
-                                      |lp, val|lp.set_cutoff(val),
-                                      |lp| format!("{:.0}", lp.cutoff.get()))),
-            Box::new( BasicParam::new("resonance", "%",
-                                      |lp: &LadderShared|lp.res.get() / 4.,
-                                      |lp, val|lp.res.set(val * 4.),
-                                      |lp| format!("{:.3}", lp.res.get()))),
-            Box::new( BasicParam::new("filter order", "poles",
-                                      |lp: &LadderShared|lp.pole_value.get(),
-                                      |lp, val|lp.set_poles(val),
-                                      |lp| format!("{}", lp.poles.load(Ordering::Relaxed) + 1))),
-            Box::new( BasicParam::new("drive", "%",
-                                      |lp: &LadderShared|lp.drive.get() / 5.,
-                                      |lp, val|lp.drive.set(val * 5.),
-                                      |lp| format!("{:.3}", lp.drive.get()))),
+            basic(BasicParam::new("cutoff", "",
+                            |lp: &LadderShared|lp.get_cutoff(),
+                            |lp, val|lp.set_cutoff(val),
+                            |lp| format_hz(lp.cutoff.get()))
+                .with_id("cutoff")
+                .with_parse(|text| parse_hz(text).map(cutoff_hz_to_normalized))
+                .with_description("The frequency above which the filter starts to cut.")),
+            basic(BasicParam::new("resonance", "",
+                            |lp: &LadderShared|lp.res.get() / RES_MAX,
+                            |lp, val|lp.res.set(val * RES_MAX),
+                            |lp| format!("{:.3}", lp.res.get()))
+                .with_id("resonance")
+                .with_parse(|text| parse_plain(text, "").map(|res| res / RES_MAX))
+                .with_description("Feedback around the ladder. Boosts frequencies near the cutoff, and self oscillates at the top of the range.")),
+            Box::new(DiscreteParam::new("filter order", &POLE_NAMES,
+                               |lp: &LadderShared|lp.poles.load(Ordering::Relaxed),
+                               |lp, index|lp.set_poles(index))
+                .with_id("poles")
+                .with_label("poles")
+                .with_default(defaults.poles.load(Ordering::Relaxed))
+                .with_description("Which stage of the ladder to listen to. Each pole makes the slope 6 dB/octave steeper.")),
+            basic(BasicParam::new("drive", "%",
+                            |lp: &LadderShared|lp.drive.get() / 5.,
+                            |lp, val|lp.drive.set(val * 5.),
+                            |lp| format!("{:.3}", lp.drive.get()))
+                .with_id("drive")
+                .with_parse(|text| parse_plain(text, "%").map(|drive| drive / 5.))
+                .with_description("How hard the input is pushed into the saturation stage.")),
+            Box::new(DiscreteParam::new("drive type", &DriveType::NAMES,
+                               |lp: &LadderShared|lp.get_drive_type().index(),
+                               |lp, index|lp.set_drive_type(DriveType::from_index(index)))
+                .with_id("drive_type")
+                .with_default(defaults.get_drive_type().index())
+                .with_description("The saturation curve: smooth tanh, a harder soft clip, or asymmetric diode clipping.")),
+            Box::new(DiscreteParam::new("quality", &Quality::NAMES,
+                               |lp: &LadderShared|lp.get_quality().index(),
+                               |lp, index|lp.set_quality(Quality::from_index(index)))
+                .with_id("quality")
+                .with_default(defaults.get_quality().index())
+                .with_description("Trades CPU for accuracy of the nonlinear solve. Bounces always use High.")
+                .without_randomize()),
+            basic(BasicParam::new("res compensation", "%",
+                            |lp: &LadderShared|lp.res_comp.get(),
+                            |lp, val|lp.res_comp.set(val),
+                            |lp| format!("{:.0}", lp.res_comp.get() * 100.))
+                .with_id("res_compensation")
+                .with_parse(parse_percent)
+                .with_description("How much of the bass lost to resonance is restored.")),
+            basic(BasicParam::new("keytrack", "%",
+                            |lp: &LadderShared|lp.keytrack.get(),
+                            |lp, val|lp.keytrack.set(val),
+                            |lp| format!("{:.0}", lp.keytrack.get() * 100.))
+                .with_id("keytrack")
+                .with_parse(parse_percent)
+                .with_description("How far the cutoff follows the held note. 100% tracks the keyboard exactly.")),
+            basic(BasicParam::new("sidechain", "%",
+                            |lp: &LadderShared|lp.sidechain.get(),
+                            |lp, val|lp.sidechain.set(val),
+                            |lp| format!("{:.0}", lp.sidechain.get() * 100.))
+                .with_id("sidechain")
+                .with_parse(parse_percent)
+                .with_description("How far the level of the sidechain input opens the cutoff, for auto-wah.")),
+            Box::new(DiscreteParam::new("stereo link", &STEREO_LINK_NAMES,
+                               |lp: &LadderShared|lp.stereo_link.load(Ordering::Relaxed) as usize,
+                               |lp, index|lp.stereo_link.store(index > 0, Ordering::Relaxed))
+                .with_id("stereo_link")
+                .with_default(defaults.stereo_link.load(Ordering::Relaxed) as usize)
+                .with_active(LadderShared::is_stereo)
+                .with_description("Whether a stereo sidechain opens both channels together, by its louder side, or each by its own side.")),
+            Box::new(DiscreteParam::new("channel mode", &ChannelMode::NAMES,
+                               |lp: &LadderShared|lp.get_channel_mode().index(),
+                               |lp, index|lp.set_channel_mode(ChannelMode::from_index(index)))
+                .with_id("channel_mode")
+                .with_default(defaults.get_channel_mode().index())
+                .with_active(LadderShared::is_stereo)
+                .with_description("Filter left and right, or mid and side, e.g. to filter the width without touching the centre.")),
+            Box::new(DiscreteParam::new("side link", &SIDE_LINK_NAMES,
+                               |lp: &LadderShared|lp.side_link.load(Ordering::Relaxed) as usize,
+                               |lp, index|lp.side_link.store(index > 0, Ordering::Relaxed))
+                .with_id("side_link")
+                .with_default(defaults.side_link.load(Ordering::Relaxed) as usize)
+                .with_active(LadderShared::is_stereo)
+                .with_description("In mid/side, whether the side follows the cutoff or has its own.")),
+            basic(BasicParam::new("side cutoff", "",
+                            |lp: &LadderShared|cutoff_hz_to_normalized(lp.side_cutoff.get()),
+                            |lp, val|lp.side_cutoff.set(normalized_to_cutoff_hz(val)),
+                            |lp| format_hz(lp.side_cutoff.get()))
+                .with_id("side_cutoff")
+                .with_parse(|text| parse_hz(text).map(cutoff_hz_to_normalized))
+                .with_active(LadderShared::is_stereo)
+                .with_description("The side's cutoff in mid/side mode, when the side link is off.")),
+            Box::new(DiscreteParam::new("dc blocker", &DC_BLOCK_NAMES,
+                               |lp: &LadderShared|lp.dc_block.load(Ordering::Relaxed) as usize,
+                               |lp, index|lp.dc_block.store(index > 0, Ordering::Relaxed))
+                .with_id("dc_block")
+                .with_default(defaults.dc_block.load(Ordering::Relaxed) as usize)
+                .with_description("Filters out the DC offset asymmetric drive adds, which otherwise eats headroom.")
+                .without_randomize()),
         ]
     }
 
@@ -88,21 +473,77 @@ impl CarnyxProcessor for LadderProcessor {
 
 
 
-    fn editor(&self) -> Self::Editor {
-        DruidEditor::new(
-            Arc::clone(&self.host),
-            self.listener.clone(),
-            Arc::clone(&self.model),
-            make_editor_widget,
-        )
+    #[cfg(feature = "gui")]
+    fn editor(&self) -> Option<Self::Editor> {
+        Some(editor::editor(self))
     }
 
-    fn process(&mut self, buffer: &mut AudioBuffer<f32>) {
-        for (input_buffer, output_buffer) in buffer.zip() {
-            for (input_sample, output_sample) in input_buffer.iter().zip(output_buffer) {
-                self.tick_pivotal(*input_sample);
-                // the poles parameter chooses which filter stage we take our output from.
-                *output_sample = self.vout[self.model.poles.load(Ordering::Relaxed)];
+    fn process(&mut self, buffer: &mut AudioBuffer<f32>, context: &mut ProcessContext) {
+        self.update_control(context);
+        let trim = self.model.utility.input_gain();
+        let sample_rate = context.sample_rate;
+        let sidechain_amount = self.settings.sidechain * SIDECHAIN_OCTAVES;
+        self.sidechain_envelope.set_sample_rate(sample_rate);
+        self.other_channel.sidechain_envelope.set_sample_rate(sample_rate);
+        self.dc_blocker.set_sample_rate(sample_rate);
+        self.other_channel.dc_blocker.set_sample_rate(sample_rate);
+        self.drive_stage.set(self.settings.drive_type, self.settings.drive);
+        // bounces always get the most accurate solve
+        self.quality = match self.processing_mode {
+            ProcessingMode::Offline => Quality::High,
+            ProcessingMode::Realtime => self.settings.quality,
+        };
+        let Buses { main, sidechain, outputs } = buffer.buses(&self.layout);
+        let (sidechain_left, sidechain_right) = match sidechain.len() {
+            _ if sidechain_amount <= 0. => (None, None),
+            0 => (None, None),
+            1 => (Some(sidechain.get(0)), None),
+            _ => (Some(sidechain.get(0)), Some(sidechain.get(1))),
+        };
+        let (mut main, mut outputs) = (main.into_iter(), outputs.into_iter());
+        let (left_input, left_output) = match main.next().zip(outputs.next()) {
+            Some(channel) => channel,
+            None => return,
+        };
+        let mut right = main.next().zip(outputs.next());
+        // keytracking follows notes from the sample they arrive at
+        for sub_block in BlockSplitter::new(left_input.len(), context.events) {
+            for timed in sub_block.events {
+                if let MidiMessage::Note { event, .. } = timed.message {
+                    self.keys.apply(event);
+                }
+            }
+            let octaves = self.keytrack_octaves();
+            let g = self.cutoff_g(octaves, sample_rate);
+            for i in sub_block.range() {
+                let audition = self.audition.next_sample();
+                // linked, both channels follow the louder side of the sidechain
+                let (key_left, key_right) = match (sidechain_left, sidechain_right) {
+                    (Some(first), Some(second)) if self.settings.stereo_link => {
+                        let louder = first[i].abs().max(second[i].abs());
+                        (Some(louder), Some(louder))
+                    }
+                    (Some(first), Some(second)) => (Some(first[i]), Some(second[i])),
+                    (Some(first), None) => (Some(first[i]), Some(first[i])),
+                    _ => (None, None),
+                };
+                // hosts may alias inputs and outputs, so the input samples are read before
+                // the output samples are written
+                let left_sample = left_input[i] * trim + audition;
+                let scoped = match &mut right {
+                    Some((input, output)) => {
+                        let right_sample = input[i] * trim + audition;
+                        let (left, right) = self.tick_stereo(left_sample, right_sample, (key_left, key_right), g, octaves, sample_rate);
+                        left_output[i] = left;
+                        output[i] = right;
+                        0.5 * (left + right)
+                    }
+                    None => {
+                        left_output[i] = self.tick_channel(left_sample, key_left, g, octaves, sample_rate);
+                        left_output[i]
+                    }
+                };
+                self.scope.push(scoped);
             }
         }
     }
@@ -110,6 +551,62 @@ impl CarnyxProcessor for LadderProcessor {
     fn listener(&self) -> SettableListener<Self::Model> {
         self.listener.clone()
     }
+
+    fn presets(&self) -> Option<Arc<PresetBank<LadderParametersSnap>>> {
+        Some(Arc::clone(&self.presets))
+    }
+
+    fn descriptor(&self) -> CarnyxDescriptor {
+        ladder_descriptor()
+    }
+
+    fn bus_layout(&self) -> BusLayout {
+        self.layout
+    }
+
+    // true mono on mono tracks, two filters on stereo ones
+    fn supported_layouts(&self) -> Vec<BusLayout> {
+        vec![BusLayout::new(1, 1).with_sidechain(1), BusLayout::new(2, 2).with_sidechain(2)]
+    }
+
+    fn set_layout(&mut self, layout: BusLayout) -> bool {
+        if !self.supported_layouts().contains(&layout) {
+            return false;
+        }
+        self.layout = layout;
+        self.model.channels.store(layout.main_inputs, Ordering::Relaxed);
+        self.clear_state();
+        true
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        // notes for keytracking, as well as program change
+        Capabilities { receives_midi: true, ..Capabilities::default() }
+    }
+
+    fn set_processing_mode(&mut self, mode: ProcessingMode) {
+        self.processing_mode = mode;
+    }
+
+    fn output_limiter(&mut self) -> Option<&mut Limiter> {
+        Some(&mut self.limiter)
+    }
+
+    fn is_silent(&self) -> bool {
+        // a self oscillating filter makes sound from nothing, whether the resonance is set
+        // that high or modulated there
+        self.model.res.get().max(self.settings.res) < SELF_OSCILLATION_RES
+            && !self.audition.is_active()
+            && self.notes.is_empty()
+            && self.fade.is_none()
+            && self.other_channel.fade.is_none()
+            && self.vout.iter().chain(self.s.iter()).all(|v| v.abs() < SILENCE)
+            && self.other_channel.vout.iter().chain(self.other_channel.s.iter()).all(|v| v.abs() < SILENCE)
+    }
+
+    fn skipped(&mut self, context: &mut ProcessContext) {
+        self.update_control(context);
+    }
 }
 
 impl CarnyxModel for LadderShared {
@@ -121,27 +618,195 @@ impl CarnyxModel for LadderShared {
             res: self.res.get(),
             poles: self.poles.load(Ordering::Relaxed),
             drive: self.drive.get(),
+            drive_type: self.get_drive_type(),
+            quality: self.get_quality(),
+            res_comp: self.res_comp.get(),
+            keytrack: self.keytrack.get(),
+            sidechain: self.sidechain.get(),
+            stereo_link: self.stereo_link.load(Ordering::Relaxed),
+            channel_mode: self.get_channel_mode(),
+            side_link: self.side_link.load(Ordering::Relaxed),
+            side_cutoff: cutoff_hz_to_normalized(self.side_cutoff.get()),
+            dc_block: self.dc_block.load(Ordering::Relaxed),
         }
     }
 
     fn set_snap(&self, snap: &LadderParametersSnap) {
-        self.set_cutoff(snap.cutoff);
-        self.res.set(snap.res);
-        self.set_poles_usize(snap.poles);
-        self.drive.set(snap.drive);
+        self.seqlock.write(|| {
+            self.set_cutoff(snap.cutoff);
+            self.res.set(snap.res);
+            self.set_poles(snap.poles);
+            self.drive.set(snap.drive);
+            self.set_drive_type(snap.drive_type);
+            self.set_quality(snap.quality);
+            self.res_comp.set(snap.res_comp);
+            self.keytrack.set(snap.keytrack);
+            self.sidechain.set(snap.sidechain);
+            self.stereo_link.store(snap.stereo_link, Ordering::Relaxed);
+            self.set_channel_mode(snap.channel_mode);
+            self.side_link.store(snap.side_link, Ordering::Relaxed);
+            self.side_cutoff.set(normalized_to_cutoff_hz(snap.side_cutoff));
+            self.dc_block.store(snap.dc_block, Ordering::Relaxed);
+        })
+    }
+
+    fn seqlock(&self) -> Option<&SeqLock> {
+        Some(&self.seqlock)
+    }
+
+    fn state_version(&self) -> u32 {
+        STATE_VERSION
+    }
+
+    fn migrate(&self, old_version: u32, _state: &[u8]) -> Option<LadderParametersSnap> {
+        let mut snap = self.snap();
+        if old_version < 1 {
+            // the same normalized value meant less resonance
+            snap.res *= STATE_0_RES_MAX / RES_MAX;
+        }
+        if old_version < 2 {
+            // the stereo link also linked the side
+            snap.side_link = snap.stereo_link;
+        }
+        Some(snap)
+    }
+
+    fn preset_crossfade(&self) -> Option<&PresetCrossfade> {
+        Some(&self.crossfade)
+    }
+
+    fn utility(&self) -> Option<&UtilityParams> {
+        Some(&self.utility)
+    }
+
+    fn lfo(&self) -> Option<&LfoParams> {
+        Some(&self.lfo)
+    }
+
+    fn macros(&self) -> Option<&MacroParams> {
+        Some(&self.macros)
+    }
+
+    fn midi_routes(&self) -> Option<&MidiRoutes> {
+        Some(&self.midi_routes)
+    }
+
+    fn modulation(&self) -> Option<&Modulation> {
+        Some(&self.modulation)
+    }
+
+    fn telemetry(&self) -> Option<&TelemetryBus> {
+        Some(&self.telemetry)
+    }
+
+    fn ui_state(&self) -> Option<&UiState> {
+        Some(&self.ui)
+    }
+
+    fn locks(&self) -> Option<&ParamLocks> {
+        Some(&self.locks)
+    }
+
+    fn dsp_load(&self) -> Option<&DspLoad> {
+        Some(&self.load)
+    }
+
+    fn probes(&self) -> Option<&Probes> {
+        Some(&self.probes)
+    }
+
+    fn instance(&self) -> Option<&Instance> {
+        self.instance.as_ref()
     }
 
+    fn instance_tag(&self) -> Option<&InstanceTag> {
+        Some(&self.tag)
+    }
 }
 
-#[derive(Data, Clone, Lens, Debug)]
+// fields missing from older JSON presets take their defaults
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "gui", derive(Data, Lens))]
+#[serde(default)]
 pub struct LadderParametersSnap {
     cutoff: f32,
     // makes a peak at cutoff
     res: f32,
     // used to choose where we want our output to be
     poles: usize,
-    // a drive parameter. Just used to increase the volume, which results in heavier distortion
+    // a drive parameter. Increases the gain into the saturation stage
     drive: f32,
+    // the saturation curve
+    drive_type: DriveType,
+    quality: Quality,
+    // resonance passband gain compensation
+    res_comp: f32,
+    // cutoff keytracking amount
+    keytrack: f32,
+    // cutoff modulation by the sidechain envelope
+    sidechain: f32,
+    // one sidechain envelope for both channels, rather than one each
+    stereo_link: bool,
+    channel_mode: ChannelMode,
+    // the side follows the cutoff, rather than side_cutoff
+    side_link: bool,
+    // the side's cutoff in mid/side, normalized like cutoff
+    side_cutoff: f32,
+    // remove the drive's DC offset
+    dc_block: bool,
+}
+
+fn factory_presets() -> Vec<Preset<LadderParametersSnap>> {
+    let init = LadderShared::default().snap();
+    vec![
+        Preset::new("Init", init.clone()),
+        Preset::new("Acid", LadderParametersSnap {
+            cutoff: cutoff_hz_to_normalized(600.),
+            res: 3.6,
+            drive: 1.5,
+            keytrack: 0.5,
+            ..init.clone()
+        }),
+        Preset::new("Warm", LadderParametersSnap {
+            cutoff: cutoff_hz_to_normalized(4000.),
+            res: 0.5,
+            poles: 1,
+            drive: 0.8,
+            res_comp: 1.,
+            ..init.clone()
+        }),
+        Preset::new("Diode Grit", LadderParametersSnap {
+            cutoff: cutoff_hz_to_normalized(2000.),
+            res: 2.5,
+            drive: 3.,
+            drive_type: DriveType::Diode,
+            ..init.clone()
+        }),
+        Preset::new("Sine Oscillator", LadderParametersSnap {
+            res: 4.2,
+            keytrack: 1.,
+            ..init.clone()
+        }),
+        Preset::new("Auto-wah", LadderParametersSnap {
+            cutoff: cutoff_hz_to_normalized(300.),
+            res: 3.,
+            poles: 1,
+            sidechain: 0.8,
+            ..init
+        }),
+    ]
+}
+
+fn ladder_descriptor() -> CarnyxDescriptor {
+    CarnyxDescriptor::new("LadderFilter", 9263)
+        .with_vendor("Robert Wittams")
+        .with_bus_layout(BusLayout::new(1, 1).with_sidechain(1))
+}
+
+impl Default for LadderParametersSnap {
+    fn default() -> Self {
+        LadderShared::default().snap()
+    }
 }
 
 impl Default for LadderShared {
@@ -150,10 +815,32 @@ impl Default for LadderShared {
             cutoff: AtomicFloat::new(1000.),
             res: AtomicFloat::new(2.),
             poles: AtomicUsize::new(3),
-            pole_value: AtomicFloat::new(1.),
             drive: AtomicFloat::new(0.),
-            sample_rate: AtomicFloat::new(44100.),
-            g: AtomicFloat::new(0.07135868),
+            drive_type: AtomicUsize::new(DriveType::Tanh.index()),
+            quality: AtomicUsize::new(Quality::Normal.index()),
+            res_comp: AtomicFloat::new(0.),
+            keytrack: AtomicFloat::new(0.),
+            sidechain: AtomicFloat::new(0.),
+            stereo_link: AtomicBool::new(true),
+            channel_mode: AtomicUsize::new(ChannelMode::LeftRight.index()),
+            side_link: AtomicBool::new(true),
+            side_cutoff: AtomicFloat::new(1000.),
+            dc_block: AtomicBool::new(true),
+            channels: AtomicUsize::new(ladder_descriptor().bus_layout.main_inputs),
+            utility: UtilityParams::default(),
+            lfo: LfoParams::default(),
+            macros: MacroParams::default(),
+            midi_routes: MidiRoutes::default(),
+            modulation: Modulation::new(),
+            telemetry: TelemetryBus::default(),
+            probes: Probes::new(&PROBE_NAMES),
+            ui: UiState::new(),
+            locks: ParamLocks::new(),
+            load: DspLoad::new(),
+            seqlock: SeqLock::new(),
+            crossfade: PresetCrossfade::new(),
+            instance: None,
+            tag: InstanceTag::new(),
         }
     }
 }
@@ -162,12 +849,209 @@ impl Default for LadderShared {
 impl LadderProcessor {
 
     pub fn new(host: Arc<dyn CarnyxHost>)->Self{
-        LadderProcessor {
+        let model = Arc::new(LadderShared {
+            instance: Instance::register(&ladder_descriptor()),
+            ..LadderShared::default()
+        });
+        let mut processor = LadderProcessor {
             host,
             listener: SettableListener::new(),
-            model: Arc::new(LadderShared::default()),
+            settings: model.settings(),
+            model,
+            notes: NoteQueue::new(NOTE_QUEUE_CAPACITY),
+            commands: CommandQueue::new(COMMAND_QUEUE_CAPACITY),
+            audition: AuditionGenerator::new(Arc::new(AuditionSettings::default())),
+            presets: Arc::new(PresetBank::new(factory_presets())),
+            scope: Arc::new(SampleTap::new(SCOPE_CAPACITY)),
+            keys: NoteStack::default(),
+            sidechain_envelope: EnvelopeFollower::default(),
+            drive_stage: DriveStage::default(),
+            dc_blocker: DcBlocker::default(),
+            layout: ladder_descriptor().bus_layout,
+            quality: Quality::Normal,
+            processing_mode: ProcessingMode::default(),
+            vectorized: cfg!(feature = "simd"),
             vout: [0f32; 4],
             s: [0f32; 4],
+            fade: None,
+            other_channel: ChannelState::default(),
+            lfo: LfoModulator::default(),
+            midi: MidiRouter::default(),
+            targets: Vec::new(),
+            limiter: Limiter::new(),
+            probe: ProbeTap::default(),
+        };
+        processor.targets = processor.parameters().iter().map(|param| ModTarget::from_id(param.id())).collect();
+        processor
+    }
+
+    /// Whether eco quality evaluates the stage pivots in one vector, rather than the
+    /// `simd` feature deciding.
+    pub fn set_vectorized(&mut self, vectorized: bool) {
+        self.vectorized = vectorized;
+    }
+
+    /// The queue editors send [`LadderCommand`]s through. Clones share the queue.
+    pub fn commands(&self) -> CommandQueue<LadderCommand> {
+        self.commands.clone()
+    }
+
+    fn command(&mut self, command: LadderCommand) {
+        match command {
+            LadderCommand::ResetFilter => self.clear_state(),
+            LadderCommand::AllNotesOff => {
+                self.keys.clear();
+                self.audition.all_notes_off();
+            }
+        }
+    }
+
+    // the once a block work, which carries on through skipped blocks so modulation keeps
+    // its place: commands, notes, and the settings with the modulators moved on
+    fn update_control(&mut self, context: &ProcessContext) {
+        while let Some(command) = self.commands.pop() {
+            self.command(command);
+        }
+        for note in self.notes.drain() {
+            self.audition.note(note);
+            self.keys.apply(note);
+        }
+        let previous = self.settings;
+        self.settings = self.model.settings();
+        self.probe.update(&self.model.probes);
+        self.model.modulation.clear();
+        self.lfo.process(&self.model.lfo, &self.model.modulation, context);
+        self.model.macros.modulate(&self.model.modulation);
+        self.midi.process(&self.model.midi_routes, &self.model.modulation, context.events);
+        self.modulate();
+        self.model.modulation.send_telemetry(&self.model.telemetry, self.targets.len());
+        if self.model.crossfade.take() {
+            self.start_fade(previous, context.sample_rate);
+        }
+    }
+
+    // the modulators move their targets in this block's settings only, so the parameters
+    // themselves, as the host and editor see them, stay put
+    fn modulate(&mut self) {
+        for (index, target) in self.targets.iter().enumerate() {
+            let offset = self.model.modulation.offset(index);
+            match target {
+                Some(target) if offset != 0. => self.settings.modulate(*target, offset),
+                _ => (),
+            }
+        }
+    }
+
+    fn clear_state(&mut self) {
+        self.vout = [0f32; 4];
+        self.lfo.reset();
+        self.midi.reset();
+        self.s = [0f32; 4];
+        self.fade = None;
+        self.sidechain_envelope.reset();
+        self.dc_blocker.reset();
+        self.limiter.reset();
+        self.other_channel = ChannelState::default();
+    }
+
+    // exchange the filter state in the processor's fields with the other channel's
+    fn swap_channel(&mut self) {
+        let other = &mut self.other_channel;
+        mem::swap(&mut self.vout, &mut other.vout);
+        mem::swap(&mut self.s, &mut other.s);
+        mem::swap(&mut self.fade, &mut other.fade);
+        mem::swap(&mut self.sidechain_envelope, &mut other.sidechain_envelope);
+        mem::swap(&mut self.dc_blocker, &mut other.dc_blocker);
+    }
+
+    // a sample through each filter, as left and right or as mid and side
+    fn tick_stereo(&mut self, left: f32, right: f32, keys: (Option<f32>, Option<f32>), g: f32, octaves: f32, sample_rate: f32) -> (f32, f32) {
+        let mid_side = self.settings.channel_mode == ChannelMode::MidSide;
+        let (first, second) = if mid_side { (0.5 * (left + right), 0.5 * (left - right)) } else { (left, right) };
+        let first = self.tick_channel(first, keys.0, g, octaves, sample_rate);
+        self.swap_channel();
+        let second = if mid_side && !self.settings.side_link {
+            let octaves = octaves + (self.settings.side_cutoff_hz / self.settings.cutoff_hz).log2();
+            self.tick_channel(second, keys.1, self.cutoff_g(octaves, sample_rate), octaves, sample_rate)
+        } else {
+            self.tick_channel(second, keys.1, g, octaves, sample_rate)
+        };
+        self.swap_channel();
+        if mid_side {
+            (first + second, first - second)
+        } else {
+            (first, second)
+        }
+    }
+
+    // one sample through the current channel's filter
+    fn tick_channel(&mut self, input: f32, sidechain: Option<f32>, g: f32, octaves: f32, sample_rate: f32) -> f32 {
+        // auto-wah: the sidechain level sweeps the cutoff up, per sample
+        let (g, octaves) = match sidechain {
+            Some(sidechain) => {
+                let level = self.sidechain_envelope.next(sidechain);
+                let octaves = octaves + level * self.settings.sidechain * SIDECHAIN_OCTAVES;
+                (self.cutoff_g(octaves, sample_rate), octaves)
+            }
+            None => (g, octaves),
+        };
+        let output = self.tick_pivotal(input, g);
+        self.probe.probe(PROBE_OUTPUT, output);
+        // a soloed probe is heard as is, as the old filter would overwrite it
+        if self.fade.is_some() && !self.probe.is_soloing() {
+            self.fade_sample(input, octaves, sample_rate, output)
+        } else {
+            self.probe.output(output)
+        }
+    }
+
+    // both filters start from the current state, so they only differ by their settings
+    fn start_fade(&mut self, previous: LadderSettings, sample_rate: f32) {
+        let drive_stage = || DriveStage::new(previous.drive_type, previous.drive);
+        let length = ((PRESET_CROSSFADE_SECONDS * sample_rate) as usize).max(1);
+        self.fade = Some(Fade { settings: previous, drive_stage: drive_stage(), dc_blocker: self.dc_blocker, vout: self.vout, s: self.s, remaining: length, length });
+        let other = &mut self.other_channel;
+        other.fade = Some(Fade { settings: previous, drive_stage: drive_stage(), dc_blocker: other.dc_blocker, vout: other.vout, s: other.s, remaining: length, length });
+    }
+
+    fn swap_fade(&mut self, fade: &mut Fade) {
+        mem::swap(&mut self.settings, &mut fade.settings);
+        mem::swap(&mut self.drive_stage, &mut fade.drive_stage);
+        mem::swap(&mut self.dc_blocker, &mut fade.dc_blocker);
+        mem::swap(&mut self.vout, &mut fade.vout);
+        mem::swap(&mut self.s, &mut fade.s);
+    }
+
+    // run the old filter for a sample and mix it with the new one's output
+    fn fade_sample(&mut self, input: f32, octaves: f32, sample_rate: f32, output: f32) -> f32 {
+        let mut fade = match self.fade.take() {
+            Some(fade) => fade,
+            None => return output,
+        };
+        self.swap_fade(&mut fade);
+        let g = self.cutoff_g(octaves, sample_rate);
+        let old_output = self.tick_pivotal(input, g);
+        self.swap_fade(&mut fade);
+        fade.remaining -= 1;
+        let old_gain = fade.remaining as f32 / fade.length as f32;
+        if fade.remaining > 0 {
+            self.fade = Some(fade);
+        }
+        old_output * old_gain + output * (1. - old_gain)
+    }
+
+    // Keep the stage voltages, so the output doesn't click, but drop the integrators'
+    // history, which only makes sense at the rate it was built up at. Anything not
+    // finite or out of range is cleared.
+    fn settle_state(&mut self) {
+        self.fade = None;
+        self.other_channel.fade = None;
+        let other = &mut self.other_channel;
+        let states = self.vout.iter_mut().zip(self.s.iter_mut()).chain(other.vout.iter_mut().zip(other.s.iter_mut()));
+        for (vout, s) in states {
+            let v = if vout.is_finite() { vout.max(-STATE_LIMIT).min(STATE_LIMIT) } else { 0. };
+            *vout = v;
+            *s = v;
         }
     }
 
@@ -178,32 +1062,67 @@ impl LadderProcessor {
         self.s[2] = 2. * self.vout[2] - self.s[2];
         self.s[3] = 2. * self.vout[3] - self.s[3];
     }
-    // performs a complete filter process (mystran's method)
-    fn tick_pivotal(&mut self, input: f32) {
-        let g = self.model.g.get();
-        let res = self.model.res.get();
-        let drive = self.model.drive.get();
+    // how far the held note moves the cutoff, if keytracking
+    fn keytrack_octaves(&self) -> f32 {
+        let keytrack = self.settings.keytrack;
+        match self.keys.current() {
+            Some(note) if keytrack > 0. => (note as f32 - KEYTRACK_CENTER_NOTE) / 12. * keytrack,
+            _ => 0.,
+        }
+    }
+
+    // g for the cutoff moved by some octaves
+    fn cutoff_g(&self, octaves: f32, sample_rate: f32) -> f32 {
+        let cutoff_hz = (self.settings.cutoff_hz * 2f32.powf(octaves)).min(sample_rate * 0.49);
+        // bilinear transformation for g gives us a very accurate cutoff
+        (PI * cutoff_hz / sample_rate).tan()
+    }
 
-        if drive > 0. {
-            self.run_ladder_nonlinear(g, res, input * (drive + 0.7));
+    // performs a complete filter process (mystran's method), returning the output
+    fn tick_pivotal(&mut self, input: f32, g: f32) -> f32 {
+        let res = self.settings.res;
+        let drive = self.settings.drive;
+        // the ladder's passband gain is 1 / (1 + res), so boosting the input by the same
+        // factor mixes back in the bass that resonance takes away
+        let input = input * (1. + self.settings.res_comp * res);
+        let self_oscillating = res >= SELF_OSCILLATION_RES;
+        let input = if self_oscillating && self.vout[3].abs() < SILENCE {
+            input + SELF_OSCILLATION_KICK
+        } else {
+            input
+        };
+
+        // the linear ladder has nothing to limit self oscillation, so it would blow up
+        let nonlinear = drive > 0. || self_oscillating;
+        if nonlinear {
+            let input = if drive > 0. { self.drive_stage.process(input) } else { input };
+            self.probe.probe(PROBE_DRIVE, input);
+            self.run_ladder_nonlinear(g, res, input);
+            if self.quality == Quality::High {
+                self.refine_ladder_nonlinear(g, res, input, HIGH_QUALITY_ITERATIONS);
+            }
         } else {
-            //
+            self.probe.probe(PROBE_DRIVE, input);
             self.run_ladder_linear(g, res, input);
         }
+        for (stage, vout) in self.vout.iter().enumerate() {
+            self.probe.probe(PROBE_FIRST_STAGE + stage, *vout);
+        }
         self.update_state();
+        // the poles parameter chooses which filter stage we take our output from.
+        let output = self.vout[self.settings.poles];
+        // the saturating stages turn asymmetric drive into DC, so it is blocked after them
+        if nonlinear && self.settings.dc_block {
+            self.dc_blocker.process(output)
+        } else {
+            output
+        }
     }
     // nonlinear ladder filter function with distortion.
     fn run_ladder_nonlinear(&mut self, g: f32, res: f32, input: f32) {
-        let mut a = [1f32; 5];
         let base = [input, self.s[0], self.s[1], self.s[2], self.s[3]];
         // a[n] is the fixed-pivot approximation for tanh()
-        for n in 0..base.len() {
-            a[n] = if base[n] == 0. {
-                1.
-            } else {
-                base[n].tanh() / base[n]
-            };
-        }
+        let a = pivot_gains(self.quality.pivot_mode(self.vectorized), base);
         // denominators of solutions of individual stages. Simplifies the math a bit
         let g0 = 1. / (1. + g * a[1]);
         let g1 = 1. / (1. + g * a[2]);
@@ -227,6 +1146,38 @@ impl LadderProcessor {
         self.vout[1] = g1 * (g * a[2] * self.vout[0] + self.s[1]);
         self.vout[2] = g2 * (g * a[3] * self.vout[1] + self.s[2]);
     }
+    // Newton-Raphson on the full nonlinear ladder, starting from the pivot solution in vout.
+    // Stage n solves vout[n] = s[n] + g * (u[n] - tanh(vout[n])), where u[0] is the input
+    // less the feedback and u[n] is tanh of the previous stage.
+    fn refine_ladder_nonlinear(&mut self, g: f32, res: f32, input: f32, iterations: usize) {
+        let input = input.tanh();
+        for _ in 0..iterations {
+            let y = self.vout;
+            let t = [y[0].tanh(), y[1].tanh(), y[2].tanh(), y[3].tanh()];
+            let d = [1. - t[0] * t[0], 1. - t[1] * t[1], 1. - t[2] * t[2], 1. - t[3] * t[3]];
+            let f = [
+                y[0] - self.s[0] - g * (input - res * t[3] - t[0]),
+                y[1] - self.s[1] - g * (t[0] - t[1]),
+                y[2] - self.s[2] - g * (t[1] - t[2]),
+                y[3] - self.s[3] - g * (t[2] - t[3]),
+            ];
+            // the jacobian is lower bidiagonal apart from the feedback term, so write each
+            // step as p + q * step[3] and solve for step[3] at the end
+            let diag = [1. + g * d[0], 1. + g * d[1], 1. + g * d[2], 1. + g * d[3]];
+            let mut p = [0f32; 4];
+            let mut q = [0f32; 4];
+            p[0] = -f[0] / diag[0];
+            q[0] = -g * res * d[3] / diag[0];
+            for n in 1..4 {
+                p[n] = (-f[n] + g * d[n - 1] * p[n - 1]) / diag[n];
+                q[n] = g * d[n - 1] * q[n - 1] / diag[n];
+            }
+            let step3 = p[3] / (1. - q[3]);
+            for n in 0..4 {
+                self.vout[n] += p[n] + q[n] * step3;
+            }
+        }
+    }
     // linear version without distortion
     pub fn run_ladder_linear(&mut self, g: f32, res: f32, input: f32) {
         // denominators of solutions of individual stages. Simplifies the math a bit
@@ -245,111 +1196,140 @@ impl LadderProcessor {
     }
 }
 
+/// Peak follower with separate attack and release, for the sidechain.
+struct EnvelopeFollower {
+    sample_rate: f32,
+    attack: f32,
+    release: f32,
+    level: f32,
+}
+
+impl Default for EnvelopeFollower {
+    fn default() -> Self {
+        let mut follower = EnvelopeFollower { sample_rate: 0., attack: 0., release: 0., level: 0. };
+        follower.set_sample_rate(44100.);
+        follower
+    }
+}
+
+impl EnvelopeFollower {
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.attack = one_pole_coefficient(SIDECHAIN_ATTACK_SECONDS, sample_rate);
+            self.release = one_pole_coefficient(SIDECHAIN_RELEASE_SECONDS, sample_rate);
+        }
+    }
+
+    fn next(&mut self, input: f32) -> f32 {
+        let input = input.abs();
+        let coefficient = if input > self.level { self.attack } else { self.release };
+        self.level = input + coefficient * (self.level - input);
+        self.level
+    }
+
+    fn reset(&mut self) {
+        self.level = 0.;
+    }
+}
+
+impl LadderSettings {
+    // move the target setting by a normalized offset, as its parameter maps it
+    fn modulate(&mut self, target: ModTarget, offset: f32) {
+        let shift = |normalized: f32| (normalized + offset).max(0.).min(1.);
+        let step = |index: usize, names: &[&str]| {
+            let last = (names.len() - 1) as f32;
+            (shift(index as f32 / last) * last).round() as usize
+        };
+        match target {
+            ModTarget::Cutoff => self.cutoff_hz = normalized_to_cutoff_hz(shift(cutoff_hz_to_normalized(self.cutoff_hz))),
+            ModTarget::Resonance => self.res = shift(self.res / RES_MAX) * RES_MAX,
+            ModTarget::Poles => self.poles = step(self.poles, &POLE_NAMES),
+            ModTarget::Drive => self.drive = shift(self.drive / 5.) * 5.,
+            ModTarget::DriveType => self.drive_type = DriveType::from_index(step(self.drive_type.index(), &DriveType::NAMES)),
+            ModTarget::Quality => self.quality = Quality::from_index(step(self.quality.index(), &Quality::NAMES)),
+            ModTarget::ResCompensation => self.res_comp = shift(self.res_comp),
+            ModTarget::Keytrack => self.keytrack = shift(self.keytrack),
+            ModTarget::Sidechain => self.sidechain = shift(self.sidechain),
+            ModTarget::StereoLink => self.stereo_link = step(self.stereo_link as usize, &STEREO_LINK_NAMES) > 0,
+            ModTarget::ChannelMode => self.channel_mode = ChannelMode::from_index(step(self.channel_mode.index(), &ChannelMode::NAMES)),
+            ModTarget::SideLink => self.side_link = step(self.side_link as usize, &SIDE_LINK_NAMES) > 0,
+            ModTarget::SideCutoff => self.side_cutoff_hz = normalized_to_cutoff_hz(shift(cutoff_hz_to_normalized(self.side_cutoff_hz))),
+            ModTarget::DcBlock => self.dc_block = step(self.dc_block as usize, &DC_BLOCK_NAMES) > 0,
+        }
+    }
+}
+
+// cutoff formula gives us a natural feeling cutoff knob that spends more time in the low frequencies
+fn normalized_to_cutoff_hz(value: f32) -> f32 {
+    20000. * (1.8f32.powf(10. * value - 10.))
+}
+
+// the inverse of the cutoff knob curve
+fn cutoff_hz_to_normalized(cutoff_hz: f32) -> f32 {
+    1. + 0.17012975 * (0.00005 * cutoff_hz).ln()
+}
+
 impl LadderShared {
     pub fn set_cutoff(&self, value: f32) {
-        // cutoff formula gives us a natural feeling cutoff knob that spends more time in the low frequencies
-        let cutoff_hz = 20000. * (1.8f32.powf(10. * value - 10.));
-        self.cutoff.set(cutoff_hz);
-        // bilinear transformation for g gives us a very accurate cutoff
-        self.g
-            .set((PI * cutoff_hz / (self.sample_rate.get())).tan());
+        self.cutoff.set(normalized_to_cutoff_hz(value));
     }
     // returns the value used to set cutoff. for get_parameter function
     pub fn get_cutoff(&self) -> f32 {
-        1. + 0.17012975 * (0.00005 * self.cutoff.get()).ln()
+        cutoff_hz_to_normalized(self.cutoff.get())
     }
-    pub fn set_poles(&self, value: f32) {
-        self.pole_value.set(value);
-        self.poles
-            .store(((value * 3.).round()) as usize, Ordering::Relaxed);
+    /// Take the output from after this many poles, less one.
+    pub fn set_poles(&self, index: usize) {
+        self.poles.store(index.min(POLE_NAMES.len() - 1), Ordering::Relaxed);
     }
 
-    pub fn set_poles_usize(&self, value: usize) {
-        let value = value.clamp(0, 3);
-        self.pole_value.set((value as f32) / 4.);
-        self.poles.store(value, Ordering::Relaxed);
+    pub fn get_drive_type(&self) -> DriveType {
+        DriveType::from_index(self.drive_type.load(Ordering::Relaxed))
     }
-}
-
 
-/// Gui code
-
-struct F32Lens;
+    pub fn set_drive_type(&self, drive_type: DriveType) {
+        self.drive_type.store(drive_type.index(), Ordering::Relaxed);
+    }
 
-impl Lens<f32, f64> for F32Lens {
-    fn with<V, F: FnOnce(&f64) -> V>(&self, data: &f32, f: F) -> V {
-        f(&(*data as f64))
+    pub fn get_quality(&self) -> Quality {
+        Quality::from_index(self.quality.load(Ordering::Relaxed))
     }
 
-    fn with_mut<V, F: FnOnce(&mut f64) -> V>(&self, data: &mut f32, f: F) -> V {
-        let mut temp = *data as f64;
-        let v = f(&mut temp);
-        *data = temp as f32;
-        v
+    pub fn set_quality(&self, quality: Quality) {
+        self.quality.store(quality.index(), Ordering::Relaxed);
     }
-}
 
-fn control_labelled<P: Data>(
-    axis: Axis,
-    name: impl Into<LabelText<P>>,
-    w: impl Widget<P> + 'static,
-) -> impl Widget<P> {
-    Flex::for_axis(axis)
-        .with_child(Label::new(name).fix_width(80.))
-        .with_flex_child(w, 1.0)
-        .padding(Insets::uniform_xy(0., 5.))
-}
+    pub fn get_channel_mode(&self) -> ChannelMode {
+        ChannelMode::from_index(self.channel_mode.load(Ordering::Relaxed))
+    }
 
-fn slider_labelled<P: Data>(
-    name: impl Into<LabelText<P>>,
-    end: f64,
-    l: impl Lens<P, f32> + 'static,
-) -> impl Widget<P> {
-    control_labelled(
-        Axis::Vertical,
-        name,
-        Slider::for_axis(Axis::Vertical)
-            .with_range(0., end)
-            .lens(l.then(F32Lens))
-            .expand_height(),
-    )
-}
+    pub fn set_channel_mode(&self, channel_mode: ChannelMode) {
+        self.channel_mode.store(channel_mode.index(), Ordering::Relaxed);
+    }
 
-fn dial_labelled<P: Data>(
-    name: impl Into<LabelText<P>>,
-    end: f64,
-    l: impl Lens<P, f32> + 'static,
-) -> impl Widget<P> {
-    control_labelled(
-        Axis::Vertical,
-        name,
-        Dial::new().with_range(0., end).lens(l.then(F32Lens)),
-    )
-}
+    /// Whether the processor is running in a stereo layout.
+    pub fn is_stereo(&self) -> bool {
+        self.channels.load(Ordering::Relaxed) > 1
+    }
 
-fn make_editor_widget() -> impl Widget<EditorState<LadderShared>> {
-    Flex::column()
-        .cross_axis_alignment(CrossAxisAlignment::Start)
-        .with_flex_child(
-            Flex::row()
-                .with_child(slider_labelled("Cutoff", 1.0, LadderParametersSnap::cutoff))
-                .with_child(slider_labelled("Resonance", 4.0, LadderParametersSnap::res))
-                .with_child(slider_labelled("Drive", 5.0, LadderParametersSnap::drive)),
-            1.0,
-        )
-        .with_flex_child(
-            Flex::row()
-                .with_child(dial_labelled("Cutoff", 1.0, LadderParametersSnap::cutoff))
-                .with_child(dial_labelled("Resonance", 4.0, LadderParametersSnap::res))
-                .with_child(dial_labelled("Drive", 5.0, LadderParametersSnap::drive)),
-            1.0,
-        )
-        .with_child(control_labelled(
-            Axis::Horizontal,
-            "Filter order",
-            RadioGroup::for_axis(Axis::Horizontal, (0..=3).map(|i| (i.to_string(), i)))
-                .lens(LadderParametersSnap::poles),
-        ))
-        .lens(EditorState::snap)
+    // everything processing reads, as one consistent set
+    fn settings(&self) -> LadderSettings {
+        self.seqlock.read(|| LadderSettings {
+            cutoff_hz: self.cutoff.get(),
+            res: self.res.get(),
+            poles: self.poles.load(Ordering::Relaxed),
+            drive: self.drive.get(),
+            drive_type: self.get_drive_type(),
+            quality: self.get_quality(),
+            res_comp: self.res_comp.get(),
+            keytrack: self.keytrack.get(),
+            sidechain: self.sidechain.get(),
+            stereo_link: self.stereo_link.load(Ordering::Relaxed),
+            channel_mode: self.get_channel_mode(),
+            side_link: self.side_link.load(Ordering::Relaxed),
+            side_cutoff_hz: self.side_cutoff.get(),
+            dc_block: self.dc_block.load(Ordering::Relaxed),
+        })
+    }
 }
 
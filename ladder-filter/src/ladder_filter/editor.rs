@@ -0,0 +1,298 @@
+//! The ladder's editor: sliders and dials for the filter, a scope and keyboard, and the
+//! LFO, macro and utility parameters on pages of their own.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use carnyx::carnyx::{CarnyxModel, CarnyxProcessor};
+use carnyx::units::format_hz;
+use carnyx::{CommandQueue, LfoParams, MidiSource, SampleTap};
+use carnyx_druid::{ClipLed, command_button, dial_for_param, dropdown_for_param, macro_panel, probe_panel, Dial, DruidEditor, EditorState, Keyboard, LoadMeter, Oscilloscope, Pages, ParamHandle, ResetToDefault};
+use druid::lens::Unit;
+use druid::widget::{Axis, CrossAxisAlignment, Flex, Label, LabelText, RadioGroup, SizedBox, Slider};
+use druid::{Data, Insets, Lens, LensExt, Widget, WidgetExt};
+
+use super::{normalized_to_cutoff_hz, ChannelMode, LadderCommand, LadderParametersSnap, LadderProcessor, LadderShared, Quality, DC_BLOCK_NAMES, POLE_NAMES, RES_MAX, SIDE_LINK_NAMES, STEREO_LINK_NAMES};
+use crate::drive::DriveType;
+
+// dials ease to automated values over this long
+const DIAL_ANIMATION: Duration = Duration::from_millis(60);
+
+pub(super) fn editor(processor: &LadderProcessor) -> DruidEditor<LadderShared> {
+    let scope = Arc::clone(&processor.scope);
+    let commands = processor.commands.clone();
+    let handles = ParamHandle::all(Arc::clone(&processor.model), Arc::new(processor.all_parameters()));
+    let model = Arc::clone(&processor.model);
+    DruidEditor::new(
+        Arc::clone(&processor.host),
+        processor.listener.clone(),
+        Arc::clone(&processor.model),
+        move || make_editor_widget(Arc::clone(&model), Arc::clone(&scope), commands.clone(), &handles),
+    )
+    .with_parameters(processor.all_parameters())
+    .with_preset_files(processor.descriptor())
+    .with_note_queue(processor.notes.clone())
+    .with_audition(processor.audition.settings())
+    // the sliders take whatever height they are given
+    .with_size(500., 500.)
+}
+
+struct F32Lens;
+
+impl Lens<f32, f64> for F32Lens {
+    fn with<V, F: FnOnce(&f64) -> V>(&self, data: &f32, f: F) -> V {
+        f(&(*data as f64))
+    }
+
+    fn with_mut<V, F: FnOnce(&mut f64) -> V>(&self, data: &mut f32, f: F) -> V {
+        let mut temp = *data as f64;
+        let v = f(&mut temp);
+        *data = temp as f32;
+        v
+    }
+}
+
+fn control_labelled<P: Data>(
+    axis: Axis,
+    name: impl Into<LabelText<P>>,
+    w: impl Widget<P> + 'static,
+) -> impl Widget<P> {
+    Flex::for_axis(axis)
+        .with_child(Label::new(name).fix_width(80.))
+        .with_flex_child(w, 1.0)
+        .padding(Insets::uniform_xy(0., 5.))
+}
+
+fn slider_labelled<P: Data>(
+    name: impl Into<LabelText<P>>,
+    end: f64,
+    default: f32,
+    l: impl Lens<P, f32> + 'static,
+) -> impl Widget<P> {
+    control_labelled(
+        Axis::Vertical,
+        name,
+        Slider::for_axis(Axis::Vertical)
+            .with_range(0., end)
+            .controller(ResetToDefault::new(default as f64))
+            .lens(l.then(F32Lens))
+            .expand_height(),
+    )
+}
+
+fn dial_labelled<P: Data>(
+    name: impl Into<LabelText<P>>,
+    end: f64,
+    default: f32,
+    modulation: Option<usize>,
+    l: impl Lens<P, f32> + 'static,
+) -> impl Widget<P> {
+    let dial = Dial::new()
+        .with_range(0., end)
+        .with_default(default as f64)
+        .with_automation_overlay()
+        .with_animation(DIAL_ANIMATION);
+    let dial = match modulation {
+        Some(index) => dial.with_modulation(index),
+        None => dial,
+    };
+    control_labelled(Axis::Vertical, name, dial.lens(l.then(F32Lens)))
+}
+
+fn make_editor_widget(
+    model: Arc<LadderShared>,
+    scope: Arc<SampleTap>,
+    commands: CommandQueue<LadderCommand>,
+    params: &[ParamHandle<LadderShared>],
+) -> impl Widget<EditorState<LadderShared>> {
+    let filter_params = params.to_vec();
+    // the framework's parameters, found by their id prefixes
+    let with_prefix = |prefix: &str| params.iter().filter(|h| h.param().id().starts_with(prefix)).cloned().collect::<Vec<_>>();
+    let lfo_params = with_prefix("lfo");
+    let macro_params = with_prefix("macro");
+    // pitch bend then mod wheel, a target and depth each
+    let midi_params = [with_prefix("bend."), with_prefix("modwheel.")];
+    // input trim, output gain, mix and the limiter, shown in their own units
+    let utility_params = with_prefix("utility.");
+    let probed = Arc::clone(&model);
+    Pages::new("ladder.page")
+        .with_page("Filter", move || {
+            make_filter_controls(Arc::clone(&scope), commands.clone(), &filter_params).lens(EditorState::snap.in_arc())
+        })
+        .with_page("Modulation", move || {
+            // a row per LFO slot, each with the same parameters
+            let mut slots = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+            let per_slot = (lfo_params.len() / LfoParams::SLOTS).max(1);
+            for slot in lfo_params.chunks(per_slot) {
+                let mut row = Flex::row().cross_axis_alignment(CrossAxisAlignment::Start);
+                for handle in slot {
+                    // shape and target are choices, rate and depth continuous
+                    if handle.param().steps() > 0 {
+                        row.add_child(dropdown_for_param(handle));
+                    } else {
+                        row.add_child(dial_for_param(handle));
+                    }
+                }
+                slots.add_child(row);
+            }
+            // then the keyboard's wheels
+            slots.add_spacer(10.);
+            for (name, route) in MidiSource::NAMES.iter().zip(&midi_params) {
+                let mut row = Flex::row().cross_axis_alignment(CrossAxisAlignment::Start);
+                for handle in route {
+                    if handle.param().steps() > 0 {
+                        row.add_child(dropdown_for_param(handle));
+                    } else {
+                        row.add_child(dial_for_param(handle));
+                    }
+                }
+                slots.add_child(control_labelled(Axis::Horizontal, *name, row));
+            }
+            slots
+        })
+        .with_page("Macros", move || macro_panel(&macro_params))
+        .with_page("Output", move || {
+            let mut utility_row = Flex::row().cross_axis_alignment(CrossAxisAlignment::Start);
+            for handle in &utility_params {
+                if handle.param().steps() > 0 {
+                    utility_row.add_child(dropdown_for_param(handle));
+                } else {
+                    utility_row.add_child(dial_for_param(handle));
+                }
+            }
+            let clip = Flex::row()
+                .with_child(Label::new("Clip"))
+                .with_spacer(4.)
+                .with_child(ClipLed::new().lens(Unit));
+            Flex::column()
+                .with_child(utility_row)
+                .with_spacer(10.)
+                .with_child(clip)
+                .with_spacer(10.)
+                .with_child(LoadMeter::new(Arc::clone(&model)).lens(Unit))
+                .with_child(instance_label(Arc::clone(&model)))
+        })
+        // solo a stage of the ladder, to hear which one misbehaves
+        .with_page("Debug", move || probe_panel(&*probed))
+}
+
+// e.g. "LadderFilter 2 of 3", to tell instances apart in a busy session
+fn instance_label(model: Arc<LadderShared>) -> impl Widget<EditorState<LadderShared>> {
+    Label::dynamic(move |_: &EditorState<LadderShared>, _| match model.instance() {
+        Some(instance) => format!("{} of {}", instance.name(), instance.count()),
+        None => String::new(),
+    })
+}
+
+// show the parameter's description when hovering over its control
+fn described(
+    params: &[ParamHandle<LadderShared>],
+    name: &str,
+    control: impl Widget<LadderParametersSnap> + 'static,
+) -> Box<dyn Widget<LadderParametersSnap>> {
+    match params.iter().find(|h| h.name() == name) {
+        Some(handle) => Box::new(handle.with_tooltip(control)),
+        None => Box::new(control),
+    }
+}
+
+// the named parameter's index, for its dial to show what the LFOs are doing to it
+fn modulation(params: &[ParamHandle<LadderShared>], name: &str) -> Option<usize> {
+    params.iter().find(|h| h.name() == name && h.is_modulatable()).map(|h| h.index())
+}
+
+// only on stereo tracks; the editor is rebuilt when the layout changes
+fn stereo_controls(params: &[ParamHandle<LadderShared>]) -> Box<dyn Widget<LadderParametersSnap>> {
+    if !params.iter().any(|h| h.name() == "stereo link" && h.is_active()) {
+        return Box::new(SizedBox::empty());
+    }
+    let defaults = LadderShared::default().snap();
+    let choices = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(described(params, "stereo link", control_labelled(
+            Axis::Horizontal,
+            "Stereo link",
+            RadioGroup::for_axis(Axis::Horizontal, STEREO_LINK_NAMES.iter().enumerate().map(|(i, name)| (*name, i > 0)))
+                .lens(LadderParametersSnap::stereo_link),
+        )))
+        .with_child(described(params, "channel mode", control_labelled(
+            Axis::Horizontal,
+            "Channels",
+            RadioGroup::for_axis(Axis::Horizontal, ChannelMode::ALL.iter().map(|m| (m.name(), *m)))
+                .lens(LadderParametersSnap::channel_mode),
+        )))
+        .with_child(described(params, "side link", control_labelled(
+            Axis::Horizontal,
+            "Side",
+            RadioGroup::for_axis(Axis::Horizontal, SIDE_LINK_NAMES.iter().enumerate().map(|(i, name)| (*name, i > 0)))
+                .lens(LadderParametersSnap::side_link),
+        )));
+    Box::new(
+        Flex::row()
+            .with_child(choices)
+            .with_child(described(params, "side cutoff", dial_labelled("Side cutoff", 1.0, defaults.side_cutoff, modulation(params, "side cutoff"), LadderParametersSnap::side_cutoff))),
+    )
+}
+
+fn make_filter_controls(
+    scope: Arc<SampleTap>,
+    commands: CommandQueue<LadderCommand>,
+    params: &[ParamHandle<LadderShared>],
+) -> impl Widget<LadderParametersSnap> {
+    // what cmd/ctrl-click and double-click reset the controls to
+    let defaults = LadderShared::default().snap();
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_flex_child(
+            Flex::row()
+                .with_child(described(params, "cutoff", slider_labelled("Cutoff", 1.0, defaults.cutoff, LadderParametersSnap::cutoff)))
+                .with_child(described(params, "resonance", slider_labelled("Resonance", RES_MAX as f64, defaults.res, LadderParametersSnap::res)))
+                .with_child(described(params, "drive", slider_labelled("Drive", 5.0, defaults.drive, LadderParametersSnap::drive))),
+            1.0,
+        )
+        .with_flex_child(
+            Flex::row()
+                .with_child(described(params, "cutoff", Flex::column()
+                    .with_child(dial_labelled("Cutoff", 1.0, defaults.cutoff, modulation(params, "cutoff"), LadderParametersSnap::cutoff))
+                    .with_child(Label::dynamic(|snap: &LadderParametersSnap, _| format_hz(normalized_to_cutoff_hz(snap.cutoff))))))
+                .with_child(described(params, "resonance", dial_labelled("Resonance", RES_MAX as f64, defaults.res, modulation(params, "resonance"), LadderParametersSnap::res)))
+                .with_child(described(params, "drive", dial_labelled("Drive", 5.0, defaults.drive, modulation(params, "drive"), LadderParametersSnap::drive)))
+                .with_child(described(params, "res compensation", dial_labelled("Res comp", 1.0, defaults.res_comp, modulation(params, "res compensation"), LadderParametersSnap::res_comp)))
+                .with_child(described(params, "keytrack", dial_labelled("Keytrack", 1.0, defaults.keytrack, modulation(params, "keytrack"), LadderParametersSnap::keytrack)))
+                .with_child(described(params, "sidechain", dial_labelled("Sidechain", 1.0, defaults.sidechain, modulation(params, "sidechain"), LadderParametersSnap::sidechain))),
+            1.0,
+        )
+        .with_child(described(params, "filter order", control_labelled(
+            Axis::Horizontal,
+            "Filter order",
+            RadioGroup::for_axis(Axis::Horizontal, POLE_NAMES.iter().enumerate().map(|(i, name)| (*name, i)))
+                .lens(LadderParametersSnap::poles),
+        )))
+        .with_child(described(params, "drive type", control_labelled(
+            Axis::Horizontal,
+            "Drive type",
+            RadioGroup::for_axis(Axis::Horizontal, DriveType::ALL.iter().map(|t| (t.name(), *t)))
+                .lens(LadderParametersSnap::drive_type),
+        )))
+        .with_child(described(params, "dc blocker", control_labelled(
+            Axis::Horizontal,
+            "DC blocker",
+            RadioGroup::for_axis(Axis::Horizontal, DC_BLOCK_NAMES.iter().enumerate().map(|(i, name)| (*name, i > 0)))
+                .lens(LadderParametersSnap::dc_block),
+        )))
+        .with_child(described(params, "quality", control_labelled(
+            Axis::Horizontal,
+            "Quality",
+            RadioGroup::for_axis(Axis::Horizontal, Quality::ALL.iter().map(|q| (q.name(), *q)))
+                .lens(LadderParametersSnap::quality),
+        )))
+        .with_child(stereo_controls(params))
+        .with_child(Oscilloscope::new(scope).lens(Unit))
+        .with_child(Keyboard::new().lens(Unit))
+        .with_child(
+            Flex::row()
+                .with_child(command_button("Reset filter", commands.clone(), LadderCommand::ResetFilter))
+                .with_child(command_button("All notes off", commands, LadderCommand::AllNotesOff)),
+        )
+}
+
@@ -1,2 +1,6 @@
+pub mod drive;
 pub mod ladder_filter;
-pub use ladder_filter::*;
\ No newline at end of file
+pub mod pivot;
+pub use drive::*;
+pub use ladder_filter::*;
+pub use pivot::*;
@@ -0,0 +1,103 @@
+//! The fixed-pivot gains `tanh(x) / x` used by the nonlinear ladder.
+//!
+//! These dominate the cost of the nonlinear path, so there is a rational approximation
+//! alongside the exact version, used at eco quality. On x86_64 the four stage states can be
+//! evaluated together in one SSE vector; the `simd` feature makes that eco's default.
+
+// past this tanh is within f32 rounding of its limit
+const TANH_CLAMP: f32 = 6.3;
+
+// Lambert's continued fraction for tanh, truncated after 17. Max error ~7e-6 (-103 dB).
+const NUM: [f32; 5] = [34459425., 4729725., 135135., 990., 1.];
+const DEN: [f32; 5] = [34459425., 16216200., 945945., 13860., 45.];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PivotMode {
+    /// Uses `f32::tanh`.
+    Exact,
+    /// Uses the rational approximation.
+    Fast,
+    /// As `Fast`, with the stage states in one vector. The same results, and the same
+    /// code off x86_64.
+    Vectorized,
+}
+
+pub fn pivot_gain(x: f32) -> f32 {
+    if x == 0. {
+        1.
+    } else {
+        x.tanh() / x
+    }
+}
+
+pub fn fast_pivot_gain(x: f32) -> f32 {
+    let xc = x.clamp(-TANH_CLAMP, TANH_CLAMP);
+    let y = xc * xc;
+    let p = NUM[0] + y * (NUM[1] + y * (NUM[2] + y * (NUM[3] + y * NUM[4])));
+    let q = DEN[0] + y * (DEN[1] + y * (DEN[2] + y * (DEN[3] + y * DEN[4])));
+    let ratio = p / q;
+    if xc == x {
+        ratio
+    } else {
+        ratio * xc / x
+    }
+}
+
+/// Pivot gains for the input and the four stage states.
+pub fn pivot_gains(mode: PivotMode, base: [f32; 5]) -> [f32; 5] {
+    match mode {
+        PivotMode::Exact => {
+            let mut a = [1f32; 5];
+            for n in 0..base.len() {
+                a[n] = pivot_gain(base[n]);
+            }
+            a
+        }
+        PivotMode::Fast => {
+            let mut a = [1f32; 5];
+            for n in 0..base.len() {
+                a[n] = fast_pivot_gain(base[n]);
+            }
+            a
+        }
+        PivotMode::Vectorized => {
+            let states = fast_pivot_gains4([base[1], base[2], base[3], base[4]]);
+            [fast_pivot_gain(base[0]), states[0], states[1], states[2], states[3]]
+        }
+    }
+}
+
+/// [`fast_pivot_gain`] of each of `x`, giving exactly the same results.
+#[cfg(target_arch = "x86_64")]
+pub fn fast_pivot_gains4(x: [f32; 4]) -> [f32; 4] {
+    use std::arch::x86_64::*;
+    // SAFETY: SSE is part of the x86_64 baseline, so these are always available, and the
+    // unaligned load and store stay within the two [f32; 4]s
+    unsafe {
+        let v = _mm_loadu_ps(x.as_ptr());
+        let limit = _mm_set1_ps(TANH_CLAMP);
+        let xc = _mm_max_ps(_mm_min_ps(v, limit), _mm_sub_ps(_mm_setzero_ps(), limit));
+        let y = _mm_mul_ps(xc, xc);
+        let horner = |c: [f32; 5]| {
+            let mut acc = _mm_set1_ps(c[4]);
+            for k in (0..4).rev() {
+                acc = _mm_add_ps(_mm_mul_ps(acc, y), _mm_set1_ps(c[k]));
+            }
+            acc
+        };
+        let ratio = _mm_div_ps(horner(NUM), horner(DEN));
+        // outside the clamp tanh(x) / x = tanh(xc) / x; x can't be zero there
+        let clamped = _mm_cmpneq_ps(v, xc);
+        let beyond = _mm_div_ps(_mm_mul_ps(ratio, xc), v);
+        let out = _mm_or_ps(_mm_and_ps(clamped, beyond), _mm_andnot_ps(clamped, ratio));
+        let mut a = [0f32; 4];
+        _mm_storeu_ps(a.as_mut_ptr(), out);
+        a
+    }
+}
+
+/// [`fast_pivot_gain`] of each of `x`.
+#[cfg(not(target_arch = "x86_64"))]
+pub fn fast_pivot_gains4(x: [f32; 4]) -> [f32; 4] {
+    [fast_pivot_gain(x[0]), fast_pivot_gain(x[1]), fast_pivot_gain(x[2]), fast_pivot_gain(x[3])]
+}
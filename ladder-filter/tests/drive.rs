@@ -0,0 +1,60 @@
+use ladder_filter::{DriveStage, DriveType};
+
+const DRIVES: [f32; 4] = [0., 0.5, 2., 5.];
+
+fn stage(curve: DriveType, drive: f32) -> DriveStage {
+    let mut stage = DriveStage::default();
+    stage.set(curve, drive);
+    stage
+}
+
+#[test]
+fn full_scale_stays_full_scale_whatever_the_drive() {
+    for &curve in &DriveType::ALL {
+        for &drive in &DRIVES {
+            let level = stage(curve, drive).process(1.);
+            assert!((level - 1.).abs() < 1e-6, "{} at drive {} took full scale to {}", curve.name(), drive, level);
+        }
+    }
+}
+
+#[test]
+fn the_default_stage_is_compensated_too() {
+    let level = DriveStage::default().process(1.);
+    assert!((level - 1.).abs() < 1e-6, "the default stage took full scale to {}", level);
+    // and a fade from the default sound starts from the same level
+    assert_eq!(DriveStage::new(DriveType::Tanh, 0.).process(0.5), DriveStage::default().process(0.5));
+}
+
+#[test]
+fn more_drive_squashes_quieter_samples_up() {
+    for &curve in &DriveType::ALL {
+        let levels: Vec<f32> = DRIVES.iter().map(|&drive| stage(curve, drive).process(0.5)).collect();
+        // the soft clipper is flat from a gain of 2, so only the first step has to rise
+        assert!(levels[1] > levels[0], "{} didn't saturate more with drive: {:?}", curve.name(), levels);
+        assert!(levels.windows(2).all(|pair| pair[1] >= pair[0]), "{} levels fell with drive: {:?}", curve.name(), levels);
+        assert!(levels.iter().all(|&level| level > 0.5 && level <= 1.), "{} levels out of range: {:?}", curve.name(), levels);
+    }
+}
+
+#[test]
+fn only_the_diode_is_asymmetric() {
+    for &curve in &[DriveType::Tanh, DriveType::SoftClip] {
+        let stage = stage(curve, 2.);
+        assert!((stage.process(0.3) + stage.process(-0.3)).abs() < 1e-6, "{} is lopsided", curve.name());
+    }
+    // the negative half is squashed less, so a symmetric input comes out with DC
+    let diode = stage(DriveType::Diode, 2.);
+    assert!(diode.process(0.3) + diode.process(-0.3) < -0.01);
+}
+
+#[test]
+fn curves_round_trip_through_their_index() {
+    for (index, &curve) in DriveType::ALL.iter().enumerate() {
+        assert_eq!(curve.index(), index);
+        assert_eq!(DriveType::from_index(index), curve);
+        assert_eq!(curve.name(), DriveType::NAMES[index]);
+    }
+    // an index from a newer version falls back to the last curve
+    assert_eq!(DriveType::from_index(7), DriveType::Diode);
+}
@@ -0,0 +1,208 @@
+//! Opens the ladder's editor in a native window as a host would, on whatever platform the
+//! tests run on: opens it, lets it run a few frames, resizes it through its resize
+//! widget, then closes and reopens it, checking it comes and goes cleanly. Last it opens
+//! and closes the editor many times over, checking nothing is left behind.
+//!
+//! `cargo test -p ladder-filter --features native-tests --test embedding`
+//!
+//! Needs a display. Runs without the test harness, since some platforms only allow
+//! windows on the main thread.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use carnyx::carnyx::{CarnyxEditor, CarnyxProcessor, CarnyxWindowResizer, SettableListener};
+use carnyx::test::NullCarnyxHost;
+use carnyx_druid::{DruidEditor, IDLE_RESIZE};
+use druid::widget::prelude::*;
+use druid::{AppLauncher, Application, NativeWindowHandle, Target, TimerToken, WindowDesc};
+use ladder_filter::{LadderProcessor, LadderShared};
+use raw_window_handle::HasRawWindowHandle;
+
+const FRAME: Duration = Duration::from_millis(16);
+// frames the editor gets to settle after each step
+const SETTLE_FRAMES: usize = 10;
+const REOPENS: usize = 3;
+const STRESS_REOPENS: usize = 100;
+// handles the platform may still be tidying away once the stress run settles
+const HANDLE_SLACK: usize = 10;
+const RESIZED: (usize, usize) = (600, 450);
+
+type Resizes = Arc<Mutex<Vec<(usize, usize)>>>;
+
+struct RecordingResizer(Resizes);
+
+impl CarnyxWindowResizer for RecordingResizer {
+    fn resize_editor_window(&self, width: usize, height: usize) -> bool {
+        self.0.lock().unwrap().push((width, height));
+        true
+    }
+}
+
+// what a closed editor leaves: listeners, holders of the model, and OS handles if we can count them
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Footprint {
+    listeners: usize,
+    models: usize,
+    handles: Option<usize>,
+}
+
+#[cfg(target_os = "linux")]
+fn open_handles() -> Option<usize> {
+    std::fs::read_dir("/proc/self/fd").ok().map(|fds| fds.count())
+}
+
+#[cfg(target_os = "windows")]
+fn open_handles() -> Option<usize> {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcess() -> *mut std::ffi::c_void;
+        fn GetProcessHandleCount(process: *mut std::ffi::c_void, count: *mut u32) -> i32;
+    }
+    let mut count = 0u32;
+    // SAFETY: the pseudo handle for this process is always valid, and `count` outlives the call
+    let ok = unsafe { GetProcessHandleCount(GetCurrentProcess(), &mut count) };
+    if ok != 0 { Some(count as usize) } else { None }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn open_handles() -> Option<usize> {
+    None
+}
+
+// stands in for the host's plugin window
+struct Harness {
+    editor: DruidEditor<LadderShared>,
+    listener: SettableListener<LadderShared>,
+    // before the editor was first opened
+    unopened_listeners: usize,
+    model: Arc<LadderShared>,
+    // before the stress run
+    baseline: Option<Footprint>,
+    resizes: Resizes,
+    native: Option<NativeWindowHandle>,
+    timer: TimerToken,
+    frame: usize,
+}
+
+impl Harness {
+    fn open(&mut self) {
+        let raw = self.native.as_ref().expect("no native window").0.raw_window_handle();
+        let resizer = RecordingResizer(Arc::clone(&self.resizes));
+        assert!(self.editor.open(Some(raw), Box::new(resizer)), "editor failed to open");
+        assert!(self.editor.is_open());
+        assert!(self.editor.event_sink().is_some(), "open editor has no event sink");
+    }
+
+    fn close(&mut self) {
+        self.editor.close();
+        assert!(!self.editor.is_open(), "editor still open after close");
+        assert!(self.editor.event_sink().is_none(), "closed editor still has an event sink");
+    }
+
+    fn footprint(&self) -> Footprint {
+        Footprint { listeners: self.listener.listener_count(), models: Arc::strong_count(&self.model), handles: open_handles() }
+    }
+
+    fn step(&mut self) {
+        self.frame += 1;
+        if self.frame % SETTLE_FRAMES != 0 {
+            return;
+        }
+        match self.frame / SETTLE_FRAMES {
+            1 => {
+                let (width, height) = RESIZED;
+                let size = Size::new(width as f64, height as f64);
+                let sink = self.editor.event_sink().expect("editor closed unexpectedly");
+                sink.submit_command(IDLE_RESIZE, size, Target::Global).expect("editor stopped taking commands");
+            }
+            2 => {
+                assert!(self.resizes.lock().unwrap().contains(&RESIZED), "the resize widget didn't ask the host to resize");
+                for _ in 0..REOPENS {
+                    self.close();
+                    self.open();
+                }
+            }
+            3 => {
+                self.close();
+                assert_eq!(self.listener.listener_count(), self.unopened_listeners, "reopening left listeners behind");
+                self.baseline = Some(self.footprint());
+                for _ in 0..STRESS_REOPENS {
+                    self.open();
+                    self.close();
+                }
+            }
+            4 => {
+                // checked a few frames on, once closed windows have gone
+                let baseline = self.baseline.expect("no footprint before the stress run");
+                let after = self.footprint();
+                assert_eq!(after.listeners, baseline.listeners, "listeners left behind by closed editors");
+                assert_eq!(after.models, baseline.models, "closed editors still hold the model");
+                if let (Some(before), Some(after)) = (baseline.handles, after.handles) {
+                    assert!(after <= before + HANDLE_SLACK, "{} handles before {} reopens, {} after", before, STRESS_REOPENS, after);
+                }
+                self.open();
+            }
+            _ => {
+                self.close();
+                Application::global().quit();
+            }
+        }
+    }
+}
+
+impl Widget<()> for Harness {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut (), _env: &Env) {
+        match event {
+            Event::NativeWindowConnected(native) => {
+                self.native = Some(native.clone());
+                self.open();
+                self.timer = ctx.request_timer(FRAME);
+            }
+            Event::Timer(token) if *token == self.timer => {
+                self.step();
+                self.timer = ctx.request_timer(FRAME);
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &(), _env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            let (w, h) = self.editor.initial_size();
+            ctx.request_native_window(Size::new(w as f64, h as f64))
+        }
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &(), _data: &(), _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &(), _env: &Env) -> Size {
+        let (w, h) = self.editor.initial_size();
+        bc.constrain(Size::new(w as f64, h as f64))
+    }
+
+    fn paint(&mut self, _ctx: &mut PaintCtx, _data: &(), _env: &Env) {}
+
+    fn post_render(&mut self) {}
+}
+
+fn main() {
+    let processor = LadderProcessor::new(Arc::new(NullCarnyxHost));
+    let editor = processor.editor().expect("the ladder has an editor");
+    let listener = processor.listener();
+    let harness = Harness {
+        editor,
+        unopened_listeners: listener.listener_count(),
+        listener,
+        model: processor.model(),
+        baseline: None,
+        resizes: Arc::default(),
+        native: None,
+        timer: TimerToken::INVALID,
+        frame: 0,
+    };
+    AppLauncher::with_window(WindowDesc::new(harness).title("embedding test"))
+        .launch(())
+        .expect("failed to launch the host window");
+    println!("embedding: ok");
+}
@@ -0,0 +1,56 @@
+//! Renders fixed signals through the ladder and compares them with the recordings in
+//! `tests/golden`, so DSP changes can't alter the sound unnoticed. Run with `CARNYX_BLESS=1`
+//! to record them again after an intended change.
+
+use std::path::PathBuf;
+
+use carnyx::test::{check_golden, impulse, noise_bursts, processor, run, sweep};
+use ladder_filter::LadderProcessor;
+
+const BLOCK_SIZE: usize = 512;
+const LEN: usize = 22050;
+// about -80 dB
+const TOLERANCE: f32 = 1e-4;
+
+fn golden(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(format!("{}.wav", name))
+}
+
+const CLEAN: &[(&str, f32)] = &[("cutoff", 0.5), ("resonance", 0.3), ("drive", 0.)];
+const DRIVEN: &[(&str, f32)] = &[("cutoff", 0.6), ("resonance", 0.9), ("drive", 0.8)];
+
+#[test]
+fn impulse_clean() {
+    let output = run(&mut processor(LadderProcessor::new, CLEAN), &[impulse(LEN)], BLOCK_SIZE);
+    check_golden(golden("impulse_clean"), &output, TOLERANCE).unwrap();
+}
+
+#[test]
+fn sweep_clean() {
+    let output = run(&mut processor(LadderProcessor::new, CLEAN), &[sweep(20., 20000., 0.5, LEN)], BLOCK_SIZE);
+    check_golden(golden("sweep_clean"), &output, TOLERANCE).unwrap();
+}
+
+#[test]
+fn sweep_driven() {
+    let output = run(&mut processor(LadderProcessor::new, DRIVEN), &[sweep(20., 20000., 0.5, LEN)], BLOCK_SIZE);
+    check_golden(golden("sweep_driven"), &output, TOLERANCE).unwrap();
+}
+
+#[test]
+fn noise_driven() {
+    let output = run(&mut processor(LadderProcessor::new, DRIVEN), &[noise_bursts(0x5eed, 0.5, 2048, LEN)], BLOCK_SIZE);
+    check_golden(golden("noise_driven"), &output, TOLERANCE).unwrap();
+}
+
+#[test]
+fn missing_recordings_fail_rather_than_record() {
+    if std::env::var_os("CARNYX_BLESS").is_some() {
+        return;
+    }
+    let missing = std::env::temp_dir().join("carnyx-golden-never-recorded.wav");
+    let output = run(&mut processor(LadderProcessor::new, CLEAN), &[impulse(64)], BLOCK_SIZE);
+    let error = check_golden(&missing, &output, TOLERANCE).unwrap_err();
+    assert!(error.contains("missing"), "{}", error);
+    assert!(!missing.exists());
+}
@@ -0,0 +1,51 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use carnyx::carnyx::{CarnyxHost, CarnyxModelListener, CarnyxProcessor, ChangeEvent, ChangeOrigin, ParamFilter};
+use ladder_filter::{LadderProcessor, LadderShared};
+
+#[derive(Default)]
+struct LayoutHost {
+    layout_changes: AtomicUsize,
+}
+
+impl CarnyxHost for LayoutHost {
+    fn update_host_display(&self) {}
+
+    fn parameter_layout_changed(&self) {
+        self.layout_changes.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<ChangeEvent>>,
+}
+
+impl CarnyxModelListener<LadderShared> for Recorder {
+    fn notify_change(&self, _model: &LadderShared, event: ChangeEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+#[test]
+fn layout_changes_reach_the_host_and_every_listener() {
+    let host = Arc::new(LayoutHost::default());
+    let processor = LadderProcessor::new(host.clone());
+    let recorder = Arc::new(Recorder::default());
+    let listener: Arc<dyn CarnyxModelListener<LadderShared>> = recorder.clone();
+    // even one only listening to the cutoff rebuilds its controls
+    processor.listener().add_listener(&listener, ParamFilter::Indices(vec![0]));
+
+    processor.parameter_layout_changed(&*host);
+    assert_eq!(host.layout_changes.load(Ordering::Relaxed), 1);
+    let events = recorder.events.lock().unwrap().clone();
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0].param_index, events[0].origin, events[0].layout), (None, ChangeOrigin::Processor, true));
+
+    // changes to other parameters still pass it by
+    processor.listener().notify_change(&processor.model(), ChangeEvent::param(1, ChangeOrigin::Editor));
+    processor.listener().notify_change(&processor.model(), ChangeEvent::param(0, ChangeOrigin::Host));
+    let events = recorder.events.lock().unwrap().clone();
+    assert_eq!(&events[1..], [ChangeEvent::param(0, ChangeOrigin::Host)]);
+}
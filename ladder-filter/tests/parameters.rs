@@ -0,0 +1,162 @@
+use carnyx::carnyx::{CarnyxModel, CarnyxProcessor};
+use carnyx::preset;
+use carnyx::random::Rng;
+use carnyx::test::processor;
+use ladder_filter::LadderProcessor;
+
+#[test]
+fn discrete_parameters_round_trip_every_step() {
+    let processor = processor(LadderProcessor::new, &[]);
+    let model = processor.model();
+    for param in processor.all_parameters().iter().filter(|p| p.steps() > 0) {
+        let steps = param.steps();
+        for step in 0..steps {
+            let value = step as f32 / (steps - 1) as f32;
+            param.set_value(&model, value);
+            assert_eq!(param.get_value(&model), value, "{} step {}", param.name(&model), step);
+            assert_eq!(param.formatted(&model), param.step_names(&model)[step]);
+            assert_eq!(param.parse(&model, &param.formatted(&model)), Some(value));
+        }
+    }
+}
+
+#[test]
+fn values_read_back_set_unchanged() {
+    let processor = processor(LadderProcessor::new, &[]);
+    let model = processor.model();
+    let mut rng = Rng::new(0x7e57);
+    for param in processor.all_parameters().iter() {
+        for _ in 0..100 {
+            param.set_value(&model, rng.next_f32());
+            let read = param.get_value(&model);
+            param.set_value(&model, read);
+            assert!((param.get_value(&model) - read).abs() < 1e-5, "{} drifts from {}", param.name(&model), read);
+        }
+    }
+}
+
+#[test]
+fn filter_order_survives_snapshots() {
+    let processor = processor(LadderProcessor::new, &[]);
+    let model = processor.model();
+    let params = processor.all_parameters();
+    let order = params.iter().find(|p| p.name(&model) == "filter order").unwrap();
+    assert_eq!(order.steps(), 4);
+    order.set_value(&model, 2. / 3.);
+    let snap = model.snap();
+    order.set_value(&model, 0.);
+    model.set_snap(&snap);
+    assert_eq!(order.get_value(&model), 2. / 3.);
+    assert_eq!(order.formatted(&model), "3");
+}
+
+#[test]
+fn every_published_id_resolves_to_a_parameter() {
+    let processor = processor(LadderProcessor::new, &[]);
+    let params = processor.all_parameters();
+    let ids = processor.param_ids();
+    let published = processor.published_param_ids();
+    // every parameter has an id, so none is left for hosts to find by position
+    assert_eq!(published.len(), params.len());
+    assert_eq!(ids.len(), published.len());
+    for (host_index, id) in published.iter().enumerate() {
+        let position = ids.param(host_index).unwrap_or_else(|| panic!("published parameter {} is missing", id));
+        assert_eq!(params[position].id(), id);
+        assert_eq!(ids.host_index(position), Some(host_index));
+    }
+}
+
+#[test]
+fn randomize_leaves_excluded_parameters_alone() {
+    let processor = processor(LadderProcessor::new, &[]);
+    let model = processor.model();
+    let params = processor.all_parameters();
+    let before: Vec<f32> = params.iter().map(|p| p.get_value(&model)).collect();
+    preset::randomize(&params, &*model, &mut Rng::new(2049));
+    let mut moved = 0;
+    for (param, before) in params.iter().zip(before) {
+        let after = param.get_value(&model);
+        if !param.randomizable() {
+            assert_eq!(after, before, "{} was randomized", param.name(&model));
+        } else if after != before {
+            moved += 1;
+        }
+    }
+    let randomizable = params.iter().filter(|p| p.randomizable()).count();
+    assert!(randomizable < params.len(), "nothing to leave alone");
+    assert!(moved > randomizable / 2, "only {} of {} moved", moved, randomizable);
+}
+
+#[test]
+fn mutate_nudges_by_no_more_than_the_amount() {
+    let processor = processor(LadderProcessor::new, &[]);
+    let model = processor.model();
+    let params = processor.all_parameters();
+    let amount = 0.05;
+    let mut rng = Rng::new(7);
+    for _ in 0..20 {
+        let before: Vec<f32> = params.iter().map(|p| p.get_value(&model)).collect();
+        preset::mutate(&params, &*model, amount, &mut rng);
+        // stepped parameters move a whole step or not at all
+        for (param, before) in params.iter().zip(before).filter(|(p, _)| p.steps() == 0) {
+            let after = param.get_value(&model);
+            assert!((after - before).abs() <= amount + 1e-5, "{} moved from {} to {}", param.name(&model), before, after);
+            assert!((0. ..=1.).contains(&after));
+        }
+    }
+}
+
+#[test]
+fn defaults_are_what_a_fresh_instance_reads() {
+    let fresh = processor(LadderProcessor::new, &[]);
+    let fresh_model = fresh.model();
+    let processor = processor(LadderProcessor::new, &[]);
+    let model = processor.model();
+    let mut rng = Rng::new(0xdefa);
+    let params = processor.all_parameters();
+    for param in params.iter() {
+        let default = param.default_value();
+        let name = param.name(&fresh_model);
+        assert!((default - param.get_value(&fresh_model)).abs() < 1e-5, "{} defaults to {} but starts at {}", name, default, param.get_value(&fresh_model));
+        param.set_value(&model, rng.next_f32());
+    }
+    // resetting a control puts back what it started as
+    for (param, fresh_param) in params.iter().zip(fresh.all_parameters().iter()) {
+        param.set_value(&model, param.default_value());
+        assert!((param.get_value(&model) - fresh_param.get_value(&fresh_model)).abs() < 1e-5, "{} didn't reset", param.name(&model));
+    }
+}
+
+#[test]
+fn values_typed_in_display_units_set_the_parameter() {
+    let processor = processor(LadderProcessor::new, &[]);
+    let model = processor.model();
+    let params = processor.all_parameters();
+    let typed = |name: &str, text: &str| {
+        let param = params.iter().find(|param| param.name(&model) == name).unwrap();
+        let value = param.parse(&model, text)?;
+        param.set_value(&model, value);
+        Some(param.formatted(&model))
+    };
+    assert_eq!(typed("cutoff", "1k").as_deref(), Some("1.00 kHz"));
+    assert_eq!(typed("cutoff", "250 Hz").as_deref(), Some("250 Hz"));
+    assert_eq!(typed("resonance", "2.25").as_deref(), Some("2.250"));
+    assert_eq!(typed("res compensation", "40%").as_deref(), Some("40"));
+    assert_eq!(typed("output gain", "-6 dB").as_deref(), Some("-6.0"));
+    assert_eq!(typed("drive type", "diode").as_deref(), Some("Diode"));
+    // out of range values are clamped, and nonsense is refused
+    assert_eq!(typed("resonance", "100").as_deref(), Some("4.500"));
+    assert_eq!(typed("cutoff", "loud"), None);
+}
+
+#[test]
+fn every_parameter_describes_itself_in_a_sentence() {
+    let processor = processor(LadderProcessor::new, &[]);
+    let model = processor.model();
+    for param in processor.all_parameters().iter() {
+        let description = param.description();
+        let name = param.name(&model);
+        assert!(description.starts_with(char::is_uppercase), "{}: {:?}", name, description);
+        assert!(description.ends_with('.') && !description.contains(" ,"), "{}: {:?}", name, description);
+    }
+}
@@ -0,0 +1,91 @@
+use carnyx::carnyx::{CarnyxProcessor, ProcessingMode};
+use carnyx::random::Rng;
+use carnyx::test::{peak, processor, run, TEST_SAMPLE_RATE};
+use ladder_filter::pivot::{fast_pivot_gain, fast_pivot_gains4, pivot_gain};
+use ladder_filter::{LadderProcessor, Quality};
+
+// how far eco quality's fast pivot gains may take the output from the exact ones
+const MAX_DEVIATION_DB: f32 = -90.;
+
+// the pivot path the features choose
+const SIMD: bool = cfg!(feature = "simd");
+
+const DRIVEN: &[(&str, f32)] = &[("cutoff", 0.7), ("resonance", 0.8), ("drive", 0.5)];
+
+// with the pivots seeing a wide range, through a driven resonant filter
+fn render(mode: ProcessingMode, quality: Quality, vectorized: bool, input: &[f32]) -> Vec<f32> {
+    let mut processor = processor(LadderProcessor::new, DRIVEN);
+    processor.set_processing_mode(mode);
+    processor.set_vectorized(vectorized);
+    processor.model().set_quality(quality);
+    run(&mut processor, &[input.to_vec()], 512).remove(0)
+}
+
+#[test]
+fn eco_quality_stays_close_to_exact() {
+    let mut rng = Rng::new(0x5eed);
+    let mut phase = 0f32;
+    let input: Vec<f32> = (0..2 * TEST_SAMPLE_RATE as usize)
+        .map(|_| {
+            phase = (phase + 110. / TEST_SAMPLE_RATE).fract();
+            0.5 * (2. * phase - 1.) + 0.25 * rng.next_bipolar()
+        })
+        .collect();
+    let exact = render(ProcessingMode::Realtime, Quality::Normal, SIMD, &input);
+    // the scalar and the vector paths both, whichever the features choose
+    for &vectorized in &[false, true] {
+        let fast = render(ProcessingMode::Realtime, Quality::Eco, vectorized, &input);
+        let deviation = exact.iter().zip(&fast).fold(0f32, |m, (a, b)| m.max((a - b).abs()));
+        let deviation_db = 20. * (deviation / peak(&exact).max(f32::EPSILON)).max(1e-12).log10();
+        assert!(
+            deviation_db <= MAX_DEVIATION_DB,
+            "fast path (vectorized: {}) deviates by {:.1} dB",
+            vectorized,
+            deviation_db
+        );
+    }
+}
+
+#[test]
+fn vectorized_eco_sounds_the_same_as_scalar() {
+    let mut rng = Rng::new(0x51d);
+    let input: Vec<f32> = (0..8192).map(|_| 0.8 * rng.next_bipolar()).collect();
+    let scalar = render(ProcessingMode::Realtime, Quality::Eco, false, &input);
+    assert_eq!(render(ProcessingMode::Realtime, Quality::Eco, true, &input), scalar);
+}
+
+#[test]
+fn fast_gains_follow_tanh() {
+    for i in -2000..=2000 {
+        let x = i as f32 / 100.;
+        assert!((fast_pivot_gain(x) - pivot_gain(x)).abs() < 1e-5, "at {}", x);
+    }
+}
+
+#[test]
+fn vectorized_gains_match_scalar_lane_for_lane() {
+    // either side of the clamp, zero, and the smallest values either way
+    let edges = [0., -0., 6.3, -6.3, 6.299999, 6.300001, f32::MIN_POSITIVE, -f32::MIN_POSITIVE, 1e-20, 1e6, -1e6];
+    let sweep = (-12_000..=12_000).map(|i| i as f32 / 1000.);
+    let inputs: Vec<f32> = edges.iter().copied().chain(sweep).collect();
+    // each value in every lane, next to different neighbours
+    for (n, chunk) in inputs.windows(4).enumerate() {
+        let x = [chunk[n % 4], chunk[(n + 1) % 4], chunk[(n + 2) % 4], chunk[(n + 3) % 4]];
+        let vector = fast_pivot_gains4(x);
+        for lane in 0..4 {
+            assert_eq!(vector[lane].to_bits(), fast_pivot_gain(x[lane]).to_bits(), "lane {} of {:?}", lane, x);
+        }
+    }
+}
+
+#[test]
+fn offline_rendering_uses_high_quality() {
+    let mut rng = Rng::new(0xb0b);
+    let input: Vec<f32> = (0..8192).map(|_| 0.8 * rng.next_bipolar()).collect();
+    let high = render(ProcessingMode::Realtime, Quality::High, SIMD, &input);
+    assert_ne!(render(ProcessingMode::Realtime, Quality::Eco, SIMD, &input), high);
+    // whatever the quality chosen for playing live
+    for &quality in &[Quality::Eco, Quality::Normal, Quality::High] {
+        assert_eq!(render(ProcessingMode::Offline, quality, SIMD, &input), high);
+    }
+}
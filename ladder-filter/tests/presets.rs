@@ -0,0 +1,241 @@
+use carnyx::carnyx::{CarnyxModel, CarnyxProcessor};
+use carnyx::json_preset;
+use carnyx::preset::Preset;
+use carnyx::state;
+use carnyx::test::{processor, run, set_parameter, sine};
+use ladder_filter::{LadderParametersSnap, LadderProcessor};
+
+fn values(processor: &LadderProcessor) -> Vec<f32> {
+    let model = processor.model();
+    processor.parameters().iter().map(|p| p.get_value(&model)).collect()
+}
+
+// some parameters go through a curve and back, so can be an ulp or so out
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert!((a - e).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+    }
+}
+
+#[test]
+fn json_presets_round_trip() {
+    let source = processor(LadderProcessor::new, &[]);
+    assert!(set_parameter(&source, "cutoff", 0.3));
+    assert!(set_parameter(&source, "drive type", 1.));
+    let text = json_preset::to_json(&source.descriptor(), &Preset::new("Test", source.model().snap()));
+
+    let target = processor(LadderProcessor::new, &[]);
+    let preset: Preset<LadderParametersSnap> = json_preset::from_json(&target.descriptor(), &text).unwrap();
+    assert_eq!(preset.name, "Test");
+    target.model().set_snap(&preset.snap);
+    assert_close(&values(&target), &values(&source));
+}
+
+#[test]
+fn json_presets_tolerate_unknown_and_missing_settings() {
+    let text = r#"{
+        "format": "carnyx-preset",
+        "version": 1,
+        "plugin": "LadderFilter",
+        "name": "Hand written",
+        "settings": { "res": 3.0, "oversampling": 4 }
+    }"#;
+    let processor = processor(LadderProcessor::new, &[]);
+    let preset: Preset<LadderParametersSnap> = json_preset::from_json(&processor.descriptor(), text).unwrap();
+    processor.model().set_snap(&preset.snap);
+    let model = processor.model();
+    let params = processor.parameters();
+    let value = |name: &str| params.iter().find(|p| p.name(&model) == name).map(|p| p.formatted(&model));
+    assert_eq!(value("resonance"), Some("3.000".to_string()));
+    assert_eq!(value("cutoff"), Some("1.00 kHz".to_string()));
+}
+
+#[test]
+fn json_presets_for_other_plugins_are_rejected() {
+    let text = r#"{"format": "carnyx-preset", "version": 1, "plugin": "Chorus", "name": "x", "settings": {}}"#;
+    let processor = processor(LadderProcessor::new, &[]);
+    assert!(json_preset::from_json::<LadderParametersSnap>(&processor.descriptor(), text).is_err());
+}
+
+#[test]
+fn saved_state_round_trips() {
+    let source = processor(LadderProcessor::new, &[]);
+    assert!(set_parameter(&source, "cutoff", 0.3));
+    assert!(set_parameter(&source, "filter order", 0.));
+    let saved = state::save(&source.all_parameters(), &*source.model());
+
+    let target = processor(LadderProcessor::new, &[]);
+    state::load(&target.all_parameters(), &*target.model(), &saved).unwrap();
+    assert_close(&values(&target), &values(&source));
+}
+
+#[test]
+fn saved_state_keeps_modulation_routings() {
+    let source = processor(LadderProcessor::new, &[]);
+    assert!(set_parameter(&source, "lfo 3 target", 1.));
+    assert!(set_parameter(&source, "lfo 3 depth", 0.6));
+    let saved = state::save(&source.all_parameters(), &*source.model());
+
+    let target = processor(LadderProcessor::new, &[]);
+    state::load(&target.all_parameters(), &*target.model(), &saved).unwrap();
+    let model = target.model();
+    let slot = model.lfo().unwrap().slot(2);
+    assert_eq!(slot.target(), Some(target.parameters().len() - 1));
+    assert!((slot.depth() - 0.6).abs() < 1e-6);
+}
+
+#[test]
+fn saved_state_keeps_macro_assignments() {
+    let source = processor(LadderProcessor::new, &[]);
+    assert!(set_parameter(&source, "macro 2 target 3", 1.));
+    assert!(set_parameter(&source, "macro 2 end 3", 0.1));
+    assert!(set_parameter(&source, "macro 2", 0.8));
+    let saved = state::save(&source.all_parameters(), &*source.model());
+
+    let target = processor(LadderProcessor::new, &[]);
+    state::load(&target.all_parameters(), &*target.model(), &saved).unwrap();
+    let model = target.model();
+    let control = model.macros().unwrap().control(1);
+    assert_eq!(control.assignment(2).target(), Some(target.parameters().len() - 1));
+    assert!((control.assignment(2).end() + 0.8).abs() < 1e-6);
+    assert!((control.value() - 0.8).abs() < 1e-6);
+}
+
+#[test]
+fn saved_state_keeps_the_instance_tag() {
+    let source = processor(LadderProcessor::new, &[]);
+    let source_model = source.model();
+    let tag = source_model.instance_tag().unwrap();
+    tag.set_nickname("Bass ladder \u{2014} verse");
+    tag.set_color(Some(4));
+    let saved = state::save(&source.all_parameters(), &*source.model());
+
+    let target = processor(LadderProcessor::new, &[]);
+    state::load(&target.all_parameters(), &*target.model(), &saved).unwrap();
+    let target_model = target.model();
+    let loaded = target_model.instance_tag().unwrap();
+    assert_eq!(loaded.nickname(), "Bass ladder \u{2014} verse");
+    assert_eq!(loaded.color(), Some(4));
+
+    // state from before tags leaves the instance's alone
+    let values: Vec<f32> = source.all_parameters().iter().map(|p| p.get_value(&*source.model())).collect();
+    state::load(&target.all_parameters(), &*target.model(), &state::encode(target.model().state_version(), &values)).unwrap();
+    assert_eq!(loaded.nickname(), "Bass ladder \u{2014} verse");
+}
+
+#[test]
+fn older_state_missing_parameters_loads_defaults() {
+    let source = processor(LadderProcessor::new, &[]);
+    assert!(set_parameter(&source, "cutoff", 0.3));
+    let mut saved_values = values(&source);
+    // as if saved before the last parameter was added
+    saved_values.pop();
+    let saved = state::encode(source.model().state_version(), &saved_values);
+
+    let target = processor(LadderProcessor::new, &[]);
+    let params = target.parameters();
+    let last = params.last().unwrap();
+    last.set_value(&target.model(), if last.default_value() < 0.5 { 1. } else { 0. });
+    state::load(&target.all_parameters(), &*target.model(), &saved).unwrap();
+    let loaded = values(&target);
+    assert_close(&loaded[..saved_values.len()], &saved_values);
+    assert_eq!(last.get_value(&target.model()), last.default_value());
+}
+
+#[test]
+fn saved_state_loads_by_id_after_reordering() {
+    let source = processor(LadderProcessor::new, &[]);
+    assert!(set_parameter(&source, "cutoff", 0.3));
+    assert!(set_parameter(&source, "resonance", 0.7));
+    // as if saved by a version listing the parameters the other way round
+    let params = source.all_parameters();
+    let reversed: Vec<f32> = params.iter().rev().map(|p| p.get_value(&source.model())).collect();
+    let ids: Vec<&str> = params.iter().rev().map(|p| p.id()).collect();
+    let saved = state::encode_with_ids(source.model().state_version(), &reversed, &ids);
+
+    let target = processor(LadderProcessor::new, &[]);
+    state::load(&target.all_parameters(), &*target.model(), &saved).unwrap();
+    assert_close(&values(&target), &values(&source));
+}
+
+#[test]
+fn state_from_before_the_wider_resonance_range_keeps_its_resonance() {
+    let source = processor(LadderProcessor::new, &[]);
+    assert!(set_parameter(&source, "cutoff", 0.3));
+    let params = source.all_parameters();
+    let model = source.model();
+    let resonance = params.iter().position(|p| p.id() == "resonance").unwrap();
+    // version 0 normalized resonance to 0-4, so this was 2
+    let mut saved_values: Vec<f32> = params.iter().map(|p| p.get_value(&model)).collect();
+    saved_values[resonance] = 0.5;
+    let ids: Vec<&str> = params.iter().map(|p| p.id()).collect();
+    let saved = state::encode_with_ids(0, &saved_values, &ids);
+
+    let target = processor(LadderProcessor::new, &[]);
+    let target_params = target.all_parameters();
+    state::load(&target_params, &*target.model(), &saved).unwrap();
+    assert_eq!(target_params[resonance].formatted(&target.model()), "2.000");
+    assert_eq!(target_params[resonance].label(&target.model()), "");
+    // the rest load as saved
+    assert_close(&[target_params[0].get_value(&target.model())], &saved_values[..1]);
+
+    // and state saved now round trips unchanged
+    let current = state::save(&target_params, &*target.model());
+    let again = processor(LadderProcessor::new, &[]);
+    state::load(&again.all_parameters(), &*again.model(), &current).unwrap();
+    assert_eq!(again.all_parameters()[resonance].formatted(&again.model()), "2.000");
+}
+
+#[test]
+fn state_from_before_the_side_link_keeps_an_unlinked_side() {
+    let source = processor(LadderProcessor::new, &[]);
+    assert!(set_parameter(&source, "channel mode", 1.));
+    assert!(set_parameter(&source, "stereo link", 0.));
+    let params = source.all_parameters();
+    // the side link hadn't been added
+    let (values, ids): (Vec<f32>, Vec<&str>) = params
+        .iter()
+        .filter(|p| p.id() != "side_link")
+        .map(|p| (p.get_value(&source.model()), p.id()))
+        .unzip();
+    let saved = state::encode_with_ids(1, &values, &ids);
+
+    let target = processor(LadderProcessor::new, &[]);
+    state::load(&target.all_parameters(), &*target.model(), &saved).unwrap();
+    let side_link = target.all_parameters().into_iter().find(|p| p.id() == "side_link").unwrap();
+    assert_eq!(side_link.formatted(&target.model()), "Own cutoff");
+
+    // where it was linked, it stays linked
+    assert!(set_parameter(&source, "stereo link", 1.));
+    let values: Vec<f32> = params.iter().filter(|p| p.id() != "side_link").map(|p| p.get_value(&source.model())).collect();
+    state::load(&target.all_parameters(), &*target.model(), &state::encode_with_ids(1, &values, &ids)).unwrap();
+    assert_eq!(side_link.formatted(&target.model()), "Follows cutoff");
+}
+
+#[test]
+fn malformed_state_is_rejected() {
+    let target = processor(LadderProcessor::new, &[]);
+    assert!(state::load(&target.all_parameters(), &*target.model(), b"not state").is_err());
+    assert!(state::load(&target.all_parameters(), &*target.model(), &state::encode(0, &[0.5; 4])[..20]).is_err());
+}
+
+// the largest sample to sample change around a preset switching the output stage
+fn largest_step_at_preset_change(crossfade: bool) -> f32 {
+    let mut processor = processor(LadderProcessor::new, &[]);
+    processor.model().preset_crossfade().unwrap().set_enabled(crossfade);
+    assert!(set_parameter(&processor, "filter order", 0.));
+    let input = sine(440., 0.5, 4096);
+    let mut output = run(&mut processor, &[input[..2048].to_vec()], 256).remove(0);
+    // "Init" takes the output after four poles
+    assert!(processor.presets().unwrap().select(0, &*processor.model()));
+    output.extend(run(&mut processor, &[input[2048..].to_vec()], 256).remove(0));
+    output[2040..2100].windows(2).map(|pair| (pair[1] - pair[0]).abs()).fold(0., f32::max)
+}
+
+#[test]
+fn preset_changes_crossfade() {
+    let faded = largest_step_at_preset_change(true);
+    let jumped = largest_step_at_preset_change(false);
+    assert!(faded < jumped * 0.5, "crossfaded step {} vs {} without", faded, jumped);
+}
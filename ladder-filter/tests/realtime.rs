@@ -0,0 +1,92 @@
+//! Run with `cargo test -p ladder-filter --features audit` to check the audio thread
+//! paths don't allocate or lock. Without the feature the checks do nothing.
+
+use std::sync::Arc;
+
+use carnyx::audit;
+use carnyx::carnyx::{CarnyxModel, CarnyxProcessor};
+use carnyx::test::{noise_bursts, run, run_block_with_events, silence, NullCarnyxHost, TEST_SAMPLE_RATE};
+use carnyx::{MidiMessage, TimedMidi};
+use ladder_filter::LadderProcessor;
+
+// counts allocations inside audit::realtime
+#[cfg(feature = "audit")]
+#[global_allocator]
+static ALLOCATOR: audit::AuditAllocator = audit::AuditAllocator;
+
+#[test]
+fn process_is_realtime_safe() {
+    let mut processor = LadderProcessor::new(Arc::new(NullCarnyxHost));
+    processor.set_sample_rate(TEST_SAMPLE_RATE);
+    // the test harness checks each block
+    run(&mut processor, &[noise_bursts(1, 0.5, 1024, 8192)], 256);
+}
+
+#[test]
+fn setting_parameters_is_realtime_safe() {
+    let processor = LadderProcessor::new(Arc::new(NullCarnyxHost));
+    let model = processor.model();
+    let params = processor.all_parameters();
+    audit::realtime("set_value", || {
+        for param in params.iter() {
+            param.set_value(&model, 0.7);
+        }
+    });
+}
+
+#[test]
+fn program_changes_are_realtime_safe() {
+    let mut processor = LadderProcessor::new(Arc::new(NullCarnyxHost));
+    processor.set_sample_rate(TEST_SAMPLE_RATE);
+    let presets = processor.presets().unwrap();
+    let events = [
+        TimedMidi::new(0, MidiMessage::ControlChange { channel: 0, controller: 0, value: 0 }),
+        TimedMidi::new(0, MidiMessage::ControlChange { channel: 0, controller: 32, value: 0 }),
+        TimedMidi::new(16, MidiMessage::ProgramChange { channel: 0, program: 1 }),
+    ];
+    // the harness audits the block
+    run_block_with_events(&mut processor, &[silence(256)], &events);
+    assert_eq!(presets.current(), 0, "the preset was loaded on the audio thread");
+    assert!(presets.has_request());
+
+    // as the bridge does off the audio thread
+    let model = processor.model();
+    assert!(presets.apply_requested(&*model));
+    assert_eq!(presets.current(), 1);
+    assert!(!presets.has_request());
+    let reference = LadderProcessor::new(Arc::new(NullCarnyxHost));
+    reference.model().set_snap(&presets.get(1).unwrap().snap);
+    let (model, reference_model) = (processor.model(), reference.model());
+    for (param, reference_param) in processor.parameters().iter().zip(reference.parameters().iter()) {
+        assert!((param.get_value(&model) - reference_param.get_value(&reference_model)).abs() < 1e-5, "{}", param.id());
+    }
+}
+
+#[test]
+fn program_changes_outside_the_bank_are_ignored() {
+    let mut processor = LadderProcessor::new(Arc::new(NullCarnyxHost));
+    processor.set_sample_rate(TEST_SAMPLE_RATE);
+    let presets = processor.presets().unwrap();
+    let events = [
+        TimedMidi::new(0, MidiMessage::ControlChange { channel: 0, controller: 0, value: 1 }),
+        TimedMidi::new(0, MidiMessage::ProgramChange { channel: 0, program: 0 }),
+    ];
+    run_block_with_events(&mut processor, &[silence(64)], &events);
+    assert!(!presets.has_request());
+    assert!(!presets.apply_requested(&*processor.model()));
+}
+
+#[test]
+#[cfg(feature = "audit")]
+#[should_panic(expected = "listener lock")]
+fn the_audit_catches_a_lock() {
+    let listener = LadderProcessor::new(Arc::new(NullCarnyxHost)).listener();
+    audit::realtime("count listeners", || listener.listener_count());
+}
+
+#[test]
+#[cfg(feature = "audit")]
+#[should_panic(expected = "allocation")]
+fn the_audit_catches_an_allocation() {
+    audit::realtime("allocate", || vec![0u8; 64]);
+}
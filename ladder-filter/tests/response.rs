@@ -0,0 +1,477 @@
+use carnyx::buffer::BusLayout;
+use carnyx::carnyx::{CarnyxModel, CarnyxProcessor};
+use carnyx::{MidiMessage, NoteEvent, Telemetry, TimedMidi};
+use carnyx::test::{impulse, peak, processor, render, rms, run, run_block, run_block_with_events, set_parameter, sine, TEST_SAMPLE_RATE};
+use ladder_filter::{LadderCommand, LadderProcessor};
+
+const BLOCK_SIZE: usize = 256;
+
+#[test]
+fn impulse_response_decays() {
+    let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.)]);
+    let output = run(&mut processor, &[impulse(8192)], BLOCK_SIZE).remove(0);
+    let head = peak(&output[..512]);
+    assert!(head > 0., "no response to the impulse");
+    assert!(peak(&output[4096..]) < head * 0.01, "impulse response still ringing");
+}
+
+#[test]
+fn attenuates_above_cutoff() {
+    let mut low = processor(LadderProcessor::new, &[("cutoff", 0.3), ("resonance", 0.)]);
+    let mut high = processor(LadderProcessor::new, &[("cutoff", 0.3), ("resonance", 0.)]);
+    let passed = run(&mut low, &[sine(100., 0.1, 8192)], BLOCK_SIZE).remove(0);
+    let cut = run(&mut high, &[sine(15000., 0.1, 8192)], BLOCK_SIZE).remove(0);
+    // skip the filter settling
+    assert!(rms(&cut[4096..]) < rms(&passed[4096..]) * 0.1);
+}
+
+#[test]
+fn stable_at_max_resonance() {
+    let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.9), ("resonance", 1.)]);
+    assert!(set_parameter(&processor, "drive", 1.));
+    let output = run(&mut processor, &[sine(220., 0.5, TEST_SAMPLE_RATE as usize)], BLOCK_SIZE).remove(0);
+    let level = peak(&output);
+    assert!(level.is_finite() && level < 10., "output blew up, peak {}", level);
+}
+
+#[test]
+fn res_compensation_restores_the_bass() {
+    // well below the cutoff, and quiet enough to stay linear
+    let input = sine(60., 0.01, 16384);
+    let level = |comp: f32| {
+        let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.6), ("resonance", 3. / 4.5)]);
+        assert!(set_parameter(&processor, "res compensation", comp));
+        let output = run(&mut processor, &[input.clone()], BLOCK_SIZE).remove(0);
+        rms(&output[8192..]) / rms(&input[8192..])
+    };
+    // a resonance of 3 leaves a quarter of the passband
+    let lost = level(0.);
+    assert!((lost - 0.25).abs() < 0.02, "uncompensated gain {}", lost);
+    let restored = level(1.);
+    assert!((restored - 1.).abs() < 0.08, "compensated gain {}", restored);
+    let half = level(0.5);
+    assert!((half - 0.625).abs() < 0.05, "half compensated gain {}", half);
+}
+
+#[test]
+fn keytracking_moves_the_cutoff_with_the_held_note() {
+    // above the cutoff, so its level follows the cutoff closely
+    let input = sine(3000., 0.01, BLOCK_SIZE);
+    let level = |cutoff: f32, keytrack: f32, note: Option<u8>| {
+        let mut processor = processor(LadderProcessor::new, &[("cutoff", cutoff), ("resonance", 0.)]);
+        assert!(set_parameter(&processor, "keytrack", keytrack));
+        let mut output = Vec::new();
+        for block in 0..32 {
+            let events: Vec<TimedMidi> = match note {
+                Some(note) if block == 0 => vec![TimedMidi::new(0, MidiMessage::Note { channel: 0, event: NoteEvent::On { note, velocity: 1. } })],
+                _ => Vec::new(),
+            };
+            output.extend(run_block_with_events(&mut processor, &[input.clone()], &events).remove(0));
+        }
+        rms(&output[output.len() / 2..])
+    };
+    // the knob moves this far for an octave
+    let octave = 0.17012975 * 2f32.ln();
+    let untracked = level(0.5, 1., None);
+    let an_octave_up = level(0.5, 1., Some(72));
+    let expected = level(0.5 + octave, 0., None);
+    assert!(an_octave_up > untracked * 1.5, "C5 left the cutoff alone, rms {} against {}", an_octave_up, untracked);
+    assert!((an_octave_up / expected - 1.).abs() < 0.02, "C5 tracked to rms {}, expected {}", an_octave_up, expected);
+    // half tracking, two octaves down moves by one
+    let down = level(0.5, 0.5, Some(36));
+    let expected = level(0.5 - octave, 0., None);
+    assert!((down / expected - 1.).abs() < 0.02, "C2 tracked to rms {}, expected {}", down, expected);
+    // and without keytracking, notes do nothing
+    assert_eq!(level(0.5, 0., Some(72)), untracked);
+}
+
+#[test]
+fn self_oscillates_at_the_cutoff_from_silence() {
+    let one_second = vec![0.; TEST_SAMPLE_RATE as usize];
+    let mut below = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 3.5 / 4.5)]);
+    assert_eq!(peak(&run(&mut below, &[one_second.clone()], BLOCK_SIZE).remove(0)), 0.);
+
+    let mut oscillating = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 1.)]);
+    let output = run(&mut oscillating, &[one_second], BLOCK_SIZE).remove(0);
+    let settled = &output[output.len() / 2..];
+    let level = peak(settled);
+    assert!(level > 0.1 && level < 10., "oscillation peak {}", level);
+    // half a second at about 1058 Hz
+    let crossings = settled.windows(2).filter(|pair| pair[0] < 0. && pair[1] >= 0.).count() as f32;
+    assert!((crossings / 529. - 1.).abs() < 0.2, "{} cycles in half a second", crossings);
+}
+
+#[test]
+fn output_does_not_depend_on_the_block_size() {
+    const NOTE_AT: usize = 700;
+    let input = sine(440., 0.8, 6000);
+    let note = |offset: usize| TimedMidi::new(offset, MidiMessage::Note { channel: 0, event: NoteEvent::On { note: 67, velocity: 1. } });
+    let keytracked = || {
+        let processor = processor(LadderProcessor::new, &[("cutoff", 0.4), ("resonance", 0.6)]);
+        assert!(set_parameter(&processor, "drive", 0.4));
+        assert!(set_parameter(&processor, "keytrack", 1.));
+        processor
+    };
+    let expected = run_block_with_events(&mut keytracked(), &[input.clone()], &[note(NOTE_AT)]).remove(0);
+
+    // hosts change the block size from call to call, and the note lands mid block
+    let mut processor = keytracked();
+    let mut output = Vec::new();
+    let mut start = 0;
+    for &size in [1, 64, 511, 1024, 3, 4000].iter().cycle() {
+        if start >= input.len() {
+            break;
+        }
+        let end = (start + size).min(input.len());
+        let events: Vec<TimedMidi> = if (start..end).contains(&NOTE_AT) { vec![note(NOTE_AT - start)] } else { Vec::new() };
+        output.extend(run_block_with_events(&mut processor, &[input[start..end].to_vec()], &events).remove(0));
+        start = end;
+    }
+    assert_eq!(output.len(), expected.len());
+    for (i, (sample, expected)) in output.iter().zip(&expected).enumerate() {
+        assert!((sample - expected).abs() < 1e-6, "sample {} is {}, expected {}", i, sample, expected);
+    }
+}
+
+#[test]
+fn silent_once_the_tail_has_decayed() {
+    let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.3), ("resonance", 0.7)]);
+    assert!(processor.is_silent());
+    run_block(&mut processor, &[impulse(BLOCK_SIZE)]);
+    assert!(!processor.is_silent(), "silent while still ringing");
+    // the tail finishes on its own, without the host having to play silence through
+    let tail = run(&mut processor, &[vec![0.; 8 * 4096]], BLOCK_SIZE).remove(0);
+    assert!(peak(&tail[..BLOCK_SIZE]) > 0.);
+    assert!(processor.is_silent());
+    // but a self oscillating filter never is
+    assert!(set_parameter(&processor, "resonance", 1.));
+    assert!(!processor.is_silent());
+}
+
+// the first LFO, pointed at `target` at full depth
+fn lfo_on(processor: &LadderProcessor, target: &str) {
+    let params = processor.all_parameters();
+    let model = processor.model();
+    let param = params.iter().find(|p| p.id() == "lfo.target").unwrap();
+    param.set_value(&model, param.parse(&model, target).unwrap());
+    assert!(set_parameter(processor, "lfo depth", 1.));
+}
+
+#[test]
+fn an_lfo_can_wake_a_silent_filter_into_self_oscillation() {
+    // set just short of self oscillation
+    let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.8)]);
+    lfo_on(&processor, "resonance");
+    assert!(processor.is_silent());
+    let output = run(&mut processor, &[vec![0.; 2 * TEST_SAMPLE_RATE as usize]], BLOCK_SIZE).remove(0);
+    assert!(peak(&output) > 0.01, "the filter stayed asleep, peak {}", peak(&output));
+}
+
+#[test]
+fn the_lfo_keeps_time_through_skipped_blocks() {
+    let (mut skipping, mut playing) = (processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.)]), processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.)]));
+    lfo_on(&skipping, "cutoff");
+    lfo_on(&playing, "cutoff");
+    run(&mut skipping, &[vec![0.; 8192]], BLOCK_SIZE);
+    assert!(skipping.is_silent());
+    run(&mut playing, &[sine(440., 0.5, 8192)], BLOCK_SIZE);
+    let offset = |processor: &LadderProcessor| processor.model().modulation().unwrap().amount(0).offset;
+    assert_ne!(offset(&playing), 0.);
+    assert_eq!(offset(&skipping), offset(&playing));
+}
+
+#[test]
+fn a_loud_sidechain_opens_the_cutoff() {
+    let input = sine(3000., 0.05, 8192);
+    let key = sine(100., 0.8, 8192);
+    let level = |amount: f32, sidechain: &[f32]| {
+        // mono with a mono sidechain is the default layout
+        let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.4), ("resonance", 0.)]);
+        assert!(set_parameter(&processor, "sidechain", amount));
+        let output = run(&mut processor, &[input.clone(), sidechain.to_vec()], BLOCK_SIZE).remove(0);
+        rms(&output[4096..])
+    };
+    let quiet = vec![0.; 8192];
+    let closed = level(1., &quiet);
+    let opened = level(1., &key);
+    assert!(opened > closed * 4., "the sidechain opened the cutoff to rms {} from {}", opened, closed);
+    // at 0% the sidechain is ignored
+    assert_eq!(level(0., &key), closed);
+
+    // and is only ever a key, never heard
+    let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.4), ("resonance", 0.)]);
+    assert!(set_parameter(&processor, "sidechain", 1.));
+    let output = run(&mut processor, &[quiet, key], BLOCK_SIZE).remove(0);
+    assert_eq!(peak(&output), 0.);
+}
+
+#[test]
+fn reset_silences_a_ringing_filter() {
+    let ring = |reset: bool| {
+        let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.85)]);
+        run_block(&mut processor, &[impulse(BLOCK_SIZE)]);
+        if reset {
+            assert!(processor.commands().push(LadderCommand::ResetFilter));
+        }
+        peak(&run_block(&mut processor, &[vec![0.; BLOCK_SIZE]]).remove(0))
+    };
+    assert!(ring(false) > 1e-3, "nothing was ringing");
+    assert_eq!(ring(true), 0.);
+}
+
+#[test]
+fn all_notes_off_lets_go_of_the_keytracked_note() {
+    let input = vec![sine(3000., 0.01, BLOCK_SIZE)];
+    let mut tracked = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.)]);
+    assert!(set_parameter(&tracked, "keytrack", 1.));
+    let mut untracked = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.)]);
+    let note = [TimedMidi::new(0, MidiMessage::Note { channel: 0, event: NoteEvent::On { note: 84, velocity: 1. } })];
+    run_block_with_events(&mut tracked, &input, &note);
+    run_block(&mut untracked, &input);
+    assert_ne!(run_block(&mut tracked, &input), run_block(&mut untracked, &input));
+
+    assert!(tracked.commands().push(LadderCommand::AllNotesOff));
+    // the filters' states differ, so compare once they've settled
+    for _ in 0..32 {
+        run_block(&mut tracked, &input);
+        run_block(&mut untracked, &input);
+    }
+    let (tracked, untracked) = (rms(&run_block(&mut tracked, &input)[0]), rms(&run_block(&mut untracked, &input)[0]));
+    assert!((tracked / untracked - 1.).abs() < 1e-3, "still tracking, rms {} against {}", tracked, untracked);
+}
+
+#[test]
+fn stable_across_sample_rate_changes() {
+    let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.8), ("resonance", 1.)]);
+    let input = sine(220., 0.5, 8192);
+    run(&mut processor, &[input.clone()], BLOCK_SIZE);
+    let mut old_rate = TEST_SAMPLE_RATE;
+    for &rate in &[192000., 22050., TEST_SAMPLE_RATE] {
+        processor.set_sample_rate(rate);
+        processor.on_sample_rate_changed(old_rate, rate);
+        old_rate = rate;
+        let output = render(&mut processor, rate, &[input.clone()], BLOCK_SIZE).remove(0);
+        let level = peak(&output);
+        assert!(level.is_finite() && level < 10., "output blew up at {} Hz, peak {}", rate, level);
+    }
+}
+
+#[test]
+fn stereo_channels_are_filtered_separately() {
+    let mut mono = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.3)]);
+    let mut stereo = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.3)]);
+    assert!(!stereo.set_layout(BusLayout::new(6, 6)));
+    assert!(stereo.set_layout(BusLayout::new(2, 2).with_sidechain(2)));
+    let expected = run(&mut mono, &[impulse(4096)], BLOCK_SIZE).remove(0);
+    let outputs = run(&mut stereo, &[impulse(4096), vec![0.; 4096]], BLOCK_SIZE);
+    assert_eq!(outputs.len(), 2);
+    assert_eq!(outputs[0], expected);
+    assert_eq!(peak(&outputs[1]), 0., "the silent channel picked up the other");
+}
+
+#[test]
+fn mid_side_leaves_a_centred_signal_alone() {
+    let mut mono = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.3)]);
+    let mut stereo = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.3)]);
+    assert!(stereo.set_layout(BusLayout::new(2, 2).with_sidechain(2)));
+    assert!(set_parameter(&stereo, "channel mode", 1.));
+    // the side would be filtered much harder, were there any
+    assert!(set_parameter(&stereo, "side link", 0.));
+    assert!(set_parameter(&stereo, "side cutoff", 0.1));
+    let input = sine(220., 0.5, 4096);
+    let expected = run(&mut mono, &[input.clone()], BLOCK_SIZE).remove(0);
+    let outputs = run(&mut stereo, &[input.clone(), input], BLOCK_SIZE);
+    assert_eq!(outputs[0], expected);
+    assert_eq!(outputs[1], expected);
+}
+
+#[test]
+fn the_side_follows_the_cutoff_unless_unlinked() {
+    // all side, no mid
+    let left = sine(2000., 0.5, 8192);
+    let right: Vec<f32> = left.iter().map(|sample| -sample).collect();
+    let side_level = |side_link: f32| {
+        let mut stereo = processor(LadderProcessor::new, &[("cutoff", 0.9), ("resonance", 0.)]);
+        assert!(stereo.set_layout(BusLayout::new(2, 2).with_sidechain(2)));
+        assert!(set_parameter(&stereo, "channel mode", 1.));
+        assert!(set_parameter(&stereo, "side cutoff", 0.1));
+        // the sidechain's link has no say over the side's cutoff
+        assert!(set_parameter(&stereo, "stereo link", 0.));
+        assert!(set_parameter(&stereo, "side link", side_link));
+        let outputs = run(&mut stereo, &[left.clone(), right.clone()], BLOCK_SIZE);
+        rms(&outputs[0][4096..])
+    };
+    let followed = side_level(1.);
+    let own = side_level(0.);
+    assert!(followed > rms(&left[4096..]) * 0.5, "a linked side was cut, rms {}", followed);
+    assert!(own < followed * 0.1, "the side's own cutoff was ignored, rms {} against {}", own, followed);
+}
+
+#[test]
+fn lfo_moves_its_target_without_changing_it() {
+    let mut plain = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.)]);
+    let mut modulated = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.)]);
+    let params = modulated.all_parameters();
+    let model = modulated.model();
+    let target = params.iter().find(|p| p.id() == "lfo.target").unwrap();
+    target.set_value(&model, target.parse(&model, "cutoff").unwrap());
+    assert_eq!(target.formatted(&model), "cutoff");
+    assert!(set_parameter(&modulated, "lfo depth", 1.));
+    let input = sine(440., 0.5, 8192);
+    let expected = run(&mut plain, &[input.clone()], BLOCK_SIZE).remove(0);
+    let output = run(&mut modulated, &[input], BLOCK_SIZE).remove(0);
+    assert_ne!(output, expected, "the LFO didn't move the cutoff");
+    let cutoff = params.iter().find(|p| p.id() == "cutoff").unwrap();
+    assert_eq!(cutoff.get_value(&model), cutoff.get_value(&plain.model()));
+    // what editors show
+    let amount = model.modulation().unwrap().amount(0);
+    assert_eq!(amount.range, 1.);
+    assert!(amount.offset.abs() <= amount.range);
+}
+
+#[test]
+fn modulation_is_sent_to_a_listening_editor() {
+    let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.)]);
+    assert!(set_parameter(&processor, "lfo target", 1. / processor.parameters().len() as f32));
+    let model = processor.model();
+    let telemetry = model.telemetry().unwrap();
+    run(&mut processor, &[sine(440., 0.5, 1024)], BLOCK_SIZE);
+    assert_eq!(telemetry.drain().count(), 0, "sent with no editor listening");
+
+    telemetry.set_listening(true);
+    run(&mut processor, &[sine(440., 0.5, 1024)], BLOCK_SIZE);
+    // peaks are sent too
+    let sent: Vec<Telemetry> = telemetry.drain().filter(|message| matches!(message, Telemetry::Modulation { .. })).collect();
+    assert_eq!(sent.len(), 1024 / BLOCK_SIZE);
+    assert!(sent.iter().all(|message| matches!(message, Telemetry::Modulation { index: 0, amount } if amount.range == 0.25)));
+}
+
+#[test]
+fn limiter_keeps_the_output_under_full_scale() {
+    // hard clip, then brickwall
+    for &limiter in &[0.5, 1.] {
+        let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.9), ("resonance", 0.)]);
+        assert!(set_parameter(&processor, "output gain", 1.));
+        assert!(set_parameter(&processor, "limiter", limiter));
+        let output = run(&mut processor, &[sine(220., 0.9, 8192)], BLOCK_SIZE).remove(0);
+        assert!(peak(&output) <= 1., "limiter {} let through {}", limiter, peak(&output));
+        assert!(peak(&output[4096..]) > 0.5, "limiter {} silenced the output", limiter);
+    }
+}
+
+#[test]
+fn peaks_are_sent_from_before_the_limiter() {
+    let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.9), ("resonance", 0.)]);
+    assert!(set_parameter(&processor, "output gain", 1.));
+    assert!(set_parameter(&processor, "limiter", 0.5));
+    let model = processor.model();
+    let telemetry = model.telemetry().unwrap();
+    telemetry.set_listening(true);
+    run(&mut processor, &[sine(220., 0.9, 4096)], BLOCK_SIZE);
+    assert!(telemetry.drain().any(|message| matches!(message, Telemetry::Peak { channel: 0, level } if level > 1.)));
+}
+
+#[test]
+fn dc_blocker_removes_the_offset_of_asymmetric_drive() {
+    let mean = |dc_block: f32| {
+        let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.9), ("resonance", 0.)]);
+        assert!(set_parameter(&processor, "drive", 1.));
+        assert!(set_parameter(&processor, "drive type", 1.));
+        assert!(set_parameter(&processor, "dc blocker", dc_block));
+        let output = run(&mut processor, &[sine(100., 0.5, TEST_SAMPLE_RATE as usize)], BLOCK_SIZE).remove(0);
+        // whole cycles, once the blocker has settled
+        let settled = &output[22050..22050 + 441 * 40];
+        settled.iter().sum::<f32>() / settled.len() as f32
+    };
+    let (blocked, unblocked) = (mean(1.), mean(0.));
+    assert!(unblocked.abs() > 0.01, "the diode curve left no offset to remove: {}", unblocked);
+    assert!(blocked.abs() < 1e-3, "offset of {} left with the blocker on", blocked);
+}
+
+#[test]
+fn soloed_probe_replaces_the_output() {
+    let input = sine(10000., 0.5, 8192);
+    let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.3), ("resonance", 0.)]);
+    let filtered = run(&mut processor, &[input.clone()], BLOCK_SIZE).remove(0);
+    let model = processor.model();
+    let probes = model.probes().unwrap();
+    // without drive the signal after the drive stage is the input, give or take the
+    // rounding of the gain stages around the filter
+    probes.solo(probes.names().iter().position(|name| *name == "Post-drive"));
+    let probed = run(&mut processor, &[input.clone()], BLOCK_SIZE).remove(0);
+    assert!(probed.iter().zip(&input).all(|(p, i)| (p - i).abs() < 1e-6));
+    probes.solo(probes.names().iter().position(|name| *name == "Output"));
+    let output = run(&mut processor, &[input.clone()], BLOCK_SIZE).remove(0);
+    assert!(rms(&output[4096..]) < rms(&input[4096..]) * 0.1);
+    assert!((rms(&output[4096..]) - rms(&filtered[4096..])).abs() < 1e-4);
+}
+
+#[test]
+fn macro_sweeps_several_targets_without_changing_them() {
+    let mut plain = processor(LadderProcessor::new, &[("cutoff", 0.7), ("resonance", 0.)]);
+    let mut swept = processor(LadderProcessor::new, &[("cutoff", 0.7), ("resonance", 0.)]);
+    let params = swept.all_parameters();
+    let model = swept.model();
+    let set = |id: &str, text: &str| {
+        let param = params.iter().find(|p| p.id() == id).unwrap();
+        param.set_value(&model, param.parse(&model, text).unwrap());
+    };
+    // cutoff down and drive up as the macro turns
+    set("macro1.target1", "cutoff");
+    set("macro1.end1", "-40%");
+    set("macro1.target2", "drive");
+    set("macro1.end2", "+60%");
+    assert!(set_parameter(&swept, "macro 1", 1.));
+    let input = sine(440., 0.5, 8192);
+    let expected = run(&mut plain, &[input.clone()], BLOCK_SIZE).remove(0);
+    let output = run(&mut swept, &[input], BLOCK_SIZE).remove(0);
+    assert_ne!(output, expected, "the macro didn't move its targets");
+    for id in &["cutoff", "drive"] {
+        let param = params.iter().find(|p| p.id() == *id).unwrap();
+        assert_eq!(param.get_value(&model), param.get_value(&plain.model()), "{} changed", id);
+    }
+    let modulation = model.modulation().unwrap();
+    let index = |id: &str| params.iter().position(|p| p.id() == id).unwrap();
+    assert!((modulation.offset(index("cutoff")) + 0.4).abs() < 1e-5);
+    assert!((modulation.offset(index("drive")) - 0.6).abs() < 1e-5);
+}
+
+#[test]
+fn macro_curves_bend_the_sweep() {
+    let processor = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.)]);
+    let model = processor.model();
+    let assignment = model.macros().unwrap().control(0).assignment(0);
+    assert!(set_parameter(&processor, "macro 1 start 1", 0.5));
+    assert!(set_parameter(&processor, "macro 1 end 1", 1.));
+    assert!((assignment.offset(0.5) - 0.5).abs() < 1e-6);
+    assert!(set_parameter(&processor, "macro 1 curve 1", 1.));
+    assert!(assignment.offset(0.5) > 0.5);
+    assert!(set_parameter(&processor, "macro 1 curve 1", 0.));
+    assert!(assignment.offset(0.5) < 0.5);
+    assert_eq!(assignment.offset(0.), 0.);
+    assert!((assignment.offset(1.) - 1.).abs() < 1e-6);
+}
+
+#[test]
+fn keyboard_wheels_move_their_targets() {
+    let mut processor = processor(LadderProcessor::new, &[("cutoff", 0.5), ("resonance", 0.)]);
+    let model = processor.model();
+    let targets = processor.parameters().len() as f32;
+    // the cutoff is the first target, after none
+    assert!(set_parameter(&processor, "mod wheel target", 1. / targets));
+    assert!(set_parameter(&processor, "pitch bend target", 1. / targets));
+    assert!(set_parameter(&processor, "pitch bend depth", 0.));
+    let cutoff = || model.modulation().unwrap().offset(0);
+    let input = vec![sine(440., 0.5, BLOCK_SIZE)];
+    run_block_with_events(&mut processor, &input, &[TimedMidi::new(0, MidiMessage::ControlChange { channel: 0, controller: 1, value: 127 })]);
+    // the default depth of +50%
+    assert!((cutoff() - 0.5).abs() < 1e-6);
+    // full bend down with a depth of -100% adds another 100%
+    run_block_with_events(&mut processor, &input, &[TimedMidi::new(10, MidiMessage::PitchBend { channel: 0, value: -1. })]);
+    assert!((cutoff() - 1.5).abs() < 1e-6);
+    // the wheels stay put between messages
+    run_block_with_events(&mut processor, &input, &[]);
+    assert!((cutoff() - 1.5).abs() < 1e-6);
+    run_block_with_events(&mut processor, &input, &[TimedMidi::new(0, MidiMessage::ControlChange { channel: 3, controller: 1, value: 0 })]);
+    assert!((cutoff() - 1.).abs() < 1e-6);
+}
@@ -0,0 +1,44 @@
+use carnyx::test::{processor, rms, run, sine};
+use ladder_filter::LadderProcessor;
+
+const BLOCK_SIZE: usize = 128;
+
+// through a dark filter, so the processed signal is far from the input
+fn output(settings: &[(&str, f32)], input: &[f32]) -> Vec<f32> {
+    let settings = [&[("cutoff", 0.2)][..], settings].concat();
+    run(&mut processor(LadderProcessor::new, &settings), &[input.to_vec()], BLOCK_SIZE).remove(0)
+}
+
+#[test]
+fn a_dry_mix_passes_the_input_through() {
+    let input = sine(3000., 0.5, 4096);
+    let dry = output(&[("mix", 0.)], &input);
+    for (out, sample) in dry.iter().zip(&input) {
+        assert!((out - sample).abs() < 1e-6);
+    }
+    // and fully wet, the filter has cut it
+    assert!(rms(&output(&[], &input)) < rms(&input) * 0.5);
+}
+
+#[test]
+fn output_gain_scales_the_mixed_output() {
+    let input = sine(200., 0.25, 4096);
+    let unity = output(&[("mix", 0.5)], &input);
+    // +6 dB
+    let louder = output(&[("mix", 0.5), ("output gain", 0.625)], &input);
+    let expected = 10f32.powf(6. / 20.);
+    for (louder, unity) in louder.iter().zip(&unity) {
+        assert!((louder - unity * expected).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn input_trim_turns_down_what_the_filter_hears() {
+    // quiet enough that the ladder stays linear
+    let input = sine(200., 0.01, 8192);
+    let unity = output(&[], &input);
+    // -12 dB
+    let trimmed = output(&[("input trim", 0.25)], &input);
+    let ratio = rms(&trimmed[4096..]) / rms(&unity[4096..]);
+    assert!((ratio - 10f32.powf(-12. / 20.)).abs() < 0.01, "ratio {}", ratio);
+}